@@ -0,0 +1,101 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Devices which have been configured at some point, kept across sessions
+/// and Syncthing instances to recognize them again
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct AddressBook {
+    #[serde(default)]
+    devices: Vec<AddressBookEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AddressBookEntry {
+    pub name: String,
+    pub device_id: String,
+    /// Free text, only edited in the file itself
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+impl AddressBook {
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|path| path.join("synctui").join("address-book.toml"))
+    }
+
+    /// Loads the address book. Falls back to an empty one, as it is only
+    /// ever used for hints.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
+                log::warn!(
+                    "failed to parse address book at {}: {:?}",
+                    path.display(),
+                    e
+                );
+                Self::default()
+            }),
+            Err(e) => {
+                log::debug!("no address book loaded from {}: {:?}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            log::warn!("could not determine where to save the address book");
+            return;
+        };
+        let result = toml::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                fs::write(&path, content).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            log::warn!("failed to save address book to {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn get(&self, device_id: &str) -> Option<&AddressBookEntry> {
+        self.devices
+            .iter()
+            .find(|entry| entry.device_id == device_id)
+    }
+
+    pub fn entries(&self) -> &[AddressBookEntry] {
+        &self.devices
+    }
+
+    /// Adds the device, or updates its name if it is known already. Returns
+    /// whether anything changed.
+    pub fn remember(&mut self, device_id: &str, name: &str) -> bool {
+        match self
+            .devices
+            .iter_mut()
+            .find(|entry| entry.device_id == device_id)
+        {
+            Some(entry) if entry.name == name || name.is_empty() => false,
+            Some(entry) => {
+                entry.name = name.to_string();
+                true
+            }
+            None => {
+                self.devices.push(AddressBookEntry {
+                    name: name.to_string(),
+                    device_id: device_id.to_string(),
+                    notes: String::new(),
+                });
+                true
+            }
+        }
+    }
+}
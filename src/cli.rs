@@ -0,0 +1,659 @@
+use std::{
+    fs::read_to_string,
+    io::{self, BufRead, IsTerminal, Write},
+    path::PathBuf,
+};
+
+use clap::Subcommand;
+use color_eyre::eyre::{self, Context};
+use syncthing_rs::{
+    Client,
+    types::config::{FolderDeviceConfiguration, NewDeviceConfiguration, NewFolderConfiguration},
+};
+
+use crate::{
+    AddressBook, AppConfig, AppError, ConfigIssue, parse_device_link,
+    plan::Plan,
+    tui::{control, keymap::Keymap},
+};
+
+/// Non-interactive commands, which run without starting the TUI
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Manage pending devices and folders
+    Pending {
+        /// Do not ask for confirmation
+        #[arg(short, long, global = true)]
+        yes: bool,
+
+        #[command(subcommand)]
+        command: PendingCommand,
+    },
+    /// Add a device by its ID or a synctui://add-device/<ID>?name=<name> link.
+    /// Opens the TUI with the device pre-filled, unless --cli is passed
+    AddDevice {
+        /// Device ID or link
+        link: String,
+
+        /// Name of the device, takes precedence over the name in the link
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Print the effective keymap, including overrides from the config file
+    Keys,
+    /// Send a command to the running TUI: reload, pause-folder <id>,
+    /// resume-folder <id> or status-json
+    Control {
+        #[arg(required = true, num_args = 1..)]
+        command: Vec<String>,
+    },
+    /// Add the devices and folders declared in a TOML file, and share the
+    /// folders as declared. Shows the changes before applying them
+    Apply {
+        /// TOML file with [[devices]] and [[folders]]
+        plan: PathBuf,
+
+        /// Also remove devices, folders and shares which are not declared
+        #[arg(long)]
+        prune: bool,
+
+        /// Only show the changes
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// List, add and share folders. Lists are printed without a header, one
+    /// folder per line with tab-separated columns
+    Folders {
+        /// Do not ask for confirmation
+        #[arg(short, long, global = true)]
+        yes: bool,
+
+        #[command(subcommand)]
+        command: FoldersCommand,
+    },
+    /// List and accept devices. Lists are printed without a header, one
+    /// device per line with tab-separated columns
+    Devices {
+        /// Do not ask for confirmation
+        #[arg(short, long, global = true)]
+        yes: bool,
+
+        #[command(subcommand)]
+        command: DevicesCommand,
+    },
+    /// Devices remembered across sessions and Syncthing instances
+    AddressBook {
+        #[command(subcommand)]
+        command: AddressBookCommand,
+    },
+    /// Inspect the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Print the completion script of a shell, e.g., for bash:
+    /// source <(synctui completions bash)
+    Completions { shell: clap_complete::Shell },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FoldersCommand {
+    /// Print the ID, label, path and state (active or paused) of every folder
+    List,
+    /// Add a new folder, shared with no device
+    Add {
+        /// ID of the new folder
+        #[arg(long)]
+        id: String,
+
+        /// Local path of the folder
+        #[arg(long)]
+        path: String,
+
+        /// Label of the folder, defaults to none
+        #[arg(long)]
+        label: Option<String>,
+    },
+    /// Share a folder with a configured device
+    Share {
+        /// ID of the folder
+        folder_id: String,
+
+        /// ID of the device
+        device_id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DevicesCommand {
+    /// Print the ID, name and state (connected, disconnected or paused) of
+    /// every device
+    List,
+    /// Accept a pending device
+    Accept {
+        /// ID of the pending device
+        device_id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Check the config file for errors and unknown keys
+    Validate {
+        /// Also test the connection to Syncthing with the configured API key
+        #[arg(long)]
+        connect: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AddressBookCommand {
+    /// List all devices in the address book
+    List,
+    /// Add the devices of the address book which Syncthing does not know
+    Restore {
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PendingCommand {
+    /// List all pending devices and folders
+    List,
+    /// Accept a pending device
+    AcceptDevice {
+        /// ID of the pending device
+        device_id: String,
+    },
+    /// Accept a pending folder and share it with every device offering it
+    AcceptFolder {
+        /// ID of the pending folder
+        folder_id: String,
+
+        /// Local path of the folder. Not needed if the folder already exists
+        #[arg(short, long)]
+        path: Option<String>,
+    },
+    /// Dismiss a pending device or folder
+    Dismiss {
+        #[command(subcommand)]
+        target: DismissTarget,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DismissTarget {
+    /// Dismiss a pending device
+    Device {
+        /// ID of the pending device
+        device_id: String,
+    },
+    /// Dismiss a pending folder
+    Folder {
+        /// ID of the pending folder
+        folder_id: String,
+
+        /// Only dismiss the offer of this device
+        #[arg(short, long)]
+        device: Option<String>,
+    },
+}
+
+pub async fn run_command(client: Client, config: &AppConfig, command: Command) -> eyre::Result<()> {
+    match command {
+        Command::Keys => print_keymap(config),
+        Command::Pending { yes, command } => run_pending(client, command, yes).await,
+        Command::Folders { yes, command } => run_folders(client, command, yes).await,
+        Command::Devices { yes, command } => run_devices(client, command, yes).await,
+        Command::AddressBook { command } => run_address_book(client, command).await,
+        Command::Control { command } => {
+            let answer = control::send(&command.join(" ")).await.wrap_err(
+                "Failed to reach the control interface, is synctui running with control=true?",
+            )?;
+            println!("{}", answer);
+            if answer.starts_with("error:") {
+                return Err(eyre::eyre!(answer));
+            }
+            Ok(())
+        }
+        Command::Apply {
+            plan,
+            prune,
+            dry_run,
+            yes,
+        } => {
+            let plan = Plan::load(&plan)?;
+            let configuration = client.get_configuration().await?;
+            let own_id = client.get_id().await?;
+            let changes = plan.diff(&configuration, &own_id, prune)?;
+
+            if changes.is_empty() {
+                println!("Nothing to change");
+                return Ok(());
+            }
+            for change in &changes {
+                println!("{}", change);
+            }
+            if !dry_run {
+                confirm(yes, &format!("Apply {} changes?", changes.len()))?;
+                crate::plan::apply(&client, changes).await?;
+            }
+            Ok(())
+        }
+        Command::AddDevice { link, name, yes } => {
+            let (device_id, link_name) = parse_device_link(&link)?;
+            let name = name.or(link_name);
+
+            let prompt = match &name {
+                Some(name) => format!("Add device {} ({})?", name, device_id),
+                None => format!("Add device {}?", device_id),
+            };
+            confirm(yes, &prompt)?;
+            let mut device = NewDeviceConfiguration::new(device_id);
+            if let Some(name) = name {
+                device = device.name(name);
+            }
+            client.add_device(device).await?;
+            Ok(())
+        }
+        // Handled by `validate_config`, as it must not require a valid config
+        Command::Config { .. } => Ok(()),
+        // Handled by `print_completions`, as it must not require a config
+        Command::Completions { .. } => Ok(()),
+    }
+}
+
+fn print_keymap(config: &AppConfig) -> eyre::Result<()> {
+    let bindings = Keymap::new(&config.keys)?.describe();
+    let width = bindings
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    println!("{:<14} {:<width$}  DESCRIPTION", "ACTION", "KEYS");
+    for (keys, action) in bindings {
+        let screens = if action.screens.is_empty() {
+            String::new()
+        } else {
+            let screens: Vec<_> = action.screens.iter().map(|s| s.to_string()).collect();
+            format!(" ({})", screens.join(", "))
+        };
+        println!(
+            "{:<14} {:<width$}  {}{}",
+            action.id, keys, action.description, screens
+        );
+    }
+    println!("{:<14} {:<width$}  Switch screen", "", "1-9");
+    Ok(())
+}
+
+/// Validates the config file at `path`, or the default location, printing
+/// every issue with its location
+pub async fn validate_config(path: Option<String>, connect: bool) -> eyre::Result<()> {
+    let path = AppConfig::path(path)?;
+    let content = read_to_string(&path).map_err(|source| AppError::ConfigReadError {
+        path: path.clone(),
+        source,
+    })?;
+
+    let (config, mut issues) = AppConfig::validate(&content);
+
+    if connect && let Some(config) = &config {
+        match check_connection(config).await {
+            Ok(missing_folders) => {
+                for folder_id in missing_folders {
+                    issues.push(ConfigIssue {
+                        location: None,
+                        message: format!("'auto-resume' folder '{}' does not exist", folder_id),
+                    });
+                }
+            }
+            Err(e) => issues.push(ConfigIssue {
+                location: None,
+                message: format!("connection test failed: {}", e),
+            }),
+        }
+    }
+
+    for issue in &issues {
+        match issue.location {
+            Some((line, column)) => {
+                println!("{}:{}:{}: {}", path.display(), line, column, issue.message)
+            }
+            None => println!("{}: {}", path.display(), issue.message),
+        }
+    }
+
+    if issues.is_empty() {
+        println!("{} is valid", path.display());
+        Ok(())
+    } else {
+        Err(eyre::eyre!(
+            "found {} issue{} in {}",
+            issues.len(),
+            if issues.len() == 1 { "" } else { "s" },
+            path.display()
+        ))
+    }
+}
+
+/// Connects to Syncthing with the API key of `config` and returns the
+/// auto-resume folders, which do not exist
+async fn check_connection(config: &AppConfig) -> eyre::Result<Vec<String>> {
+    let client = config.client()?;
+    client.ping().await?;
+    let configuration = client.get_configuration().await?;
+
+    Ok(config
+        .auto_resume
+        .iter()
+        .filter(|id| !configuration.folders.iter().any(|f| &f.id == *id))
+        .cloned()
+        .collect())
+}
+
+async fn run_pending(client: Client, command: PendingCommand, yes: bool) -> eyre::Result<()> {
+    match command {
+        PendingCommand::List => {
+            let devices = client.get_pending_devices().await?;
+            let folders = client.get_pending_folders().await?;
+
+            let address_book = AddressBook::load();
+            println!("Pending devices:");
+            for (device_id, device) in devices.devices.iter() {
+                match address_book.get(device_id) {
+                    Some(known) => println!(
+                        "  {} ({}), known as '{}'",
+                        device.name, device_id, known.name
+                    ),
+                    None => println!("  {} ({})", device.name, device_id),
+                }
+            }
+
+            println!("Pending folders:");
+            for (folder_id, folder) in folders.folders.iter() {
+                for (device_id, offerer) in folder.offered_by.iter() {
+                    println!(
+                        "  {} ({}) offered by {}",
+                        offerer.label, folder_id, device_id
+                    );
+                }
+            }
+        }
+        PendingCommand::AcceptDevice { device_id } => {
+            accept_device(&client, device_id, yes).await?;
+        }
+        PendingCommand::AcceptFolder { folder_id, path } => {
+            let folders = client.get_pending_folders().await?;
+            let folder = folders
+                .folders
+                .get(&folder_id)
+                .ok_or(AppError::UnknownFolder)?;
+
+            let configuration = client.get_configuration().await?;
+            let offerers: Vec<_> = folder.offered_by.keys().cloned().collect();
+
+            // Folder exists already locally, so we only have to share it
+            if let Some(mut existing) = configuration
+                .folders
+                .into_iter()
+                .find(|f| f.id == folder_id)
+            {
+                confirm(
+                    yes,
+                    &format!(
+                        "Share {} ({}) with {}?",
+                        existing.label,
+                        existing.id,
+                        offerers.join(", ")
+                    ),
+                )?;
+                for device_id in offerers {
+                    if !existing.devices.iter().any(|d| d.device_id == device_id) {
+                        existing.devices.push(FolderDeviceConfiguration {
+                            device_id,
+                            introduced_by: String::new(),
+                            encryption_password: String::new(),
+                        });
+                    }
+                }
+                client.post_folder(existing).await?;
+            } else {
+                let path = path.ok_or(AppError::MissingFolderPath)?;
+                let label = folder
+                    .offered_by
+                    .values()
+                    .next()
+                    .map(|offerer| offerer.label.clone())
+                    .unwrap_or_default();
+
+                confirm(
+                    yes,
+                    &format!(
+                        "Add {} ({}) at '{}' and share it with {}?",
+                        label,
+                        folder_id,
+                        path,
+                        offerers.join(", ")
+                    ),
+                )?;
+                let devices = offerers
+                    .into_iter()
+                    .map(|device_id| FolderDeviceConfiguration {
+                        device_id,
+                        introduced_by: String::new(),
+                        encryption_password: String::new(),
+                    })
+                    .collect();
+                client
+                    .add_folder(
+                        NewFolderConfiguration::new(folder_id, path)
+                            .label(label)
+                            .devices(devices),
+                    )
+                    .await?;
+            }
+        }
+        PendingCommand::Dismiss { target } => match target {
+            DismissTarget::Device { device_id } => {
+                confirm(yes, &format!("Dismiss device {}?", device_id))?;
+                client.dismiss_pending_device(&device_id).await?;
+            }
+            DismissTarget::Folder { folder_id, device } => {
+                let prompt = match &device {
+                    Some(device_id) => format!("Dismiss folder {} from {}?", folder_id, device_id),
+                    None => format!("Dismiss folder {} from all devices?", folder_id),
+                };
+                confirm(yes, &prompt)?;
+                client
+                    .dismiss_pending_folder(&folder_id, device.as_deref())
+                    .await?;
+            }
+        },
+    }
+    Ok(())
+}
+
+async fn accept_device(client: &Client, device_id: String, yes: bool) -> eyre::Result<()> {
+    let devices = client.get_pending_devices().await?;
+    let device = devices
+        .devices
+        .get(&device_id)
+        .ok_or(AppError::UnknownDevice)?;
+
+    confirm(
+        yes,
+        &format!("Accept device {} ({})?", device.name, device_id),
+    )?;
+    client
+        .add_device(NewDeviceConfiguration::new(device_id).name(device.name.clone()))
+        .await?;
+    Ok(())
+}
+
+async fn run_folders(client: Client, command: FoldersCommand, yes: bool) -> eyre::Result<()> {
+    match command {
+        FoldersCommand::List => {
+            for folder in client.get_configuration().await?.folders {
+                let state = if folder.paused { "paused" } else { "active" };
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    folder.id, folder.label, folder.path, state
+                );
+            }
+        }
+        FoldersCommand::Add { id, path, label } => {
+            let configuration = client.get_configuration().await?;
+            if configuration.folders.iter().any(|f| f.id == id) {
+                return Err(eyre::eyre!("folder {} exists already", id));
+            }
+            confirm(yes, &format!("Add folder {} at '{}'?", id, path))?;
+            let mut folder = NewFolderConfiguration::new(id, path);
+            if let Some(label) = label {
+                folder = folder.label(label);
+            }
+            client.add_folder(folder).await?;
+        }
+        FoldersCommand::Share {
+            folder_id,
+            device_id,
+        } => {
+            let configuration = client.get_configuration().await?;
+            if !configuration
+                .devices
+                .iter()
+                .any(|d| d.device_id == device_id)
+            {
+                return Err(AppError::UnknownDevice.into());
+            }
+            let mut folder = configuration
+                .folders
+                .into_iter()
+                .find(|f| f.id == folder_id)
+                .ok_or(AppError::UnknownFolder)?;
+
+            if folder.devices.iter().any(|d| d.device_id == device_id) {
+                println!("{} is already shared with {}", folder_id, device_id);
+                return Ok(());
+            }
+            confirm(yes, &format!("Share {} with {}?", folder_id, device_id))?;
+            folder.devices.push(FolderDeviceConfiguration {
+                device_id,
+                introduced_by: String::new(),
+                encryption_password: String::new(),
+            });
+            client.post_folder(folder).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn run_devices(client: Client, command: DevicesCommand, yes: bool) -> eyre::Result<()> {
+    match command {
+        DevicesCommand::List => {
+            let configuration = client.get_configuration().await?;
+            let connections = client.get_connections().await?;
+            // Syncthing lists this device as well, which is never connected
+            let own_id = client.get_id().await?;
+            for device in configuration
+                .devices
+                .into_iter()
+                .filter(|d| d.device_id != own_id)
+            {
+                let connected = connections
+                    .connections
+                    .get(&device.device_id)
+                    .is_some_and(|c| c.connected);
+                let state = if device.paused {
+                    "paused"
+                } else if connected {
+                    "connected"
+                } else {
+                    "disconnected"
+                };
+                println!("{}\t{}\t{}", device.device_id, device.name, state);
+            }
+        }
+        DevicesCommand::Accept { device_id } => accept_device(&client, device_id, yes).await?,
+    }
+    Ok(())
+}
+
+async fn run_address_book(client: Client, command: AddressBookCommand) -> eyre::Result<()> {
+    let address_book = AddressBook::load();
+    match command {
+        AddressBookCommand::List => {
+            for entry in address_book.entries() {
+                if entry.notes.is_empty() {
+                    println!("{} ({})", entry.name, entry.device_id);
+                } else {
+                    println!("{} ({}): {}", entry.name, entry.device_id, entry.notes);
+                }
+            }
+        }
+        AddressBookCommand::Restore { yes } => {
+            let configuration = client.get_configuration().await?;
+            let missing: Vec<_> = address_book
+                .entries()
+                .iter()
+                .filter(|entry| {
+                    !configuration
+                        .devices
+                        .iter()
+                        .any(|d| d.device_id == entry.device_id)
+                })
+                .collect();
+            if missing.is_empty() {
+                println!("Nothing to restore");
+                return Ok(());
+            }
+            for entry in &missing {
+                println!("{} ({})", entry.name, entry.device_id);
+            }
+            confirm(yes, &format!("Add {} devices?", missing.len()))?;
+            for entry in missing {
+                client
+                    .add_device(
+                        NewDeviceConfiguration::new(entry.device_id.clone())
+                            .name(entry.name.clone()),
+                    )
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Asks the user a yes/no question on stdin, unless `yes` was passed.
+/// Anything but yes fails, so that the command does not exit successfully
+/// without doing anything. Without a terminal to ask on, `--yes` is
+/// required.
+fn confirm(yes: bool, prompt: &str) -> eyre::Result<()> {
+    if yes {
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        return Err(AppError::NotConfirmed(prompt.to_string()).into());
+    }
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        Ok(())
+    } else {
+        Err(AppError::Declined.into())
+    }
+}
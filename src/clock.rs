@@ -0,0 +1,105 @@
+use std::{
+    fmt,
+    ops::Deref,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+
+/// Source of the current time. Replaced by a [`MockClock`] to test
+/// time-dependent behavior deterministically.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Monotonic time, for measuring durations
+    fn now(&self) -> Instant;
+    /// Wall clock time, for timestamps
+    fn utc_now(&self) -> DateTime<Utc>;
+
+    /// Time passed since `earlier`, or zero if it lies in the future
+    fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+/// The actual time of the system
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock which stands still until it is advanced. Clones share the same
+/// time.
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    time: Arc<Mutex<(Instant, DateTime<Utc>)>>,
+}
+
+impl MockClock {
+    /// Starts at the current time of the system
+    pub fn new() -> Self {
+        Self::starting_at(Utc::now())
+    }
+
+    /// Starts at `utc` as wall clock time
+    pub fn starting_at(utc: DateTime<Utc>) -> Self {
+        Self {
+            time: Arc::new(Mutex::new((Instant::now(), utc))),
+        }
+    }
+
+    /// Moves both the monotonic and the wall clock time forward
+    pub fn advance(&self, duration: Duration) {
+        let mut time = self.time.lock().unwrap();
+        time.0 += duration;
+        time.1 += duration;
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.time.lock().unwrap().0
+    }
+
+    fn utc_now(&self) -> DateTime<Utc> {
+        self.time.lock().unwrap().1
+    }
+}
+
+/// A clock shared by everything which needs the time, the system's by
+/// default
+#[derive(Clone, Debug)]
+pub struct SharedClock(Arc<dyn Clock>);
+
+impl SharedClock {
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Self(Arc::new(clock))
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl Deref for SharedClock {
+    type Target = dyn Clock;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
@@ -0,0 +1,84 @@
+use std::io;
+
+use clap_complete::Shell;
+
+/// Completes the IDs of folders and devices by asking the connected Syncthing
+/// through `synctui folders list` and `synctui devices list`, whose first
+/// column is the ID. Prints nothing if the arguments before the cursor take no
+/// ID, or if Syncthing cannot be reached.
+const BASH: &str = r#"
+_synctui_ids() {
+    local args=() word
+    for word in "${COMP_WORDS[@]:1:COMP_CWORD-1}"; do
+        [[ $word == -* ]] || args+=("$word")
+    done
+    case "${args[*]}" in
+        "folders share" | "control pause-folder" | "control resume-folder")
+            synctui folders list 2>/dev/null | cut -f1 ;;
+        "folders share "*)
+            [[ ${#args[@]} -eq 3 ]] && synctui devices list 2>/dev/null | cut -f1 ;;
+    esac
+}
+
+_synctui_dynamic() {
+    local ids
+    ids=$(_synctui_ids)
+    if [[ -n $ids ]]; then
+        COMPREPLY=($(compgen -W "$ids" -- "${COMP_WORDS[COMP_CWORD]}"))
+    else
+        _synctui "$@"
+    fi
+}
+
+complete -F _synctui_dynamic -o bashdefault -o default synctui
+"#;
+
+const ZSH: &str = r#"
+_synctui_ids() {
+    local -a args=(${words[2,CURRENT-1]:#-*})
+    case "$args" in
+        "folders share" | "control pause-folder" | "control resume-folder")
+            synctui folders list 2>/dev/null | cut -f1 ;;
+        "folders share "*)
+            (( ${#args} == 3 )) && synctui devices list 2>/dev/null | cut -f1 ;;
+    esac
+}
+
+_synctui_dynamic() {
+    local -a ids=(${(f)"$(_synctui_ids)"})
+    if (( ${#ids} )); then
+        compadd -a ids
+    else
+        _synctui "$@"
+    fi
+}
+
+compdef _synctui_dynamic synctui
+"#;
+
+const FISH: &str = r#"
+function __synctui_ids
+    set -l args (string match -v -- '-*' (commandline -opc))[2..]
+    switch "$args"
+        case 'folders share' 'control pause-folder' 'control resume-folder'
+            synctui folders list 2>/dev/null | cut -f1
+        case 'folders share *'
+            test (count $args) -eq 3; and synctui devices list 2>/dev/null | cut -f1
+    end
+end
+
+complete -c synctui -f -n 'test -n "$(__synctui_ids)"' -a '(__synctui_ids)'
+"#;
+
+/// Prints the completion script of `shell` for `command`. The scripts of
+/// bash, zsh and fish also complete the IDs of folders and devices.
+pub fn print_completions(shell: Shell, command: &mut clap::Command) {
+    clap_complete::generate(shell, command, "synctui", &mut io::stdout());
+    let dynamic = match shell {
+        Shell::Bash => BASH,
+        Shell::Zsh => ZSH,
+        Shell::Fish => FISH,
+        _ => "",
+    };
+    print!("{}", dynamic);
+}
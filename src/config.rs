@@ -1,29 +1,703 @@
-use std::{fs::read_to_string, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::read_to_string,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
 use color_eyre::eyre;
+use ratatui::style::Color;
 use serde::{Deserialize, Serialize};
+use syncthing_rs::{Client, types::config::FolderConfiguration};
 
-use crate::AppError;
+use crate::{
+    AppError,
+    tui::{CurrentScreen, keymap::Keymap},
+};
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Address of the Syncthing GUI and REST API, unless configured otherwise
+const DEFAULT_ADDRESS: &str = "http://localhost:8384";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AppConfig {
     #[serde(rename = "api-key")]
     pub api_key: String,
+    /// Where Syncthing's GUI is reachable, e.g., `https://nas:8384`
+    pub address: Option<String>,
+    /// Accept any TLS certificate, e.g., Syncthing's self-signed one
+    #[serde(default)]
+    pub insecure: bool,
+    /// PEM file with an additional certificate authority to trust
+    #[serde(rename = "ca-cert")]
+    pub ca_cert: Option<PathBuf>,
+    /// Seconds a folder may stay out of sync before it is considered stuck
+    #[serde(rename = "stuck-after", default = "default_stuck_after")]
+    pub stuck_after: u64,
+    /// Days a device may stay unseen before it is suggested for cleanup
+    #[serde(rename = "stale-after", default = "default_stale_after")]
+    pub stale_after: u64,
+    /// IDs of paused folders which get resumed once their path is available again
+    #[serde(rename = "auto-resume", default)]
+    pub auto_resume: Vec<String>,
+    /// IDs of devices which are accepted as soon as they try to connect
+    #[serde(rename = "auto-accept", default)]
+    pub auto_accept: Vec<String>,
+    /// Columns shown next to the name in the devices list
+    #[serde(rename = "device-columns", default = "default_device_columns")]
+    pub device_columns: Vec<DeviceColumn>,
+    /// Where `--watch` sends notifications to
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    /// Where received events are recorded to
+    #[serde(default)]
+    pub recorder: RecorderConfig,
+    /// Keys replacing the default ones, keyed by action
+    #[serde(default)]
+    pub keys: HashMap<String, Vec<String>>,
+    /// Sections of the folders list
+    #[serde(rename = "folder-groups", default)]
+    pub folder_groups: Vec<FolderGroup>,
+    /// Commands offered in the commands menu of folders
+    #[serde(rename = "folder-commands", default)]
+    pub folder_commands: Vec<FolderCommand>,
+    /// Create folders step by step instead of in a single popup
+    #[serde(rename = "folder-wizard", default)]
+    pub folder_wizard: bool,
+    /// Show counts of folders, devices and pending requests in the title of
+    /// the terminal window
+    #[serde(rename = "terminal-title", default)]
+    pub terminal_title: bool,
+    /// Watch the paths of folders for changes, when running on the same
+    /// machine as Syncthing, to tell about them before Syncthing scans
+    #[serde(rename = "watch-local", default)]
+    pub watch_local: bool,
+    /// Accept commands of `synctui control` while the TUI runs
+    #[serde(default)]
+    pub control: bool,
+    /// Glyphs used in lists and badges
+    #[serde(default)]
+    pub icons: IconSet,
+    /// Keep the recent versions of the state to step through them, when
+    /// tracking down bugs
+    #[serde(rename = "debug-state", default)]
+    pub debug_state: bool,
+    /// Start in advanced mode, which shows rarely used options, destructive
+    /// actions and the raw configuration
+    #[serde(default)]
+    pub advanced: bool,
+    /// Data loaded on startup. Everything else is loaded once it is shown.
+    #[serde(default = "default_preload")]
+    pub preload: Vec<Preload>,
+    /// Seconds between polls of the connections and of syncing folders
+    #[serde(rename = "poll-interval", default = "default_poll_interval")]
+    pub poll_interval: u64,
+    /// Settings for different tasks, by name, applied with `--profile` or
+    /// switched to at runtime
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(skip)]
+    pub applied_profile: Option<AppliedProfile>,
+    /// File the API key was read from, which is updated when the key gets
+    /// rotated. Unset if the key was passed with `--api-key`.
+    #[serde(skip)]
+    pub api_key_path: Option<PathBuf>,
+}
+
+/// Settings for a task, e.g., monitoring, which replace the ones of the rest
+/// of the config while the profile is applied
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Profile {
+    /// Screen shown when the profile is applied
+    pub screen: Option<CurrentScreen>,
+    pub icons: Option<IconSet>,
+    /// Keys replacing the ones of the config, keyed by action
+    #[serde(default)]
+    pub keys: HashMap<String, Vec<String>>,
+    #[serde(rename = "poll-interval")]
+    pub poll_interval: Option<u64>,
+}
+
+/// A profile applied to the config, with the settings it replaced to
+/// restore them
+#[derive(Clone, Debug)]
+pub struct AppliedProfile {
+    pub name: String,
+    icons: IconSet,
+    keys: HashMap<String, Vec<String>>,
+    poll_interval: u64,
+}
+
+/// Folders shown together in their own section. A folder belongs to the
+/// first group it matches.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FolderGroup {
+    pub name: String,
+    /// Accent color, e.g., `blue` or `#ff8800`. Picked automatically if unset
+    pub color: Option<String>,
+    #[serde(rename = "label-prefix")]
+    pub label_prefix: Option<String>,
+    #[serde(rename = "path-prefix")]
+    pub path_prefix: Option<String>,
+    /// IDs of folders which are part of this group regardless of the prefixes
+    #[serde(default)]
+    pub folders: Vec<String>,
+}
+
+impl FolderGroup {
+    pub fn contains(&self, folder: &FolderConfiguration) -> bool {
+        self.folders.contains(&folder.id)
+            || self
+                .label_prefix
+                .as_ref()
+                .is_some_and(|prefix| folder.label.starts_with(prefix))
+            || self
+                .path_prefix
+                .as_ref()
+                .is_some_and(|prefix| folder.path.starts_with(prefix))
+    }
+}
+
+/// A shell command which can be run on a folder
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FolderCommand {
+    pub name: String,
+    /// Run by the shell, after replacing `{path}`, `{id}` and `{label}`
+    pub command: String,
+    /// IDs of the folders to offer this command for, all if empty
+    #[serde(default)]
+    pub folders: Vec<String>,
+}
+
+impl FolderCommand {
+    pub fn applies_to(&self, folder: &FolderConfiguration) -> bool {
+        self.folders.is_empty() || self.folders.contains(&folder.id)
+    }
+
+    /// The command with the placeholders replaced by the properties of
+    /// `folder`, quoted so that the shell takes each as a single word. The
+    /// command is scanned once, so placeholders within the properties are
+    /// left alone.
+    pub fn expand(&self, folder: &FolderConfiguration) -> Result<String, AppError> {
+        let values = [
+            ("{path}", &folder.path),
+            ("{id}", &folder.id),
+            ("{label}", &folder.label),
+        ];
+        let mut expanded = String::with_capacity(self.command.len());
+        let mut rest = self.command.as_str();
+        while let Some(start) = rest.find('{') {
+            expanded.push_str(&rest[..start]);
+            rest = &rest[start..];
+            match values
+                .iter()
+                .find(|(placeholder, _)| rest.starts_with(placeholder))
+            {
+                Some((placeholder, value)) => {
+                    expanded.push_str(&shell_quote(value)?);
+                    rest = &rest[placeholder.len()..];
+                }
+                None => {
+                    expanded.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+}
+
+#[cfg(not(windows))]
+fn shell_quote(s: &str) -> Result<String, AppError> {
+    Ok(format!("'{}'", s.replace('\'', "'\\''")))
+}
+
+/// cmd expands variables like `%PATH%` even within quotes, and has no
+/// reliable way to escape them or quotes, so such values are refused
+#[cfg(windows)]
+fn shell_quote(s: &str) -> Result<String, AppError> {
+    if s.contains(['%', '"', '\r', '\n']) {
+        return Err(AppError::UnsafeCommandArgument(s.to_string()));
+    }
+    Ok(format!("\"{}\"", s))
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct NotificationConfig {
+    /// URL which receives each notification as JSON in a POST request
+    pub webhook: Option<String>,
+    pub smtp: Option<SmtpConfig>,
+    /// Kinds of notifications to send, all if empty
+    #[serde(default)]
+    pub events: Vec<NotificationKind>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecorderConfig {
+    /// Record from the start, instead of only once toggled
+    #[serde(default)]
+    pub enabled: bool,
+    /// Defaults to `events.jsonl` or `events.csv` in the data directory
+    pub path: Option<PathBuf>,
+    #[serde(default)]
+    pub format: RecordFormat,
+    /// MiB after which the file is rotated
+    #[serde(rename = "max-size", default = "default_max_record_size")]
+    pub max_size: u64,
+    /// Rotated files kept besides the current one
+    #[serde(default = "default_kept_records")]
+    pub keep: usize,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            format: RecordFormat::default(),
+            max_size: default_max_record_size(),
+            keep: default_kept_records(),
+        }
+    }
+}
+
+fn default_max_record_size() -> u64 {
+    10
+}
+
+fn default_kept_records() -> usize {
+    3
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordFormat {
+    /// One JSON object per line, as sent by Syncthing
+    #[default]
+    Jsonl,
+    /// Time, type, folder and device in their own columns
+    Csv,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SmtpConfig {
+    pub server: String,
+    /// Defaults to the submission port with STARTTLS
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotificationKind {
+    /// A folder has been out of sync for longer than `stuck-after`
+    OutOfSync,
+    /// A new device wants to connect
+    PendingDevice,
+    /// A device offers a new folder
+    PendingFolder,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeviceColumn {
+    /// Connection and sync status
+    Status,
+    /// Current download and upload rate
+    Rate,
+    /// When a disconnected device was last seen
+    LastSeen,
+}
+
+/// Data which is expensive to load, and can therefore be left out on startup
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preload {
+    /// When devices were last seen, shown on the devices and health pages
+    DeviceStats,
+    /// How far the clock of Syncthing is off, shown on the health page
+    ClockSkew,
+    /// How far each folder is synced, shown on the folders and health pages
+    Completion,
+    /// How far the other devices are with each folder, shown in the details
+    /// of the folder. Takes a request per folder and device.
+    RemoteCompletion,
+}
+
+/// Glyphs for lists and badges. Plain ASCII works on every terminal, while
+/// the nerd font icons need a patched font.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IconSet {
+    Ascii,
+    #[default]
+    Unicode,
+    NerdFont,
+}
+
+impl IconSet {
+    /// Put in front of folder names, including the space
+    pub fn folder(self) -> &'static str {
+        match self {
+            IconSet::Ascii | IconSet::Unicode => "",
+            IconSet::NerdFont => "\u{f07b} ",
+        }
+    }
+
+    /// Put in front of device names, including the space
+    pub fn device(self) -> &'static str {
+        match self {
+            IconSet::Ascii | IconSet::Unicode => "",
+            IconSet::NerdFont => "\u{f108} ",
+        }
+    }
+
+    /// Put in front of encryption badges, including the space
+    pub fn lock(self) -> &'static str {
+        match self {
+            IconSet::Ascii | IconSet::Unicode => "",
+            IconSet::NerdFont => "\u{f023} ",
+        }
+    }
+
+    /// Put in front of pause badges, including the space
+    pub fn pause(self) -> &'static str {
+        match self {
+            IconSet::Ascii | IconSet::Unicode => "",
+            IconSet::NerdFont => "\u{f04c} ",
+        }
+    }
+
+    /// Put in front of favorite folders, including the space
+    pub fn favorite(self) -> &'static str {
+        match self {
+            IconSet::Ascii => "* ",
+            IconSet::Unicode => "★ ",
+            IconSet::NerdFont => "\u{f005} ",
+        }
+    }
+
+    pub fn checkbox(self, checked: bool) -> &'static str {
+        match (self, checked) {
+            (IconSet::Ascii, true) => "[x]",
+            (IconSet::Ascii, false) => "[ ]",
+            (IconSet::Unicode, true) => "✓",
+            (IconSet::Unicode, false) => "☐",
+            (IconSet::NerdFont, true) => "\u{f14a}",
+            (IconSet::NerdFont, false) => "\u{f096}",
+        }
+    }
+
+    /// Marks whether a group or tree node is collapsed
+    pub fn expander(self, collapsed: bool) -> &'static str {
+        match (self, collapsed) {
+            (IconSet::Ascii, true) => ">",
+            (IconSet::Ascii, false) => "v",
+            (IconSet::Unicode, true) => "▸",
+            (IconSet::Unicode, false) => "▾",
+            (IconSet::NerdFont, true) => "\u{f0da}",
+            (IconSet::NerdFont, false) => "\u{f0d7}",
+        }
+    }
+
+    /// Connects an item of a tree to its parent, differently for the last
+    /// item
+    pub fn branch(self, last: bool) -> &'static str {
+        match (self, last) {
+            (IconSet::Ascii, false) => "|-",
+            (IconSet::Ascii, true) => "`-",
+            (_, false) => "├─",
+            (_, true) => "└─",
+        }
+    }
+
+    /// Marks the color of a folder group
+    pub fn group_marker(self) -> &'static str {
+        match self {
+            IconSet::Ascii => "|",
+            IconSet::Unicode | IconSet::NerdFont => "▌",
+        }
+    }
+
+    /// Arrows in front of the download and upload rate
+    pub fn rates(self) -> (&'static str, &'static str) {
+        match self {
+            IconSet::Ascii => ("down", "up"),
+            IconSet::Unicode => ("↓", "↑"),
+            IconSet::NerdFont => ("\u{f063}", "\u{f062}"),
+        }
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            address: None,
+            insecure: false,
+            ca_cert: None,
+            stuck_after: default_stuck_after(),
+            stale_after: default_stale_after(),
+            auto_resume: Vec::new(),
+            auto_accept: Vec::new(),
+            device_columns: default_device_columns(),
+            notifications: NotificationConfig::default(),
+            recorder: RecorderConfig::default(),
+            keys: HashMap::new(),
+            folder_groups: Vec::new(),
+            folder_commands: Vec::new(),
+            folder_wizard: false,
+            terminal_title: false,
+            watch_local: false,
+            control: false,
+            icons: IconSet::default(),
+            debug_state: false,
+            advanced: false,
+            preload: default_preload(),
+            poll_interval: default_poll_interval(),
+            profiles: BTreeMap::new(),
+            applied_profile: None,
+            api_key_path: None,
+        }
+    }
+}
+
+fn default_device_columns() -> Vec<DeviceColumn> {
+    vec![
+        DeviceColumn::Status,
+        DeviceColumn::Rate,
+        DeviceColumn::LastSeen,
+    ]
+}
+
+fn default_preload() -> Vec<Preload> {
+    vec![
+        Preload::DeviceStats,
+        Preload::ClockSkew,
+        Preload::Completion,
+    ]
+}
+
+fn default_poll_interval() -> u64 {
+    5
+}
+
+fn default_stuck_after() -> u64 {
+    60 * 60
+}
+
+fn default_stale_after() -> u64 {
+    30
+}
+
+/// Keys which may appear at the top level of the config file
+const KNOWN_KEYS: [&str; 24] = [
+    "api-key",
+    "address",
+    "insecure",
+    "ca-cert",
+    "stuck-after",
+    "stale-after",
+    "auto-resume",
+    "auto-accept",
+    "device-columns",
+    "notifications",
+    "recorder",
+    "keys",
+    "folder-groups",
+    "folder-commands",
+    "folder-wizard",
+    "terminal-title",
+    "watch-local",
+    "control",
+    "icons",
+    "debug-state",
+    "advanced",
+    "preload",
+    "poll-interval",
+    "profiles",
+];
+
+const NOTIFICATION_KEYS: [&str; 3] = ["webhook", "smtp", "events"];
+
+const SMTP_KEYS: [&str; 6] = ["server", "port", "username", "password", "from", "to"];
+
+const RECORDER_KEYS: [&str; 5] = ["enabled", "path", "format", "max-size", "keep"];
+
+const PROFILE_KEYS: [&str; 4] = ["screen", "icons", "keys", "poll-interval"];
+
+const FOLDER_GROUP_KEYS: [&str; 5] = ["name", "color", "label-prefix", "path-prefix", "folders"];
+
+const FOLDER_COMMAND_KEYS: [&str; 3] = ["name", "command", "folders"];
+
+/// A problem found while validating the config file
+#[derive(Debug)]
+pub struct ConfigIssue {
+    /// 1-based line and column the issue refers to, if known
+    pub location: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn new(location: Option<(usize, usize)>, message: impl Into<String>) -> Self {
+        Self {
+            location,
+            message: message.into(),
+        }
+    }
+}
+
+/// Converts a byte offset into `content` into a 1-based line and column
+fn location(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+/// Location of the line assigning `path`, which is a key preceded by the
+/// tables it is in, e.g., `notifications.smtp.port`. Falls back to the first
+/// line assigning the key in any table, as tables may also be inline.
+fn key_location(content: &str, path: &str) -> Option<(usize, usize)> {
+    let (table, key) = path.rsplit_once('.').unwrap_or(("", path));
+    let mut section = "";
+    let mut fallback = None;
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some(header) = trimmed.strip_prefix('[')
+            && trimmed.ends_with(']')
+        {
+            section = header.trim_matches(['[', ']']).trim();
+            continue;
+        }
+        let Some(rest) = trimmed.strip_prefix(key) else {
+            continue;
+        };
+        if rest.trim_start().starts_with('=') {
+            let location = (i + 1, line.len() - line.trim_start().len() + 1);
+            if section == table {
+                return Some(location);
+            }
+            fallback.get_or_insert(location);
+        }
+    }
+    fallback
+}
+
+/// Reports the keys of `value` which are not in `known`, if it is a table.
+/// `path` names the table, e.g., `notifications.smtp`.
+fn unknown_keys(
+    content: &str,
+    value: &toml::Value,
+    path: &str,
+    known: &[&str],
+    issues: &mut Vec<ConfigIssue>,
+) {
+    let Some(table) = value.as_table() else {
+        return;
+    };
+    for key in table.keys() {
+        if !known.contains(&key.as_str()) {
+            issues.push(ConfigIssue::new(
+                key_location(content, &format!("{}.{}", path, key)),
+                format!("unknown key '{}' in '{}'", key, path),
+            ));
+        }
+    }
+}
+
+/// Writes `content` to `path`, readable only by the owner as it contains the
+/// API key
+fn write_private(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode only applies to new files
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+/// Location of the first occurrence of `text`
+fn text_location(content: &str, text: &str) -> Option<(usize, usize)> {
+    content.find(text).map(|offset| location(content, offset))
 }
 
 impl AppConfig {
-    pub fn load<T>(path_arg: Option<T>) -> eyre::Result<Self>
+    /// Address of Syncthing's GUI, without a trailing slash
+    pub fn address(&self) -> &str {
+        self.address
+            .as_deref()
+            .unwrap_or(DEFAULT_ADDRESS)
+            .trim_end_matches('/')
+    }
+
+    /// Whether Syncthing runs on this machine, judging by the host of its
+    /// address
+    pub fn is_local(&self) -> bool {
+        let address = self.address();
+        let authority = address.split_once("://").map_or(address, |(_, rest)| rest);
+        let authority = authority.split('/').next().unwrap_or_default();
+        let host = match authority.strip_prefix('[') {
+            Some(rest) => rest.split(']').next().unwrap_or_default(),
+            None => authority.split(':').next().unwrap_or_default(),
+        };
+        host.eq_ignore_ascii_case("localhost") || host == "::1" || host.starts_with("127.")
+    }
+
+    /// HTTP client trusting `ca-cert` in addition to the system's
+    /// certificates, or any certificate if `insecure` is set
+    pub fn http_client(&self) -> Result<reqwest::Client, AppError> {
+        let mut builder = reqwest::Client::builder().danger_accept_invalid_certs(self.insecure);
+        if let Some(path) = &self.ca_cert {
+            let pem = std::fs::read(path).map_err(|source| AppError::CaCertReadError {
+                path: path.clone(),
+                source,
+            })?;
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Client for the Syncthing instance at `address`
+    pub fn client(&self) -> Result<Client, AppError> {
+        Ok(Client::builder(&self.api_key)
+            .base_url(format!("{}/rest", self.address()))
+            .http_client(self.http_client()?)
+            .build()?)
+    }
+
+    /// Path of the config file, which is either `path_arg` or the default
+    /// location in the user's config directory
+    pub fn path<T>(path_arg: Option<T>) -> Result<PathBuf, AppError>
     where
         T: Into<PathBuf>,
     {
-        let effective_path: PathBuf = match path_arg {
-            Some(p) => p.into(),
+        match path_arg {
+            Some(p) => Ok(p.into()),
             None => {
                 let base_config_dir =
                     dirs::config_dir().ok_or(AppError::DefaultConfigDirNotFound)?;
-                base_config_dir.join("synctui").join("config.toml")
+                Ok(base_config_dir.join("synctui").join("config.toml"))
             }
-        };
+        }
+    }
+
+    pub fn load<T>(path_arg: Option<T>) -> eyre::Result<Self>
+    where
+        T: Into<PathBuf>,
+    {
+        let effective_path = Self::path(path_arg)?;
 
         let config_content =
             read_to_string(&effective_path).map_err(|io_error| AppError::ConfigReadError {
@@ -33,10 +707,348 @@ impl AppConfig {
 
         let config_struct: Self =
             toml::from_str(&config_content).map_err(|toml_error| AppError::ConfigParseError {
-                path: effective_path,
+                path: effective_path.clone(),
                 source: toml_error,
             })?;
 
-        Ok(config_struct)
+        Ok(Self {
+            api_key_path: Some(effective_path),
+            ..config_struct
+        })
+    }
+
+    /// `content` of a config file with the value of `api-key` replaced by
+    /// `api_key`, or added if it is missing. Everything else, including
+    /// comments, stays as it is.
+    pub fn replace_api_key(content: &str, api_key: &str) -> String {
+        let entry = format!("api-key = {}", toml::Value::String(api_key.to_string()));
+        match key_location(content, "api-key") {
+            Some((line, _)) => {
+                let mut lines: Vec<&str> = content.lines().collect();
+                lines[line - 1] = &entry;
+                let mut replaced = lines.join("\n");
+                if content.ends_with('\n') {
+                    replaced.push('\n');
+                }
+                replaced
+            }
+            None => format!("{}\n{}", entry, content),
+        }
+    }
+
+    /// Writes `api_key` to the config file at `path`
+    pub fn save_api_key(path: &Path, api_key: &str) -> Result<(), AppError> {
+        let content = read_to_string(path).map_err(|source| AppError::ConfigReadError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        write_private(path, &Self::replace_api_key(&content, api_key)).map_err(|source| {
+            AppError::ConfigWriteError {
+                path: path.to_path_buf(),
+                source,
+            }
+        })
+    }
+
+    /// Content of the config file written by the setup on the first run
+    pub fn first_run_content(address: &str, api_key: &str) -> String {
+        format!(
+            "# Written by the setup of synctui, see the README for all options\naddress = {}\napi-key = {}\n",
+            toml::Value::String(address.to_string()),
+            toml::Value::String(api_key.to_string())
+        )
+    }
+
+    /// Creates the config file at `path`, along with its directory
+    pub fn create(path: &Path, address: &str, api_key: &str) -> Result<(), AppError> {
+        let write_error = |source| AppError::ConfigWriteError {
+            path: path.to_path_buf(),
+            source,
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(write_error)?;
+        }
+        write_private(path, &Self::first_run_content(address, api_key)).map_err(write_error)
+    }
+
+    /// Checks the content of a config file, returning the parsed config if it
+    /// could be read at all, and every issue found on the way
+    pub fn validate(content: &str) -> (Option<Self>, Vec<ConfigIssue>) {
+        let mut issues = Vec::new();
+
+        let table = match content.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(e) => {
+                let location = e.span().map(|span| location(content, span.start));
+                issues.push(ConfigIssue::new(location, e.message().trim()));
+                return (None, issues);
+            }
+        };
+
+        for key in table.keys() {
+            if !KNOWN_KEYS.contains(&key.as_str()) {
+                issues.push(ConfigIssue::new(
+                    key_location(content, key),
+                    format!("unknown key '{}'", key),
+                ));
+            }
+        }
+        if let Some(notifications) = table.get("notifications") {
+            unknown_keys(
+                content,
+                notifications,
+                "notifications",
+                &NOTIFICATION_KEYS,
+                &mut issues,
+            );
+            if let Some(smtp) = notifications.get("smtp") {
+                unknown_keys(content, smtp, "notifications.smtp", &SMTP_KEYS, &mut issues);
+            }
+        }
+        if let Some(recorder) = table.get("recorder") {
+            unknown_keys(content, recorder, "recorder", &RECORDER_KEYS, &mut issues);
+        }
+        if let Some(profiles) = table.get("profiles").and_then(|p| p.as_table()) {
+            for (name, profile) in profiles {
+                let path = format!("profiles.{}", name);
+                unknown_keys(content, profile, &path, &PROFILE_KEYS, &mut issues);
+            }
+        }
+        for (key, known) in [
+            ("folder-groups", FOLDER_GROUP_KEYS.as_slice()),
+            ("folder-commands", FOLDER_COMMAND_KEYS.as_slice()),
+        ] {
+            if let Some(entries) = table.get(key).and_then(|e| e.as_array()) {
+                for entry in entries {
+                    unknown_keys(content, entry, key, known, &mut issues);
+                }
+            }
+        }
+
+        let config: Self = match toml::from_str(content) {
+            Ok(config) => config,
+            Err(e) => {
+                let location = e.span().map(|span| location(content, span.start));
+                issues.push(ConfigIssue::new(location, e.message().trim()));
+                return (None, issues);
+            }
+        };
+
+        if config.api_key.trim().is_empty() {
+            issues.push(ConfigIssue::new(
+                key_location(content, "api-key"),
+                "'api-key' is empty",
+            ));
+        }
+        if config.stuck_after == 0 {
+            issues.push(ConfigIssue::new(
+                key_location(content, "stuck-after"),
+                "'stuck-after' must be greater than 0",
+            ));
+        }
+        if config.stale_after == 0 {
+            issues.push(ConfigIssue::new(
+                key_location(content, "stale-after"),
+                "'stale-after' must be greater than 0",
+            ));
+        }
+        for (i, column) in config.device_columns.iter().enumerate() {
+            if config.device_columns[..i].contains(column) {
+                issues.push(ConfigIssue::new(
+                    key_location(content, "device-columns"),
+                    format!("device column '{:?}' is listed more than once", column),
+                ));
+            }
+        }
+        for (i, folder_id) in config.auto_resume.iter().enumerate() {
+            if folder_id.trim().is_empty() {
+                issues.push(ConfigIssue::new(
+                    key_location(content, "auto-resume"),
+                    "'auto-resume' contains an empty folder ID",
+                ));
+            } else if config.auto_resume[..i].contains(folder_id) {
+                issues.push(ConfigIssue::new(
+                    key_location(content, "auto-resume"),
+                    format!("folder '{}' is listed more than once", folder_id),
+                ));
+            }
+        }
+        for (i, device_id) in config.auto_accept.iter().enumerate() {
+            if device_id.trim().is_empty() {
+                issues.push(ConfigIssue::new(
+                    key_location(content, "auto-accept"),
+                    "'auto-accept' contains an empty device ID",
+                ));
+            } else if config.auto_accept[..i]
+                .iter()
+                .any(|other| other.eq_ignore_ascii_case(device_id))
+            {
+                issues.push(ConfigIssue::new(
+                    key_location(content, "auto-accept"),
+                    format!("device '{}' is listed more than once", device_id),
+                ));
+            }
+        }
+
+        let mut keymaps = vec![config.keys.clone()];
+        for profile in config.profiles.values() {
+            let mut keys = config.keys.clone();
+            keys.extend(profile.keys.clone());
+            keymaps.push(keys);
+        }
+        for keys in keymaps {
+            let issue = match Keymap::new(&keys) {
+                Err(AppError::UnknownAction(action)) => ConfigIssue::new(
+                    key_location(content, &format!("keys.{}", action)),
+                    format!("there is no action called '{}'", action),
+                ),
+                Err(AppError::InvalidKey(key)) => ConfigIssue::new(
+                    text_location(content, &format!("\"{}\"", key)),
+                    format!("'{}' is not a valid key", key),
+                ),
+                _ => continue,
+            };
+            if !issues.iter().any(|i| i.message == issue.message) {
+                issues.push(issue);
+            }
+        }
+        if config.poll_interval == 0 {
+            issues.push(ConfigIssue::new(
+                key_location(content, "poll-interval"),
+                "'poll-interval' must be greater than 0",
+            ));
+        }
+        for (name, profile) in &config.profiles {
+            if profile.poll_interval == Some(0) {
+                issues.push(ConfigIssue::new(
+                    key_location(content, &format!("profiles.{}.poll-interval", name)),
+                    format!(
+                        "'poll-interval' of profile '{}' must be greater than 0",
+                        name
+                    ),
+                ));
+            }
+        }
+        for group in &config.folder_groups {
+            let location = text_location(content, &format!("\"{}\"", group.name));
+            if group.label_prefix.is_none()
+                && group.path_prefix.is_none()
+                && group.folders.is_empty()
+            {
+                issues.push(ConfigIssue::new(
+                    location,
+                    format!("folder group '{}' matches no folders", group.name),
+                ));
+            }
+            if let Some(color) = &group.color
+                && Color::from_str(color).is_err()
+            {
+                issues.push(ConfigIssue::new(
+                    text_location(content, &format!("\"{}\"", color)),
+                    format!("'{}' is not a color", color),
+                ));
+            }
+        }
+        for command in &config.folder_commands {
+            if command.command.trim().is_empty() {
+                issues.push(ConfigIssue::new(
+                    text_location(content, &format!("\"{}\"", command.name)),
+                    format!("folder command '{}' has no command", command.name),
+                ));
+            }
+        }
+        if let Some(address) = &config.address
+            && !(address.starts_with("http://") || address.starts_with("https://"))
+        {
+            issues.push(ConfigIssue::new(
+                key_location(content, "address"),
+                format!("address '{}' is not an http(s) URL", address),
+            ));
+        }
+        if let Some(webhook) = &config.notifications.webhook
+            && !(webhook.starts_with("http://") || webhook.starts_with("https://"))
+        {
+            issues.push(ConfigIssue::new(
+                key_location(content, "notifications.webhook"),
+                format!("webhook '{}' is not an http(s) URL", webhook),
+            ));
+        }
+        if config.recorder.max_size == 0 {
+            issues.push(ConfigIssue::new(
+                key_location(content, "recorder.max-size"),
+                "'max-size' must be greater than 0",
+            ));
+        }
+        if let Some(smtp) = &config.notifications.smtp {
+            for (key, address) in [("from", &smtp.from), ("to", &smtp.to)] {
+                if !address.contains('@') {
+                    issues.push(ConfigIssue::new(
+                        key_location(content, &format!("notifications.smtp.{}", key)),
+                        format!("'{}' is not an email address", address),
+                    ));
+                }
+            }
+        }
+
+        (Some(config), issues)
+    }
+
+    /// Whether notifications of `kind` should be sent
+    pub fn notifies(&self, kind: NotificationKind) -> bool {
+        let events = &self.notifications.events;
+        events.is_empty() || events.contains(&kind)
+    }
+
+    /// The config with the settings of the profile `name` applied, instead
+    /// of the ones of any other profile
+    pub fn with_profile(&self, name: &str) -> Result<Self, AppError> {
+        let mut config = self.without_profile();
+        let profile = config
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| AppError::UnknownProfile(name.to_string()))?;
+        let applied = AppliedProfile {
+            name: name.to_string(),
+            icons: config.icons,
+            keys: config.keys.clone(),
+            poll_interval: config.poll_interval,
+        };
+        config.icons = profile.icons.unwrap_or(config.icons);
+        config.keys.extend(profile.keys);
+        config.poll_interval = profile.poll_interval.unwrap_or(config.poll_interval);
+        config.applied_profile = Some(applied);
+        Ok(config)
+    }
+
+    /// The config with the settings replaced by a profile restored
+    pub fn without_profile(&self) -> Self {
+        let mut config = self.clone();
+        if let Some(applied) = config.applied_profile.take() {
+            config.icons = applied.icons;
+            config.keys = applied.keys;
+            config.poll_interval = applied.poll_interval;
+        }
+        config
+    }
+
+    /// Name and settings of the applied profile, if any
+    pub fn profile(&self) -> Option<(&str, &Profile)> {
+        let name = &self.applied_profile.as_ref()?.name;
+        self.profiles
+            .get(name)
+            .map(|profile| (name.as_str(), profile))
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval)
+    }
+
+    pub fn stuck_after(&self) -> Duration {
+        Duration::from_secs(self.stuck_after)
+    }
+
+    pub fn stale_after(&self) -> Duration {
+        Duration::from_secs(self.stale_after * 24 * 60 * 60)
     }
 }
@@ -16,18 +16,19 @@ pub enum AppError {
         source: std::io::Error,
     },
 
+    #[error("Failed to write configuration file to '{path}'")]
+    ConfigWriteError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
     #[error("Failed to parse TOML configuration from '{path}'")]
     ConfigParseError {
         path: PathBuf,
         #[source]
         source: toml::de::Error,
     },
-    #[error(transparent)]
-    APIError(#[from] reqwest::Error),
-    #[error("syncthing ID header not set")]
-    SyncthingIDError,
-    #[error(transparent)]
-    ToStrError(#[from] reqwest::header::ToStrError),
     #[error("folder ID already exists")]
     DuplicateFolderID,
     #[error(transparent)]
@@ -38,6 +39,71 @@ pub enum AppError {
     UnknownFolder,
     #[error("device not found")]
     UnknownDevice,
+    #[error("'{0}' cannot be passed safely to the shell")]
+    UnsafeCommandArgument(String),
+    #[error("'{0}' kept being changed by someone else while updating it")]
+    ConfigKeptChanging(String),
+    #[error("folder '{0}' not found")]
+    StartupFolderNotFound(String),
+    #[error("device '{0}' not found")]
+    StartupDeviceNotFound(String),
+    #[error("'{0}' needs confirmation, pass --yes when not running in a terminal")]
+    NotConfirmed(String),
+    #[error("declined, nothing was changed")]
+    Declined,
+    #[error("a path is required to add a new folder")]
+    MissingFolderPath,
+    #[error("'{0}' is neither a device ID nor a synctui://add-device link")]
+    InvalidDeviceLink(String),
+    #[error("'{0}' is not a valid key, e.g., 'q', 'Ctrl+f' or 'Enter'")]
+    InvalidKey(String),
+    #[error("there is no action called '{0}'")]
+    UnknownAction(String),
+    #[error("there is no profile called '{0}'")]
+    UnknownProfile(String),
     #[error("syncthing API error")]
     SyncthingError(#[from] syncthing_rs::error::Error),
+    #[error("Failed to read plan from '{path}'")]
+    PlanReadError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Failed to parse TOML plan from '{path}'")]
+    PlanParseError {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error(
+        "folder '{folder_id}' is shared with '{device_id}', which is neither planned nor configured"
+    )]
+    PlanUnknownDevice {
+        folder_id: String,
+        device_id: String,
+    },
+    #[error(
+        "folder '{folder_id}' is at '{configured}', not at '{planned}', and Syncthing does not move folders"
+    )]
+    PlanPathDiffers {
+        folder_id: String,
+        planned: String,
+        configured: String,
+    },
+    #[error("failed to read the CA certificate at {path}")]
+    CaCertReadError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("syncthing did not send a valid date")]
+    InvalidServerTime,
+    #[error("failed to record events to '{path}'")]
+    RecordError {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("syncthing REST API error")]
+    RestError(#[from] reqwest::Error),
 }
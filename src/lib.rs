@@ -1,8 +1,39 @@
+mod address_book;
+pub use address_book::{AddressBook, AddressBookEntry};
+
+mod completions;
+pub use completions::print_completions;
+
+mod clock;
+pub use clock::{Clock, MockClock, SharedClock, SystemClock};
+
+mod cli;
+pub use cli::{Command, ConfigCommand, run_command, validate_config};
+
 mod config;
-pub use config::AppConfig;
+pub use config::{
+    AppConfig, AppliedProfile, ConfigIssue, DeviceColumn, FolderCommand, FolderGroup, IconSet,
+    Profile,
+};
 
 mod error;
 pub use error::AppError;
 
+mod link;
+pub use link::parse_device_link;
+
+mod local;
+pub use local::LocalSyncthing;
+
+mod plan;
+
+mod rest;
+
+mod signal;
+pub use rest::{DiscoveredDevice, EventStreamItem, RestClient, ServiceStatus, SystemStatus};
+
 mod tui;
-pub use tui::start;
+pub use tui::{CurrentScreen, StartupView, set_log_file, setup, start};
+
+mod watch;
+pub use watch::watch;
@@ -0,0 +1,132 @@
+use crate::AppError;
+
+const LINK_PREFIX: &str = "synctui://add-device/";
+
+/// Parses either a plain device ID or a link of the form
+/// `synctui://add-device/<ID>?name=<name>` into the device ID and the
+/// optional name.
+pub fn parse_device_link(link: &str) -> Result<(String, Option<String>), AppError> {
+    let link = link.trim();
+    let (id, query) = match link.strip_prefix(LINK_PREFIX) {
+        Some(rest) => match rest.split_once('?') {
+            Some((id, query)) => (id, Some(query)),
+            None => (rest, None),
+        },
+        None => (link, None),
+    };
+
+    let id = id.trim_end_matches('/').to_uppercase();
+    if !is_device_id(&id) {
+        return Err(AppError::InvalidDeviceLink(link.to_string()));
+    }
+
+    let name = query.and_then(|query| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == "name")
+            .map(|(_, value)| percent_decode(value))
+    });
+
+    Ok((id, name))
+}
+
+/// Whether `id` looks like a Syncthing device ID, i.e., eight groups of
+/// seven base32 characters, separated by dashes
+fn is_device_id(id: &str) -> bool {
+    let groups: Vec<_> = id.split('-').collect();
+    groups.len() == 8
+        && groups.iter().all(|group| {
+            group.len() == 7
+                && group
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || ('2'..='7').contains(&c))
+        })
+}
+
+fn percent_decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut chars = value.bytes();
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = chars.by_ref().take(2).collect();
+                match std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(decoded) => bytes.push(decoded),
+                    None => {
+                        bytes.push(b'%');
+                        bytes.extend(hex);
+                    }
+                }
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD";
+
+    #[test]
+    fn device_ids_are_recognized() {
+        assert!(is_device_id(ID));
+        // Lowercase IDs are uppercased before
+        assert!(!is_device_id(&ID.to_lowercase()));
+        // 0, 1, 8 and 9 are not part of base32
+        assert!(!is_device_id(&ID.replace('3', "8")));
+        assert!(!is_device_id(&ID[..ID.len() - 8]));
+        assert!(!is_device_id(&ID.replace('-', "")));
+        assert!(!is_device_id(""));
+    }
+
+    #[test]
+    fn bare_id_has_no_name() {
+        assert_eq!(
+            parse_device_link(&format!("  {}\n", ID.to_lowercase())).unwrap(),
+            (ID.to_string(), None)
+        );
+    }
+
+    #[test]
+    fn link_name_is_decoded() {
+        let link = format!(
+            "synctui://add-device/{}?x=1&name=J%C3%BCrgen%27s+laptop",
+            ID
+        );
+        assert_eq!(
+            parse_device_link(&link).unwrap(),
+            (ID.to_string(), Some("Jürgen's laptop".to_string()))
+        );
+
+        let link = format!("synctui://add-device/{}/", ID);
+        assert_eq!(parse_device_link(&link).unwrap(), (ID.to_string(), None));
+    }
+
+    #[test]
+    fn broken_escapes_are_kept() {
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zzab"), "%zzab");
+    }
+
+    #[test]
+    fn invalid_links_are_rejected() {
+        for link in [
+            "synctui://add-device/",
+            "synctui://add-device/NOT-AN-ID?name=x",
+            "https://example.com/MFZWI3D",
+        ] {
+            assert!(matches!(
+                parse_device_link(link),
+                Err(AppError::InvalidDeviceLink(_))
+            ));
+        }
+    }
+}
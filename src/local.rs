@@ -0,0 +1,67 @@
+//! The Syncthing running on this machine, as far as its config.xml tells
+
+use std::path::{Path, PathBuf};
+
+/// Address and API key of a local Syncthing, read from its config.xml
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalSyncthing {
+    /// The config.xml they were read from
+    pub path: PathBuf,
+    /// Address of the GUI, if it listens on TCP
+    pub address: Option<String>,
+    pub api_key: String,
+}
+
+impl LocalSyncthing {
+    /// Looks for config.xml where Syncthing keeps it, starting with
+    /// `STHOMEDIR`
+    pub fn find() -> Option<Self> {
+        let home = std::env::var_os("STHOMEDIR").map(PathBuf::from);
+        let dirs = [
+            dirs::state_dir().map(|dir| dir.join("syncthing")),
+            dirs::config_dir().map(|dir| dir.join("syncthing")),
+            dirs::config_dir().map(|dir| dir.join("Syncthing")),
+            dirs::data_local_dir().map(|dir| dir.join("Syncthing")),
+        ];
+        home.into_iter()
+            .chain(dirs.into_iter().flatten())
+            .map(|dir| dir.join("config.xml"))
+            .find_map(|path| {
+                let content = std::fs::read_to_string(&path).ok()?;
+                Self::parse(&path, &content)
+            })
+    }
+
+    /// Reads the `gui` element of the config.xml at `path`. The address is
+    /// left out if the GUI listens on a Unix socket, and listening on all
+    /// interfaces is reached through localhost.
+    pub fn parse(path: &Path, content: &str) -> Option<Self> {
+        let start = content.find("<gui")?;
+        let gui = &content[start..];
+        let gui = &gui[..gui.find("</gui>")?];
+        let tls = gui[..gui.find('>')?].contains(r#"tls="true""#);
+
+        let api_key = element(gui, "apikey").filter(|key| !key.is_empty())?;
+        let address = element(gui, "address")
+            .filter(|address| !address.is_empty() && !address.contains('/'))
+            .map(|address| {
+                let address = address
+                    .replace("0.0.0.0:", "localhost:")
+                    .replace("[::]:", "localhost:");
+                format!("{}://{}", if tls { "https" } else { "http" }, address)
+            });
+        Some(Self {
+            path: path.to_path_buf(),
+            address,
+            api_key: api_key.to_string(),
+        })
+    }
+}
+
+/// Text of the first element named `name` in `xml`
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    Some(xml[start..end].trim())
+}
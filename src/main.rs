@@ -1,11 +1,14 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use color_eyre::eyre::{self, Context};
+use futures::StreamExt;
 use serde::Serialize;
-use syncthing_rs::Client;
-use synctui::{AppConfig, start};
-use tokio::{sync::broadcast, task};
+use synctui::{
+    AppConfig, AppError, Command, ConfigCommand, CurrentScreen, EventStreamItem, RestClient,
+    StartupView, parse_device_link, print_completions, run_command, set_log_file, setup, start,
+    validate_config, watch,
+};
 
 #[derive(clap::ValueEnum, Clone, Debug, Serialize, Default)]
 enum LevelFilter {
@@ -39,10 +42,26 @@ struct Args {
     #[arg(short, long)]
     api_key: Option<String>,
 
+    /// Address of Syncthing's GUI, e.g., https://nas:8384
+    #[arg(long)]
+    address: Option<String>,
+
+    /// Accept any TLS certificate, e.g., Syncthing's self-signed one
+    #[arg(long)]
+    insecure: bool,
+
     /// Run only as CLI, do not start TUI
     #[arg(long)]
     cli: bool,
 
+    /// Keep the recent versions of the state, to step through them with (H)
+    #[arg(long)]
+    debug_state: bool,
+
+    /// Run headless and send notifications as configured in the config file
+    #[arg(long, conflicts_with = "cli")]
+    watch: bool,
+
     /// Provide custom config path
     #[arg(short, long)]
     config: Option<String>,
@@ -54,6 +73,29 @@ struct Args {
     /// Set path of log file
     #[arg(long, requires = "log_level")]
     log_file: Option<PathBuf>,
+
+    /// Screen to open on startup
+    #[arg(long)]
+    screen: Option<CurrentScreen>,
+
+    /// Folder to select on startup
+    #[arg(long, conflicts_with = "device")]
+    folder: Option<String>,
+
+    /// Device to select on startup
+    #[arg(long)]
+    device: Option<String>,
+
+    /// Open the popup of the folder or device selected on startup
+    #[arg(long)]
+    open: bool,
+
+    /// Apply the profile with this name from the config file
+    #[arg(long)]
+    profile: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
 fn default_log_file_path() -> Option<PathBuf> {
@@ -64,26 +106,40 @@ fn default_log_file_path() -> Option<PathBuf> {
     })
 }
 
-fn setup_logging(path: PathBuf, level: log::LevelFilter) -> eyre::Result<()> {
-    if let Some(parent_dir) = path.parent() {
-        if !parent_dir.as_os_str().is_empty() {
-            std::fs::create_dir_all(parent_dir).wrap_err_with(|| {
-                format!(
-                    "Failed to create parent directory '{}' while preparing log file",
-                    parent_dir.display()
-                )
-            })?;
+/// Whether loading the config failed because there is no config file
+fn is_missing(error: &eyre::Report) -> bool {
+    match error.downcast_ref::<AppError>() {
+        Some(AppError::ConfigReadError { source, .. }) => {
+            source.kind() == std::io::ErrorKind::NotFound
         }
+        Some(AppError::DefaultConfigDirNotFound) => true,
+        _ => false,
+    }
+}
+
+fn setup_logging(path: PathBuf, level: log::LevelFilter) -> eyre::Result<()> {
+    if let Some(parent_dir) = path.parent()
+        && !parent_dir.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent_dir).wrap_err_with(|| {
+            format!(
+                "Failed to create parent directory '{}' while preparing log file",
+                parent_dir.display()
+            )
+        })?;
     }
 
     let target_file = std::fs::File::create(&path)
         .wrap_err_with(|| format!("Failed to create log file at '{}'", path.display()))?;
     let target = Box::new(target_file);
 
+    // Let everything through the logger itself, so that the level can be
+    // changed at runtime with `log::set_max_level`
     env_logger::Builder::new()
         .target(env_logger::Target::Pipe(target))
-        .filter(None, level)
+        .filter(None, log::LevelFilter::Trace)
         .init();
+    log::set_max_level(level);
 
     Ok(())
 }
@@ -98,37 +154,96 @@ async fn main() -> eyre::Result<()> {
             .or_else(default_log_file_path)
             .ok_or_else(|| eyre::eyre!("Failed to determine a log file path: No path specified via --log-file and could not determine a default path."))?;
 
+        set_log_file(path.clone());
         setup_logging(path, level.into())?;
     }
-    let api_key = {
-        match args.api_key {
-            Some(key) => key,
-            None => AppConfig::load(args.config)?.api_key,
+    if let Some(Command::Config { command }) = args.command {
+        let ConfigCommand::Validate { connect } = command;
+        return validate_config(args.config, connect).await;
+    }
+    if let Some(Command::Completions { shell }) = args.command {
+        print_completions(shell, &mut Args::command());
+        return Ok(());
+    }
+
+    // Without a config file, the TUI sets one up on the first run
+    let first_run_path = AppConfig::path(args.config.clone())
+        .ok()
+        .filter(|path| args.command.is_none() && !args.cli && !args.watch && !path.exists());
+    let config = match (AppConfig::load(args.config), args.api_key) {
+        (Ok(config), None) => config,
+        (Ok(config), Some(api_key)) => AppConfig {
+            api_key,
+            api_key_path: None,
+            ..config
+        },
+        // The config file is optional if the API key is passed directly, but
+        // one which exists has to be valid
+        (Err(e), Some(api_key)) if is_missing(&e) => {
+            log::info!("no config file, using defaults: {:?}", e);
+            AppConfig {
+                api_key,
+                ..Default::default()
+            }
+        }
+        (Err(e), Some(_)) => return Err(e),
+        (Err(e), None) => {
+            let Some(path) = first_run_path else {
+                return Err(e);
+            };
+            match setup(path, args.address.clone(), args.insecure).await? {
+                Some(config) => config,
+                None => return Ok(()),
+            }
         }
     };
 
-    let client = Client::builder(&api_key).build()?;
-
-    if args.cli {
+    let config = AppConfig {
+        address: args.address.or(config.address),
+        insecure: args.insecure || config.insecure,
+        debug_state: args.debug_state || config.debug_state,
+        ..config
+    };
+    let config = match &args.profile {
+        Some(profile) => config.with_profile(profile)?,
+        None => config,
+    };
+    let client = config.client()?;
+
+    if let Some(Command::AddDevice { link, name, .. }) = &args.command
+        && !args.cli
+    {
+        let (device_id, link_name) = parse_device_link(link)?;
+        let startup_view = StartupView {
+            new_device: Some((device_id, name.clone().or(link_name))),
+            ..Default::default()
+        };
+        start(client, config, startup_view).await?;
+    } else if let Some(command) = args.command {
+        run_command(client, &config, command).await?;
+    } else if args.watch {
+        watch(client, config).await?;
+    } else if args.cli {
         client.ping().await?;
         client.get_configuration().await?;
 
-        let (tx_event, mut rx_event) = broadcast::channel(1);
-
-        task::spawn(async move {
-            if let Err(error) = client.get_events(tx_event, false).await {
-                println!("Error: {error:?}");
-            }
-        });
-
-        task::spawn(async move {
-            while let Ok(event) = rx_event.recv().await {
+        let mut events = std::pin::pin!(RestClient::new(&config)?.events(false));
+        while let Some(item) = events.next().await {
+            if let EventStreamItem::Event(event) = item {
                 println!("{:#?}", event);
             }
-        })
-        .await?;
+        }
     } else {
-        start(client).await?;
+        let startup_view = StartupView {
+            screen: args
+                .screen
+                .or_else(|| config.profile().and_then(|(_, p)| p.screen.clone())),
+            folder: args.folder,
+            device: args.device,
+            open_popup: args.open,
+            new_device: None,
+        };
+        start(client, config, startup_view).await?;
     }
 
     Ok(())
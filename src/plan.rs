@@ -0,0 +1,394 @@
+use std::{fmt, fs::read_to_string, path::Path};
+
+use serde::Deserialize;
+use syncthing_rs::{
+    Client,
+    types::config::{
+        Configuration, DeviceConfiguration, FolderConfiguration, FolderDeviceConfiguration,
+        NewDeviceConfiguration, NewFolderConfiguration,
+    },
+};
+
+use crate::AppError;
+
+/// Devices and folders an instance should have, as declared for
+/// `synctui apply`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Plan {
+    #[serde(default)]
+    devices: Vec<PlannedDevice>,
+    #[serde(default)]
+    folders: Vec<PlannedFolder>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlannedDevice {
+    id: String,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlannedFolder {
+    id: String,
+    label: Option<String>,
+    path: String,
+    /// IDs of the devices the folder is shared with
+    #[serde(default)]
+    devices: Vec<String>,
+}
+
+/// A single step to reconcile an instance with a [`Plan`]
+#[derive(Debug)]
+pub enum Change {
+    AddDevice(NewDeviceConfiguration),
+    RenameDevice(DeviceConfiguration),
+    RemoveDevice(DeviceConfiguration),
+    AddFolder(NewFolderConfiguration),
+    /// Changes the label or the devices the folder is shared with
+    UpdateFolder(FolderConfiguration),
+    RemoveFolder(FolderConfiguration),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::AddDevice(device) => write!(
+                f,
+                "+ device {} ({})",
+                device.get_name().as_deref().unwrap_or_default(),
+                device.get_device_id()
+            ),
+            Change::RenameDevice(device) => {
+                write!(f, "~ device {} ({})", device.name, device.device_id)
+            }
+            Change::RemoveDevice(device) => {
+                write!(f, "- device {} ({})", device.name, device.device_id)
+            }
+            Change::AddFolder(folder) => write!(
+                f,
+                "+ folder {} ({}) at '{}'",
+                folder.get_label().as_deref().unwrap_or_default(),
+                folder.get_id(),
+                folder.get_path()
+            ),
+            Change::UpdateFolder(folder) => write!(
+                f,
+                "~ folder {} ({}), shared with {} devices",
+                folder.label,
+                folder.id,
+                folder.devices.len()
+            ),
+            Change::RemoveFolder(folder) => write!(f, "- folder {} ({})", folder.label, folder.id),
+        }
+    }
+}
+
+fn share(device_id: &str) -> FolderDeviceConfiguration {
+    FolderDeviceConfiguration {
+        device_id: device_id.to_string(),
+        introduced_by: String::new(),
+        encryption_password: String::new(),
+    }
+}
+
+impl Plan {
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let content = read_to_string(path).map_err(|source| AppError::PlanReadError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&content).map_err(|source| AppError::PlanParseError {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// The changes needed to turn `configuration` into this plan. Devices
+    /// and folders not in the plan are only removed if `prune` is set, and
+    /// `own_id` is never touched. Syncthing does not move folders, so an
+    /// existing folder planned at another path is an error.
+    pub fn diff(
+        &self,
+        configuration: &Configuration,
+        own_id: &str,
+        prune: bool,
+    ) -> Result<Vec<Change>, AppError> {
+        let mut changes = Vec::new();
+
+        for planned in &self.devices {
+            match configuration
+                .devices
+                .iter()
+                .find(|d| d.device_id == planned.id)
+            {
+                Some(existing) => {
+                    if let Some(name) = &planned.name
+                        && *name != existing.name
+                    {
+                        let mut device = existing.clone();
+                        device.name = name.clone();
+                        changes.push(Change::RenameDevice(device));
+                    }
+                }
+                None => {
+                    let mut device = NewDeviceConfiguration::new(planned.id.clone());
+                    if let Some(name) = &planned.name {
+                        device = device.name(name.clone());
+                    }
+                    changes.push(Change::AddDevice(device));
+                }
+            }
+        }
+
+        let is_known = |device_id: &str| {
+            device_id == own_id
+                || self.devices.iter().any(|d| d.id == device_id)
+                || (!prune
+                    && configuration
+                        .devices
+                        .iter()
+                        .any(|d| d.device_id == device_id))
+        };
+        for planned in &self.folders {
+            if let Some(device_id) = planned.devices.iter().find(|id| !is_known(id)) {
+                return Err(AppError::PlanUnknownDevice {
+                    folder_id: planned.id.clone(),
+                    device_id: device_id.clone(),
+                });
+            }
+
+            match configuration.folders.iter().find(|f| f.id == planned.id) {
+                Some(existing) if existing.path != planned.path => {
+                    return Err(AppError::PlanPathDiffers {
+                        folder_id: planned.id.clone(),
+                        planned: planned.path.clone(),
+                        configured: existing.path.clone(),
+                    });
+                }
+                Some(existing) => {
+                    let mut folder = existing.clone();
+                    if let Some(label) = &planned.label {
+                        folder.label = label.clone();
+                    }
+                    if prune {
+                        folder.devices.retain(|d| {
+                            d.device_id == own_id || planned.devices.contains(&d.device_id)
+                        });
+                    }
+                    for device_id in &planned.devices {
+                        if !folder.devices.iter().any(|d| d.device_id == *device_id) {
+                            folder.devices.push(share(device_id));
+                        }
+                    }
+                    if folder != *existing {
+                        changes.push(Change::UpdateFolder(folder));
+                    }
+                }
+                None => {
+                    let mut folder =
+                        NewFolderConfiguration::new(planned.id.clone(), planned.path.clone())
+                            .devices(planned.devices.iter().map(|id| share(id)).collect());
+                    if let Some(label) = &planned.label {
+                        folder = folder.label(label.clone());
+                    }
+                    changes.push(Change::AddFolder(folder));
+                }
+            }
+        }
+
+        if prune {
+            for folder in &configuration.folders {
+                if !self.folders.iter().any(|f| f.id == folder.id) {
+                    changes.push(Change::RemoveFolder(folder.clone()));
+                }
+            }
+            for device in &configuration.devices {
+                if device.device_id != own_id
+                    && !self.devices.iter().any(|d| d.id == device.device_id)
+                {
+                    changes.push(Change::RemoveDevice(device.clone()));
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+/// Applies `changes` in order. Devices are added before the folders which
+/// are shared with them.
+pub async fn apply(client: &Client, changes: Vec<Change>) -> Result<(), AppError> {
+    for change in changes {
+        log::info!("applying {}", change);
+        match change {
+            Change::AddDevice(device) => client.add_device(device).await?,
+            Change::RenameDevice(device) => client.post_device(device).await?,
+            Change::RemoveDevice(device) => client.delete_device(&device.device_id).await?,
+            Change::AddFolder(folder) => client.add_folder(folder).await?,
+            Change::UpdateFolder(folder) => client.post_folder(folder).await?,
+            Change::RemoveFolder(folder) => client.delete_folder(&folder.id).await?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAPTOP: &str = "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD";
+    const PHONE: &str = "P56IOI7-MZJNU2Y-IQGDREY-DM2MGTI-MGL3BXN-PQ6W5BM-TBBZ4TJ-XZWICQ2";
+    const TABLET: &str = "7777777-777777N-7777777-777777N-7777777-777777N-7777777-77777Q4";
+
+    /// The recorded configuration, with the documents shared with both
+    /// devices and the photos only with the laptop
+    fn configuration() -> Configuration {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/config.json");
+        serde_json::from_str(&read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn parse(content: &str) -> Plan {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn planned_device_and_folder_are_added() {
+        let plan = parse(&format!(
+            r#"
+[[devices]]
+id = "{TABLET}"
+name = "tablet"
+
+[[folders]]
+id = "music"
+path = "/home/user/Music"
+devices = ["{TABLET}"]
+"#
+        ));
+        let changes = plan.diff(&configuration(), LAPTOP, false).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(
+            &changes[0],
+            Change::AddDevice(device) if device.get_device_id() == TABLET
+        ));
+        assert!(matches!(
+            &changes[1],
+            Change::AddFolder(folder)
+                if folder.get_id() == "music" && folder.get_devices().as_ref().unwrap().len() == 1
+        ));
+    }
+
+    /// The configuration as it is, so nothing has to change
+    fn plan_unchanged() -> Plan {
+        parse(&format!(
+            r#"
+[[devices]]
+id = "{PHONE}"
+name = "phone"
+
+[[folders]]
+id = "abcd-1234"
+path = "/home/user/Documents"
+devices = ["{PHONE}"]
+"#
+        ))
+    }
+
+    #[test]
+    fn existing_device_is_renamed() {
+        let plan = parse(&format!(
+            "[[devices]]\nid = \"{PHONE}\"\nname = \"old phone\"\n"
+        ));
+        let changes = plan.diff(&configuration(), LAPTOP, false).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(
+            &changes[0],
+            Change::RenameDevice(device) if device.device_id == PHONE && device.name == "old phone"
+        ));
+
+        let plan = plan_unchanged();
+        assert!(
+            plan.diff(&configuration(), LAPTOP, false)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn pruning_keeps_this_device() {
+        let changes = plan_unchanged()
+            .diff(&configuration(), LAPTOP, true)
+            .unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(&changes[0], Change::RemoveFolder(folder) if folder.id == "efgh-5678"));
+
+        let changes = parse("").diff(&configuration(), LAPTOP, true).unwrap();
+        let removed_devices: Vec<_> = changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::RemoveDevice(device) => Some(device.device_id.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(removed_devices, [PHONE]);
+    }
+
+    #[test]
+    fn pruning_unshares_unplanned_devices_but_this_one() {
+        let plan = parse(&format!(
+            r#"
+[[devices]]
+id = "{PHONE}"
+
+[[folders]]
+id = "abcd-1234"
+path = "/home/user/Documents"
+
+[[folders]]
+id = "efgh-5678"
+path = "/mnt/backup/Photos"
+"#
+        ));
+        let changes = plan.diff(&configuration(), LAPTOP, true).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(
+            &changes[0],
+            Change::UpdateFolder(folder)
+                if folder.id == "abcd-1234"
+                    && folder.devices.iter().map(|d| d.device_id.as_str()).eq([LAPTOP])
+        ));
+    }
+
+    #[test]
+    fn sharing_with_an_unknown_device_is_an_error() {
+        let plan = parse(&format!(
+            "[[folders]]\nid = \"music\"\npath = \"/music\"\ndevices = [\"{TABLET}\"]\n"
+        ));
+        assert!(matches!(
+            plan.diff(&configuration(), LAPTOP, false),
+            Err(AppError::PlanUnknownDevice { device_id, .. }) if device_id == TABLET
+        ));
+
+        // Configured devices are only known as long as they are not pruned
+        let plan = parse(&format!(
+            "[[folders]]\nid = \"music\"\npath = \"/music\"\ndevices = [\"{PHONE}\"]\n"
+        ));
+        assert!(plan.diff(&configuration(), LAPTOP, false).is_ok());
+        assert!(matches!(
+            plan.diff(&configuration(), LAPTOP, true),
+            Err(AppError::PlanUnknownDevice { device_id, .. }) if device_id == PHONE
+        ));
+    }
+
+    #[test]
+    fn moving_a_folder_is_an_error() {
+        let plan = parse("[[folders]]\nid = \"abcd-1234\"\npath = \"/elsewhere\"\n");
+        assert!(matches!(
+            plan.diff(&configuration(), LAPTOP, false),
+            Err(AppError::PlanPathDiffers { planned, .. }) if planned == "/elsewhere"
+        ));
+    }
+}
@@ -0,0 +1,550 @@
+//! Endpoints of the Syncthing REST API which [`syncthing_rs::Client`] does
+//! not cover yet. Everything it does cover goes through it, this client only
+//! fills the gaps:
+//!
+//! - the raw configuration, including the options it does not model, and
+//!   changes to the GUI, the options, rate limits and ignored devices and
+//!   folders
+//! - whether a change requires a restart, and restarting
+//! - system status, discovery, the server's clock and random strings
+//! - rescanning a folder
+//! - events as a stream which reconnects and resumes after the last seen ID,
+//!   where syncthing-rs starts over
+//!
+//! Both clients are built from the same [`AppConfig`], so they share the
+//! address and TLS settings, and errors of both end up as [`AppError`].
+//! Endpoints should move to syncthing-rs once it has them.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::json;
+use syncthing_rs::types::events::Event;
+
+use crate::{AppConfig, AppError};
+
+/// How long to wait before polling events again after the first failure.
+/// Each further failure doubles the delay, up to [`EVENT_RETRY_MAX_DELAY`].
+const EVENT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between two attempts to poll events
+const EVENT_RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// How often a change of the configuration is started over, if it keeps
+/// being changed by someone else meanwhile
+const CONFIG_UPDATE_ATTEMPTS: usize = 3;
+
+/// Client for the endpoints of the Syncthing REST API, which are not
+/// covered by [`syncthing_rs::Client`] yet, see the module documentation
+#[derive(Clone, Debug)]
+pub struct RestClient {
+    client: reqwest::Client,
+    address: String,
+    /// Shared between all clones, so that a rotated key is used everywhere
+    api_key: Arc<RwLock<String>>,
+}
+
+/// Answer of `/rest/system/status`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemStatus {
+    #[serde(rename = "myID")]
+    pub my_id: String,
+    pub start_time: DateTime<Utc>,
+    /// Seconds since Syncthing started
+    pub uptime: u64,
+    /// Always 0 since Syncthing 1.23
+    #[serde(default)]
+    pub cpu_percent: f64,
+    /// Bytes of memory allocated
+    #[serde(default)]
+    pub alloc: u64,
+    /// Bytes of memory obtained from the system
+    #[serde(default)]
+    pub sys: u64,
+    #[serde(default)]
+    pub goroutines: u64,
+    /// The latest version of usage reporting, which is accepted by setting
+    /// `urAccepted` to it
+    #[serde(default)]
+    pub ur_version_max: u64,
+    /// Listeners, keyed by the address they listen on
+    #[serde(default)]
+    pub connection_service_status: HashMap<String, ServiceStatus>,
+    #[serde(default)]
+    pub discovery_enabled: bool,
+    /// Local and global discovery, keyed by method
+    #[serde(default)]
+    pub discovery_status: HashMap<String, ServiceStatus>,
+}
+
+/// Whether a listener or discovery method works, and where it is reachable
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceStatus {
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lan_addresses: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wan_addresses: Vec<String>,
+}
+
+/// Bandwidth limits in KiB/s, either of all connections or of a single
+/// device. 0 means unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RateLimits {
+    pub max_send_kbps: u64,
+    pub max_recv_kbps: u64,
+}
+
+/// A setting of Syncthing's GUI, which also serves the REST API
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GuiField {
+    /// Where the GUI listens, e.g., `127.0.0.1:8384`
+    Address,
+    User,
+    /// Hashed by Syncthing before it is stored
+    Password,
+    /// The key the REST API accepts, which stops the current one from
+    /// working right away
+    ApiKey,
+}
+
+impl GuiField {
+    /// Name of the field in the configuration of the GUI
+    pub fn key(self) -> &'static str {
+        match self {
+            GuiField::Address => "address",
+            GuiField::User => "user",
+            GuiField::Password => "password",
+            GuiField::ApiKey => "apiKey",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GuiField::Address => "GUI address",
+            GuiField::User => "GUI user",
+            GuiField::Password => "GUI password",
+            GuiField::ApiKey => "API key",
+        }
+    }
+}
+
+/// A device found through discovery, as cached by Syncthing
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DiscoveredDevice {
+    pub addresses: Vec<String>,
+}
+
+/// What the stream of [`RestClient::events`] yields
+#[derive(Clone, Debug)]
+pub enum EventStreamItem {
+    Event(Event),
+    /// Polling failed, and is retried after `retry_in`
+    Disconnected {
+        attempt: u32,
+        retry_in: Duration,
+    },
+    /// Polling works again after it failed. Events might have been missed
+    /// in between.
+    Reconnected,
+}
+
+/// State of the stream of [`RestClient::events`]
+struct EventPoller {
+    client: RestClient,
+    /// ID of the last event seen
+    last_id: Option<u64>,
+    /// Number of failed attempts in a row
+    failures: u32,
+    /// How long to wait before the next attempt
+    backoff: Option<Duration>,
+    buffered: VecDeque<EventStreamItem>,
+}
+
+impl EventPoller {
+    /// Waits longer before each further attempt, and reports the failure
+    fn fail(&mut self) {
+        self.failures += 1;
+        let retry_in = EVENT_RETRY_DELAY
+            .saturating_mul(2u32.saturating_pow(self.failures - 1))
+            .min(EVENT_RETRY_MAX_DELAY);
+        self.backoff = Some(retry_in);
+        self.buffered.push_back(EventStreamItem::Disconnected {
+            attempt: self.failures,
+            retry_in,
+        });
+    }
+}
+
+#[derive(Deserialize)]
+struct RandomString {
+    random: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestartRequired {
+    requires_restart: bool,
+}
+
+impl RestClient {
+    pub fn new(config: &AppConfig) -> Result<Self, AppError> {
+        Ok(Self {
+            client: config.http_client()?,
+            address: config.address().to_string(),
+            api_key: Arc::new(RwLock::new(config.api_key.clone())),
+        })
+    }
+
+    fn api_key(&self) -> String {
+        self.api_key.read().unwrap().clone()
+    }
+
+    /// Uses `api_key` for all further requests
+    pub fn set_api_key(&self, api_key: String) {
+        *self.api_key.write().unwrap() = api_key;
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, AppError> {
+        self.get_query(path, &[]).await
+    }
+
+    /// Like [`get`](Self::get), with the parameters in `query` encoded
+    async fn get_query<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, AppError> {
+        Ok(self
+            .client
+            .get(format!("{}{}", self.address, path))
+            .header("X-API-Key", self.api_key())
+            .query(query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    async fn post(&self, path: &str) -> Result<(), AppError> {
+        self.post_query(path, &[]).await
+    }
+
+    /// Like [`post`](Self::post), with the parameters in `query` encoded
+    async fn post_query(&self, path: &str, query: &[(&str, &str)]) -> Result<(), AppError> {
+        self.client
+            .post(format!("{}{}", self.address, path))
+            .header("X-API-Key", self.api_key())
+            .query(query)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn send<T: Serialize>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: &T,
+    ) -> Result<(), AppError> {
+        self.client
+            .request(method, format!("{}{}", self.address, path))
+            .header("X-API-Key", self.api_key())
+            .json(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn patch<T: Serialize>(&self, path: &str, body: &T) -> Result<(), AppError> {
+        self.send(reqwest::Method::PATCH, path, body).await
+    }
+
+    async fn put<T: Serialize>(&self, path: &str, body: &T) -> Result<(), AppError> {
+        self.send(reqwest::Method::PUT, path, body).await
+    }
+
+    /// Events with an ID greater than `since`. Blocks until there is at
+    /// least one, or Syncthing's timeout of a minute passes.
+    async fn get_events(&self, since: u64) -> Result<Vec<Event>, AppError> {
+        self.get_query("/rest/events", &[("since", &since.to_string())])
+            .await
+    }
+
+    /// ID of the most recent event, or 0 if there is none
+    async fn latest_event_id(&self) -> Result<u64, AppError> {
+        let events: Vec<Event> = self
+            .get_query("/rest/events", &[("limit", "1"), ("timeout", "0")])
+            .await?;
+        Ok(events.last().map_or(0, |event| event.id))
+    }
+
+    /// All events from now on, or from the oldest one Syncthing still has if
+    /// `skip_old` is false. Reconnects after failures with an exponential
+    /// backoff, continuing after the last event seen, and starts over if
+    /// Syncthing restarted meanwhile.
+    pub fn events(&self, skip_old: bool) -> impl Stream<Item = EventStreamItem> + Send + 'static {
+        let poller = EventPoller {
+            client: self.clone(),
+            last_id: if skip_old { None } else { Some(0) },
+            failures: 0,
+            backoff: None,
+            buffered: VecDeque::new(),
+        };
+        futures::stream::unfold(poller, |mut poller| async move {
+            loop {
+                if let Some(item) = poller.buffered.pop_front() {
+                    return Some((item, poller));
+                }
+                if let Some(delay) = poller.backoff.take() {
+                    tokio::time::sleep(delay).await;
+                }
+
+                // Event IDs start over when Syncthing restarts
+                if poller.last_id.is_none() || poller.failures > 0 {
+                    match poller.client.latest_event_id().await {
+                        Ok(latest) => {
+                            poller.last_id = match poller.last_id {
+                                Some(id) if id <= latest => Some(id),
+                                Some(_) => Some(0),
+                                None => Some(latest),
+                            };
+                            if poller.failures > 0 {
+                                log::info!("reconnected after {} failures", poller.failures);
+                                poller.failures = 0;
+                                poller.buffered.push_back(EventStreamItem::Reconnected);
+                            }
+                        }
+                        Err(e) => {
+                            log::warn!("failed to get the latest event: {:?}", e);
+                            poller.fail();
+                        }
+                    }
+                    continue;
+                }
+
+                let since = poller.last_id.unwrap_or_default();
+                match poller.client.get_events(since).await {
+                    Ok(events) => {
+                        for event in events.into_iter().filter(|event| event.id > since) {
+                            poller.last_id = Some(event.id);
+                            poller.buffered.push_back(EventStreamItem::Event(event));
+                        }
+                    }
+                    Err(e) => {
+                        log::warn!("failed to get events since {}: {:?}", since, e);
+                        poller.fail();
+                    }
+                }
+            }
+        })
+    }
+
+    /// Whether a saved configuration change only takes effect after a restart
+    pub async fn requires_restart(&self) -> Result<bool, AppError> {
+        self.get::<RestartRequired>("/rest/config/restart-required")
+            .await
+            .map(|r| r.requires_restart)
+    }
+
+    /// The whole configuration, including the options not modeled by
+    /// [`syncthing_rs::types::config::Configuration`]
+    pub async fn get_config(&self) -> Result<serde_json::Value, AppError> {
+        self.get("/rest/config").await
+    }
+
+    /// Reads the document at `path`, lets `change` edit it and writes it
+    /// back, unless `change` returns false. Syncthing offers no revisions to
+    /// make this atomic, the `version` of its configuration being that of the
+    /// schema. So the document is read again right before writing, and the
+    /// change starts over if it differs, e.g., after an edit in the web GUI.
+    async fn update_json(
+        &self,
+        path: &str,
+        mut change: impl FnMut(&mut serde_json::Value) -> bool,
+    ) -> Result<(), AppError> {
+        for _ in 0..CONFIG_UPDATE_ATTEMPTS {
+            let read: serde_json::Value = self.get(path).await?;
+            let mut changed = read.clone();
+            if !change(&mut changed) {
+                return Ok(());
+            }
+            let current: serde_json::Value = self.get(path).await?;
+            if current == read {
+                return self.put(path, &changed).await;
+            }
+            log::debug!("{} changed while updating it, starting over", path);
+        }
+        Err(AppError::ConfigKeptChanging(path.to_string()))
+    }
+
+    /// Adds the device to the ignored devices, whose connection attempts
+    /// are not brought up as pending anymore. Syncthing has no endpoint for
+    /// the ignored devices alone, so the whole configuration is written back,
+    /// as its web GUI does. It is kept as JSON, as the types of syncthing-rs
+    /// would drop what they do not model.
+    pub async fn ignore_device(&self, device_id: &str, name: &str) -> Result<(), AppError> {
+        let entry = json!({
+            "deviceID": device_id,
+            "name": name,
+            "address": "",
+            "time": Utc::now(),
+        });
+        self.update_json("/rest/config", |config| {
+            let Some(config) = config.as_object_mut() else {
+                return false;
+            };
+            let ignored = config
+                .entry("remoteIgnoredDevices")
+                .or_insert_with(|| json!([]));
+            if !ignored.is_array() {
+                *ignored = json!([]);
+            }
+            let Some(ignored) = ignored.as_array_mut() else {
+                return false;
+            };
+            if ignored.iter().any(|ignored| {
+                ignored.get("deviceID").and_then(|id| id.as_str()) == Some(device_id)
+            }) {
+                return false;
+            }
+            ignored.push(entry.clone());
+            true
+        })
+        .await
+    }
+
+    /// Adds the folders, given as ID and label, to the ignored folders of the
+    /// device, whose offers to share them are not brought up as pending
+    /// anymore
+    pub async fn ignore_folders(
+        &self,
+        device_id: &str,
+        folders: &[(String, String)],
+    ) -> Result<(), AppError> {
+        let now = Utc::now();
+        self.update_json(&format!("/rest/config/devices/{}", device_id), |device| {
+            let Some(device) = device.as_object_mut() else {
+                return false;
+            };
+            let ignored = device.entry("ignoredFolders").or_insert_with(|| json!([]));
+            if !ignored.is_array() {
+                *ignored = json!([]);
+            }
+            let Some(ignored) = ignored.as_array_mut() else {
+                return false;
+            };
+            let mut changed = false;
+            for (folder_id, label) in folders {
+                if ignored.iter().any(|ignored| {
+                    ignored.get("id").and_then(|id| id.as_str()) == Some(folder_id.as_str())
+                }) {
+                    continue;
+                }
+                ignored.push(json!({
+                    "id": folder_id,
+                    "label": label,
+                    "time": now,
+                }));
+                changed = true;
+            }
+            changed
+        })
+        .await
+    }
+
+    /// The current time of the machine Syncthing runs on, to the second, as
+    /// sent in the `Date` header
+    pub async fn server_time(&self) -> Result<DateTime<Utc>, AppError> {
+        let response = self
+            .client
+            .get(format!("{}/rest/system/ping", self.address))
+            .header("X-API-Key", self.api_key())
+            .send()
+            .await?
+            .error_for_status()?;
+        response
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+            .map(|date| date.with_timezone(&Utc))
+            .ok_or(AppError::InvalidServerTime)
+    }
+
+    /// Uptime, memory usage, listeners and discovery of Syncthing
+    pub async fn system_status(&self) -> Result<SystemStatus, AppError> {
+        self.get("/rest/system/status").await
+    }
+
+    /// The addresses of all devices found through discovery, keyed by
+    /// device ID
+    pub async fn discovery(&self) -> Result<HashMap<String, DiscoveredDevice>, AppError> {
+        self.get("/rest/system/discovery").await
+    }
+
+    /// Changes a single setting of the GUI. Syncthing hashes passwords, so
+    /// only the hash is stored. After changing the API key,
+    /// [`set_api_key`](Self::set_api_key) has to follow.
+    pub async fn set_gui(&self, field: GuiField, value: &str) -> Result<(), AppError> {
+        let mut gui = serde_json::Map::new();
+        gui.insert(field.key().to_string(), json!(value));
+        self.patch("/rest/config/gui", &gui).await
+    }
+
+    /// Replaces the global options, which have to be complete
+    pub async fn put_options(&self, options: &serde_json::Value) -> Result<(), AppError> {
+        self.put("/rest/config/options", options).await
+    }
+
+    /// Limits the bandwidth of the connection to the device, on top of the
+    /// limits of all connections, or of all connections without a device
+    pub async fn set_rate_limits(
+        &self,
+        device_id: Option<&str>,
+        limits: RateLimits,
+    ) -> Result<(), AppError> {
+        match device_id {
+            Some(device_id) => {
+                self.patch(&format!("/rest/config/devices/{}", device_id), &limits)
+                    .await
+            }
+            None => self.patch("/rest/config/options", &limits).await,
+        }
+    }
+
+    /// A random string of `length` alphanumeric characters, generated by
+    /// Syncthing the same way as its own API keys
+    pub async fn random_string(&self, length: usize) -> Result<String, AppError> {
+        let random: RandomString = self
+            .get_query(
+                "/rest/svc/random/string",
+                &[("length", &length.to_string())],
+            )
+            .await?;
+        Ok(random.random)
+    }
+
+    /// Asks Syncthing to scan the folder right away
+    pub async fn rescan(&self, folder_id: &str) -> Result<(), AppError> {
+        self.post_query("/rest/db/scan", &[("folder", folder_id)])
+            .await
+    }
+
+    /// Restarts Syncthing
+    pub async fn restart(&self) -> Result<(), AppError> {
+        self.post("/rest/system/restart").await
+    }
+}
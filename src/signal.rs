@@ -0,0 +1,33 @@
+//! Signals asking synctui to stop, e.g., from systemd or a closed SSH session
+
+/// Waits until the process is asked to stop and returns the name of the
+/// signal. Waits forever if the signals cannot be listened to.
+#[cfg(unix)]
+pub async fn shutdown() -> &'static str {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    match (
+        signal(SignalKind::terminate()),
+        signal(SignalKind::interrupt()),
+        signal(SignalKind::hangup()),
+    ) {
+        (Ok(mut terminate), Ok(mut interrupt), Ok(mut hangup)) => tokio::select! {
+            _ = terminate.recv() => "SIGTERM",
+            _ = interrupt.recv() => "SIGINT",
+            _ = hangup.recv() => "SIGHUP",
+        },
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+            log::warn!("failed to listen to signals: {:?}", e);
+            std::future::pending().await
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn shutdown() -> &'static str {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        log::warn!("failed to listen to Ctrl+C: {:?}", e);
+        return std::future::pending().await;
+    }
+    "Ctrl+C"
+}
@@ -1,6 +1,11 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
 
 use log::{debug, warn};
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use syncthing_rs::{
     Client,
@@ -11,27 +16,84 @@ use syncthing_rs::{
 };
 use tokio::sync::{broadcast, mpsc};
 
-use crate::{AppError, tui::state::State};
+use crate::{
+    AppConfig, AppError,
+    config::Preload,
+    rest::{GuiField, RestClient},
+    tui::{StartupView, state::State},
+};
 
 use super::{
+    clipboard,
+    controllers::{
+        ActivityController, DeviceItem, DevicesAction, DevicesController, FolderItem,
+        FoldersAction, FoldersController, HealthController, SettingsAction, SettingsController,
+    },
+    crash, disk_watch,
+    hits::{HitMap, HitTarget},
     input::Message,
-    pages::PendingPageState,
+    keymap::Keymap,
+    notes::NoteTarget,
+    pages::{
+        ConfigTreeState, DevicesPage, FolderRow, FoldersPage, GUI_FIELDS, MeshPageState,
+        PendingPageState, SETTINGS, UNGROUPED, activity_rows, config_rows, folder_rows,
+        saved_settings,
+    },
     popup::{
-        DevicePopup, FolderPopup, NewFolderPopup, PendingDevicePopup, PendingShareFolderPopup,
-        Popup,
+        AddressBookPopup, CommandsPopup, ConfirmAction, ConfirmPopup, DevicePopup, DiagnosePopup,
+        ErrorsPopup, FilterPopup, FolderPopup, FolderWizardPopup, GuiFieldPopup, HelpPopup,
+        LogLevelPopup, NewDevicePopup, NewFolderPopup, NotePopup, OutputPopup, PendingDevicePopup,
+        PendingShareFolderPopup, Popup, ProfilesPopup, RateLimitsPopup, RecentPopup, SearchPopup,
+        SessionPopup, StateHistoryPopup,
     },
-    state::Reload,
+    recorder,
+    search::SearchTarget,
+    snapshot::Snapshot,
+    state::{Folder, InnerState, Reload, Section, StateChange},
+    ui_state::UiState,
 };
 
-#[derive(Default, Debug, strum::EnumIter, PartialEq)]
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    strum::EnumIter,
+    strum::Display,
+    clap::ValueEnum,
+    PartialEq,
+    Deserialize,
+    Serialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum CurrentScreen {
     #[default]
     Folders,
     Devices,
     Pending,
     ID,
+    Health,
+    Config,
+    /// Folders and devices side by side
+    Mesh,
+    /// Recent changes and connections
+    Activity,
+    /// Uptime, listeners and discovery of Syncthing
+    System,
+    /// Global options of Syncthing, e.g., discovery and relays
+    Settings,
+}
+
+impl CurrentScreen {
+    /// Whether the screen is only shown in advanced mode
+    pub fn is_advanced(&self) -> bool {
+        *self == CurrentScreen::Config
+    }
 }
 
+/// Number of recently viewed folders and devices which are remembered
+const RECENT_ITEMS: usize = 20;
+
 /// VIM modes
 #[derive(Debug, Clone, PartialEq)]
 pub enum CurrentMode {
@@ -52,6 +114,8 @@ impl TryFrom<u32> for CurrentScreen {
     type Error = ();
 
     fn try_from(v: u32) -> Result<Self, Self::Error> {
+        // 0 comes after 9 on the keyboard
+        let v = if v == 0 { 10 } else { v };
         if let Some((_, screen)) = CurrentScreen::iter()
             .enumerate()
             .find(|(i, _)| i + 1 == (v as usize))
@@ -67,39 +131,134 @@ impl TryFrom<u32> for CurrentScreen {
 #[derive(Debug)]
 pub struct App {
     rerender_tx: mpsc::Sender<Message>,
+    pub config: AppConfig,
     pub running: bool,
     pub current_screen: CurrentScreen,
     pub state: State,
-    pub selected_folder: Option<usize>,
-    pub selected_device: Option<usize>,
+    pub folders: FoldersController,
+    pub devices: DevicesController,
+    pub health: HealthController,
+    pub activity: ActivityController,
+    pub settings: SettingsController,
+    /// Whether IDs are shown in full instead of truncated
+    pub reveal_ids: bool,
+    /// Whether the lists show IDs instead of labels and names
+    pub list_ids: bool,
+    /// Whether rarely used options, destructive actions and the raw
+    /// configuration are shown
+    pub advanced: bool,
     pub pending_state: PendingPageState,
+    pub mesh_state: MeshPageState,
+    pub config_tree: ConfigTreeState,
     pub mode: Arc<Mutex<CurrentMode>>,
     pub popup: Option<Box<dyn Popup>>,
+    pub ui_state: UiState,
+    /// Replaced when switching profiles
+    pub keymap: Arc<Mutex<Keymap>>,
+    /// What can be clicked, as of the last frame
+    pub hits: RefCell<HitMap>,
+    /// Folders and devices at the end of the last session, until the
+    /// changes since then have been shown
+    last_session: Option<Snapshot>,
+    /// Recently viewed folders and devices, most recent first
+    recent: VecDeque<SearchTarget>,
+    /// Messages reopening pending popups which were closed without a
+    /// decision, most recent last
+    snoozed: Vec<Message>,
+    /// Item to select as soon as it has been loaded
+    startup_view: Option<StartupView>,
 }
 
 impl App {
-    pub fn new(client: Client, rerender_tx: mpsc::Sender<Message>) -> Self {
-        let app = App {
+    pub fn new(
+        client: Client,
+        rest: RestClient,
+        config: AppConfig,
+        keymap: Keymap,
+        startup_view: StartupView,
+        rerender_tx: mpsc::Sender<Message>,
+    ) -> Self {
+        let current_screen = match &startup_view {
+            StartupView {
+                screen: Some(screen),
+                ..
+            } => screen.clone(),
+            StartupView {
+                device: Some(_), ..
+            }
+            | StartupView {
+                new_device: Some(_),
+                ..
+            } => CurrentScreen::Devices,
+            _ => CurrentScreen::default(),
+        };
+        let advanced = config.advanced;
+        let current_screen = if current_screen.is_advanced() && !advanced {
+            CurrentScreen::default()
+        } else {
+            current_screen
+        };
+        let new_device = startup_view.new_device.clone();
+        let state = State::new(client.clone(), rest, &config.preload);
+        state.set_poll_interval(config.poll_interval());
+        let mut app = App {
             rerender_tx,
+            config,
             running: true,
-            current_screen: CurrentScreen::default(),
-            state: State::new(client.clone()),
-            selected_folder: None,
-            selected_device: None,
+            current_screen,
+            state,
+            folders: FoldersController::default(),
+            devices: DevicesController::default(),
+            health: HealthController::default(),
+            activity: ActivityController::default(),
+            settings: SettingsController::default(),
+            reveal_ids: false,
+            list_ids: false,
+            advanced,
             pending_state: PendingPageState::default(),
+            mesh_state: MeshPageState::default(),
+            config_tree: ConfigTreeState::default(),
             mode: Arc::new(Mutex::new(CurrentMode::Normal)),
             popup: None,
+            ui_state: UiState::load(),
+            keymap: Arc::new(Mutex::new(keymap)),
+            hits: RefCell::default(),
+            last_session: Snapshot::load(),
+            recent: VecDeque::new(),
+            snoozed: Vec::new(),
+            startup_view: Some(startup_view),
         };
 
-        // React to events
+        // React to events, including the ones received while we were starting
         let rerender_tx = app.rerender_tx.clone();
-        let event_rx = app.state.subscribe_to_events();
-        tokio::spawn(async move { Self::handle_event(event_rx, rerender_tx).await });
+        let (missed_events, event_rx) = app.state.subscribe_with_replay(usize::MAX);
+        tokio::spawn(async move { Self::handle_event(missed_events, event_rx, rerender_tx).await });
 
         // Start listen to changes to the config and rerender based on them
         let rerender_tx = app.rerender_tx.clone();
-        let config_rx = app.state.subscribe_to_config();
-        tokio::spawn(async move { Self::handle_rerender(config_rx, rerender_tx).await });
+        let change_rx = app.state.subscribe_to_changes();
+        tokio::spawn(async move { Self::handle_rerender(change_rx, rerender_tx).await });
+
+        app.state
+            .auto_resume(app.config.auto_resume.clone(), app.config.is_local());
+        app.state.auto_accept(app.config.auto_accept.clone());
+        if app.config.watch_local {
+            disk_watch::watch(app.state.clone());
+        }
+        if app.config.debug_state {
+            app.state.keep_history();
+        }
+        if app.config.recorder.enabled {
+            recorder::start(&app.state, &app.config.recorder);
+        }
+
+        if let Some((device_id, name)) = new_device {
+            app.popup = Some(Box::new(NewDevicePopup::new(
+                device_id,
+                name.unwrap_or_default(),
+                app.mode.clone(),
+            )));
+        }
 
         // TODO maybe reload state here again, as the state might already have fully
         // been fully initialized while we were setting up the listeners
@@ -107,31 +266,39 @@ impl App {
         app
     }
 
-    /// Runs in the background and reacts to Syncthing events.
+    /// Runs in the background and reacts to Syncthing events, starting with
+    /// the `missed` ones.
     async fn handle_event(
+        missed: Vec<Event>,
         mut event_rx: broadcast::Receiver<Event>,
         rerender_tx: mpsc::Sender<Message>,
     ) {
-        while let Ok(event) = event_rx.recv().await {
+        let mut missed = missed.into_iter();
+        loop {
+            let event = match missed.next() {
+                Some(event) => event,
+                None => match event_rx.recv().await {
+                    Ok(event) => event,
+                    Err(_) => break,
+                },
+            };
             debug!("Received event: {:?}", event);
             match event.ty {
                 EventType::PendingDevicesChanged {
                     ref added,
                     ref removed,
                 } => {
-                    if let Some(added) = added {
-                        if let Some(first) = added.first() {
-                            if let Err(e) = rerender_tx
-                                .send(Message::NewPendingDevice(first.device_id.clone()))
-                                .await
-                            {
-                                warn!(
-                                    "failed to send rerender message with new popup about new pending device: {:?}",
-                                    e
-                                );
-                                // Don't set an error, as this is not really mission critical
-                            }
-                        }
+                    if let Some(added) = added
+                        && let Some(first) = added.first()
+                        && let Err(e) = rerender_tx
+                            .send(Message::NewPendingDevice(first.device_id.clone()))
+                            .await
+                    {
+                        warn!(
+                            "failed to send rerender message with new popup about new pending device: {:?}",
+                            e
+                        );
+                        // Don't set an error, as this is not really mission critical
                     }
                     if let Some(_removed) = removed {
                         // TODO close popup if we have one with a removed device opened
@@ -141,22 +308,20 @@ impl App {
                     ref added,
                     ref removed,
                 } => {
-                    if let Some(added) = added {
-                        if let Some(first) = added.first() {
-                            if let Err(e) = rerender_tx
-                                .send(Message::NewPendingFolder {
-                                    folder_label: first.folder_label.clone(),
-                                    folder_id: first.folder_id.clone(),
-                                    device_id: first.device_id.clone(),
-                                })
-                                .await
-                            {
-                                warn!(
-                                    "failed to send rerender message with new popup about new pending folder-share: {:?}",
-                                    e
-                                );
-                            }
-                        }
+                    if let Some(added) = added
+                        && let Some(first) = added.first()
+                        && let Err(e) = rerender_tx
+                            .send(Message::NewPendingFolder {
+                                folder_label: first.folder_label.clone(),
+                                folder_id: first.folder_id.clone(),
+                                device_id: first.device_id.clone(),
+                            })
+                            .await
+                    {
+                        warn!(
+                            "failed to send rerender message with new popup about new pending folder-share: {:?}",
+                            e
+                        );
                     }
                     if let Some(_removed) = removed {
                         // TODO close popup if we have one with a removed folder opened
@@ -167,103 +332,280 @@ impl App {
         }
     }
 
-    /// Listens to config changes and just initiates a rerender of the UI
+    /// Listens to state changes and just initiates a rerender of the UI
     async fn handle_rerender(
-        mut reload_rx: broadcast::Receiver<()>,
+        mut change_rx: broadcast::Receiver<StateChange>,
         rerender_tx: mpsc::Sender<Message>,
     ) {
-        while reload_rx.recv().await.is_ok() {
+        loop {
+            match change_rx.recv().await {
+                Ok(change) => debug!("rerendering due to {:?}", change),
+                // We rerender everything anyway, so missed changes don't matter
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => {
+                    unreachable!("the change sender should never have been dropped")
+                }
+            }
             rerender_tx.send(Message::None).await.unwrap();
         }
-        unreachable!("the config sender should never have been dropped")
     }
 
     fn update_folders(&mut self, msg: Message) -> Option<Message> {
-        match msg {
-            Message::Down => {
-                let len = self.state.read(|state| state.get_folders().len());
-                if len == 0 {
-                    return None;
-                }
-                if let Some(highlighted_folder) = self.selected_folder {
-                    self.selected_folder = Some((highlighted_folder + 1) % len)
-                } else {
-                    self.selected_folder = Some(0);
+        let rows: Vec<FolderItem> = self.state.read(|state| {
+            folder_rows(state, &self.config, &self.ui_state)
+                .iter()
+                .map(|row| match row.folder() {
+                    Some(folder) => FolderItem::Folder(folder.config.id.clone()),
+                    None => FolderItem::Group(row.group_name().unwrap_or_default().to_string()),
+                })
+                .collect()
+        });
+        let detail_len = if self.folders.detail.focused {
+            self.with_selected_folder(|state, folder| {
+                FoldersPage::detail(state, folder, self.reveal_ids, self.config.icons).len()
+            })
+            .unwrap_or(0)
+        } else {
+            0
+        };
+        let action = self.folders.update(&msg, &rows, detail_len)?;
+        let folder = |folder_id: &str| {
+            self.state
+                .read(|state| state.get_folder(folder_id).map(|f| f.config.clone()).ok())
+        };
+        match action {
+            FoldersAction::AcceptOffers(folder_id) => {
+                let offers: Vec<String> = self.state.read(|state| {
+                    state
+                        .get_pending_offers(&folder_id)
+                        .into_iter()
+                        .cloned()
+                        .collect()
+                });
+                if !offers.is_empty() {
+                    self.state.share_folder_with_all(&folder_id, &offers);
                 }
             }
-            Message::Up => {
-                let len = self.state.read(|state| state.get_folders().len());
-                if len == 0 {
-                    return None;
+            FoldersAction::TogglePause(folder_id) => {
+                if let Some(mut folder) = folder(&folder_id) {
+                    folder.paused = !folder.paused;
+                    self.state.hint(format!(
+                        "{} {}",
+                        if folder.paused { "Pausing" } else { "Resuming" },
+                        folder.label
+                    ));
+                    self.state.edit_folder(folder);
                 }
-
-                if let Some(highlighted_folder) = self.selected_folder {
-                    self.selected_folder = Some((highlighted_folder + len - 1) % len)
-                } else {
-                    self.selected_folder = Some(len - 1);
+            }
+            FoldersAction::ToggleFavorite(folder_id) => {
+                self.ui_state.toggle_favorite(&folder_id);
+                self.ui_state.save();
+                // The folder moved, so keep it selected
+                self.folders.list.selected = self.folder_row_index(&folder_id);
+            }
+            FoldersAction::Commands(folder_id) => {
+                if let Some(folder) = folder(&folder_id) {
+                    let commands: Vec<_> = self
+                        .config
+                        .folder_commands
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, command)| command.applies_to(&folder))
+                        .map(|(i, command)| (i, command.name.clone()))
+                        .collect();
+                    if commands.is_empty() {
+                        self.state.hint("No commands configured for this folder");
+                    } else {
+                        self.popup = Some(Box::new(CommandsPopup::new(
+                            folder.id,
+                            folder.label,
+                            commands,
+                        )));
+                    }
                 }
             }
-            Message::Add => {
-                self.popup = Some(Box::new(NewFolderPopup::new(
-                    self.mode.clone(),
-                    self.state.clone(),
-                )));
+            FoldersAction::Diagnose(folder_id) => {
+                self.popup = Some(Box::new(DiagnosePopup::new(folder_id, self.config.icons)));
             }
-            Message::Select => {
-                if let Some(highlighted_folder) = self.selected_folder {
-                    self.state.read(|state| {
-                        if let Some(folder) = state.get_folders().get(highlighted_folder) {
-                            self.popup = Some(Box::new(FolderPopup::new(
-                                folder.config.clone(),
-                                self.mode.clone(),
-                            )))
-                        }
-                    })
+            FoldersAction::Rescan(folder_id) => self.state.rescan_folder(folder_id),
+            FoldersAction::Add => {
+                self.popup = Some(self.new_folder_popup(None));
+            }
+            FoldersAction::Open(folder_id) => {
+                if let Some(folder) = folder(&folder_id) {
+                    self.popup = Some(Box::new(FolderPopup::new(
+                        folder,
+                        self.mode.clone(),
+                        self.config.icons,
+                        self.advanced,
+                    )));
                 }
             }
-            _ => {}
-        };
+            FoldersAction::ToggleGroup(group) => {
+                self.ui_state.toggle_group(&group);
+                self.ui_state.save();
+            }
+        }
         None
     }
 
     fn update_devices(&mut self, msg: Message) -> Option<Message> {
-        let len = self.state.read(|state| state.get_other_devices().len());
-        match msg {
-            Message::Down => {
-                if len == 0 {
-                    return None;
-                }
+        let devices: Vec<DeviceItem> = self.state.read(|state| {
+            state
+                .get_listed_devices()
+                .iter()
+                .map(|device| DeviceItem {
+                    device_id: device.config.device_id.clone(),
+                    paused: device.config.paused,
+                    local: device.config.device_id == state.id,
+                })
+                .collect()
+        });
+        let detail_len = if self.devices.detail.focused {
+            self.state.read(|state| {
+                self.devices
+                    .list
+                    .selected
+                    .and_then(|i| state.get_listed_devices().get(i).copied())
+                    .map_or(0, |device| {
+                        DevicesPage::detail(state, device, self.reveal_ids, self.config.icons).len()
+                    })
+            })
+        } else {
+            0
+        };
+        let action = self.devices.update(&msg, &devices, detail_len)?;
+        self.handle_devices_action(action);
+        None
+    }
 
-                if let Some(highlighted_device) = self.selected_device {
-                    self.selected_device = Some((highlighted_device + 1) % len)
+    /// Executes what the devices or the health screen asked for
+    fn handle_devices_action(&mut self, action: DevicesAction) {
+        match action {
+            DevicesAction::AddressBook => {
+                let entries: Vec<_> = self.state.read(|state| {
+                    state
+                        .address_book
+                        .entries()
+                        .iter()
+                        .filter(|entry| state.get_device(&entry.device_id).is_err())
+                        .cloned()
+                        .collect()
+                });
+                if entries.is_empty() {
+                    self.state
+                        .hint("Every device of the address book is configured already");
                 } else {
-                    self.selected_device = Some(0)
+                    self.popup = Some(Box::new(AddressBookPopup::new(entries)));
                 }
             }
-            Message::Up => {
-                if len == 0 {
-                    return None;
-                }
-                if let Some(highlighted_device) = self.selected_device {
-                    self.selected_device = Some((highlighted_device + len - 1) % len)
-                } else {
-                    self.selected_device = Some(len - 1);
-                }
+            DevicesAction::Pause(device_id) => self.state.pause_device(&device_id),
+            DevicesAction::Resume(device_id) => self.state.resume_device(&device_id),
+            DevicesAction::Remove(device_id) => self.confirm(Message::RemoveDevice(device_id)),
+            DevicesAction::Add => {
+                self.popup = Some(Box::new(NewDevicePopup::new(
+                    String::new(),
+                    String::new(),
+                    self.mode.clone(),
+                )));
             }
-            Message::Select => {
-                if let Some(highlighted_device) = self.selected_device {
-                    self.state.read(|state| {
-                        if let Some(device) = state.get_other_devices().get(highlighted_device) {
-                            self.popup = Some(Box::new(DevicePopup::new(
-                                device.config.clone(),
-                                self.mode.clone(),
-                            )))
-                        }
-                    })
+            DevicesAction::Open(device_id) => {
+                if let Some((device, rate_limits)) = self.state.read(|state| {
+                    state
+                        .get_device(&device_id)
+                        .map(|d| (d.config.clone(), d.rate_limits))
+                        .ok()
+                }) {
+                    self.popup = Some(Box::new(DevicePopup::new(
+                        device,
+                        rate_limits,
+                        self.mode.clone(),
+                        self.advanced,
+                    )));
                 }
             }
-            _ => {}
+        }
+    }
+
+    fn update_health(&mut self, msg: Message) -> Option<Message> {
+        let stale_devices: Vec<String> = self.state.read(|state| {
+            state
+                .get_stale_devices(&self.config)
+                .into_iter()
+                .map(|(device, _)| device.config.device_id.clone())
+                .collect()
+        });
+        let action = self.health.update(&msg, &stale_devices)?;
+        self.handle_devices_action(action);
+        None
+    }
+
+    fn update_activity(&mut self, msg: Message) -> Option<Message> {
+        let len = self.state.read(|state| activity_rows(state).len());
+        self.activity.update(&msg, len);
+        None
+    }
+
+    fn update_settings(&mut self, msg: Message) -> Option<Message> {
+        let saved = self.state.read(saved_settings)?;
+        let changes = match self.settings.update(&msg, &saved, GUI_FIELDS.len() + 1)? {
+            SettingsAction::Save(changes) => changes,
+            SettingsAction::Open(index) => {
+                return Some(
+                    GUI_FIELDS
+                        .get(index)
+                        .map_or(Message::RotateApiKey, |field| Message::EditGui(*field)),
+                );
+            }
         };
+        let (options, ur_version) = self.state.read(|state| {
+            (
+                state
+                    .raw_config
+                    .as_ref()
+                    .and_then(|config| config.get("options"))
+                    .cloned(),
+                state
+                    .system_status
+                    .as_ref()
+                    .map_or(0, |status| status.ur_version_max),
+            )
+        });
+        if let Some(mut options) = options {
+            for (index, on) in changes {
+                SETTINGS[index].set(&mut options, on, ur_version);
+            }
+            self.state.put_options(options);
+        }
+        None
+    }
+
+    fn update_config(&mut self, msg: Message) -> Option<Message> {
+        match msg {
+            Message::Filter => {
+                self.popup = Some(Box::new(FilterPopup::new(self.config_tree.filter.clone())));
+                *self.mode.lock().unwrap() = CurrentMode::Insert;
+            }
+            msg => self.state.read(|state| {
+                if let Some(config) = &state.raw_config {
+                    let rows = config_rows(config, &self.config_tree);
+                    self.config_tree.update(&msg, &rows);
+                }
+            }),
+        }
+        None
+    }
+
+    fn update_mesh(&mut self, msg: Message) -> Option<Message> {
+        let (folders_len, devices_len) = self
+            .state
+            .read(|state| (state.get_folders().len(), state.count_other_devices()));
+        self.mesh_state.update(&msg, folders_len, devices_len);
+        if msg == Message::Select
+            && let Some(target) = self.state.read(|state| self.mesh_state.selected(state))
+        {
+            self.jump_to(&target);
+        }
         None
     }
 
@@ -273,6 +615,23 @@ impl App {
         let folders_len = self.state.read(|state| state.get_pending_folders().len());
 
         self.pending_state.update(&msg, devices_len, folders_len);
+        if matches!(msg, Message::DismissOffers) {
+            let device_id = self.pending_state.folder_selected().and_then(|index| {
+                self.state.read(|state| {
+                    state
+                        .get_pending_folders()
+                        .get(index)
+                        .map(|(device_id, _)| device_id.clone())
+                })
+            });
+            match device_id {
+                Some(device_id) => self.confirm_offers_of(device_id),
+                None => self
+                    .state
+                    .hint("Select a pending folder of the device first"),
+            }
+            return None;
+        }
         if matches!(msg, Message::Select) {
             // Device Popup
             if let Some(index) = self.pending_state.device_selected() {
@@ -285,31 +644,226 @@ impl App {
                 });
             };
             // Folder Popup
-            if let Some(index) = self.pending_state.folder_selected() {
-                self.state.read(|state| {
-                    if let Some((device_id, folder)) = state.get_pending_folders().get(index) {
-                        // Only need to share, folder exists already locally
-                        if state.get_folder(folder.get_id()).is_ok() {
-                            self.popup = Some(Box::new(PendingShareFolderPopup::new(
+            if let Some(index) = self.pending_state.folder_selected()
+                && let Some((label, folder_id, device_id)) = self.state.read(|state| {
+                    state
+                        .get_pending_folders()
+                        .get(index)
+                        .map(|(device_id, folder)| {
+                            (
+                                folder.get_label().clone().unwrap_or_default(),
                                 folder.get_id().to_string(),
                                 device_id.to_string(),
-                            )))
-                        } else {
-                            self.popup = Some(Box::new(NewFolderPopup::new_from_device(
-                                folder.get_label().clone().unwrap_or("".to_string()),
-                                folder.get_id().to_string(),
-                                device_id.to_string(),
-                                self.mode.clone(),
-                                self.state.clone(),
-                            )))
-                        }
-                    }
-                });
+                            )
+                        })
+                })
+            {
+                self.popup = Some(self.pending_folder_popup(&label, &folder_id, &device_id));
             }
         };
         None
     }
 
+    /// The popup creating a new folder, either offered by a device or from
+    /// scratch, as configured with `folder-wizard`
+    fn new_folder_popup(&self, offer: Option<(&str, &str, &str)>) -> Box<dyn Popup> {
+        let (mode, state, icons) = (self.mode.clone(), self.state.clone(), self.config.icons);
+        match (self.config.folder_wizard, offer) {
+            (false, None) => Box::new(NewFolderPopup::new(mode, state, icons)),
+            (false, Some((label, id, device_id))) => Box::new(NewFolderPopup::new_from_device(
+                label, id, device_id, mode, state, icons,
+            )),
+            (true, None) => Box::new(FolderWizardPopup::new(mode, state, icons)),
+            (true, Some((label, id, device_id))) => Box::new(FolderWizardPopup::new_from_device(
+                label, id, device_id, mode, state, icons,
+            )),
+        }
+    }
+
+    /// The popup accepting a folder offered by `device_id`
+    fn pending_folder_popup(
+        &self,
+        label: &str,
+        folder_id: &str,
+        device_id: &str,
+    ) -> Box<dyn Popup> {
+        // Only need to share, folder exists already locally
+        if self.state.read(|state| state.get_folder(folder_id).is_ok()) {
+            Box::new(PendingShareFolderPopup::new(
+                folder_id.to_string(),
+                device_id.to_string(),
+            ))
+        } else {
+            self.new_folder_popup(Some((label, folder_id, device_id)))
+        }
+    }
+
+    /// Switches to the page of `target` and selects it there
+    fn jump_to(&mut self, target: &SearchTarget) {
+        self.reset_details();
+        if let SearchTarget::Folder(folder_id) = target {
+            self.folders.list.selected = self.folder_row_index(folder_id);
+        }
+        self.state.read(|state| match target {
+            SearchTarget::Folder(_) => self.current_screen = CurrentScreen::Folders,
+            SearchTarget::Device(device_id) => {
+                self.current_screen = CurrentScreen::Devices;
+                self.devices.list.selected = state
+                    .get_listed_devices()
+                    .iter()
+                    .position(|d| &d.config.device_id == device_id);
+            }
+            SearchTarget::PendingDevice(device_id) => {
+                self.current_screen = CurrentScreen::Pending;
+                if let Some(index) = state
+                    .get_pending_devices()
+                    .iter()
+                    .position(|d| d.get_device_id() == device_id)
+                {
+                    self.pending_state.select_device(index);
+                }
+            }
+            SearchTarget::PendingFolder {
+                folder_id,
+                device_id,
+            } => {
+                self.current_screen = CurrentScreen::Pending;
+                if let Some(index) = state
+                    .get_pending_folders()
+                    .iter()
+                    .position(|(d, f)| d == device_id && f.get_id() == folder_id)
+                {
+                    self.pending_state.select_folder(index);
+                }
+            }
+        });
+    }
+
+    /// Applies the profile `name`, or none, to the config, and switches to
+    /// its screen
+    fn switch_profile(&mut self, name: Option<String>) {
+        let config = match &name {
+            Some(name) => match self.config.with_profile(name) {
+                Ok(config) => config,
+                Err(e) => return self.state.set_error(e),
+            },
+            None => self.config.without_profile(),
+        };
+        match Keymap::new(&config.keys) {
+            Ok(keymap) => *self.keymap.lock().unwrap() = keymap,
+            Err(e) => return self.state.set_error(e),
+        }
+        self.state.set_poll_interval(config.poll_interval());
+        if let Some(screen) = config
+            .profile()
+            .and_then(|(_, profile)| profile.screen.clone())
+            && (self.advanced || !screen.is_advanced())
+        {
+            self.current_screen = screen;
+            self.reset_details();
+        }
+        self.state.hint(match &name {
+            Some(name) => format!("Switched to profile {}", name),
+            None => "Switched to no profile".to_string(),
+        });
+        self.config = config;
+    }
+
+    /// Translates a click into the message for whatever was drawn there.
+    /// While a popup is open, only its buttons can be clicked.
+    fn click(&mut self, column: u16, row: u16) -> Option<Message> {
+        let (area, target) = self.hits.borrow().hit(column, row)?;
+        match target {
+            HitTarget::Button(index) => Some(Message::Press(index)),
+            _ if self.popup.is_some() => None,
+            HitTarget::Tab(screen) => {
+                self.current_screen = screen;
+                self.reset_details();
+                None
+            }
+            HitTarget::Rows { offset } => {
+                Some(Message::ClickRow(offset + usize::from(row - area.y)))
+            }
+        }
+    }
+
+    /// Stops the app once the current frame is done, remembering what was
+    /// shown for the next session
+    pub fn quit(&mut self) {
+        self.running = false;
+        // Only save complete snapshots, so that the next session does not
+        // report everything as added
+        self.state.read(|state| {
+            if state.is_fully_loaded() {
+                Snapshot::of(state).save();
+            }
+        });
+    }
+
+    /// Switches between basic and advanced mode. Leaves screens which are
+    /// only shown in advanced mode.
+    fn toggle_advanced(&mut self) {
+        self.advanced = !self.advanced;
+        if !self.advanced && self.current_screen.is_advanced() {
+            self.current_screen = CurrentScreen::default();
+            self.reset_details();
+        }
+        self.state.hint(if self.advanced {
+            "Advanced mode"
+        } else {
+            "Basic mode"
+        });
+    }
+
+    /// Tells that something is hidden in basic mode, and how to show it
+    fn hint_advanced(&self) {
+        let keys = self.keymap.lock().unwrap().keys("advanced");
+        self.state.hint(format!(
+            "Only available in advanced mode, switch with ({})",
+            keys
+        ));
+    }
+
+    /// Moves the focus of the detail panes back to their lists
+    fn reset_details(&mut self) {
+        self.folders.detail.reset();
+        self.devices.detail.reset();
+    }
+
+    /// The ID of the folder or device currently selected on the current screen
+    fn selected_id(&self) -> Option<String> {
+        self.state.read(|state| match self.current_screen {
+            CurrentScreen::Folders => self.folders.list.selected.and_then(|i| {
+                folder_rows(state, &self.config, &self.ui_state)
+                    .get(i)
+                    .and_then(|row| row.folder().map(|f| f.config.id.clone()))
+            }),
+            CurrentScreen::Devices => self.devices.list.selected.and_then(|i| {
+                state
+                    .get_listed_devices()
+                    .get(i)
+                    .map(|d| d.config.device_id.clone())
+            }),
+            CurrentScreen::Pending => {
+                if let Some(i) = self.pending_state.device_selected() {
+                    state
+                        .get_pending_devices()
+                        .get(i)
+                        .map(|d| d.get_device_id().clone())
+                } else {
+                    self.pending_state.folder_selected().and_then(|i| {
+                        state
+                            .get_pending_folders()
+                            .get(i)
+                            .map(|(_, f)| f.get_id().clone())
+                    })
+                }
+            }
+            CurrentScreen::ID => Some(state.id.clone()),
+            _ => None,
+        })
+    }
+
     fn handle_new_folder(&mut self, folder: NewFolderConfiguration) -> Option<Message> {
         // Raise an error if we have a duplicate id.
         // Probably, this should also be done in the state
@@ -326,9 +880,661 @@ impl App {
         None
     }
 
+    /// Loads what the current screen shows, unless it has been loaded
+    /// already. Called before drawing each frame.
+    pub fn load_shown(&self) {
+        self.state.poll_system(matches!(
+            self.current_screen,
+            CurrentScreen::Devices | CurrentScreen::System | CurrentScreen::Settings
+        ));
+        match self.current_screen {
+            CurrentScreen::Folders => {
+                self.state.load(Preload::Completion);
+                if let Some(folder_id) = self.with_selected_folder(|_, f| f.config.id.clone()) {
+                    self.state.load_remote_completions(&folder_id);
+                }
+            }
+            CurrentScreen::Devices => {
+                self.state.load(Preload::DeviceStats);
+                self.state.load(Preload::Completion);
+                let folder_ids = self.state.read(|state| {
+                    self.devices
+                        .list
+                        .selected
+                        .and_then(|i| state.get_listed_devices().get(i).copied())
+                        .map(|device| {
+                            state
+                                .get_device_folders(&device.config.device_id)
+                                .iter()
+                                .map(|f| f.config.id.clone())
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default()
+                });
+                for folder_id in folder_ids {
+                    self.state.load_remote_completions(&folder_id);
+                }
+            }
+            CurrentScreen::Health => {
+                self.state.load(Preload::Completion);
+                self.state.load(Preload::DeviceStats);
+                self.state.load(Preload::ClockSkew);
+            }
+            _ => {}
+        }
+    }
+
+    /// Runs `f` on the folder selected on the folders page, unless nothing or
+    /// a group header is selected
+    fn with_selected_folder<R>(&self, f: impl FnOnce(&InnerState, &Folder) -> R) -> Option<R> {
+        let index = self.folders.list.selected?;
+        self.state.read(|state| {
+            folder_rows(state, &self.config, &self.ui_state)
+                .get(index)
+                .and_then(|row| row.folder())
+                .map(|folder| f(state, folder))
+        })
+    }
+
+    /// What currently hides items of the current page, e.g., a filter,
+    /// each described for the page header
+    pub fn view_restrictions(&self) -> Vec<String> {
+        match self.current_screen {
+            CurrentScreen::Folders => {
+                let collapsed = self.state.read(|state| {
+                    folder_rows(state, &self.config, &self.ui_state)
+                        .iter()
+                        .filter(|row| {
+                            matches!(
+                                row,
+                                FolderRow::Group {
+                                    collapsed: true,
+                                    ..
+                                }
+                            )
+                        })
+                        .count()
+                });
+                match collapsed {
+                    0 => Vec::new(),
+                    1 => vec!["1 group collapsed".to_string()],
+                    n => vec![format!("{} groups collapsed", n)],
+                }
+            }
+            CurrentScreen::Config if !self.config_tree.filter.is_empty() => {
+                vec![format!("filter: {}", self.config_tree.filter)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Index of `folder_id` in the folders list. Expands the group of the
+    /// folder, if it is collapsed.
+    fn folder_row_index(&mut self, folder_id: &str) -> Option<usize> {
+        let group = self.state.read(|state| {
+            let folder = state.get_folder(folder_id).ok()?;
+            Some(
+                self.config
+                    .folder_groups
+                    .iter()
+                    .find(|g| g.contains(&folder.config))
+                    .map_or(UNGROUPED.to_string(), |g| g.name.clone()),
+            )
+        })?;
+        if self.ui_state.is_group_collapsed(&group) {
+            self.ui_state.expand_group(&group);
+            self.ui_state.save();
+        }
+
+        self.state.read(|state| {
+            folder_rows(state, &self.config, &self.ui_state)
+                .iter()
+                .position(|row| row.folder().is_some_and(|f| f.config.id == folder_id))
+        })
+    }
+
+    /// Selects the folder or device requested on startup, once it has been loaded
+    fn apply_startup_view(&mut self) {
+        let Some(view) = self.startup_view.take() else {
+            return;
+        };
+        // Until the configuration is loaded, items may just be missing yet
+        let (loaded, folder_exists, device_exists) = self.state.read(|state| {
+            (
+                state.is_loaded(Section::Configuration),
+                view.folder
+                    .as_ref()
+                    .is_some_and(|folder_id| state.get_folder(folder_id).is_ok()),
+                view.device
+                    .as_ref()
+                    .is_some_and(|device_id| state.get_device(device_id).is_ok()),
+            )
+        });
+        if let Some(folder_id) = &view.folder {
+            match self.folder_row_index(folder_id) {
+                Some(index) => {
+                    self.folders.list.selected = Some(index);
+                    if view.open_popup {
+                        self.update_folders(Message::Select);
+                    }
+                }
+                // Not loaded yet, try again on the next update
+                None if !loaded => self.startup_view = Some(view),
+                None if folder_exists => self
+                    .state
+                    .hint(format!("Folder '{}' is hidden by the view", folder_id)),
+                None => self
+                    .state
+                    .set_error(AppError::StartupFolderNotFound(folder_id.clone())),
+            }
+        } else if let Some(device_id) = &view.device {
+            match self.state.read(|state| {
+                state
+                    .get_listed_devices()
+                    .iter()
+                    .position(|d| &d.config.device_id == device_id)
+            }) {
+                Some(index) => {
+                    self.devices.list.selected = Some(index);
+                    if view.open_popup {
+                        self.update_devices(Message::Select);
+                    }
+                }
+                None if !loaded => self.startup_view = Some(view),
+                None if device_exists => self
+                    .state
+                    .hint(format!("Device '{}' is hidden by the view", device_id)),
+                None => self
+                    .state
+                    .set_error(AppError::StartupDeviceNotFound(device_id.clone())),
+            }
+        }
+    }
+
+    /// Shows what changed since the last session, once everything has been
+    /// loaded and no other popup is open
+    fn summarize_last_session(&mut self) {
+        if self.last_session.is_none()
+            || self.popup.is_some()
+            || !self.state.read(|state| state.is_fully_loaded())
+        {
+            return;
+        }
+        let Some(last_session) = self.last_session.take() else {
+            return;
+        };
+        let changes = self
+            .state
+            .read(|state| Snapshot::of(state).changes_since(&last_session));
+        if !changes.is_empty() {
+            self.popup = Some(Box::new(OutputPopup::new(
+                "Since your last session".to_string(),
+                changes.join("\n"),
+            )));
+        }
+    }
+
+    /// The folder or device which is currently viewed
+    fn viewed_item(&self) -> Option<SearchTarget> {
+        match self.current_screen {
+            CurrentScreen::Folders => self
+                .with_selected_folder(|_, folder| SearchTarget::Folder(folder.config.id.clone())),
+            CurrentScreen::Devices => self.devices.list.selected.and_then(|i| {
+                self.state.read(|state| {
+                    state
+                        .get_listed_devices()
+                        .get(i)
+                        .map(|device| SearchTarget::Device(device.config.device_id.clone()))
+                })
+            }),
+            CurrentScreen::Mesh => self.state.read(|state| self.mesh_state.selected(state)),
+            _ => None,
+        }
+    }
+
+    /// Moves the viewed folder or device to the front of the recent items
+    fn record_recent(&mut self) {
+        let Some(item) = self.viewed_item() else {
+            return;
+        };
+        if self.recent.front() == Some(&item) {
+            return;
+        }
+        self.recent.retain(|recent| *recent != item);
+        self.recent.push_front(item);
+        self.recent.truncate(RECENT_ITEMS);
+    }
+
+    /// Number of snoozed pending requests which are still pending
+    pub fn snoozed_count(&self) -> usize {
+        self.state.read(|state| {
+            self.snoozed
+                .iter()
+                .filter(|msg| is_still_pending(state, msg))
+                .count()
+        })
+    }
+
+    /// The message reopening the most recently snoozed request which is still
+    /// pending. Requests decided on in the meantime are forgotten.
+    fn take_snoozed(&mut self) -> Option<Message> {
+        let state = self.state.clone();
+        state.read(|state| {
+            while let Some(msg) = self.snoozed.pop() {
+                if is_still_pending(state, &msg) {
+                    return Some(msg);
+                }
+            }
+            None
+        })
+    }
+
+    /// Opens a popup with the recent items, except for the viewed one
+    fn show_recent(&mut self) {
+        let viewed = self.viewed_item();
+        let items: Vec<_> = self.state.read(|state| {
+            self.recent
+                .iter()
+                .filter(|item| Some(*item) != viewed.as_ref())
+                .filter_map(|item| match item {
+                    SearchTarget::Folder(id) => state
+                        .get_folder(id)
+                        .ok()
+                        .map(|folder| (item.clone(), "Folder", folder.config.label.clone())),
+                    SearchTarget::Device(id) => state
+                        .get_device(id)
+                        .ok()
+                        .map(|device| (item.clone(), "Device", device.config.name.clone())),
+                    _ => None,
+                })
+                .collect()
+        });
+        if items.is_empty() {
+            self.state.hint("No recently viewed folders or devices");
+        } else {
+            self.popup = Some(Box::new(RecentPopup::new(items)));
+        }
+    }
+
+    /// Opens a popup to edit the note of the selected folder or device
+    fn edit_note(&mut self) {
+        let selected = match self.current_screen {
+            CurrentScreen::Folders => self.with_selected_folder(|_, folder| {
+                (
+                    NoteTarget::Folder(folder.config.id.clone()),
+                    folder.config.label.clone(),
+                )
+            }),
+            CurrentScreen::Devices => self.devices.list.selected.and_then(|i| {
+                self.state.read(|state| {
+                    state.get_listed_devices().get(i).map(|device| {
+                        (
+                            NoteTarget::Device(device.config.device_id.clone()),
+                            device.config.name.clone(),
+                        )
+                    })
+                })
+            }),
+            _ => None,
+        };
+        let Some((target, name)) = selected else {
+            return;
+        };
+        let note = self
+            .state
+            .read(|state| state.notes.get(&target).cloned())
+            .unwrap_or_default();
+        self.popup = Some(Box::new(NotePopup::new(
+            target,
+            name,
+            note,
+            self.mode.clone(),
+        )));
+        *self.mode.lock().unwrap() = CurrentMode::Insert;
+    }
+
+    /// Runs the folder command with index `command` on `folder_id` in the
+    /// background, and shows its output once it finished
+    fn run_folder_command(&self, command: usize, folder_id: &str) {
+        let (Some(command), Ok(folder)) = (
+            self.config.folder_commands.get(command).cloned(),
+            self.state
+                .read(|state| state.get_folder(folder_id).map(|f| f.config.clone())),
+        ) else {
+            return;
+        };
+        let script = match command.expand(&folder) {
+            Ok(script) => script,
+            Err(e) => return self.state.set_error(e),
+        };
+        let title = format!("{} ({})", command.name, folder.label);
+        self.state.hint(format!("Running {}…", title));
+
+        let rerender_tx = self.rerender_tx.clone();
+        tokio::spawn(async move {
+            let mut shell = if cfg!(windows) {
+                let mut shell = tokio::process::Command::new("cmd");
+                shell.arg("/C");
+                shell
+            } else {
+                let mut shell = tokio::process::Command::new("sh");
+                shell.arg("-c");
+                shell
+            };
+            let output = match shell.arg(&script).current_dir(&folder.path).output().await {
+                Ok(output) => format!(
+                    "{}{}\n{}",
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr),
+                    output.status
+                ),
+                Err(e) => format!("failed to run '{}': {}", script, e),
+            };
+            if let Err(e) = rerender_tx
+                .send(Message::CommandOutput { title, output })
+                .await
+            {
+                warn!("failed to send the output of a folder command: {:?}", e);
+            }
+        });
+    }
+
+    /// Starts recording events as configured, or stops if already recording
+    fn toggle_recorder(&self) {
+        if self.state.recording_to().is_some() {
+            self.state.set_event_sink(None);
+            self.state.hint("Stopped recording events");
+        } else {
+            recorder::start(&self.state, &self.config.recorder);
+        }
+    }
+
+    /// How synctui connects to Syncthing, as shown in the session popup
+    fn session_rows(&self) -> Vec<(&'static str, String)> {
+        let api_key = if self.reveal_ids || self.config.api_key.len() <= 8 {
+            self.config.api_key.clone()
+        } else {
+            let (start, end) = self.config.api_key.split_at(self.config.api_key.len() - 4);
+            format!("{}{}", "•".repeat(start.chars().count()), end)
+        };
+        let stored_in = match &self.config.api_key_path {
+            Some(path) => path.display().to_string(),
+            None => "--api-key argument".to_string(),
+        };
+        let tls = if !self.config.address().starts_with("https://") {
+            "none".to_string()
+        } else if self.config.insecure {
+            "any certificate is accepted".to_string()
+        } else if let Some(ca_cert) = &self.config.ca_cert {
+            format!("trusting {}", ca_cert.display())
+        } else {
+            "system certificates".to_string()
+        };
+        vec![
+            ("Address", self.config.address().to_string()),
+            ("API key", api_key),
+            ("Stored in", stored_in),
+            ("TLS", tls),
+        ]
+    }
+
+    /// Replaces the API key in Syncthing in the background. Saving it to the
+    /// config file is offered once that succeeded.
+    fn rotate_api_key(&self) {
+        self.state.hint("Rotating the API key…");
+        let state = self.state.clone();
+        let config = self.config.clone();
+        let rerender_tx = self.rerender_tx.clone();
+        tokio::spawn(async move {
+            match state.rotate_api_key(&config).await {
+                Ok(api_key) => {
+                    if let Err(e) = rerender_tx.send(Message::ApiKeyRotated(api_key)).await {
+                        warn!("failed to send the rotated API key: {:?}", e);
+                    }
+                }
+                Err(e) => {
+                    log::error!("failed to rotate the API key: {:?}", e);
+                    state.hint("Failed to rotate the API key");
+                    state.set_error(e);
+                }
+            }
+        });
+    }
+
+    /// Offers to save the rotated `api_key` where the previous one came from
+    fn offer_api_key(&mut self, api_key: String) {
+        self.config.api_key = api_key.clone();
+        let Some(path) = &self.config.api_key_path else {
+            return self.show_api_key(api_key);
+        };
+        self.popup = Some(Box::new(ConfirmPopup::new(
+            "New API Key",
+            format!(
+                "Syncthing has a new API key. Save it to {}, or show it to store it yourself?",
+                path.display()
+            ),
+            vec![
+                ConfirmAction::new("Save", 'y', Message::SaveApiKey(api_key.clone())),
+                ConfirmAction::new("Show", 'n', Message::ShowApiKey(api_key)),
+            ],
+            self.mode.clone(),
+        )));
+    }
+
+    fn show_api_key(&mut self, api_key: String) {
+        self.popup = Some(Box::new(OutputPopup::new(
+            "New API key".to_string(),
+            match &self.config.api_key_path {
+                Some(path) => format!("Set api-key in {} to:\n{}", path.display(), api_key),
+                None => format!("Pass the new key with --api-key from now on:\n{}", api_key),
+            },
+        )));
+    }
+
+    /// Saves `api_key` where the previous one came from, or shows it if
+    /// that is not possible
+    fn save_api_key(&mut self, api_key: String) {
+        let Some(path) = self.config.api_key_path.clone() else {
+            return self.show_api_key(api_key);
+        };
+        match AppConfig::save_api_key(&path, &api_key) {
+            Ok(()) => self.state.hint(format!(
+                "Rotated the API key and saved it to {}",
+                path.display()
+            )),
+            Err(e) => {
+                log::error!("failed to save the rotated API key: {:?}", e);
+                self.popup = Some(Box::new(OutputPopup::new(
+                    "New API key".to_string(),
+                    format!(
+                        "Could not save the new key, set api-key in {} to:\n{}",
+                        path.display(),
+                        api_key
+                    ),
+                )));
+                self.state.set_error(e);
+            }
+        }
+    }
+
+    /// Asks the user to confirm the destructive `msg` before executing it.
+    /// Apart from dismissing pending requests, destructive actions are only
+    /// available in advanced mode.
+    fn confirm(&mut self, msg: Message) {
+        if !self.advanced
+            && !matches!(
+                msg,
+                Message::DismissDevice(_) | Message::DismissFolder { .. }
+            )
+        {
+            return self.hint_advanced();
+        }
+        let (title, message, label) = self.state.read(|state| match &msg {
+            Message::RemoveFolder(folder_id) => {
+                let label = state
+                    .get_folder(folder_id)
+                    .map(|f| f.config.label.clone())
+                    .unwrap_or_default();
+                (
+                    "Remove Folder",
+                    format!(
+                        "Remove folder {} ({})? Its files stay on disk.",
+                        label, folder_id
+                    ),
+                    "Remove",
+                )
+            }
+            Message::RemoveDevice(device_id) => {
+                let name = state
+                    .get_device(device_id)
+                    .map(|d| d.config.name.clone())
+                    .unwrap_or_else(|_| device_id.clone());
+                (
+                    "Remove Device",
+                    format!(
+                        "Remove device {}? All folders stop being shared with it.",
+                        name
+                    ),
+                    "Remove",
+                )
+            }
+            Message::DismissDevice(device_id) => (
+                "Dismiss Device",
+                format!(
+                    "Dismiss device {}? It is asked for again when it reconnects.",
+                    device_id
+                ),
+                "Dismiss",
+            ),
+            Message::DismissFolder {
+                folder_id,
+                device_id,
+            } => (
+                "Dismiss Folder",
+                format!(
+                    "Dismiss the offer of folder {} by {}?",
+                    folder_id, device_id
+                ),
+                "Dismiss",
+            ),
+            Message::Restart => (
+                "Restart Syncthing",
+                "Restart Syncthing now? Running transfers are interrupted.".to_string(),
+                "Restart",
+            ),
+            Message::SetGui {
+                field: GuiField::Address,
+                value,
+            } => (
+                "Move GUI",
+                format!(
+                    "Let Syncthing's GUI and REST API listen on {}? synctui loses its connection until it is started with the new address.",
+                    value
+                ),
+                "Move",
+            ),
+            Message::SetGui { field, value } if value.is_empty() => (
+                "Change GUI",
+                format!(
+                    "Clear the {}? Without a user and password, anyone who can reach the GUI controls Syncthing.",
+                    field.label()
+                ),
+                "Clear",
+            ),
+            Message::SetGui { field, .. } => (
+                "Change GUI",
+                format!(
+                    "Change the {}? The web GUI asks for the new credentials from now on.",
+                    field.label()
+                ),
+                "Change",
+            ),
+            Message::RotateApiKey => (
+                "Rotate API Key",
+                "Replace Syncthing's API key with a new one? Other tools using the current key stop working.".to_string(),
+                "Rotate",
+            ),
+            _ => unreachable!("{:?} does not need a confirmation", msg),
+        });
+        self.popup = Some(Box::new(ConfirmPopup::destructive(
+            title,
+            message,
+            label,
+            msg,
+            self.mode.clone(),
+        )));
+    }
+
+    /// Asks whether to dismiss or ignore all folders `device_id` offers
+    fn confirm_offers_of(&mut self, device_id: String) {
+        let (name, count) = self.state.read(|state| {
+            let name = state
+                .get_device(&device_id)
+                .map(|d| d.config.name.clone())
+                .unwrap_or_else(|_| device_id.clone());
+            let count = state
+                .get_pending_folders()
+                .iter()
+                .filter(|(d, _)| d == &device_id)
+                .count();
+            (name, count)
+        });
+        self.popup = Some(Box::new(ConfirmPopup::new(
+            "Dismiss Offers",
+            format!(
+                "{} offers {} folders. Dismiss them until it offers them again, or ignore them for good?",
+                name, count
+            ),
+            vec![
+                ConfirmAction::new(
+                    "Dismiss all",
+                    'd',
+                    Message::Confirmed(Box::new(Message::DismissFoldersOf(device_id.clone()))),
+                )
+                .danger(),
+                ConfirmAction::new(
+                    "Ignore all",
+                    'i',
+                    Message::Confirmed(Box::new(Message::IgnoreFoldersOf(device_id))),
+                )
+                .danger(),
+                ConfirmAction::new("Cancel", 'n', Message::Quit),
+            ],
+            self.mode.clone(),
+        )));
+    }
+
+    /// Executes a destructive message, once the user confirmed it
+    fn execute_confirmed(&mut self, msg: Message) {
+        match msg {
+            Message::RemoveFolder(folder_id) => self.state.remove_folder(folder_id),
+            Message::RemoveDevice(device_id) => {
+                self.state.remove_device(device_id);
+                self.health.list.selected = None;
+            }
+            Message::DismissDevice(device_id) => self.state.dismiss_device(device_id),
+            Message::DismissFolder {
+                folder_id,
+                device_id,
+            } => self.state.dismiss_folder(folder_id, device_id),
+            Message::DismissFoldersOf(device_id) => self.state.dismiss_folders_of(device_id),
+            Message::IgnoreFoldersOf(device_id) => self.state.ignore_folders_of(device_id),
+            Message::Restart => self.state.restart(),
+            Message::RotateApiKey => self.rotate_api_key(),
+            Message::SetGui { field, value } => self.state.set_gui(field, value),
+            _ => warn!("{:?} cannot be confirmed", msg),
+        }
+    }
+
     pub fn update(&mut self, msg: Message) -> Option<Message> {
+        self.apply_startup_view();
+        self.record_recent();
+        self.summarize_last_session();
+        crash::set_screen(&self.current_screen);
+
         // Mode switches and popup results take always priority
         match msg {
+            Message::Click { column, row } => return self.click(column, row),
             Message::Insert => *self.mode.lock().unwrap() = CurrentMode::Insert,
             Message::Normal => *self.mode.lock().unwrap() = CurrentMode::Normal,
             Message::NewFolder(folder) => {
@@ -339,13 +1545,65 @@ impl App {
                 self.popup = None;
                 self.state.accept_device(device);
             }
-            Message::IgnoreDevice(_) => {
+            Message::IgnoreDevice(ref device_id) => {
+                self.popup = None;
+                self.state.ignore_device(device_id);
+            }
+            Message::IgnoreFolder {
+                ref folder_id,
+                ref device_id,
+            } => {
+                self.popup = None;
+                self.state.ignore_folder(folder_id, device_id);
+            }
+            Message::DismissDevice(_)
+            | Message::DismissFolder { .. }
+            | Message::RemoveFolder(_)
+            | Message::RemoveDevice(_)
+            | Message::RotateApiKey => {
+                self.confirm(msg);
+                return None;
+            }
+            Message::SetGui { .. } => {
+                self.popup = None;
+                *self.mode.lock().unwrap() = CurrentMode::Normal;
+                self.confirm(msg);
+                return None;
+            }
+            Message::ApiKeyRotated(api_key) => {
+                self.offer_api_key(api_key);
+                return None;
+            }
+            Message::SaveApiKey(api_key) => {
                 self.popup = None;
-                todo!("add device to ignore list");
+                self.save_api_key(api_key);
+                return None;
+            }
+            Message::ShowApiKey(api_key) => {
+                self.show_api_key(api_key);
+                return None;
+            }
+            Message::EditGui(_) if !self.advanced => {
+                self.hint_advanced();
+                return None;
+            }
+            Message::EditGui(field) => {
+                let value = self.state.read(|state| {
+                    state
+                        .raw_config
+                        .as_ref()
+                        .and_then(|config| config["gui"][field.key()].as_str())
+                        .unwrap_or_default()
+                        .to_string()
+                });
+                self.popup = Some(Box::new(GuiFieldPopup::new(field, value)));
+                *self.mode.lock().unwrap() = CurrentMode::Insert;
+                return None;
             }
-            Message::DismissDevice(ref device_id) => {
+            Message::Confirmed(msg) => {
                 self.popup = None;
-                self.state.dismiss_device(device_id);
+                self.execute_confirmed(*msg);
+                return None;
             }
             Message::ShareFolder {
                 ref folder_id,
@@ -354,28 +1612,106 @@ impl App {
                 self.popup = None;
                 self.state.share_folder(folder_id, device_id);
             }
-            Message::DismissFolder {
+            Message::EditFolder(ref folder) => {
+                self.popup = None;
+                self.state.edit_folder(*folder.clone());
+            }
+            Message::AddDevice(ref device) => {
+                self.popup = None;
+                self.state.add_device(*device.clone());
+            }
+            Message::EditDevice(ref device, rate_limits) => {
+                self.popup = None;
+                self.state.edit_device(*device.clone(), rate_limits);
+            }
+            Message::SetRateLimits(rate_limits) => {
+                self.popup = None;
+                self.state.set_rate_limits(rate_limits);
+            }
+            Message::Search => {
+                self.popup = Some(Box::new(SearchPopup::new(self.mode.clone())));
+                *self.mode.lock().unwrap() = CurrentMode::Insert;
+                return None;
+            }
+            Message::Recent => {
+                self.show_recent();
+                return None;
+            }
+            Message::Help => {
+                self.popup = Some(Box::new(HelpPopup::new(
+                    self.keymap.lock().unwrap().describe(),
+                    self.current_screen.clone(),
+                )));
+                return None;
+            }
+            Message::Jump(ref target) => {
+                self.popup = None;
+                *self.mode.lock().unwrap() = CurrentMode::Normal;
+                self.jump_to(target);
+                return None;
+            }
+            Message::RunCommand {
+                command,
                 ref folder_id,
-                ref device_id,
             } => {
                 self.popup = None;
-                self.state.dismiss_folder(folder_id, device_id);
+                self.run_folder_command(command, folder_id);
+                return None;
             }
-            Message::EditFolder(ref folder) => {
+            Message::ClearErrors => {
                 self.popup = None;
-                self.state.edit_folder(*folder.clone());
+                self.state.clear_errors();
+                return None;
             }
-            Message::RemoveFolder(ref folder_id) => {
+            Message::SwitchProfile(name) => {
                 self.popup = None;
-                self.state.remove_folder(folder_id);
+                self.switch_profile(name);
+                return None;
             }
-            Message::EditDevice(ref device) => {
+            Message::SetLogLevel(level) => {
                 self.popup = None;
-                self.state.edit_device(*device.clone());
+                log::set_max_level(level);
+                log::info!("log level changed to {}", level);
+                if let Some(path) = crash::log_file() {
+                    self.state
+                        .hint(format!("Logging {} to {}", level, path.display()));
+                }
+                return None;
+            }
+            Message::CommandOutput { title, output } => {
+                self.popup = Some(Box::new(OutputPopup::new(title, output)));
+                return None;
+            }
+            Message::AddKnownDevice { device_id, name } => {
+                self.popup = Some(Box::new(NewDevicePopup::new(
+                    device_id,
+                    name,
+                    self.mode.clone(),
+                )));
+                return None;
             }
-            Message::RemoveDevice(ref device_id) => {
+            Message::SetNote { target, note } => {
                 self.popup = None;
-                self.state.remove_device(device_id);
+                *self.mode.lock().unwrap() = CurrentMode::Normal;
+                self.state.set_note(target, note);
+                return None;
+            }
+            Message::ClearView => {
+                match self.current_screen {
+                    CurrentScreen::Folders => {
+                        self.ui_state.expand_all_groups();
+                        self.ui_state.save();
+                    }
+                    CurrentScreen::Config => self.config_tree.set_filter(String::new()),
+                    _ => {}
+                }
+                return None;
+            }
+            Message::SetFilter(filter) => {
+                self.popup = None;
+                *self.mode.lock().unwrap() = CurrentMode::Normal;
+                self.config_tree.set_filter(filter);
+                return None;
             }
             _ => {}
         }
@@ -384,7 +1720,13 @@ impl App {
         if let Some(popup) = self.popup.as_mut() {
             if let Some(msg) = popup.update(msg, self.state.clone()) {
                 match msg {
-                    Message::Quit => self.popup = None,
+                    Message::Quit => {
+                        if let Some(snoozed) = popup.snooze() {
+                            self.snoozed.retain(|msg| *msg != snoozed);
+                            self.snoozed.push(snoozed);
+                        }
+                        self.popup = None;
+                    }
                     // All other messages from the popup are handles in the next
                     // iteration, normally. This allows for greater flexibility
                     _ => return Some(msg),
@@ -396,18 +1738,121 @@ impl App {
         // If there is none, handle global messages
         match msg {
             Message::Quit => {
-                self.running = false;
+                self.quit();
                 return None;
             }
             Message::Number(i) => {
                 if let Ok(screen) = CurrentScreen::try_from(i) {
+                    if screen.is_advanced() && !self.advanced {
+                        self.hint_advanced();
+                        return None;
+                    }
                     self.current_screen = screen;
+                    self.reset_details();
                     return None;
                 }
             }
             Message::Reload => {
                 self.state.reload(Reload::Configuration);
             }
+            Message::Restart => {
+                self.confirm(msg);
+                return None;
+            }
+            Message::Note => {
+                self.edit_note();
+                return None;
+            }
+            Message::Snoozed => return self.take_snoozed(),
+            Message::Errors => {
+                if self.state.read(|state| state.errors.is_empty()) {
+                    self.state.hint("No errors");
+                } else {
+                    self.popup = Some(Box::new(ErrorsPopup::default()));
+                }
+                return None;
+            }
+            Message::LogLevel => {
+                if crash::log_file().is_some() {
+                    self.popup = Some(Box::new(LogLevelPopup::new()));
+                } else {
+                    self.state
+                        .hint("No log file, start with --log-level to write one");
+                }
+                return None;
+            }
+            Message::RateLimits => {
+                let rate_limits = self.state.read(|state| state.rate_limits);
+                self.popup = Some(Box::new(RateLimitsPopup::new(
+                    rate_limits,
+                    self.mode.clone(),
+                )));
+                return None;
+            }
+            Message::Session => {
+                self.popup = Some(Box::new(SessionPopup::new(self.session_rows())));
+                return None;
+            }
+            Message::StateHistory => {
+                if self.state.read(|state| state.history.is_some()) {
+                    self.popup = Some(Box::new(StateHistoryPopup::default()));
+                } else {
+                    self.state
+                        .hint("Start with --debug-state to keep the history of the state");
+                }
+                return None;
+            }
+            Message::ToggleRecorder => {
+                self.toggle_recorder();
+                return None;
+            }
+            Message::Profiles => {
+                if self.config.profiles.is_empty() {
+                    self.state.hint("No profiles configured");
+                } else {
+                    let names = self.config.profiles.keys().cloned().collect();
+                    let current = self.config.profile().map(|(name, _)| name.to_string());
+                    self.popup = Some(Box::new(ProfilesPopup::new(names, current)));
+                }
+                return None;
+            }
+            Message::GlobalAnnounce if !self.advanced => {
+                self.hint_advanced();
+                return None;
+            }
+            Message::GlobalAnnounce => {
+                self.current_screen = CurrentScreen::Config;
+                self.config_tree
+                    .set_filter("globalAnnounceEnabled".to_string());
+                return None;
+            }
+            Message::ToggleAdvanced => {
+                self.toggle_advanced();
+                return None;
+            }
+            Message::ToggleIds => {
+                self.list_ids = !self.list_ids;
+                return None;
+            }
+            Message::RevealIds => {
+                self.reveal_ids = !self.reveal_ids;
+                return None;
+            }
+            Message::CopyId => {
+                if let Some(id) = self.selected_id()
+                    && let Err(e) = clipboard::copy(&id)
+                {
+                    warn!("failed to copy {} to clipboard: {:?}", id, e);
+                    self.state.hint(format!("Could not copy the ID: {}", e));
+                }
+                return None;
+            }
+            Message::GrowSplit | Message::ShrinkSplit => {
+                self.ui_state
+                    .resize_split(&self.current_screen, msg == Message::GrowSplit);
+                self.ui_state.save();
+                return None;
+            }
             Message::NewPendingDevice(ref device) => {
                 self.popup = Some(Box::new(PendingDevicePopup::new(device.clone())));
             }
@@ -416,21 +1861,7 @@ impl App {
                 ref folder_id,
                 ref device_id,
             } => {
-                // Folder already exists on our machine, just share
-                if self.state.read(|state| state.get_folder(folder_id).is_ok()) {
-                    self.popup = Some(Box::new(PendingShareFolderPopup::new(
-                        folder_id.clone(),
-                        device_id.to_string(),
-                    )))
-                } else {
-                    self.popup = Some(Box::new(NewFolderPopup::new_from_device(
-                        folder_label,
-                        folder_id,
-                        device_id,
-                        self.mode.clone(),
-                        self.state.clone(),
-                    )))
-                }
+                self.popup = Some(self.pending_folder_popup(folder_label, folder_id, device_id));
             }
             _ => {}
         }
@@ -440,7 +1871,31 @@ impl App {
             CurrentScreen::Folders => self.update_folders(msg),
             CurrentScreen::Devices => self.update_devices(msg),
             CurrentScreen::Pending => self.update_pending(msg),
+            CurrentScreen::Health => self.update_health(msg),
+            CurrentScreen::Config => self.update_config(msg),
+            CurrentScreen::Mesh => self.update_mesh(msg),
+            CurrentScreen::Activity => self.update_activity(msg),
+            CurrentScreen::Settings => self.update_settings(msg),
             _ => None,
         }
     }
 }
+
+/// Whether the request reopened by `msg` has not been decided on yet
+fn is_still_pending(state: &InnerState, msg: &Message) -> bool {
+    match msg {
+        Message::NewPendingDevice(device_id) => state
+            .get_pending_devices()
+            .iter()
+            .any(|d| d.get_device_id() == device_id),
+        Message::NewPendingFolder {
+            folder_id,
+            device_id,
+            ..
+        } => state
+            .get_pending_folders()
+            .iter()
+            .any(|(d, f)| d == device_id && f.get_id() == folder_id),
+        _ => false,
+    }
+}
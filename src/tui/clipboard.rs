@@ -0,0 +1,23 @@
+use std::io;
+#[cfg(feature = "clipboard")]
+use std::io::Write;
+
+#[cfg(feature = "clipboard")]
+use base64::{Engine, prelude::BASE64_STANDARD};
+
+/// Copies `text` into the clipboard of the terminal using the OSC 52 escape
+/// sequence. This also works over SSH, as long as the terminal supports it.
+#[cfg(feature = "clipboard")]
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", BASE64_STANDARD.encode(text))?;
+    stdout.flush()
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy(_text: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "synctui was built without the clipboard feature",
+    ))
+}
@@ -0,0 +1,289 @@
+//! A local socket, on which a running TUI accepts simple commands from
+//! scripts, one per line, and answers each with a single line.
+
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use super::state::{Reload, State};
+
+/// Port the control interface listens on localhost, where there are no unix
+/// sockets
+#[cfg(not(unix))]
+const CONTROL_PORT: u16 = 8386;
+
+/// The socket this instance created, which is the only one it removes
+#[cfg(unix)]
+static CREATED_SOCKET: std::sync::Mutex<Option<std::path::PathBuf>> = std::sync::Mutex::new(None);
+
+/// Where the control socket of a running TUI is created
+#[cfg(unix)]
+pub fn socket_path() -> Option<std::path::PathBuf> {
+    dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .map(|path| path.join("synctui").join("control.sock"))
+}
+
+/// Where the token is kept which clients have to send first, as anyone on
+/// this machine can connect to the port. The local data directory is only
+/// readable by the user.
+#[cfg(not(unix))]
+fn token_path() -> Option<std::path::PathBuf> {
+    dirs::data_local_dir().map(|path| path.join("synctui").join("control.token"))
+}
+
+/// A new token, unpredictable as the keys of the hasher come from the
+/// operating system's random number generator
+#[cfg(not(unix))]
+fn new_token() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    let random = std::collections::hash_map::RandomState::new();
+    (0..4u8)
+        .map(|i| {
+            let mut hasher = random.build_hasher();
+            hasher.write_u8(i);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// Starts accepting commands in the background. Failing to do so only
+/// disables the control interface.
+pub fn listen(state: State) {
+    tokio::spawn(async move {
+        if let Err(e) = accept(state).await {
+            log::warn!("control interface stopped: {:?}", e);
+        }
+    });
+}
+
+#[cfg(unix)]
+async fn accept(state: State) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = socket_path().ok_or(std::io::ErrorKind::NotFound)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if path.exists() {
+        if tokio::net::UnixStream::connect(&path).await.is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                "another synctui is listening",
+            ));
+        }
+        // Left behind by a previous session, which would make binding fail
+        std::fs::remove_file(&path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    *CREATED_SOCKET.lock().unwrap() = Some(path.clone());
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    log::info!("control interface listening on {}", path.display());
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(handle_connection(stream, state.clone(), None));
+    }
+}
+
+#[cfg(not(unix))]
+async fn accept(state: State) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", CONTROL_PORT)).await?;
+    let path = token_path().ok_or(std::io::ErrorKind::NotFound)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let token = new_token();
+    std::fs::write(&path, &token)?;
+    log::info!("control interface listening on port {}", CONTROL_PORT);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(handle_connection(
+            stream,
+            state.clone(),
+            Some(token.clone()),
+        ));
+    }
+}
+
+/// Removes the control socket if this instance created it, so that no stale
+/// one is left behind
+pub fn close() {
+    #[cfg(unix)]
+    if let Some(path) = CREATED_SOCKET.lock().unwrap().take() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Sends `command` to the running TUI and returns its answer
+pub async fn send(command: &str) -> std::io::Result<String> {
+    #[cfg(unix)]
+    let (stream, token) = {
+        let path = socket_path().ok_or(std::io::ErrorKind::NotFound)?;
+        (tokio::net::UnixStream::connect(path).await?, None::<String>)
+    };
+    #[cfg(not(unix))]
+    let (stream, token) = {
+        let path = token_path().ok_or(std::io::ErrorKind::NotFound)?;
+        let token = std::fs::read_to_string(path)?;
+        (
+            tokio::net::TcpStream::connect(("127.0.0.1", CONTROL_PORT)).await?,
+            Some(token),
+        )
+    };
+
+    let mut stream = BufReader::new(stream);
+    if let Some(token) = token {
+        stream
+            .get_mut()
+            .write_all(format!("{}\n", token.trim()).as_bytes())
+            .await?;
+    }
+    stream
+        .get_mut()
+        .write_all(format!("{}\n", command).as_bytes())
+        .await?;
+    let mut answer = String::new();
+    stream.read_line(&mut answer).await?;
+    Ok(answer.trim_end().to_string())
+}
+
+/// Answers the commands sent on `stream`, once its first line matched
+/// `token` if there is one
+async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    state: State,
+    token: Option<String>,
+) {
+    let mut lines = BufReader::new(stream).lines();
+    if let Some(token) = token {
+        match lines.next_line().await {
+            Ok(Some(line)) if line.trim() == token => {}
+            _ => {
+                log::warn!("rejected a control connection without the token");
+                let _ = lines
+                    .get_mut()
+                    .get_mut()
+                    .write_all(b"error: unauthorized\n")
+                    .await;
+                return;
+            }
+        }
+    }
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                log::warn!("failed to read from control connection: {:?}", e);
+                break;
+            }
+        };
+        let answer = match execute(line.trim(), &state) {
+            Ok(answer) => answer,
+            Err(e) => format!("error: {}", e),
+        };
+        if let Err(e) = lines
+            .get_mut()
+            .get_mut()
+            .write_all(format!("{}\n", answer).as_bytes())
+            .await
+        {
+            log::warn!("failed to answer on control connection: {:?}", e);
+            break;
+        }
+    }
+}
+
+/// A command accepted on the control interface
+#[derive(Debug, PartialEq)]
+enum Command<'a> {
+    Reload,
+    PauseFolder(&'a str),
+    ResumeFolder(&'a str),
+    StatusJson,
+}
+
+impl<'a> Command<'a> {
+    fn parse(command: &'a str) -> Result<Self, String> {
+        let (name, argument) = command
+            .split_once(' ')
+            .map(|(name, argument)| (name, argument.trim()))
+            .unwrap_or((command, ""));
+        match (name, argument) {
+            ("reload", "") => Ok(Self::Reload),
+            ("pause-folder", id) if !id.is_empty() => Ok(Self::PauseFolder(id)),
+            ("resume-folder", id) if !id.is_empty() => Ok(Self::ResumeFolder(id)),
+            ("status-json", "") => Ok(Self::StatusJson),
+            _ => Err(format!(
+                "unknown command '{}', expected reload, pause-folder <id>, resume-folder <id> or status-json",
+                command
+            )),
+        }
+    }
+}
+
+fn execute(command: &str, state: &State) -> Result<String, String> {
+    log::debug!("control command: {}", command);
+    match Command::parse(command)? {
+        Command::Reload => {
+            state.reload(Reload::Configuration);
+            Ok("ok".to_string())
+        }
+        Command::PauseFolder(id) => set_folder_paused(state, id, true),
+        Command::ResumeFolder(id) => set_folder_paused(state, id, false),
+        Command::StatusJson => Ok(state.read(|state| {
+            json!({
+                "folders": state.get_folders().iter().map(|f| json!({
+                    "id": f.config.id,
+                    "label": f.config.label,
+                    "paused": f.config.paused,
+                    "completion": f.completion,
+                })).collect::<Vec<_>>(),
+                "devices": state.get_other_devices().iter().map(|d| json!({
+                    "id": d.config.device_id,
+                    "name": d.config.name,
+                    "paused": d.config.paused,
+                    "status": format!("{:?}", d.connected),
+                })).collect::<Vec<_>>(),
+                "pendingDevices": state.get_pending_devices().len(),
+                "pendingFolders": state.get_pending_folders().len(),
+            })
+            .to_string()
+        })),
+    }
+}
+
+fn set_folder_paused(state: &State, folder_id: &str, paused: bool) -> Result<String, String> {
+    let mut folder = state
+        .read(|state| state.get_folder(folder_id).map(|f| f.config.clone()))
+        .map_err(|e| e.to_string())?;
+    folder.paused = paused;
+    state.edit_folder(folder);
+    Ok("ok".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_with_arguments() {
+        assert_eq!(Command::parse("reload"), Ok(Command::Reload));
+        assert_eq!(
+            Command::parse("pause-folder  my folder "),
+            Ok(Command::PauseFolder("my folder"))
+        );
+        assert_eq!(
+            Command::parse("resume-folder abc"),
+            Ok(Command::ResumeFolder("abc"))
+        );
+        assert_eq!(Command::parse("status-json"), Ok(Command::StatusJson));
+    }
+
+    #[test]
+    fn rejects_unknown_commands_and_missing_arguments() {
+        for command in ["", "pause-folder", "reload now", "POST / HTTP/1.1"] {
+            assert!(Command::parse(command).is_err(), "{:?}", command);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::tui::input::Message;
+
+use super::ListSelection;
+
+/// Scrolls through the rows of the activity page
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ActivityController {
+    pub list: ListSelection,
+}
+
+impl ActivityController {
+    /// Handles `msg`, with `len` the number of rows. Nothing is acted on, the
+    /// rows can only be looked at.
+    pub fn update(&mut self, msg: &Message, len: usize) {
+        match msg {
+            Message::Down => self.list.next(len),
+            Message::Up => self.list.prev(len),
+            Message::ClickRow(index) => self.list.select(*index, len),
+            _ => {}
+        }
+        if len == 0 {
+            self.list.selected = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrolls_through_rows() {
+        let mut controller = ActivityController::default();
+        controller.update(&Message::Down, 3);
+        controller.update(&Message::Down, 3);
+        assert_eq!(controller.list.selected, Some(1));
+        controller.update(&Message::Up, 0);
+        assert_eq!(controller.list.selected, None);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::tui::input::Message;
+
+use super::{DetailPane, ListSelection};
+
+/// A line of the devices list, as far as the controller is concerned
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceItem {
+    pub device_id: String,
+    pub paused: bool,
+    /// This device, which can neither be paused, removed nor edited here
+    pub local: bool,
+}
+
+/// What has to happen in response to a message on the devices or health
+/// screen. All devices are referenced by ID.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DevicesAction {
+    /// Offer the devices of the address book which are not configured
+    AddressBook,
+    Pause(String),
+    Resume(String),
+    /// Remove the device, once confirmed
+    Remove(String),
+    /// Open the popup adding a new device
+    Add,
+    /// Open the popup editing the device
+    Open(String),
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DevicesController {
+    pub list: ListSelection,
+    pub detail: DetailPane,
+}
+
+impl DevicesController {
+    /// Handles `msg`, with `devices` the lines of the devices list and
+    /// `detail_len` the number of lines in the detail pane, if it is focused
+    pub fn update(
+        &mut self,
+        msg: &Message,
+        devices: &[DeviceItem],
+        detail_len: usize,
+    ) -> Option<DevicesAction> {
+        let selected = self
+            .list
+            .clamped(devices.len())
+            .map(|i| &devices[i])
+            .filter(|device| !device.local);
+        match msg {
+            Message::FocusNext | Message::FocusBack => {
+                self.detail.toggle_focus(self.list.selected.is_some());
+            }
+            Message::Down if self.detail.focused => self.detail.scroll_down(detail_len),
+            Message::Up if self.detail.focused => self.detail.scroll_up(),
+            Message::Down => {
+                self.detail.scroll = 0;
+                self.list.next(devices.len());
+            }
+            Message::Up => {
+                self.detail.scroll = 0;
+                self.list.prev(devices.len());
+            }
+            Message::ClickRow(index) if self.list.clamped(devices.len()) != Some(*index) => {
+                self.detail.reset();
+                self.list.select(*index, devices.len());
+            }
+            Message::AddressBook => return Some(DevicesAction::AddressBook),
+            Message::Pause => {
+                return selected.map(|device| {
+                    if device.paused {
+                        DevicesAction::Resume(device.device_id.clone())
+                    } else {
+                        DevicesAction::Pause(device.device_id.clone())
+                    }
+                });
+            }
+            Message::Remove => {
+                return selected.map(|device| DevicesAction::Remove(device.device_id.clone()));
+            }
+            Message::Add => return Some(DevicesAction::Add),
+            Message::Select | Message::ClickRow(_) => {
+                return selected.map(|device| DevicesAction::Open(device.device_id.clone()));
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devices() -> Vec<DeviceItem> {
+        vec![
+            DeviceItem {
+                device_id: "LAPTOP".to_string(),
+                paused: false,
+                local: false,
+            },
+            DeviceItem {
+                device_id: "PHONE".to_string(),
+                paused: true,
+                local: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn pause_toggles_selected_device() {
+        let mut controller = DevicesController::default();
+        assert_eq!(controller.update(&Message::Pause, &devices(), 0), None);
+
+        controller.update(&Message::Down, &devices(), 0);
+        assert_eq!(
+            controller.update(&Message::Pause, &devices(), 0),
+            Some(DevicesAction::Pause("LAPTOP".to_string()))
+        );
+        controller.update(&Message::Down, &devices(), 0);
+        assert_eq!(
+            controller.update(&Message::Pause, &devices(), 0),
+            Some(DevicesAction::Resume("PHONE".to_string()))
+        );
+    }
+
+    #[test]
+    fn removed_device_moves_selection_up() {
+        let mut controller = DevicesController {
+            list: ListSelection { selected: Some(1) },
+            ..Default::default()
+        };
+        let remaining = &devices()[..1];
+        assert_eq!(
+            controller.update(&Message::Remove, remaining, 0),
+            Some(DevicesAction::Remove("LAPTOP".to_string()))
+        );
+    }
+
+    #[test]
+    fn this_device_is_not_acted_on() {
+        let mut devices = devices();
+        devices.insert(
+            0,
+            DeviceItem {
+                device_id: "DESKTOP".to_string(),
+                paused: false,
+                local: true,
+            },
+        );
+        let mut controller = DevicesController::default();
+        controller.update(&Message::Down, &devices, 0);
+        for msg in [Message::Pause, Message::Remove, Message::Select] {
+            assert_eq!(controller.update(&msg, &devices, 0), None);
+        }
+        controller.update(&Message::Down, &devices, 0);
+        assert_eq!(
+            controller.update(&Message::Select, &devices, 0),
+            Some(DevicesAction::Open("LAPTOP".to_string()))
+        );
+    }
+
+    #[test]
+    fn add_works_without_selection() {
+        assert_eq!(
+            DevicesController::default().update(&Message::Add, &[], 0),
+            Some(DevicesAction::Add)
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use crate::tui::input::Message;
+
+use super::{DetailPane, ListSelection};
+
+/// A line of the folders list, as far as the controller is concerned
+#[derive(Clone, Debug, PartialEq)]
+pub enum FolderItem {
+    /// Header of a group, by name
+    Group(String),
+    /// A folder, by ID
+    Folder(String),
+}
+
+/// What has to happen in response to a message on the folders screen. All
+/// folders are referenced by ID.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FoldersAction {
+    /// Accept all pending offers to share the folder
+    AcceptOffers(String),
+    TogglePause(String),
+    ToggleFavorite(String),
+    /// Open the commands menu of the folder
+    Commands(String),
+    /// Check why the folder does not sync
+    Diagnose(String),
+    /// Scan the folder, which retries items which failed
+    Rescan(String),
+    /// Open the popup creating a new folder
+    Add,
+    /// Open the popup editing the folder
+    Open(String),
+    /// Collapse or expand the group
+    ToggleGroup(String),
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FoldersController {
+    pub list: ListSelection,
+    pub detail: DetailPane,
+}
+
+impl FoldersController {
+    /// Handles `msg`, with `rows` the lines of the folders list and
+    /// `detail_len` the number of lines in the detail pane, if it is focused
+    pub fn update(
+        &mut self,
+        msg: &Message,
+        rows: &[FolderItem],
+        detail_len: usize,
+    ) -> Option<FoldersAction> {
+        let selected = self.list.clamped(rows.len()).map(|i| &rows[i]);
+        let folder_id = match selected {
+            Some(FolderItem::Folder(folder_id)) => Some(folder_id.clone()),
+            _ => None,
+        };
+        match msg {
+            Message::FocusNext | Message::FocusBack => {
+                self.detail.toggle_focus(self.list.selected.is_some());
+            }
+            Message::Down if self.detail.focused => self.detail.scroll_down(detail_len),
+            Message::Up if self.detail.focused => self.detail.scroll_up(),
+            Message::Down => {
+                self.detail.scroll = 0;
+                self.list.next(rows.len());
+            }
+            Message::Up => {
+                self.detail.scroll = 0;
+                self.list.prev(rows.len());
+            }
+            Message::ClickRow(index) if self.list.clamped(rows.len()) != Some(*index) => {
+                self.detail.reset();
+                self.list.select(*index, rows.len());
+            }
+            Message::Accept => return folder_id.map(FoldersAction::AcceptOffers),
+            Message::Pause => return folder_id.map(FoldersAction::TogglePause),
+            Message::Favorite => return folder_id.map(FoldersAction::ToggleFavorite),
+            Message::Commands => return folder_id.map(FoldersAction::Commands),
+            Message::Diagnose => return folder_id.map(FoldersAction::Diagnose),
+            Message::Rescan => return folder_id.map(FoldersAction::Rescan),
+            Message::Add => return Some(FoldersAction::Add),
+            Message::Select | Message::ClickRow(_) => {
+                return match selected {
+                    Some(FolderItem::Folder(folder_id)) => {
+                        Some(FoldersAction::Open(folder_id.clone()))
+                    }
+                    Some(FolderItem::Group(group)) => {
+                        Some(FoldersAction::ToggleGroup(group.clone()))
+                    }
+                    None => None,
+                };
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<FolderItem> {
+        vec![
+            FolderItem::Group("Work".to_string()),
+            FolderItem::Folder("abcd-1234".to_string()),
+            FolderItem::Folder("efgh-5678".to_string()),
+        ]
+    }
+
+    fn selecting(index: usize) -> FoldersController {
+        FoldersController {
+            list: ListSelection {
+                selected: Some(index),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn select_opens_folder_or_toggles_group() {
+        assert_eq!(
+            selecting(1).update(&Message::Select, &rows(), 0),
+            Some(FoldersAction::Open("abcd-1234".to_string()))
+        );
+        assert_eq!(
+            selecting(0).update(&Message::Select, &rows(), 0),
+            Some(FoldersAction::ToggleGroup("Work".to_string()))
+        );
+        assert_eq!(
+            FoldersController::default().update(&Message::Select, &rows(), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn folder_actions_ignore_group_headers() {
+        let mut controller = selecting(0);
+        for msg in [
+            Message::Accept,
+            Message::Pause,
+            Message::Favorite,
+            Message::Commands,
+            Message::Diagnose,
+            Message::Rescan,
+        ] {
+            assert_eq!(controller.update(&msg, &rows(), 0), None);
+        }
+        assert_eq!(
+            selecting(2).update(&Message::Pause, &rows(), 0),
+            Some(FoldersAction::TogglePause("efgh-5678".to_string()))
+        );
+    }
+
+    #[test]
+    fn clicking_selects_then_opens() {
+        let mut controller = selecting(2);
+        controller.update(&Message::FocusNext, &rows(), 0);
+        assert_eq!(controller.update(&Message::ClickRow(1), &rows(), 0), None);
+        assert_eq!(controller.list.selected, Some(1));
+        assert!(!controller.detail.focused);
+        assert_eq!(
+            controller.update(&Message::ClickRow(1), &rows(), 0),
+            Some(FoldersAction::Open("abcd-1234".to_string()))
+        );
+        assert_eq!(controller.update(&Message::ClickRow(5), &rows(), 0), None);
+        assert_eq!(controller.list.selected, Some(1));
+    }
+
+    #[test]
+    fn moving_in_the_list_resets_the_detail_pane() {
+        let mut controller = selecting(1);
+        controller.update(&Message::FocusNext, &rows(), 0);
+        controller.update(&Message::Down, &rows(), 10);
+        assert_eq!(controller.detail.scroll, 1);
+        assert_eq!(controller.list.selected, Some(1));
+
+        controller.update(&Message::FocusBack, &rows(), 0);
+        controller.update(&Message::Down, &rows(), 10);
+        assert_eq!(controller.detail.scroll, 0);
+        assert_eq!(controller.list.selected, Some(2));
+    }
+}
@@ -0,0 +1,61 @@
+use crate::tui::input::Message;
+
+use super::{DevicesAction, ListSelection};
+
+/// Controls the stale devices section of the health page
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HealthController {
+    pub list: ListSelection,
+}
+
+impl HealthController {
+    /// Handles `msg`, with `stale_devices` the IDs of the listed devices
+    pub fn update(&mut self, msg: &Message, stale_devices: &[String]) -> Option<DevicesAction> {
+        let selected = self
+            .list
+            .clamped(stale_devices.len())
+            .map(|i| stale_devices[i].clone());
+        match msg {
+            Message::Down => self.list.next(stale_devices.len()),
+            Message::Up => self.list.prev(stale_devices.len()),
+            Message::ClickRow(index) => self.list.select(*index, stale_devices.len()),
+            Message::Pause => return selected.map(DevicesAction::Pause),
+            Message::Remove => return selected.map(DevicesAction::Remove),
+            _ => {}
+        }
+        if stale_devices.is_empty() {
+            self.list.selected = None;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acts_on_selected_stale_device() {
+        let stale = ["PHONE".to_string(), "TABLET".to_string()];
+        let mut controller = HealthController::default();
+        controller.update(&Message::Up, &stale);
+        assert_eq!(
+            controller.update(&Message::Remove, &stale),
+            Some(DevicesAction::Remove("TABLET".to_string()))
+        );
+        assert_eq!(
+            controller.update(&Message::Pause, &stale),
+            Some(DevicesAction::Pause("TABLET".to_string()))
+        );
+    }
+
+    #[test]
+    fn forgets_selection_once_nothing_is_stale() {
+        let mut controller = HealthController {
+            list: ListSelection { selected: Some(0) },
+        };
+        assert_eq!(controller.update(&Message::Remove, &[]), None);
+        controller.update(&Message::None, &[]);
+        assert_eq!(controller.list.selected, None);
+    }
+}
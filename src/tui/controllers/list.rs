@@ -0,0 +1,119 @@
+/// The selected row of a list, which wraps around at both ends
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ListSelection {
+    pub selected: Option<usize>,
+}
+
+impl ListSelection {
+    /// The selected row, moved to the last one if the list shrunk since
+    pub fn clamped(&self, len: usize) -> Option<usize> {
+        if len == 0 {
+            None
+        } else {
+            self.selected.map(|i| i.min(len - 1))
+        }
+    }
+
+    /// Selects the row with `index`, e.g., when it was clicked, unless the
+    /// list is shorter
+    pub fn select(&mut self, index: usize, len: usize) {
+        if index < len {
+            self.selected = Some(index);
+        }
+    }
+
+    pub fn next(&mut self, len: usize) {
+        if len > 0 {
+            self.selected = Some(self.clamped(len).map_or(0, |i| (i + 1) % len));
+        }
+    }
+
+    pub fn prev(&mut self, len: usize) {
+        if len > 0 {
+            self.selected = Some(self.clamped(len).map_or(len - 1, |i| (i + len - 1) % len));
+        }
+    }
+}
+
+/// Focus and scroll offset of the detail pane next to a list
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DetailPane {
+    /// Whether the detail pane instead of the list has the focus
+    pub focused: bool,
+    pub scroll: usize,
+}
+
+impl DetailPane {
+    /// Moves the focus between list and detail pane. Without a selection,
+    /// there are no details to focus.
+    pub fn toggle_focus(&mut self, has_selection: bool) {
+        self.focused = !self.focused && has_selection;
+    }
+
+    /// Scrolls down, unless the last of `len` lines is at the top already
+    pub fn scroll_down(&mut self, len: usize) {
+        self.scroll = (self.scroll + 1).min(len.saturating_sub(1));
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_wraps_around() {
+        let mut list = ListSelection::default();
+        list.prev(3);
+        assert_eq!(list.selected, Some(2));
+        list.next(3);
+        assert_eq!(list.selected, Some(0));
+    }
+
+    #[test]
+    fn selection_follows_shrunk_list() {
+        let mut list = ListSelection { selected: Some(4) };
+        assert_eq!(list.clamped(2), Some(1));
+        assert_eq!(list.clamped(0), None);
+        list.prev(2);
+        assert_eq!(list.selected, Some(0));
+    }
+
+    #[test]
+    fn selecting_stays_within_the_list() {
+        let mut list = ListSelection { selected: Some(1) };
+        list.select(3, 3);
+        assert_eq!(list.selected, Some(1));
+        list.select(2, 3);
+        assert_eq!(list.selected, Some(2));
+    }
+
+    #[test]
+    fn detail_pane_needs_a_selection() {
+        let mut detail = DetailPane::default();
+        detail.toggle_focus(false);
+        assert!(!detail.focused);
+        detail.toggle_focus(true);
+        assert!(detail.focused);
+        detail.toggle_focus(true);
+        assert!(!detail.focused);
+    }
+
+    #[test]
+    fn detail_pane_scrolls_within_its_lines() {
+        let mut detail = DetailPane::default();
+        detail.scroll_up();
+        assert_eq!(detail.scroll, 0);
+        for _ in 0..5 {
+            detail.scroll_down(3);
+        }
+        assert_eq!(detail.scroll, 2);
+    }
+}
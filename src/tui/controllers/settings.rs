@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use crate::tui::input::Message;
+
+use super::ListSelection;
+
+/// What has to happen in response to a message on the settings screen
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingsAction {
+    /// Save the new values of the changed settings, by index
+    Save(BTreeMap<usize, bool>),
+    /// Open the row below the settings which can be toggled, by index
+    /// counted from the first of these rows
+    Open(usize),
+}
+
+/// Toggles the global options of Syncthing, which are saved all at once.
+/// Below them are rows which are opened instead, and changed on their own.
+#[derive(Clone, Debug, Default)]
+pub struct SettingsController {
+    pub list: ListSelection,
+    /// New values of the settings toggled since they were saved, by index
+    pub changes: BTreeMap<usize, bool>,
+}
+
+impl SettingsController {
+    /// The value of the setting at `index` as shown, with `saved` its value
+    /// in Syncthing
+    pub fn value(&self, index: usize, saved: bool) -> bool {
+        self.changes.get(&index).copied().unwrap_or(saved)
+    }
+
+    /// Handles `msg`, with `saved` the values of the settings in Syncthing and
+    /// `others` the number of rows below them
+    pub fn update(
+        &mut self,
+        msg: &Message,
+        saved: &[bool],
+        others: usize,
+    ) -> Option<SettingsAction> {
+        let len = saved.len() + others;
+        // Changes are dropped once Syncthing has them
+        self.changes
+            .retain(|index, value| saved.get(*index).is_some_and(|saved| saved != value));
+        match msg {
+            Message::Down => self.list.next(len),
+            Message::Up => self.list.prev(len),
+            Message::ClickRow(index) if self.list.clamped(len) != Some(*index) => {
+                self.list.select(*index, len)
+            }
+            Message::Select | Message::ClickRow(_) => {
+                if let Some(index) = self.list.clamped(len).filter(|i| *i >= saved.len()) {
+                    return Some(SettingsAction::Open(index - saved.len()));
+                }
+                self.toggle(saved)
+            }
+            Message::Left | Message::Right => self.toggle(saved),
+            Message::SaveSettings | Message::Submit if !self.changes.is_empty() => {
+                return Some(SettingsAction::Save(self.changes.clone()));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn toggle(&mut self, saved: &[bool]) {
+        let Some(index) = self.list.selected.filter(|i| *i < saved.len()) else {
+            return;
+        };
+        let value = !self.value(index, saved[index]);
+        if value == saved[index] {
+            self.changes.remove(&index);
+        } else {
+            self.changes.insert(index, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_toggled_settings() {
+        let saved = [true, false];
+        let mut controller = SettingsController::default();
+        assert_eq!(controller.update(&Message::SaveSettings, &saved, 0), None);
+
+        controller.update(&Message::Down, &saved, 0);
+        controller.update(&Message::Down, &saved, 0);
+        controller.update(&Message::Select, &saved, 0);
+        assert!(controller.value(1, saved[1]));
+        assert_eq!(
+            controller.update(&Message::SaveSettings, &saved, 0),
+            Some(SettingsAction::Save(BTreeMap::from([(1, true)])))
+        );
+    }
+
+    #[test]
+    fn forgets_changes_once_saved() {
+        let mut controller = SettingsController {
+            list: ListSelection { selected: Some(0) },
+            ..Default::default()
+        };
+        controller.update(&Message::Select, &[false], 0);
+        controller.update(&Message::Select, &[false], 0);
+        assert!(controller.changes.is_empty());
+
+        controller.update(&Message::Select, &[false], 0);
+        controller.update(&Message::None, &[true], 0);
+        assert!(controller.changes.is_empty());
+    }
+
+    #[test]
+    fn opens_rows_below_settings() {
+        let saved = [true];
+        let mut controller = SettingsController {
+            list: ListSelection { selected: Some(1) },
+            ..Default::default()
+        };
+        controller.update(&Message::Right, &saved, 2);
+        assert!(controller.changes.is_empty());
+        controller.update(&Message::Down, &saved, 2);
+        assert_eq!(
+            controller.update(&Message::Select, &saved, 2),
+            Some(SettingsAction::Open(1))
+        );
+    }
+}
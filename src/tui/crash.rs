@@ -0,0 +1,84 @@
+use std::{
+    backtrace::Backtrace,
+    fs,
+    io::{self, Write},
+    panic::PanicHookInfo,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use super::app::CurrentScreen;
+
+/// Number of log lines included in a crash report
+const LOG_LINES: usize = 50;
+
+static LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
+static SCREEN: Mutex<Option<CurrentScreen>> = Mutex::new(None);
+
+/// Remembers where logs are written to, so their end can be included in a
+/// crash report
+pub fn set_log_file(path: PathBuf) {
+    let _ = LOG_FILE.set(path);
+}
+
+/// Where logs are written to, if logging has been set up
+pub fn log_file() -> Option<&'static PathBuf> {
+    LOG_FILE.get()
+}
+
+/// Remembers the screen which is shown, to include it in a crash report
+pub fn set_screen(screen: &CurrentScreen) {
+    if let Ok(mut current) = SCREEN.lock() {
+        *current = Some(screen.clone());
+    }
+}
+
+/// Writes a report about the panic described by `info` into the cache
+/// directory, and returns its path
+pub fn write_report(info: &PanicHookInfo) -> io::Result<PathBuf> {
+    let dir = dirs::cache_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no cache directory"))?
+        .join("synctui");
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!(
+        "crash-{}.txt",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    let mut file = fs::File::create(&path)?;
+    writeln!(file, "synctui {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(
+        file,
+        "OS: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )?;
+    // Never block here, the panic might have happened while holding the lock
+    let screen = SCREEN.try_lock().ok().and_then(|screen| screen.clone());
+    match screen {
+        Some(screen) => writeln!(file, "Screen: {:?}", screen)?,
+        None => writeln!(file, "Screen: unknown")?,
+    }
+    writeln!(file)?;
+    writeln!(file, "{}", info)?;
+    writeln!(file)?;
+    writeln!(file, "Backtrace:")?;
+    writeln!(file, "{}", Backtrace::force_capture())?;
+
+    match LOG_FILE.get().map(fs::read_to_string) {
+        Some(Ok(log)) => {
+            let lines: Vec<_> = log.lines().collect();
+            writeln!(file, "Last {} log lines:", LOG_LINES)?;
+            for line in &lines[lines.len().saturating_sub(LOG_LINES)..] {
+                writeln!(file, "{}", line)?;
+            }
+        }
+        Some(Err(e)) => writeln!(file, "Could not read log file: {}", e)?,
+        None => writeln!(
+            file,
+            "Logging was disabled, run with --log-level to enable it"
+        )?,
+    }
+
+    Ok(path)
+}
@@ -0,0 +1,124 @@
+//! Watches the paths of folders on this machine, to tell about changed files
+//! before Syncthing's scanner notices them.
+
+use std::path::PathBuf;
+#[cfg(feature = "watch-local")]
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "watch-local")]
+use notify::{EventKind, RecursiveMode, Watcher};
+#[cfg(feature = "watch-local")]
+use tokio::sync::broadcast::error::RecvError;
+
+use super::state::State;
+#[cfg(feature = "watch-local")]
+use super::state::StateChange;
+
+/// Starts watching the paths of all unpaused folders in the background,
+/// following changes to the configuration. Failing to do so only disables
+/// the hints.
+#[cfg(feature = "watch-local")]
+pub fn watch(state: State) {
+    // Folder IDs by the path they are watched at
+    let watched: Arc<Mutex<HashMap<PathBuf, String>>> = Arc::default();
+
+    let event_state = state.clone();
+    let event_watched = watched.clone();
+    let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let event = match res {
+            Ok(event) => event,
+            Err(e) => {
+                log::debug!("watching folders failed: {:?}", e);
+                return;
+            }
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in event.paths.iter().filter(|path| !is_syncthing_file(path)) {
+            let folder_id = event_watched
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(root, _)| path.starts_with(root))
+                .max_by_key(|(root, _)| root.components().count())
+                .map(|(_, folder_id)| folder_id.clone());
+            if let Some(folder_id) = folder_id {
+                event_state.local_change(&folder_id);
+            }
+        }
+    });
+    let mut watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            log::warn!("could not watch folders for local changes: {:?}", e);
+            return;
+        }
+    };
+
+    let mut change_rx = state.subscribe_to_changes();
+    tokio::spawn(async move {
+        loop {
+            let paths: HashMap<PathBuf, String> = state.read(|state| {
+                state
+                    .get_folders()
+                    .into_iter()
+                    .filter(|folder| !folder.config.paused)
+                    .map(|folder| (local_path(&folder.config.path), folder.config.id.clone()))
+                    .collect()
+            });
+            {
+                let mut watched = watched.lock().unwrap();
+                let old: HashSet<PathBuf> = watched.keys().cloned().collect();
+                for path in old.iter().filter(|path| !paths.contains_key(*path)) {
+                    let _ = watcher.unwatch(path);
+                }
+                for path in paths.keys().filter(|path| !old.contains(*path)) {
+                    if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
+                        log::debug!("could not watch {}: {:?}", path.display(), e);
+                    }
+                }
+                *watched = paths;
+            }
+
+            // Only folders change which paths are watched
+            loop {
+                match change_rx.recv().await {
+                    Ok(StateChange::Folders) | Err(RecvError::Lagged(_)) => break,
+                    Ok(_) => continue,
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        }
+    });
+}
+
+#[cfg(not(feature = "watch-local"))]
+pub fn watch(_state: State) {
+    log::warn!("synctui was built without the watch-local feature, folders are not watched");
+}
+
+/// `path` as configured in Syncthing, with a leading `~` expanded
+pub fn local_path(path: &str) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches(['/', '\\'])),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Whether `path` is written by Syncthing itself, e.g., the folder marker or
+/// a temporary file of a download
+#[cfg(feature = "watch-local")]
+fn is_syncthing_file(path: &Path) -> bool {
+    path.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        name == ".stfolder"
+            || name == ".stversions"
+            || (name.starts_with(".syncthing.") && name.ends_with(".tmp"))
+            || (name.starts_with("~syncthing~") && name.ends_with(".tmp"))
+    })
+}
@@ -0,0 +1,120 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+
+/// How many versions of the state are kept
+const MAX_STATE_VERSIONS: usize = 200;
+
+/// What could be observed of the state at some point, keyed by what it
+/// describes, e.g., `folder abcd-1234 completion`. Ordered, so that related
+/// entries stay together.
+pub type StateSummary = BTreeMap<String, String>;
+
+/// A single difference between two versions of the state
+#[derive(Clone, Debug, PartialEq)]
+pub enum StateDiff {
+    Added {
+        key: String,
+        value: String,
+    },
+    Removed {
+        key: String,
+        value: String,
+    },
+    Changed {
+        key: String,
+        from: String,
+        to: String,
+    },
+}
+
+/// The state after something changed it
+#[derive(Clone, Debug)]
+pub struct StateVersion {
+    /// Counts up across all versions, also the dropped ones
+    pub seq: u64,
+    pub time: DateTime<Utc>,
+    /// What changed the state, e.g., the events or the reload
+    pub cause: String,
+    /// Reloads the cause started, which show up as versions of their own
+    pub triggered: Vec<String>,
+    pub diff: Vec<StateDiff>,
+    pub summary: StateSummary,
+}
+
+/// The most recent versions of the state, to step through them when
+/// tracking down races between events and reloads
+#[derive(Debug, Default)]
+pub struct StateHistory {
+    versions: VecDeque<StateVersion>,
+    next_seq: u64,
+}
+
+impl StateHistory {
+    /// Records the state `after` `cause` changed it from `before`. Nothing
+    /// is recorded if nothing observable changed.
+    pub fn record(
+        &mut self,
+        time: DateTime<Utc>,
+        cause: String,
+        triggered: Vec<String>,
+        before: &StateSummary,
+        after: StateSummary,
+    ) {
+        let diff = diff(before, &after);
+        if diff.is_empty() {
+            return;
+        }
+        log::debug!(
+            "state version {}: {} changed {} entries",
+            self.next_seq,
+            cause,
+            diff.len()
+        );
+        if self.versions.len() == MAX_STATE_VERSIONS {
+            self.versions.pop_front();
+        }
+        self.versions.push_back(StateVersion {
+            seq: self.next_seq,
+            time,
+            cause,
+            triggered,
+            diff,
+            summary: after,
+        });
+        self.next_seq += 1;
+    }
+
+    /// The kept versions, oldest first
+    pub fn versions(&self) -> &VecDeque<StateVersion> {
+        &self.versions
+    }
+}
+
+/// What changed from `before` to `after`
+pub fn diff(before: &StateSummary, after: &StateSummary) -> Vec<StateDiff> {
+    let mut diff = Vec::new();
+    for (key, value) in after {
+        match before.get(key) {
+            None => diff.push(StateDiff::Added {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            Some(old) if old != value => diff.push(StateDiff::Changed {
+                key: key.clone(),
+                from: old.clone(),
+                to: value.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, value) in before {
+        if !after.contains_key(key) {
+            diff.push(StateDiff::Removed {
+                key: key.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+    diff
+}
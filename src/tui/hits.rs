@@ -0,0 +1,42 @@
+use ratatui::layout::{Position, Rect};
+
+use super::app::CurrentScreen;
+
+/// Something drawn which reacts to clicks
+#[derive(Clone, Debug, PartialEq)]
+pub enum HitTarget {
+    /// The tab of a screen in the bottom border
+    Tab(CurrentScreen),
+    /// The rows of the list on the current screen, with `offset` the index
+    /// of the row at the top of the area
+    Rows { offset: usize },
+    /// The button of the popup with this index
+    Button(usize),
+}
+
+/// Where the clickable parts of the last frame were drawn. Filled while
+/// rendering, and looked up when the mouse is clicked.
+#[derive(Debug, Default)]
+pub struct HitMap {
+    targets: Vec<(Rect, HitTarget)>,
+}
+
+impl HitMap {
+    pub fn clear(&mut self) {
+        self.targets.clear();
+    }
+
+    pub fn add(&mut self, area: Rect, target: HitTarget) {
+        self.targets.push((area, target));
+    }
+
+    /// The target at `column` and `row` which was drawn last, i.e., is on top,
+    /// together with its area
+    pub fn hit(&self, column: u16, row: u16) -> Option<(Rect, HitTarget)> {
+        self.targets
+            .iter()
+            .rev()
+            .find(|(area, _)| area.contains(Position::new(column, row)))
+            .cloned()
+    }
+}
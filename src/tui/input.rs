@@ -3,13 +3,23 @@ use futures::StreamExt;
 use log::debug;
 use ratatui::crossterm::{
     self,
-    event::{Event as CrosstermEvent, KeyCode, KeyEvent, KeyEventKind},
+    event::{
+        Event as CrosstermEvent, KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
 };
 use syncthing_rs::types::config::{
-    DeviceConfiguration, FolderConfiguration, NewFolderConfiguration,
+    DeviceConfiguration, FolderConfiguration, NewDeviceConfiguration, NewFolderConfiguration,
 };
 
-use super::app::CurrentMode;
+use crate::rest::{GuiField, RateLimits};
+
+use super::{
+    app::CurrentMode,
+    keymap::Keymap,
+    notes::{Note, NoteTarget},
+    search::SearchTarget,
+};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Message {
@@ -28,10 +38,101 @@ pub enum Message {
     Down,
     Right,
     Left,
+    // Mouse
+    /// A left click at this position of the terminal
+    Click {
+        column: u16,
+        row: u16,
+    },
+    /// Selects the row of the list with this index, or activates it if it
+    /// is selected already
+    ClickRow(usize),
+    /// Activates the button of the popup with this index
+    Press(usize),
+    // Layout
+    GrowSplit,
+    ShrinkSplit,
+    // IDs
+    RevealIds,
+    ToggleIds,
+    CopyId,
     // General
     Add,
+    Accept,
+    Pause,
+    Remove,
+    /// Pins the selected folder to the top, or unpins it
+    Favorite,
+    Filter,
+    SetFilter(String),
+    Commands,
+    /// Checks why the selected folder does not sync
+    Diagnose,
+    /// Scans the selected folder, retrying items which failed
+    Rescan,
+    Note,
+    SetNote {
+        target: NoteTarget,
+        note: Note,
+    },
+    /// Runs the folder command with this index in the config
+    RunCommand {
+        command: usize,
+        folder_id: String,
+    },
+    CommandOutput {
+        title: String,
+        output: String,
+    },
+    Search,
+    Recent,
+    /// Reopens the most recent pending popup closed without a decision
+    Snoozed,
+    /// Shows the errors which have not been acknowledged yet
+    Errors,
+    ClearErrors,
+    /// Opens the menu to change the log level
+    LogLevel,
+    SetLogLevel(log::LevelFilter),
+    /// Shows the global announce option in the raw configuration
+    GlobalAnnounce,
+    /// Shows how synctui authenticates against Syncthing
+    Session,
+    RotateApiKey,
+    ApiKeyRotated(String),
+    /// Writes the rotated API key to the config file
+    SaveApiKey(String),
+    /// Shows the rotated API key, to store it elsewhere
+    ShowApiKey(String),
+    /// Opens the popup to change a setting of the GUI
+    EditGui(GuiField),
+    SetGui {
+        field: GuiField,
+        value: String,
+    },
+    /// Starts or stops recording events to a file
+    ToggleRecorder,
+    /// Steps through the recent versions of the state
+    StateHistory,
+    /// Opens the menu to switch profiles
+    Profiles,
+    /// Applies the profile with this name, or none
+    SwitchProfile(Option<String>),
+    /// Shows or hides advanced options, destructive actions and the raw
+    /// configuration
+    ToggleAdvanced,
+    /// Clears the filter and expands all groups of the current page
+    ClearView,
+    /// Saves the changed settings
+    SaveSettings,
+    /// Opens the popup to limit the bandwidth of all connections
+    RateLimits,
+    SetRateLimits(RateLimits),
+    Help,
+    Jump(SearchTarget),
     Quit,
     Reload,
+    Restart,
     Select,
     Submit,
     // Popups
@@ -52,50 +153,56 @@ pub enum Message {
         folder_id: String,
         device_id: String,
     },
+    IgnoreFolder {
+        folder_id: String,
+        device_id: String,
+    },
     DismissFolder {
         folder_id: String,
         device_id: String,
     },
+    /// Asks whether to dismiss or ignore all folders offered by the device
+    /// offering the selected folder
+    DismissOffers,
+    IgnoreFoldersOf(String),
+    DismissFoldersOf(String),
     // Folder
     EditFolder(Box<FolderConfiguration>),
     RemoveFolder(String),
     // Device
-    EditDevice(Box<DeviceConfiguration>),
+    AddressBook,
+    /// Opens the popup to add a device, pre-filled with this ID and name
+    AddKnownDevice {
+        device_id: String,
+        name: String,
+    },
+    AddDevice(Box<NewDeviceConfiguration>),
+    EditDevice(Box<DeviceConfiguration>, RateLimits),
     RemoveDevice(String),
+    /// A destructive message the user agreed to in a confirmation popup
+    Confirmed(Box<Message>),
     None,
 }
 
-pub fn handler(key_event: KeyEvent, mode: CurrentMode) -> Message {
+/// Translates a key into a message. In normal mode, keys are looked up in
+/// the `keymap`, while insert mode only respects bindings with Ctrl or Alt, so
+/// that all other keys can be typed.
+pub fn handler(key_event: KeyEvent, mode: CurrentMode, keymap: &Keymap) -> Message {
     if mode == CurrentMode::Normal {
-        match key_event.code {
-            KeyCode::Char('r') => Message::Reload,
-            KeyCode::Char('q') => Message::Quit,
-            KeyCode::Char('j') | KeyCode::Down => Message::Down,
-            KeyCode::Char('k') | KeyCode::Up => Message::Up,
-            KeyCode::Char('l') | KeyCode::Right => Message::Right,
-            KeyCode::Char('h') | KeyCode::Left => Message::Left,
-            KeyCode::Char('i') => Message::Insert,
-            KeyCode::Char('+') | KeyCode::Char('o') => Message::Add,
-            KeyCode::Enter => {
-                if key_event.modifiers.contains(KeyModifiers::SHIFT) {
-                    // BUG this does not work on Linux and Mac
-                    Message::Submit
-                } else {
-                    Message::Select
-                }
-            }
-            KeyCode::Tab => Message::FocusNext,
-            KeyCode::BackTab => Message::FocusBack,
-            KeyCode::Char(a) => {
-                if let Some(a) = a.to_digit(10) {
-                    Message::Number(a)
-                } else {
-                    Message::None
-                }
-            }
-            _ => Message::None,
+        if let KeyCode::Char(c) = key_event.code
+            && let Some(number) = c.to_digit(10)
+        {
+            return Message::Number(number);
         }
+        keymap.message(&key_event).unwrap_or(Message::None)
     } else {
+        if key_event
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+            && let Some(msg) = keymap.message(&key_event)
+        {
+            return msg;
+        }
         match key_event.code {
             KeyCode::Char('+') => Message::Add,
             KeyCode::Char(a) => Message::Character(a),
@@ -120,9 +227,24 @@ pub fn handler(key_event: KeyEvent, mode: CurrentMode) -> Message {
     }
 }
 
+/// Translates a click or scroll into a message. Clicks are only resolved
+/// once it is known what was drawn at their position.
+pub fn mouse_handler(mouse_event: MouseEvent) -> Message {
+    match mouse_event.kind {
+        MouseEventKind::Down(MouseButton::Left) => Message::Click {
+            column: mouse_event.column,
+            row: mouse_event.row,
+        },
+        MouseEventKind::ScrollDown => Message::Down,
+        MouseEventKind::ScrollUp => Message::Up,
+        _ => Message::None,
+    }
+}
+
 #[derive(Debug)]
 pub enum Event {
     Key(crossterm::event::KeyEvent),
+    Mouse(MouseEvent),
 }
 
 pub struct EventHandler {
@@ -137,11 +259,23 @@ impl EventHandler {
             let mut reader = crossterm::event::EventStream::new();
             loop {
                 let event = reader.next().await;
-                if let Some(Ok(CrosstermEvent::Key(key))) = event {
-                    if key.kind == KeyEventKind::Press {
+                match event {
+                    Some(Ok(CrosstermEvent::Key(key))) if key.kind == KeyEventKind::Press => {
                         debug!("got key {key:?} - sending");
                         tx.send(Event::Key(key)).unwrap();
                     }
+                    // Moving the mouse would redraw for nothing
+                    Some(Ok(CrosstermEvent::Mouse(mouse)))
+                        if matches!(
+                            mouse.kind,
+                            MouseEventKind::Down(MouseButton::Left)
+                                | MouseEventKind::ScrollDown
+                                | MouseEventKind::ScrollUp
+                        ) =>
+                    {
+                        tx.send(Event::Mouse(mouse)).unwrap();
+                    }
+                    _ => {}
                 }
             }
         });
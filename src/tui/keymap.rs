@@ -0,0 +1,511 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::{app::CurrentScreen, input::Message};
+use crate::AppError;
+
+/// Something the user can trigger with a key in normal mode
+#[derive(Debug)]
+pub struct Action {
+    /// Stable name, used to override the keys in the config file
+    pub id: &'static str,
+    pub default_keys: &'static [&'static str],
+    pub description: &'static str,
+    /// Screens on which this action does something, all if empty
+    pub screens: &'static [CurrentScreen],
+    message: fn() -> Message,
+}
+
+/// Every action which can be bound to a key. Switching screens with the
+/// digits is not part of it, as it cannot be rebound.
+pub const ACTIONS: &[Action] = &[
+    Action {
+        id: "quit",
+        default_keys: &["q"],
+        description: "Quit, or close the popup",
+        screens: &[],
+        message: || Message::Quit,
+    },
+    Action {
+        id: "reload",
+        default_keys: &["r"],
+        description: "Reload the configuration",
+        screens: &[],
+        message: || Message::Reload,
+    },
+    Action {
+        id: "restart",
+        default_keys: &["R"],
+        description: "Restart Syncthing",
+        screens: &[],
+        message: || Message::Restart,
+    },
+    Action {
+        id: "help",
+        default_keys: &["?"],
+        description: "Show all keys",
+        screens: &[],
+        message: || Message::Help,
+    },
+    Action {
+        id: "search",
+        default_keys: &["Ctrl+f"],
+        description: "Search folders, devices, pending items and events",
+        screens: &[],
+        message: || Message::Search,
+    },
+    Action {
+        id: "recent",
+        default_keys: &["Ctrl+o"],
+        description: "Jump back to a recently viewed folder or device",
+        screens: &[],
+        message: || Message::Recent,
+    },
+    Action {
+        id: "snoozed",
+        default_keys: &["z"],
+        description: "Reopen the last pending request closed without a decision",
+        screens: &[],
+        message: || Message::Snoozed,
+    },
+    Action {
+        id: "errors",
+        default_keys: &["e"],
+        description: "Show the errors, to acknowledge them",
+        screens: &[],
+        message: || Message::Errors,
+    },
+    Action {
+        id: "log-level",
+        default_keys: &["L"],
+        description: "Change how verbose the log file is, without restarting",
+        screens: &[],
+        message: || Message::LogLevel,
+    },
+    Action {
+        id: "global-announce",
+        default_keys: &["g"],
+        description: "Review global announce, e.g., after many unknown devices tried to connect",
+        screens: &[],
+        message: || Message::GlobalAnnounce,
+    },
+    Action {
+        id: "session",
+        default_keys: &["A"],
+        description: "Show how synctui connects to Syncthing, and rotate the API key",
+        screens: &[],
+        message: || Message::Session,
+    },
+    Action {
+        id: "record-events",
+        default_keys: &["E"],
+        description: "Start or stop recording all events to a file",
+        screens: &[],
+        message: || Message::ToggleRecorder,
+    },
+    Action {
+        id: "state-history",
+        default_keys: &["H"],
+        description: "Step through the recent versions of the state, with --debug-state",
+        screens: &[],
+        message: || Message::StateHistory,
+    },
+    Action {
+        id: "profiles",
+        default_keys: &["P"],
+        description: "Switch to another profile",
+        screens: &[],
+        message: || Message::Profiles,
+    },
+    Action {
+        id: "advanced",
+        default_keys: &["M"],
+        description: "Show or hide advanced options, destructive actions and the raw configuration",
+        screens: &[],
+        message: || Message::ToggleAdvanced,
+    },
+    Action {
+        id: "rate-limits",
+        default_keys: &["B"],
+        description: "Limit the bandwidth of all connections",
+        screens: &[],
+        message: || Message::RateLimits,
+    },
+    Action {
+        id: "down",
+        default_keys: &["j", "Down"],
+        description: "Move down",
+        screens: &[],
+        message: || Message::Down,
+    },
+    Action {
+        id: "up",
+        default_keys: &["k", "Up"],
+        description: "Move up",
+        screens: &[],
+        message: || Message::Up,
+    },
+    Action {
+        id: "right",
+        default_keys: &["l", "Right"],
+        description: "Move right",
+        screens: &[],
+        message: || Message::Right,
+    },
+    Action {
+        id: "left",
+        default_keys: &["h", "Left"],
+        description: "Move left",
+        screens: &[],
+        message: || Message::Left,
+    },
+    Action {
+        id: "focus-next",
+        default_keys: &["Tab"],
+        description: "Focus the next pane or field",
+        screens: &[],
+        message: || Message::FocusNext,
+    },
+    Action {
+        id: "focus-back",
+        default_keys: &["BackTab"],
+        description: "Focus the previous pane or field",
+        screens: &[],
+        message: || Message::FocusBack,
+    },
+    Action {
+        id: "select",
+        default_keys: &["Enter"],
+        description: "Open the selected item, or press the focused button",
+        screens: &[],
+        message: || Message::Select,
+    },
+    Action {
+        id: "submit",
+        default_keys: &["Shift+Enter"],
+        description: "Submit the popup",
+        screens: &[],
+        message: || Message::Submit,
+    },
+    Action {
+        id: "insert",
+        default_keys: &["i"],
+        description: "Enter insert mode to edit text fields",
+        screens: &[],
+        message: || Message::Insert,
+    },
+    Action {
+        id: "add",
+        default_keys: &["+", "o"],
+        description: "Add a new folder or device",
+        screens: &[CurrentScreen::Folders, CurrentScreen::Devices],
+        message: || Message::Add,
+    },
+    Action {
+        id: "accept",
+        default_keys: &["a"],
+        description: "Accept pending offers of the selected folder",
+        screens: &[CurrentScreen::Folders],
+        message: || Message::Accept,
+    },
+    Action {
+        id: "pause",
+        default_keys: &["p"],
+        description: "Pause or resume the selected folder or device, or pause the selected stale device",
+        screens: &[
+            CurrentScreen::Folders,
+            CurrentScreen::Devices,
+            CurrentScreen::Health,
+        ],
+        message: || Message::Pause,
+    },
+    Action {
+        id: "remove",
+        default_keys: &["x", "d"],
+        description: "Remove the selected device from all folders and forget it",
+        screens: &[CurrentScreen::Devices, CurrentScreen::Health],
+        message: || Message::Remove,
+    },
+    Action {
+        id: "dismiss-offers",
+        default_keys: &["D"],
+        description: "Dismiss or ignore all folders offered by the device offering the selected folder",
+        screens: &[CurrentScreen::Pending],
+        message: || Message::DismissOffers,
+    },
+    Action {
+        id: "note",
+        default_keys: &["n"],
+        description: "Edit the notes and tags of the selected folder or device",
+        screens: &[CurrentScreen::Folders, CurrentScreen::Devices],
+        message: || Message::Note,
+    },
+    Action {
+        id: "address-book",
+        default_keys: &["b"],
+        description: "Re-add a device from the address book",
+        screens: &[CurrentScreen::Devices],
+        message: || Message::AddressBook,
+    },
+    Action {
+        id: "favorite",
+        default_keys: &["f"],
+        description: "Pin the selected folder to the top, or unpin it",
+        screens: &[CurrentScreen::Folders],
+        message: || Message::Favorite,
+    },
+    Action {
+        id: "commands",
+        default_keys: &["c"],
+        description: "Run a custom command on the selected folder",
+        screens: &[CurrentScreen::Folders],
+        message: || Message::Commands,
+    },
+    Action {
+        id: "diagnose",
+        default_keys: &["w"],
+        description: "Find out why the selected folder does not sync",
+        screens: &[CurrentScreen::Folders],
+        message: || Message::Diagnose,
+    },
+    Action {
+        id: "rescan",
+        default_keys: &["s"],
+        description: "Rescan the selected folder, retrying items which failed",
+        screens: &[CurrentScreen::Folders],
+        message: || Message::Rescan,
+    },
+    Action {
+        id: "filter",
+        default_keys: &["/"],
+        description: "Filter the configuration, or the devices a folder is not shared with",
+        screens: &[CurrentScreen::Config],
+        message: || Message::Filter,
+    },
+    Action {
+        id: "clear-view",
+        default_keys: &["C"],
+        description: "Clear the filter, or expand all collapsed folder groups",
+        screens: &[CurrentScreen::Folders, CurrentScreen::Config],
+        message: || Message::ClearView,
+    },
+    Action {
+        id: "save-settings",
+        default_keys: &["S"],
+        description: "Save the changed settings",
+        screens: &[CurrentScreen::Settings],
+        message: || Message::SaveSettings,
+    },
+    Action {
+        id: "grow-split",
+        default_keys: &[">"],
+        description: "Widen the list pane",
+        screens: &[
+            CurrentScreen::Folders,
+            CurrentScreen::Devices,
+            CurrentScreen::Mesh,
+        ],
+        message: || Message::GrowSplit,
+    },
+    Action {
+        id: "shrink-split",
+        default_keys: &["<"],
+        description: "Narrow the list pane",
+        screens: &[
+            CurrentScreen::Folders,
+            CurrentScreen::Devices,
+            CurrentScreen::Mesh,
+        ],
+        message: || Message::ShrinkSplit,
+    },
+    Action {
+        id: "reveal-ids",
+        default_keys: &["v"],
+        description: "Show IDs in full",
+        screens: &[],
+        message: || Message::RevealIds,
+    },
+    Action {
+        id: "toggle-ids",
+        default_keys: &["t"],
+        description: "List folders and devices by ID instead of label and name",
+        screens: &[CurrentScreen::Folders, CurrentScreen::Devices],
+        message: || Message::ToggleIds,
+    },
+    Action {
+        id: "copy-id",
+        default_keys: &["y"],
+        description: "Copy the ID of the selected item",
+        screens: &[
+            CurrentScreen::Folders,
+            CurrentScreen::Devices,
+            CurrentScreen::Pending,
+        ],
+        message: || Message::CopyId,
+    },
+];
+
+/// A key together with its modifiers, e.g., `Ctrl+f`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeySpec {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    fn matches(&self, event: &KeyEvent) -> bool {
+        // Shift is implied by the character itself, and always reported
+        // together with BackTab
+        let ignored = match self.code {
+            KeyCode::Char(_) | KeyCode::BackTab => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+        self.code == event.code
+            && self.modifiers.difference(ignored) == event.modifiers.difference(ignored)
+    }
+}
+
+impl FromStr for KeySpec {
+    type Err = AppError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || AppError::InvalidKey(spec.to_string());
+        let (modifiers, key) = if spec.len() > 1 && spec.ends_with("++") {
+            (&spec[..spec.len() - 2], "+")
+        } else {
+            match spec.rsplit_once('+') {
+                Some((modifiers, key)) if !key.is_empty() => (modifiers, key),
+                _ => ("", spec),
+            }
+        };
+
+        let mut parsed_modifiers = KeyModifiers::NONE;
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            parsed_modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "enter" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "esc" => KeyCode::Esc,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                f => match f.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(invalid()),
+                },
+            },
+        };
+
+        Ok(Self {
+            code,
+            modifiers: parsed_modifiers,
+        })
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// The effective key bindings, i.e., the defaults of [`ACTIONS`] with the
+/// overrides of the config file applied
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(&'static Action, Vec<KeySpec>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&HashMap::new()).expect("default keys are valid")
+    }
+}
+
+impl Keymap {
+    /// Builds the keymap, where `overrides` maps action IDs to the keys
+    /// replacing the default ones
+    pub fn new(overrides: &HashMap<String, Vec<String>>) -> Result<Self, AppError> {
+        if let Some(unknown) = overrides
+            .keys()
+            .find(|id| !ACTIONS.iter().any(|a| a.id == id.as_str()))
+        {
+            return Err(AppError::UnknownAction(unknown.clone()));
+        }
+
+        let bindings = ACTIONS
+            .iter()
+            .map(|action| {
+                let keys: Result<Vec<KeySpec>, _> = match overrides.get(action.id) {
+                    Some(keys) => keys.iter().map(|k| k.parse()).collect(),
+                    None => action.default_keys.iter().map(|k| k.parse()).collect(),
+                };
+                keys.map(|keys| (action, keys))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { bindings })
+    }
+
+    /// The message of the action bound to `event`, if any
+    pub fn message(&self, event: &KeyEvent) -> Option<Message> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|key| key.matches(event)))
+            .map(|(action, _)| (action.message)())
+    }
+
+    /// The keys of the action `id` as text, e.g., `j, Down`
+    pub fn keys(&self, id: &str) -> String {
+        self.describe()
+            .into_iter()
+            .find(|(_, action)| action.id == id)
+            .map(|(keys, _)| keys)
+            .unwrap_or_default()
+    }
+
+    /// Every action together with its keys as text, e.g., `j, Down`
+    pub fn describe(&self) -> Vec<(String, &'static Action)> {
+        self.bindings
+            .iter()
+            .map(|(action, keys)| {
+                let keys: Vec<_> = keys.iter().map(|k| k.to_string()).collect();
+                (keys.join(", "), *action)
+            })
+            .collect()
+    }
+}
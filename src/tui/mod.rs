@@ -7,25 +7,42 @@ use ui::ui;
 
 use app::{App, CurrentMode};
 use color_eyre::eyre;
+
+use crate::{AppConfig, RestClient};
 use ratatui::{
     Terminal,
     crossterm::{
         event::{DisableMouseCapture, EnableMouseCapture},
         execute,
-        terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+        terminal::{
+            EnterAlternateScreen, LeaveAlternateScreen, SetTitle, disable_raw_mode, enable_raw_mode,
+        },
     },
     prelude::{Backend, CrosstermBackend},
 };
 
 mod app;
+mod clipboard;
+pub mod control;
+mod crash;
+mod disk_watch;
+mod history;
+mod hits;
 mod input;
+pub mod keymap;
+mod notes;
 mod popup;
+pub mod recorder;
+mod search;
+mod setup;
+mod snapshot;
 pub mod state;
-mod ui;
+pub mod ui;
+mod ui_state;
 
 mod pages {
     mod folders;
-    pub use folders::FoldersPage;
+    pub use folders::{FolderRow, FoldersPage, UNGROUPED, folder_rows};
     mod devices;
     pub use devices::DevicesPage;
     mod id;
@@ -33,9 +50,63 @@ mod pages {
     mod pending;
     pub use pending::PendingPage;
     pub use pending::PendingPageState;
+    mod health;
+    pub use health::HealthPage;
+    mod config;
+    pub use config::{ConfigPage, ConfigTreeState, config_rows};
+    mod mesh;
+    pub use mesh::{MeshPage, MeshPageState};
+    mod activity;
+    pub use activity::{ActivityPage, activity_rows};
+    mod system;
+    pub use system::SystemPage;
+    mod settings;
+    pub use settings::{GUI_FIELDS, SETTINGS, SettingsPage, saved_settings};
+}
+
+/// What happens on each screen in response to messages, separate from the
+/// app executing it
+mod controllers {
+    mod list;
+    pub use list::{DetailPane, ListSelection};
+    mod folders;
+    pub use folders::{FolderItem, FoldersAction, FoldersController};
+    mod devices;
+    pub use devices::{DeviceItem, DevicesAction, DevicesController};
+    mod health;
+    pub use health::HealthController;
+    mod activity;
+    pub use activity::ActivityController;
+    mod settings;
+    pub use settings::{SettingsAction, SettingsController};
+}
+
+pub use app::CurrentScreen;
+pub use crash::set_log_file;
+pub use setup::setup;
+
+/// What the TUI shows right after starting
+#[derive(Debug, Default)]
+pub struct StartupView {
+    pub screen: Option<CurrentScreen>,
+    /// ID of the folder to select
+    pub folder: Option<String>,
+    /// ID of the device to select
+    pub device: Option<String>,
+    /// Whether to also open the popup of the selected folder or device
+    pub open_popup: bool,
+    /// ID and optional name of a device to offer adding
+    pub new_device: Option<(String, Option<String>)>,
 }
 
-pub async fn start(client: Client) -> eyre::Result<()> {
+pub async fn start(
+    client: Client,
+    config: AppConfig,
+    startup_view: StartupView,
+) -> eyre::Result<()> {
+    // Fail before touching the terminal if the keys are misconfigured
+    let keymap = keymap::Keymap::new(&config.keys)?;
+
     init_panic_hook();
 
     // Setup terminal
@@ -44,8 +115,13 @@ pub async fn start(client: Client) -> eyre::Result<()> {
 
     let (reload_tx, reload_rx) = mpsc::channel(10);
 
-    let mut app = App::new(client, reload_tx);
+    let rest = RestClient::new(&config)?;
+    let mut app = App::new(client, rest, config, keymap, startup_view, reload_tx);
+    if app.config.control {
+        control::listen(app.state.clone());
+    }
     let _ = run(&mut terminal, &mut app, reload_rx).await;
+    control::close();
 
     //restore terminal
     restore_tui()?;
@@ -55,13 +131,21 @@ pub async fn start(client: Client) -> eyre::Result<()> {
 }
 
 /// Overwrits the default panic hook by first
-/// trying to restore our terminal
+/// trying to restore our terminal, and then saving a crash report
 fn init_panic_hook() {
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
         // Ignore errors, as we are already panicing
         let _ = restore_tui();
+        let report = crash::write_report(panic_info);
         original_hook(panic_info);
+        match report {
+            Ok(path) => eprintln!(
+                "A crash report has been saved to {}, please attach it when reporting this bug.",
+                path.display()
+            ),
+            Err(e) => eprintln!("Failed to save a crash report: {}", e),
+        }
     }));
 }
 
@@ -85,21 +169,36 @@ async fn run<B: Backend>(
     let (msg_tx, mut msg_rx) = mpsc::unbounded_channel();
 
     let mode_handle = app.mode.clone();
+    let keymap = app.keymap.clone();
 
     tokio::spawn(async move {
         let mut event = EventHandler::new();
         loop {
-            let event = event.next().await;
-            if let Some(input::Event::Key(k)) = event {
-                let mode: CurrentMode = { mode_handle.lock().unwrap().clone() };
-                msg_tx.send(input::handler(k, mode)).unwrap()
-            };
+            match event.next().await {
+                Some(input::Event::Key(k)) => {
+                    let mode: CurrentMode = { mode_handle.lock().unwrap().clone() };
+                    let msg = input::handler(k, mode, &keymap.lock().unwrap());
+                    msg_tx.send(msg).unwrap()
+                }
+                Some(input::Event::Mouse(m)) => msg_tx.send(input::mouse_handler(m)).unwrap(),
+                None => {}
+            }
         }
     });
 
+    let mut title = String::new();
+    let mut shutdown = std::pin::pin!(crate::signal::shutdown());
     while app.running {
         debug!("drawing new frame");
+        app.load_shown();
         terminal.draw(|f| ui(f, app))?;
+        if app.config.terminal_title {
+            let new_title = ui::terminal_title(app);
+            if new_title != title {
+                execute!(io::stdout(), SetTitle(&new_title))?;
+                title = new_title;
+            }
+        }
 
         tokio::select! {
             mut msg = msg_rx.recv() =>  {
@@ -112,6 +211,12 @@ async fn run<B: Backend>(
                     msg = app.update(m);
                 }
             }
+            signal = &mut shutdown => {
+                log::info!("received {}, shutting down", signal);
+                app.quit();
+            }
+            // Redraw regularly to animate the spinners
+            _ = tokio::time::sleep(ui::SPINNER_INTERVAL), if app.state.read(|state| state.is_loading()) => {}
         }
     }
     Ok(())
@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// What a note is attached to
+#[derive(Clone, Debug, PartialEq)]
+pub enum NoteTarget {
+    Folder(String),
+    Device(String),
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Note {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Note {
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.tags.is_empty()
+    }
+}
+
+/// Notes and tags on folders and devices, keyed by their ID. Only known to
+/// synctui, and persisted across sessions.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Notes {
+    #[serde(default)]
+    folders: HashMap<String, Note>,
+    #[serde(default)]
+    devices: HashMap<String, Note>,
+}
+
+impl Notes {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|path| path.join("synctui").join("notes.toml"))
+    }
+
+    /// Loads the notes of the last session. Falls back to no notes, so that
+    /// a broken file does not keep synctui from starting.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("failed to parse notes at {}: {:?}", path.display(), e);
+                Self::default()
+            }),
+            Err(e) => {
+                log::debug!("no notes loaded from {}: {:?}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            log::warn!("could not determine where to save the notes");
+            return;
+        };
+        let result = toml::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                fs::write(&path, content).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            log::warn!("failed to save notes to {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn get(&self, target: &NoteTarget) -> Option<&Note> {
+        match target {
+            NoteTarget::Folder(id) => self.folders.get(id),
+            NoteTarget::Device(id) => self.devices.get(id),
+        }
+    }
+
+    /// Replaces the note of `target`, removing it if `note` is empty
+    pub fn set(&mut self, target: NoteTarget, note: Note) {
+        let (map, id) = match target {
+            NoteTarget::Folder(id) => (&mut self.folders, id),
+            NoteTarget::Device(id) => (&mut self.devices, id),
+        };
+        if note.is_empty() {
+            map.remove(&id);
+        } else {
+            map.insert(id, note);
+        }
+    }
+}
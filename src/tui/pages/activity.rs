@@ -0,0 +1,166 @@
+use chrono::Local;
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{List, ListState, StatefulWidget, Widget},
+};
+use syncthing_rs::types::events::{Event, EventType};
+
+use crate::tui::{app::App, hits::HitTarget, state::InnerState};
+
+pub struct ActivityPage<'a> {
+    app: &'a App,
+}
+
+impl<'a> ActivityPage<'a> {
+    pub fn new(app: &'a App) -> Self {
+        Self { app }
+    }
+}
+
+impl Widget for ActivityPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        (&self).render(area, buf);
+    }
+}
+
+impl Widget for &ActivityPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let rows = self.app.state.read(activity_rows);
+        if rows.is_empty() {
+            Line::from(Span::styled(
+                "Nothing happened yet",
+                Style::default().dark_gray(),
+            ))
+            .render(area, buf);
+            return;
+        }
+
+        let selected = self
+            .app
+            .activity
+            .list
+            .selected
+            .map(|i| i.min(rows.len() - 1));
+        let list = List::new(rows).highlight_style(Style::new().bg(Color::DarkGray));
+        let mut list_state = ListState::default().with_selected(selected);
+        StatefulWidget::render(list, area, buf, &mut list_state);
+        self.app.hits.borrow_mut().add(
+            area,
+            HitTarget::Rows {
+                offset: list_state.offset(),
+            },
+        );
+    }
+}
+
+/// The interesting ones of the recent events, newest first
+pub fn activity_rows(state: &InnerState) -> Vec<Line<'static>> {
+    state
+        .events
+        .iter()
+        .rev()
+        .filter_map(|event| activity_row(state, event))
+        .collect()
+}
+
+fn activity_row(state: &InnerState, event: &Event) -> Option<Line<'static>> {
+    let folder = |folder_id: &str| {
+        state
+            .get_folder(folder_id)
+            .map_or(folder_id.to_string(), |folder| folder.config.label.clone())
+    };
+    // Some events only carry the short form of device IDs
+    let device = |device_id: &str| {
+        state
+            .get_devices()
+            .into_iter()
+            .find(|device| device.config.device_id.starts_with(device_id))
+            .map_or(device_id.to_string(), |device| device.config.name.clone())
+    };
+
+    let (badge, style, text) = match &event.ty {
+        EventType::ItemFinished {
+            item,
+            folder: folder_id,
+            error: Some(error),
+            ..
+        } => (
+            "[Failed]",
+            Style::default().red().bold(),
+            format!("{}: {} — {}", folder(folder_id), item, error),
+        ),
+        EventType::ItemFinished {
+            item,
+            folder: folder_id,
+            action,
+            ..
+        } => (
+            "[Synced]",
+            Style::default().green(),
+            format!("{}: {} ({})", folder(folder_id), item, action),
+        ),
+        EventType::LocalChangeDetected {
+            action,
+            folder_id,
+            path,
+            ..
+        } => (
+            "[Local]",
+            Style::default().cyan(),
+            format!("{}: {} {}", folder(folder_id), path, action),
+        ),
+        EventType::RemoteChangeDetected {
+            action,
+            folder_id,
+            path,
+            modified_by,
+            ..
+        } => (
+            "[Remote]",
+            Style::default().blue(),
+            format!(
+                "{}: {} {} by {}",
+                folder(folder_id),
+                path,
+                action,
+                device(modified_by)
+            ),
+        ),
+        EventType::FolderCompletion {
+            completion,
+            device: device_id,
+            folder: folder_id,
+            ..
+        } => (
+            "[Completion]",
+            Style::default().dark_gray(),
+            format!(
+                "{} is at {:.0}% of {}",
+                device(device_id),
+                completion,
+                folder(folder_id)
+            ),
+        ),
+        EventType::DeviceConnected { id, addr, .. } => (
+            "[Connected]",
+            Style::default().green().bold(),
+            format!("{} at {}", device(id), addr),
+        ),
+        EventType::DeviceDisconnected { id, error } => (
+            "[Disconnected]",
+            Style::default().yellow().bold(),
+            format!("{}: {}", device(id), error),
+        ),
+        _ => return None,
+    };
+
+    Some(Line::from(vec![
+        Span::styled(
+            format!("{} ", event.time.with_timezone(&Local).format("%H:%M:%S")),
+            Style::default().dark_gray(),
+        ),
+        Span::styled(format!("{} ", badge), style),
+        Span::raw(text),
+    ]))
+}
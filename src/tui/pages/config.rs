@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListState, StatefulWidget, Widget},
+};
+use serde_json::Value;
+
+use crate::{
+    IconSet,
+    tui::{app::App, input::Message, ui::loading_line},
+};
+
+/// Keys which identify an object inside an array, shown next to its index
+const NAME_KEYS: [&str; 4] = ["label", "name", "id", "deviceID"];
+
+/// What is expanded and selected in the raw config tree
+#[derive(Debug, Default)]
+pub struct ConfigTreeState {
+    /// Paths of the expanded objects and arrays, e.g., `folders.0`
+    expanded: HashSet<String>,
+    pub selected: Option<usize>,
+    /// Only nodes whose key or value contain this are shown, if not empty
+    pub filter: String,
+}
+
+/// A line of the raw config tree
+pub struct ConfigRow {
+    /// Keys and indices leading to this node, separated by dots
+    path: String,
+    depth: usize,
+    key: String,
+    value: RowValue,
+}
+
+enum RowValue {
+    Scalar(Value),
+    /// An object or array with this many children
+    Container {
+        array: bool,
+        children: usize,
+        name: Option<String>,
+        expanded: bool,
+    },
+}
+
+impl ConfigTreeState {
+    pub fn update(&mut self, msg: &Message, rows: &[ConfigRow]) {
+        let len = rows.len();
+        if len == 0 {
+            self.selected = None;
+            return;
+        }
+        let selected = self.selected.map(|i| i.min(len - 1));
+        match msg {
+            Message::Down => self.selected = Some(selected.map_or(0, |i| (i + 1) % len)),
+            Message::Up => self.selected = Some(selected.map_or(len - 1, |i| (i + len - 1) % len)),
+            Message::Select | Message::Right | Message::Left => {
+                let Some(row) = selected.and_then(|i| rows.get(i)) else {
+                    return;
+                };
+                if let RowValue::Container { expanded, .. } = row.value {
+                    let expand = match msg {
+                        Message::Right => true,
+                        Message::Left => false,
+                        _ => !expanded,
+                    };
+                    if expand {
+                        self.expanded.insert(row.path.clone());
+                    } else {
+                        self.expanded.remove(&row.path);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+        self.selected = None;
+    }
+}
+
+/// Whether `key` or anything inside of `value` contains `filter`, which
+/// must be lowercase
+fn matches(key: &str, value: &Value, filter: &str) -> bool {
+    if key.to_lowercase().contains(filter) {
+        return true;
+    }
+    match value {
+        Value::Object(map) => map.iter().any(|(k, v)| matches(k, v, filter)),
+        Value::Array(values) => values.iter().any(|v| matches("", v, filter)),
+        Value::String(s) => s.to_lowercase().contains(filter),
+        scalar => scalar.to_string().contains(filter),
+    }
+}
+
+/// The visible lines of the tree of `config`. While filtering, everything
+/// leading to a match is expanded.
+pub fn config_rows(config: &Value, tree: &ConfigTreeState) -> Vec<ConfigRow> {
+    let filter = tree.filter.to_lowercase();
+    let mut rows = Vec::new();
+    if let Value::Object(map) = config {
+        for (key, value) in map {
+            push_rows(&mut rows, key.clone(), key.clone(), value, 0, tree, &filter);
+        }
+    }
+    rows
+}
+
+fn push_rows(
+    rows: &mut Vec<ConfigRow>,
+    key: String,
+    path: String,
+    value: &Value,
+    depth: usize,
+    tree: &ConfigTreeState,
+    filter: &str,
+) {
+    if !filter.is_empty() && !matches(&key, value, filter) {
+        return;
+    }
+    let children: Vec<(String, &Value)> = match value {
+        Value::Object(map) => map.iter().map(|(k, v)| (k.clone(), v)).collect(),
+        Value::Array(values) => values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect(),
+        scalar => {
+            rows.push(ConfigRow {
+                path,
+                depth,
+                key,
+                value: RowValue::Scalar(scalar.clone()),
+            });
+            return;
+        }
+    };
+
+    let key_matches = !filter.is_empty() && key.to_lowercase().contains(filter);
+    let expanded = !filter.is_empty() || tree.expanded.contains(&path);
+    let name = NAME_KEYS
+        .iter()
+        .find_map(|k| value.get(k).and_then(Value::as_str))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+    rows.push(ConfigRow {
+        path: path.clone(),
+        depth,
+        key,
+        value: RowValue::Container {
+            array: value.is_array(),
+            children: children.len(),
+            name,
+            expanded,
+        },
+    });
+    if expanded {
+        // A container whose key matches shows all of its children
+        let child_filter = if key_matches { "" } else { filter };
+        for (child_key, child) in children {
+            push_rows(
+                rows,
+                child_key.clone(),
+                format!("{}.{}", path, child_key),
+                child,
+                depth + 1,
+                tree,
+                child_filter,
+            );
+        }
+    }
+}
+
+impl ConfigRow {
+    fn line(&self, icons: IconSet) -> Line<'static> {
+        let mut spans = vec![Span::raw("  ".repeat(self.depth))];
+        match &self.value {
+            RowValue::Container {
+                array,
+                children,
+                name,
+                expanded,
+            } => {
+                spans.push(Span::raw(format!("{} ", icons.expander(!*expanded))));
+                spans.push(Span::styled(self.key.clone(), Style::default().bold()));
+                let (open, close) = if *array { ('[', ']') } else { ('{', '}') };
+                spans.push(Span::styled(
+                    format!(" {}{}{}", open, children, close),
+                    Style::default().dark_gray(),
+                ));
+                if let Some(name) = name {
+                    spans.push(Span::raw(format!(" {}", name)));
+                }
+            }
+            RowValue::Scalar(value) => {
+                spans.push(Span::raw("  "));
+                spans.push(Span::styled(self.key.clone(), Style::default().bold()));
+                spans.push(Span::raw(": "));
+                spans.push(match value {
+                    Value::String(s) => {
+                        Span::styled(format!("\"{}\"", s), Style::default().green())
+                    }
+                    Value::Number(n) => Span::styled(n.to_string(), Style::default().cyan()),
+                    Value::Bool(b) => Span::styled(b.to_string(), Style::default().yellow()),
+                    other => Span::styled(other.to_string(), Style::default().dark_gray()),
+                });
+            }
+        }
+        Line::from(spans)
+    }
+}
+
+pub struct ConfigPage<'a> {
+    app: &'a App,
+}
+
+impl<'a> ConfigPage<'a> {
+    pub fn new(app: &'a App) -> Self {
+        Self { app }
+    }
+}
+
+impl Widget for ConfigPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        (&self).render(area, buf);
+    }
+}
+
+impl Widget for &ConfigPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let Some(lines) = self.app.state.read(|state| {
+            state.raw_config.as_ref().map(|config| {
+                config_rows(config, &self.app.config_tree)
+                    .iter()
+                    .map(|row| row.line(self.app.config.icons))
+                    .collect::<Vec<_>>()
+            })
+        }) else {
+            loading_line("configuration").render(area, buf);
+            return;
+        };
+
+        let tree = &self.app.config_tree;
+        let block = Block::default()
+            .borders(Borders::BOTTOM)
+            .title_bottom(Line::from("| (/) filter | (enter) expand |").right_aligned());
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        let selected = tree.selected.map(|i| i.min(lines.len().saturating_sub(1)));
+        let list = List::new(lines).highlight_style(Style::new().bg(Color::DarkGray));
+        let mut list_state = ListState::default().with_selected(selected);
+        StatefulWidget::render(list, inner_area, buf, &mut list_state);
+    }
+}
@@ -1,11 +1,32 @@
+use chrono::Utc;
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
     style::{Color, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, StatefulWidget, Widget},
+    widgets::{Block, Borders, List, ListItem, ListState, Sparkline, StatefulWidget, Widget},
 };
 
-use crate::tui::app::App;
+use crate::{
+    DeviceColumn, IconSet,
+    rest::RateLimits,
+    tui::{
+        app::{App, CurrentScreen},
+        hits::HitTarget,
+        notes::NoteTarget,
+        state::{Device, DeviceStatus, Folder, InnerState, Section, Traffic},
+        ui::{
+            display_id, format_bytes, format_compression, format_duration, format_rate,
+            loading_line, split_panes,
+        },
+    },
+};
+
+/// Rows of each transfer rate graph in the detail pane
+const GRAPH_HEIGHT: u16 = 3;
+
+/// The detail pane only shows the graphs if it has at least this many rows
+const MIN_HEIGHT_FOR_GRAPHS: u16 = 16;
 
 pub struct DevicesPage<'a> {
     app: &'a App,
@@ -17,6 +38,236 @@ impl<'a> DevicesPage<'a> {
     }
 }
 
+impl DevicesPage<'_> {
+    /// The lines shown in the detail pane of `device`
+    pub fn detail(
+        state: &InnerState,
+        device: &Device,
+        reveal: bool,
+        icons: IconSet,
+    ) -> Vec<ListItem<'static>> {
+        let mut device_info = Vec::<ListItem>::new();
+        device_info.push(ListItem::new(Line::from(vec![
+            Span::raw(" "),
+            Span::styled("ID", Style::default().bold()),
+            Span::raw(format!(
+                "      : {}",
+                display_id(&device.config.device_id, reveal)
+            )),
+        ])));
+        if device.config.device_id == state.id {
+            device_info.extend(Self::local_detail(state));
+        } else {
+            if let Some(addresses) = state.get_lan_addresses(&device.config.device_id) {
+                device_info.push(ListItem::new(Line::from(vec![
+                    Span::raw(" "),
+                    Span::styled("On LAN", Style::default().bold()),
+                    Span::raw(format!("  : {}", addresses.join(", "))),
+                ])));
+            }
+            device_info.push(ListItem::new(Line::from(vec![
+                Span::raw(" "),
+                Span::styled("Compress", Style::default().bold()),
+                Span::raw(format!(
+                    " : {}",
+                    format_compression(&device.config.compression)
+                )),
+            ])));
+            device_info.extend(Self::limits_line(device.rate_limits));
+        }
+        if let Some(note) = state
+            .notes
+            .get(&NoteTarget::Device(device.config.device_id.clone()))
+        {
+            if !note.text.is_empty() {
+                device_info.push(ListItem::new(Line::from(vec![
+                    Span::raw(" "),
+                    Span::styled("Note", Style::default().bold()),
+                    Span::raw(format!("    : {}", note.text)),
+                ])));
+            }
+            if !note.tags.is_empty() {
+                device_info.push(ListItem::new(Line::from(vec![
+                    Span::raw(" "),
+                    Span::styled("Tags", Style::default().bold()),
+                    Span::raw("    : "),
+                    Span::styled(note.tags.join(", "), Style::default().cyan()),
+                ])));
+            }
+        }
+        device_info.push(ListItem::new(Line::from("")));
+
+        let device_folders = state.get_device_folders(&device.config.device_id);
+        let s_suffix = if device_folders.len() == 1 { "" } else { "s" };
+
+        device_info.push(ListItem::new(Line::from(vec![
+            Span::raw(" "),
+            Span::styled("Sharing", Style::default().bold()),
+            Span::raw(" : "),
+            Span::styled(format!("{}", device_folders.len()), Style::default().bold()),
+            Span::raw(format!(" Folder{}", s_suffix)),
+        ])));
+
+        if !device_folders.is_empty() {
+            device_info.extend(Self::folders_table(state, device, &device_folders, icons));
+        }
+
+        device_info
+    }
+
+    /// One row per folder shared with `device`, with how far the device got
+    /// with it and when it last sent an index update
+    fn folders_table(
+        state: &InnerState,
+        device: &Device,
+        folders: &[&Folder],
+        icons: IconSet,
+    ) -> Vec<ListItem<'static>> {
+        let device_id = &device.config.device_id;
+        let local = *device_id == state.id;
+        let connected = device.connected != DeviceStatus::Disconnected;
+        let label_width = folders
+            .iter()
+            .map(|f| icons.folder().chars().count() + f.config.label.chars().count())
+            .chain(["Folder".len()])
+            .max()
+            .unwrap_or(0);
+
+        let mut rows = vec![ListItem::new(Line::styled(
+            format!(
+                "  {:<label_width$}  {:>6}  {:>10}  Last update",
+                "Folder", "Synced", "Needs"
+            ),
+            Style::default().dark_gray().bold(),
+        ))];
+        for folder in folders {
+            let (completion, need_bytes) = if local {
+                (
+                    folder.completion_loaded.then_some(folder.completion),
+                    Some(folder.progress.need_bytes),
+                )
+            } else {
+                state
+                    .get_remote_completion(device_id, &folder.config.id)
+                    .map_or((None, None), |c| (Some(c.completion), Some(c.need_bytes)))
+            };
+            let completion = match completion {
+                Some(completion) => Span::styled(
+                    format!("{:>5.0}%", completion),
+                    if completion >= 100.0 {
+                        Style::default().green()
+                    } else {
+                        Style::default().yellow()
+                    },
+                ),
+                None => Span::styled(format!("{:>6}", "…"), Style::default().dark_gray()),
+            };
+            let need = match need_bytes {
+                Some(0) | None => "-".to_string(),
+                Some(bytes) => format_bytes(bytes as f64),
+            };
+            let last_update = if local {
+                Span::raw("-")
+            } else {
+                match state
+                    .get_last_remote_index(device_id, &folder.config.id)
+                    .and_then(|time| (Utc::now() - time).to_std().ok())
+                {
+                    Some(age) => Span::raw(format!("{} ago", format_duration(age))),
+                    None if connected => {
+                        Span::styled("none this session", Style::default().dark_gray())
+                    }
+                    None => Span::raw("-"),
+                }
+            };
+            rows.push(ListItem::new(Line::from(vec![
+                Span::raw(format!(
+                    "  {:<label_width$}  ",
+                    format!("{}{}", icons.folder(), folder.config.label)
+                )),
+                completion,
+                Span::raw(format!("  {:>10}  ", need)),
+                last_update,
+            ])));
+        }
+        rows
+    }
+
+    /// Where this device listens and whether it can be discovered, in place
+    /// of what only concerns remote devices
+    fn local_detail(state: &InnerState) -> Vec<ListItem<'static>> {
+        let Some(status) = &state.system_status else {
+            return vec![ListItem::new(Line::from(" Loading listeners…").dark_gray())];
+        };
+        let mut listeners: Vec<_> = status.connection_service_status.iter().collect();
+        listeners.sort_by_key(|(address, _)| *address);
+        let mut lines = Vec::new();
+        for (i, (address, listener)) in listeners.into_iter().enumerate() {
+            let label = if i == 0 { "Listen" } else { "" };
+            let status = match &listener.error {
+                Some(error) => Span::styled(format!(" ({})", error), Style::default().red()),
+                None => Span::raw(""),
+            };
+            lines.push(ListItem::new(Line::from(vec![
+                Span::raw(" "),
+                Span::styled(format!("{:<8}", label), Style::default().bold()),
+                Span::raw(format!(": {}", address)),
+                status,
+            ])));
+        }
+
+        let discovery = if status.discovery_enabled {
+            let total = status.discovery_status.len();
+            let failing = status
+                .discovery_status
+                .values()
+                .filter(|discovery| discovery.error.is_some())
+                .count();
+            if failing == 0 {
+                Span::styled(
+                    format!("{} methods working", total),
+                    Style::default().green(),
+                )
+            } else {
+                Span::styled(
+                    format!("{} of {} methods failing", failing, total),
+                    Style::default().red(),
+                )
+            }
+        } else {
+            Span::styled("disabled", Style::default().dark_gray())
+        };
+        lines.push(ListItem::new(Line::from(vec![
+            Span::raw(" "),
+            Span::styled("Discover", Style::default().bold()),
+            Span::raw(" : "),
+            discovery,
+        ])));
+        lines.extend(Self::limits_line(state.rate_limits));
+        lines
+    }
+
+    /// The bandwidth limits, if there are any
+    fn limits_line(limits: RateLimits) -> Option<ListItem<'static>> {
+        if limits == RateLimits::default() {
+            return None;
+        }
+        let format_limit = |kbps: u64| match kbps {
+            0 => "unlimited".to_string(),
+            kbps => format!("{} KiB/s", kbps),
+        };
+        Some(ListItem::new(Line::from(vec![
+            Span::raw(" "),
+            Span::styled("Limits", Style::default().bold()),
+            Span::raw(format!(
+                "   : send {}, receive {}",
+                format_limit(limits.max_send_kbps),
+                format_limit(limits.max_recv_kbps)
+            )),
+        ])))
+    }
+}
+
 impl Widget for DevicesPage<'_> {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
         (&self).render(area, buf);
@@ -28,108 +279,249 @@ impl Widget for &DevicesPage<'_> {
     where
         Self: Sized,
     {
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(area);
-
-        let list: Vec<_> = self.app.state.read(|state| {
-            state
-                .get_other_devices()
-                .iter()
-                .map(|d| (d.config.name.clone(), d.connected.clone()))
-                .collect()
-        });
+        if !self
+            .app
+            .state
+            .read(|state| state.is_loaded(Section::Configuration))
+        {
+            loading_line("devices").render(area, buf);
+            return;
+        }
 
-        let max = list
-            .iter()
-            .max_by(|x, y| x.0.char_indices().count().cmp(&y.0.char_indices().count()))
-            .map_or(0, |f| f.0.char_indices().count());
+        let chunks = split_panes(area, self.app.ui_state.get_split(&CurrentScreen::Devices));
 
-        let list: Vec<_> = list
-            .iter()
-            .map(|(name, online)| {
-                let online_span = match online {
-                    crate::tui::state::DeviceStatus::UpToDate => {
-                        Span::styled("[Up to Date]", Style::default().green().bold())
+        // Render while reading the state, so that the rows can borrow from it
+        self.app.state.read(|state| {
+            let devices = state.get_listed_devices();
+
+            // Every row consists of the name, followed by the configured columns
+            let icons = self.app.config.icons;
+            let rows: Vec<Vec<Span>> = devices
+                .iter()
+                .map(|device| {
+                    let mut name = icons.device().to_string();
+                    if self.app.list_ids {
+                        name.push_str(&display_id(&device.config.device_id, self.app.reveal_ids));
+                    } else {
+                        name.push_str(&device.config.name);
                     }
-                    crate::tui::state::DeviceStatus::Syncing(completion) => Span::styled(
-                        format!("[Syncing ({:.0}%)]", completion),
-                        Style::default().blue().bold(),
-                    ),
-                    crate::tui::state::DeviceStatus::Disconnected => {
-                        Span::styled("[Disconnected]", Style::default().red())
+                    let local = device.config.device_id == state.id;
+                    if local {
+                        name.push_str(" (this device)");
                     }
-                };
+                    let traffic = if local {
+                        &state.total_traffic
+                    } else {
+                        &device.traffic
+                    };
+                    let rate = || {
+                        Span::raw(format!(
+                            "{} {} {} {}",
+                            icons.rates().0,
+                            format_rate(traffic.download_rate),
+                            icons.rates().1,
+                            format_rate(traffic.upload_rate)
+                        ))
+                    };
 
-                let spacing = (max + 2) - name.char_indices().count();
-                Line::from(vec![
-                    Span::raw(name),
-                    Span::raw(" ".repeat(spacing)),
-                    online_span,
-                ])
-            })
-            .collect();
+                    let mut row = vec![Span::raw(name)];
+                    for column in &self.app.config.device_columns {
+                        row.push(match column {
+                            DeviceColumn::Status if local => local_status(state),
+                            DeviceColumn::Rate if local => rate(),
+                            DeviceColumn::LastSeen if local => Span::raw(""),
+                            DeviceColumn::Status if device.config.paused => Span::styled(
+                                format!("[{}Paused]", icons.pause()),
+                                Style::default().yellow().bold(),
+                            ),
+                            DeviceColumn::Status => match device.connected {
+                                DeviceStatus::UpToDate => {
+                                    Span::styled("[Up to Date]", Style::default().green().bold())
+                                }
+                                DeviceStatus::Syncing(completion) => Span::styled(
+                                    format!("[Syncing ({:.0}%)]", completion),
+                                    Style::default().blue().bold(),
+                                ),
+                                DeviceStatus::Disconnected => {
+                                    Span::styled("[Disconnected]", Style::default().red())
+                                }
+                            },
+                            DeviceColumn::Rate => match device.connected {
+                                DeviceStatus::Disconnected => Span::raw(""),
+                                _ => rate(),
+                            },
+                            DeviceColumn::LastSeen => match (&device.connected, device.last_seen) {
+                                (DeviceStatus::Disconnected, Some(last_seen)) => Span::styled(
+                                    format!(
+                                        "seen {} ago",
+                                        format_duration(
+                                            (Utc::now() - last_seen).to_std().unwrap_or_default()
+                                        )
+                                    ),
+                                    Style::default().dark_gray(),
+                                ),
+                                _ => Span::raw(""),
+                            },
+                        });
+                    }
+                    row
+                })
+                .collect();
+
+            let column_widths: Vec<usize> = (0..=self.app.config.device_columns.len())
+                .map(|i| {
+                    rows.iter()
+                        .filter_map(|row| row.get(i))
+                        .map(|span| span.width())
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
 
-        let list = List::new(list).highlight_style(Style::new().bg(Color::DarkGray));
-        let mut list_state = ListState::default().with_selected(self.app.selected_device);
-
-        StatefulWidget::render(list, chunks[0], buf, &mut list_state);
-
-        if let Some(device_index) = self.app.selected_device {
-            self.app.state.read(|state| {
-                if let Some(device) = state.get_other_devices().get(device_index) {
-                    let block = Block::default()
-                        .title_top(
-                            Line::from(format!("| {} |", device.config.name))
-                                .centered()
-                                .bold(),
-                        )
-                        .borders(Borders::ALL);
-
-                    // Device information
-                    let mut device_info = Vec::<ListItem>::new();
-                    device_info.push(ListItem::new(Line::from(vec![
-                        Span::raw(" "),
-                        Span::styled("ID", Style::default().bold()),
-                        Span::raw(format!("      : {}", device.config.device_id)),
-                    ])));
-                    device_info.push(ListItem::new(Line::from("")));
-
-                    let device_folders = state.get_device_folders(&device.config.device_id).len();
-                    let s_suffix = if device_folders == 1 { "" } else { "s" };
-
-                    device_info.push(ListItem::new(Line::from(vec![
-                        Span::raw(" "),
-                        Span::styled("Sharing", Style::default().bold()),
-                        Span::raw(" : "),
-                        Span::styled(format!("{}", device_folders), Style::default().bold()),
-                        Span::raw(format!(" Folder{}", s_suffix)),
-                    ])));
-
-                    for i in 0..device_folders {
-                        if let Some(folder) =
-                            state.get_device_folders(&device.config.device_id).get(i)
-                        {
-                            let ident = if i < device_folders - 1 {
-                                "├─"
-                            } else {
-                                "└─"
-                            };
-                            device_info.push(ListItem::new(Line::from(format!(
-                                "  {} {}",
-                                ident, folder.config.label
-                            ))));
+            let list: Vec<_> = rows
+                .into_iter()
+                .map(|row| {
+                    let mut spans = Vec::new();
+                    let last = row.len() - 1;
+                    for (i, span) in row.into_iter().enumerate() {
+                        let spacing = (column_widths[i] + 2).saturating_sub(span.width());
+                        spans.push(span);
+                        if i < last {
+                            spans.push(Span::raw(" ".repeat(spacing)));
                         }
                     }
+                    Line::from(spans)
+                })
+                .collect();
+
+            let list = List::new(list).highlight_style(Style::new().bg(Color::DarkGray));
+            let mut list_state = ListState::default().with_selected(self.app.devices.list.selected);
+            StatefulWidget::render(list, chunks[0], buf, &mut list_state);
+            self.app.hits.borrow_mut().add(
+                chunks[0],
+                HitTarget::Rows {
+                    offset: list_state.offset(),
+                },
+            );
+
+            if let Some(device) = self.app.devices.list.selected.and_then(|i| devices.get(i)) {
+                let block = Block::default()
+                    .title_top(
+                        Line::from(format!("| {} |", device.config.name))
+                            .centered()
+                            .bold(),
+                    )
+                    .borders(Borders::ALL)
+                    .border_style(if self.app.devices.detail.focused {
+                        Style::default().fg(Color::Blue)
+                    } else {
+                        Style::default()
+                    });
+
+                let device_info =
+                    DevicesPage::detail(state, device, self.app.reveal_ids, self.app.config.icons);
+                let mut detail_state = ListState::default().with_offset(
+                    self.app
+                        .devices
+                        .detail
+                        .scroll
+                        .min(device_info.len().saturating_sub(1)),
+                );
 
-                    let inner_area = block.inner(chunks[1]);
-                    block.render(chunks[1], buf);
+                let inner_area = block.inner(chunks[1]);
+                block.render(chunks[1], buf);
 
-                    let list = List::new(device_info);
-                    Widget::render(list, inner_area, buf);
+                let traffic = if device.config.device_id == state.id {
+                    &state.total_traffic
+                } else {
+                    &device.traffic
+                };
+                let (list_area, graphs_area) = if inner_area.height >= MIN_HEIGHT_FOR_GRAPHS
+                    && traffic.download_history.len() > 1
+                {
+                    let [list_area, graphs_area] = Layout::vertical([
+                        Constraint::Fill(1),
+                        Constraint::Length(2 * (GRAPH_HEIGHT + 1)),
+                    ])
+                    .areas(inner_area);
+                    (list_area, Some(graphs_area))
+                } else {
+                    (inner_area, None)
+                };
+
+                let list = List::new(device_info);
+                StatefulWidget::render(list, list_area, buf, &mut detail_state);
+                if let Some(graphs_area) = graphs_area {
+                    render_traffic_graphs(traffic, self.app.config.icons, graphs_area, buf);
                 }
-            })
-        }
+            }
+        });
+    }
+}
+
+/// Draws the recent download and upload rates of `traffic` below each other,
+/// as many of the most recent ones as fit into `area`
+fn render_traffic_graphs(traffic: &Traffic, icons: IconSet, area: Rect, buf: &mut Buffer) {
+    let (down, up) = icons.rates();
+    let graphs = [
+        (
+            down,
+            "Download",
+            traffic.download_rate,
+            &traffic.download_history,
+            Color::Green,
+        ),
+        (
+            up,
+            "Upload",
+            traffic.upload_rate,
+            &traffic.upload_history,
+            Color::Blue,
+        ),
+    ];
+    let areas = Layout::vertical([Constraint::Length(GRAPH_HEIGHT + 1); 2]).split(area);
+    for ((icon, label, rate, history, color), area) in graphs.into_iter().zip(areas.iter()) {
+        let [title_area, graph_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Length(GRAPH_HEIGHT)])
+                .areas(*area);
+        let peak = history.iter().max().copied().unwrap_or(0);
+        Line::from(vec![
+            Span::styled(format!(" {} {}", icon, label), Style::default().bold()),
+            Span::raw(format!(
+                " {}, peak {}",
+                format_rate(rate),
+                format_rate(peak as f64)
+            )),
+        ])
+        .render(title_area, buf);
+        let shown: Vec<u64> = history
+            .iter()
+            .skip(history.len().saturating_sub(graph_area.width as usize))
+            .copied()
+            .collect();
+        Sparkline::default()
+            .data(&shown)
+            .style(Style::default().fg(color))
+            .render(graph_area, buf);
+    }
+}
+
+/// Status of this device, by whether all of its listeners work
+fn local_status(state: &InnerState) -> Span<'static> {
+    let Some(status) = &state.system_status else {
+        return Span::styled("[Local]", Style::default().bold());
+    };
+    let failing = status
+        .connection_service_status
+        .values()
+        .filter(|listener| listener.error.is_some())
+        .count();
+    if failing == 0 {
+        Span::styled("[Listening]", Style::default().green().bold())
+    } else {
+        Span::styled(
+            format!("[{} listeners failing]", failing),
+            Style::default().red().bold(),
+        )
     }
 }
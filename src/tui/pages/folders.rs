@@ -1,11 +1,152 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
     style::{Color, Style, Stylize},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, ListState, StatefulWidget, Widget},
 };
 
-use crate::tui::app::App;
+use std::str::FromStr;
+
+use syncthing_rs::types::config::VersioningType;
+
+use crate::{
+    AppConfig, FolderGroup, IconSet,
+    tui::{
+        app::{App, CurrentScreen},
+        hits::HitTarget,
+        notes::NoteTarget,
+        state::{DeviceStatus, Folder, FolderStatus, InnerState, Section},
+        ui::{
+            display_id, format_bytes, format_duration, format_folder_type, format_rate,
+            format_versioning_type, index_span, loading_line, split_panes,
+        },
+        ui_state::UiState,
+    },
+};
+
+/// Name of the section holding all folders which are in no group
+pub const UNGROUPED: &str = "Other";
+
+/// Accent colors of groups without an explicit color
+const GROUP_COLORS: [Color; 6] = [
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Yellow,
+    Color::Green,
+    Color::LightRed,
+];
+
+/// Failed items listed in the details of a folder, the rest is only counted
+const MAX_FAILED_ITEMS: usize = 20;
+
+/// A line of the folders list
+pub enum FolderRow<'a> {
+    /// Header of a group, `None` is the section of ungrouped folders
+    Group {
+        group: Option<&'a FolderGroup>,
+        color: Color,
+        folders: usize,
+        collapsed: bool,
+    },
+    Folder {
+        folder: &'a Folder,
+        /// Accent color of the group of the folder
+        color: Option<Color>,
+    },
+}
+
+impl FolderRow<'_> {
+    pub fn folder(&self) -> Option<&Folder> {
+        match self {
+            FolderRow::Folder { folder, .. } => Some(folder),
+            FolderRow::Group { .. } => None,
+        }
+    }
+
+    /// Name of the group, if this is a group header
+    pub fn group_name(&self) -> Option<&str> {
+        match self {
+            FolderRow::Group { group, .. } => Some(group.map_or(UNGROUPED, |g| g.name.as_str())),
+            FolderRow::Folder { .. } => None,
+        }
+    }
+}
+
+/// The lines of the folders list. Without configured groups, this is just
+/// every folder. Otherwise, each group gets a header followed by its
+/// folders, unless it is collapsed.
+pub fn folder_rows<'a>(
+    state: &'a InnerState,
+    config: &'a AppConfig,
+    ui_state: &UiState,
+) -> Vec<FolderRow<'a>> {
+    let mut folders = state.get_folders();
+    // Stable, so favorites keep the order among themselves
+    folders.sort_by_key(|folder| !ui_state.is_favorite(&folder.config.id));
+    if config.folder_groups.is_empty() {
+        return folders
+            .into_iter()
+            .map(|folder| FolderRow::Folder {
+                folder,
+                color: None,
+            })
+            .collect();
+    }
+
+    let mut sections: Vec<(Option<&FolderGroup>, Color, Vec<&Folder>)> = config
+        .folder_groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let color = group
+                .color
+                .as_deref()
+                .and_then(|c| Color::from_str(c).ok())
+                .unwrap_or(GROUP_COLORS[i % GROUP_COLORS.len()]);
+            (Some(group), color, Vec::new())
+        })
+        .collect();
+    let mut ungrouped = Vec::new();
+    for folder in folders {
+        match sections
+            .iter_mut()
+            .find(|(group, _, _)| group.is_some_and(|g| g.contains(&folder.config)))
+        {
+            Some((_, _, members)) => members.push(folder),
+            None => ungrouped.push(folder),
+        }
+    }
+    if !ungrouped.is_empty() {
+        sections.push((None, Color::Gray, ungrouped));
+    }
+
+    let mut rows = Vec::new();
+    for (group, color, members) in sections {
+        let collapsed = ui_state.is_group_collapsed(group.map_or(UNGROUPED, |g| &g.name));
+        rows.push(FolderRow::Group {
+            group,
+            color,
+            folders: members.len(),
+            collapsed,
+        });
+        if !collapsed {
+            rows.extend(members.into_iter().map(|folder| FolderRow::Folder {
+                folder,
+                color: Some(color),
+            }));
+        }
+    }
+    rows
+}
+
+/// How `folder` is called in the list, by its ID if `ids` is set
+fn list_name(folder: &Folder, ids: bool) -> &str {
+    if ids {
+        &folder.config.id
+    } else {
+        &folder.config.label
+    }
+}
 
 pub struct FoldersPage<'a> {
     app: &'a App,
@@ -17,6 +158,241 @@ impl<'a> FoldersPage<'a> {
     }
 }
 
+impl FoldersPage<'_> {
+    /// The lines shown in the detail pane of `folder`
+    pub fn detail(
+        state: &InnerState,
+        folder: &Folder,
+        reveal: bool,
+        icons: IconSet,
+    ) -> Vec<ListItem<'static>> {
+        let mut folder_info = Vec::<ListItem>::new();
+        folder_info.push(ListItem::new(Line::from(vec![
+            Span::raw(" "),
+            Span::styled("ID", Style::default().bold()),
+            Span::raw(format!(
+                "          : {}",
+                display_id(&folder.config.id, reveal)
+            )),
+        ])));
+        folder_info.push(ListItem::new(Line::from(vec![
+            Span::raw(" "),
+            Span::styled("Path", Style::default().bold()),
+            Span::raw(format!("        : {}", folder.config.path)),
+        ])));
+        folder_info.push(ListItem::new(Line::from(vec![
+            Span::raw(" "),
+            Span::styled("Type", Style::default().bold()),
+            Span::raw(format!(
+                "        : {}",
+                format_folder_type(&folder.config.folder_type)
+            )),
+        ])));
+        if folder.config.versioning.versioning_type != VersioningType::None {
+            folder_info.push(ListItem::new(Line::from(vec![
+                Span::raw(" "),
+                Span::styled("Versioning", Style::default().bold()),
+                Span::raw(format!(
+                    "  : {}",
+                    format_versioning_type(&folder.config.versioning.versioning_type)
+                )),
+            ])));
+        }
+        let status = match &folder.status {
+            FolderStatus::Unknown => None,
+            FolderStatus::Idle => Some(Span::raw("idle")),
+            FolderStatus::Scanning => Some(Span::styled("scanning", Style::default().cyan())),
+            FolderStatus::Syncing => Some(Span::styled("syncing", Style::default().blue())),
+            FolderStatus::Waiting => Some(Span::raw("waiting")),
+            FolderStatus::Cleaning => Some(Span::raw("cleaning")),
+            FolderStatus::Error(error) => Some(Span::styled(
+                format!("error: {}", error),
+                Style::default().red(),
+            )),
+        };
+        if let Some(status) = status {
+            folder_info.push(ListItem::new(Line::from(vec![
+                Span::raw(" "),
+                Span::styled("State", Style::default().bold()),
+                Span::raw("       : "),
+                status,
+            ])));
+        }
+        if let Some(since) = state.get_local_change(&folder.config.id) {
+            folder_info.push(ListItem::new(Line::from(vec![
+                Span::raw(" "),
+                Span::styled("Local", Style::default().bold()),
+                Span::raw("       : "),
+                Span::styled(
+                    format!(
+                        "changes detected {} ago, waiting for scan",
+                        format_duration(since)
+                    ),
+                    Style::default().yellow(),
+                ),
+            ])));
+        }
+        if folder.progress.need_bytes > 0 {
+            let mut remaining = format_bytes(folder.progress.need_bytes as f64);
+            match (folder.progress.rate, folder.progress.eta()) {
+                (Some(rate), Some(eta)) => remaining.push_str(&format!(
+                    " at {}, about {} left",
+                    format_rate(rate),
+                    format_duration(eta)
+                )),
+                (Some(_), None) => remaining.push_str(", stalled"),
+                _ => {}
+            }
+            folder_info.push(ListItem::new(Line::from(vec![
+                Span::raw(" "),
+                Span::styled("Remaining", Style::default().bold()),
+                Span::raw(format!("   : {}", remaining)),
+            ])));
+        }
+        if let Some(note) = state
+            .notes
+            .get(&NoteTarget::Folder(folder.config.id.clone()))
+        {
+            if !note.text.is_empty() {
+                folder_info.push(ListItem::new(Line::from(vec![
+                    Span::raw(" "),
+                    Span::styled("Note", Style::default().bold()),
+                    Span::raw(format!("        : {}", note.text)),
+                ])));
+            }
+            if !note.tags.is_empty() {
+                folder_info.push(ListItem::new(Line::from(vec![
+                    Span::raw(" "),
+                    Span::styled("Tags", Style::default().bold()),
+                    Span::raw("        : "),
+                    Span::styled(note.tags.join(", "), Style::default().cyan()),
+                ])));
+            }
+        }
+        folder_info.push(ListItem::new(Line::from("")));
+
+        let folder_sharer = folder.get_sharer_excluded(&state.id).len();
+        let s_suffix = if folder_sharer == 1 { "" } else { "s" };
+
+        folder_info.push(ListItem::new(Line::from(vec![
+            Span::raw(" "),
+            Span::styled("Shared with", Style::default().bold()),
+            Span::raw(" : "),
+            Span::styled(format!("{}", folder_sharer), Style::default().bold()),
+            Span::raw(format!(" Device{}", s_suffix)),
+        ])));
+
+        // Only mark plaintext shares if encryption is used at all
+        let sharers = folder.get_sharer_excluded(&state.id);
+        let uses_encryption = sharers.iter().any(|device_id| {
+            folder.is_encrypted_for(device_id)
+                || state.get_device(device_id).is_ok_and(|d| d.untrusted)
+        });
+        for i in 0..folder_sharer {
+            if let Some(device_id) = sharers.get(i) {
+                let ident = icons.branch(i + 1 == folder_sharer);
+                if let Ok(device) = state.get_device(device_id) {
+                    let mut line = Line::from(format!(
+                        "  {} {}{}",
+                        ident,
+                        icons.device(),
+                        device.config.name
+                    ));
+                    match (folder.is_encrypted_for(device_id), device.untrusted) {
+                        (true, true) => line.push_span(Span::styled(
+                            format!(" [{}Encrypted, untrusted]", icons.lock()),
+                            Style::default().green(),
+                        )),
+                        (true, false) => line.push_span(Span::styled(
+                            format!(" [{}Encrypted]", icons.lock()),
+                            Style::default().green(),
+                        )),
+                        (false, true) => line.push_span(Span::styled(
+                            " [Untrusted, no password]",
+                            Style::default().red().bold(),
+                        )),
+                        (false, false) if uses_encryption => line
+                            .push_span(Span::styled(" [Plaintext]", Style::default().dark_gray())),
+                        (false, false) => {}
+                    }
+                    if let Some(remote) = state.get_remote_completion(device_id, &folder.config.id)
+                        && remote.completion < 100.0
+                    {
+                        line.push_span(Span::styled(
+                            format!(
+                                " [{:.0}%, {} behind]",
+                                remote.completion,
+                                format_bytes(remote.need_bytes as f64)
+                            ),
+                            Style::default().yellow(),
+                        ));
+                    }
+                    if let Some(span) = index_span(
+                        state.get_last_remote_index(device_id, &folder.config.id),
+                        device.connected != DeviceStatus::Disconnected,
+                    ) {
+                        line.push_span(span);
+                    }
+                    folder_info.push(ListItem::new(line));
+                }
+            }
+        }
+
+        let offers = state.get_pending_offers(&folder.config.id);
+        if !offers.is_empty() {
+            folder_info.push(ListItem::new(Line::from("")));
+            folder_info.push(ListItem::new(Line::from(vec![
+                Span::raw(" "),
+                Span::styled("Offered by", Style::default().bold().yellow()),
+                Span::raw("  : "),
+                Span::styled(format!("{}", offers.len()), Style::default().bold()),
+                Span::raw(" Pending  "),
+                Span::styled("(a) accept", Style::default().italic()),
+            ])));
+            for (i, device_id) in offers.iter().enumerate() {
+                let ident = icons.branch(i + 1 == offers.len());
+                let name = state
+                    .get_device(device_id)
+                    .map(|device| device.config.name.clone())
+                    .unwrap_or_else(|_| display_id(device_id, reveal));
+                folder_info.push(ListItem::new(Line::from(format!("  {} {}", ident, name))));
+            }
+        }
+
+        if !folder.failed_items.is_empty() {
+            folder_info.push(ListItem::new(Line::from("")));
+            folder_info.push(ListItem::new(Line::from(vec![
+                Span::raw(" "),
+                Span::styled("Failed items", Style::default().bold().red()),
+                Span::raw(": "),
+                Span::styled(
+                    format!("{}", folder.failed_items.len()),
+                    Style::default().bold(),
+                ),
+                Span::raw("  "),
+                Span::styled("(s) rescan", Style::default().italic()),
+            ])));
+            let shown = folder.failed_items.len().min(MAX_FAILED_ITEMS);
+            let hidden = folder.failed_items.len() - shown;
+            for (i, (item, error)) in folder.failed_items.iter().take(shown).enumerate() {
+                let ident = icons.branch(i + 1 == shown && hidden == 0);
+                folder_info.push(ListItem::new(Line::from(vec![
+                    Span::raw(format!("  {} {}: ", ident, item)),
+                    Span::styled(error.clone(), Style::default().red()),
+                ])));
+            }
+            if hidden > 0 {
+                folder_info.push(ListItem::new(Line::from(format!(
+                    "  {} … and {} more",
+                    icons.branch(true),
+                    hidden
+                ))));
+            }
+        }
+        folder_info
+    }
+}
+
 impl Widget for FoldersPage<'_> {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
         (&self).render(area, buf);
@@ -25,104 +401,156 @@ impl Widget for FoldersPage<'_> {
 
 impl Widget for &FoldersPage<'_> {
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(area);
-
-        let list: Vec<_> = self.app.state.read(|state| {
-            state
-                .get_folders()
+        if !self
+            .app
+            .state
+            .read(|state| state.is_loaded(Section::Configuration))
+        {
+            loading_line("folders").render(area, buf);
+            return;
+        }
+
+        let chunks = split_panes(area, self.app.ui_state.get_split(&CurrentScreen::Folders));
+
+        // Render while reading the state, so that the lines can borrow from it
+        self.app.state.read(|state| {
+            let rows = folder_rows(state, &self.app.config, &self.app.ui_state);
+            let ids = self.app.list_ids;
+            let max = rows
                 .iter()
-                .map(|f| (f.config.label.clone(), f.completion))
-                .collect()
-        });
+                .filter_map(|row| row.folder())
+                .map(|f| list_name(f, ids).chars().count())
+                .max()
+                .unwrap_or(0);
 
-        let max = list
-            .iter()
-            .max_by(|x, y| x.0.char_indices().count().cmp(&y.0.char_indices().count()))
-            .map_or(0, |f| f.0.char_indices().count());
-
-        let list: Vec<_> = list
-            .iter()
-            .map(|(label, completion)| {
-                let online_span = if *completion == 100.0 {
-                    Span::styled("[Up to Date]", Style::default().green().bold())
-                } else {
-                    Span::styled(format!("[{:.0}%]", completion), Style::default().red())
-                };
-
-                let spacing = (max + 2) - label.char_indices().count();
-                Line::from(vec![
-                    Span::raw(label),
-                    Span::raw(" ".repeat(spacing)),
-                    online_span,
-                ])
-            })
-            .collect();
+            let list: Vec<_> = rows
+                .iter()
+                .map(|row| match row {
+                    FolderRow::Group {
+                        color,
+                        folders,
+                        collapsed,
+                        ..
+                    } => Line::from(vec![
+                        Span::styled(
+                            format!(
+                                "{} {}",
+                                self.app.config.icons.expander(*collapsed),
+                                row.group_name().unwrap_or_default()
+                            ),
+                            Style::default().fg(*color).bold(),
+                        ),
+                        Span::raw(format!(" ({})", folders)),
+                    ]),
+                    FolderRow::Folder { folder, color } => {
+                        let completion = folder.completion;
+                        let online_span = if folder.config.paused {
+                            Span::styled(
+                                format!("[{}Paused]", self.app.config.icons.pause()),
+                                Style::default().dark_gray().bold(),
+                            )
+                        } else if let FolderStatus::Error(_) = folder.status {
+                            Span::styled("[Error]", Style::default().red().bold())
+                        } else if folder.status == FolderStatus::Scanning {
+                            Span::styled("[Scanning]", Style::default().cyan().bold())
+                        } else if folder.status == FolderStatus::Waiting {
+                            Span::styled("[Waiting]", Style::default().dark_gray())
+                        } else if state.get_local_change(&folder.config.id).is_some() {
+                            Span::styled("[Changed]", Style::default().yellow())
+                        } else if completion == 100.0 {
+                            Span::styled("[Up to Date]", Style::default().green().bold())
+                        } else if state.is_folder_stuck(&folder.config.id, &self.app.config) {
+                            Span::styled(
+                                format!("[Stuck ({:.0}%)]", completion),
+                                Style::default().yellow().bold(),
+                            )
+                        } else if let Some(eta) = folder.progress.eta() {
+                            Span::styled(
+                                format!("[{:.0}%, {} left]", completion, format_duration(eta)),
+                                Style::default().red(),
+                            )
+                        } else {
+                            Span::styled(format!("[{:.0}%]", completion), Style::default().red())
+                        };
 
-        let list = List::new(list).highlight_style(Style::new().bg(Color::DarkGray));
-
-        let mut list_state = ListState::default().with_selected(self.app.selected_folder);
-
-        StatefulWidget::render(list, chunks[0], buf, &mut list_state);
-
-        if let Some(folder_index) = self.app.selected_folder {
-            self.app.state.read(|state| {
-                if let Some(folder) = state.get_folders().get(folder_index) {
-                    let block = Block::default()
-                        .title_top(
-                            Line::from(format!("| {} |", folder.config.label))
-                                .centered()
-                                .bold(),
-                        )
-                        .borders(Borders::ALL);
-                    // Folder information
-                    let mut folder_info = Vec::<ListItem>::new();
-                    folder_info.push(ListItem::new(Line::from(vec![
-                        Span::raw(" "),
-                        Span::styled("ID", Style::default().bold()),
-                        Span::raw(format!("          : {}", folder.config.id)),
-                    ])));
-                    folder_info.push(ListItem::new(Line::from(vec![
-                        Span::raw(" "),
-                        Span::styled("Path", Style::default().bold()),
-                        Span::raw(format!("        : {}", folder.config.path)),
-                    ])));
-                    folder_info.push(ListItem::new(Line::from("")));
-
-                    let folder_sharer = folder.get_sharer_excluded(&state.id).len();
-                    let s_suffix = if folder_sharer == 1 { "" } else { "s" };
-
-                    folder_info.push(ListItem::new(Line::from(vec![
-                        Span::raw(" "),
-                        Span::styled("Shared with", Style::default().bold()),
-                        Span::raw(" : "),
-                        Span::styled(format!("{}", folder_sharer), Style::default().bold()),
-                        Span::raw(format!(" Device{}", s_suffix)),
-                    ])));
-
-                    for i in 0..folder_sharer {
-                        if let Some(device_id) = folder.get_sharer_excluded(&state.id).get(i) {
-                            let ident = if i < folder_sharer - 1 {
-                                "├─"
-                            } else {
-                                "└─"
-                            };
-                            if let Ok(device) = state.get_device(device_id) {
-                                folder_info.push(ListItem::new(Line::from(format!(
-                                    "  {} {}",
-                                    ident, device.config.name
-                                ))));
-                            }
+                        let label = list_name(folder, ids);
+                        let spacing = (max + 2) - label.chars().count();
+                        let mut spans = Vec::new();
+                        if let Some(color) = color {
+                            spans.push(Span::styled(
+                                format!("{} ", self.app.config.icons.group_marker()),
+                                Style::default().fg(*color),
+                            ));
                         }
+                        let icons = self.app.config.icons;
+                        if self.app.ui_state.is_favorite(&folder.config.id) {
+                            spans.push(Span::styled(icons.favorite(), Style::default().yellow()));
+                        } else if self.app.ui_state.has_favorites() {
+                            // Keep the labels aligned with the ones of favorites
+                            spans.push(Span::raw(" ".repeat(icons.favorite().chars().count())));
+                        }
+                        spans.extend([
+                            Span::raw(self.app.config.icons.folder()),
+                            Span::raw(label),
+                            Span::raw(" ".repeat(spacing)),
+                            online_span,
+                        ]);
+                        if !folder.failed_items.is_empty() {
+                            spans.push(Span::styled(
+                                format!(" [{} failed]", folder.failed_items.len()),
+                                Style::default().red(),
+                            ));
+                        }
+                        Line::from(spans)
                     }
-                    let inner_area = block.inner(chunks[1]);
-                    block.render(chunks[1], buf);
-                    let list = List::new(folder_info);
-                    Widget::render(list, inner_area, buf);
-                }
-            });
-        }
+                })
+                .collect();
+
+            let list = List::new(list).highlight_style(Style::new().bg(Color::DarkGray));
+            let mut list_state = ListState::default().with_selected(self.app.folders.list.selected);
+            StatefulWidget::render(list, chunks[0], buf, &mut list_state);
+            self.app.hits.borrow_mut().add(
+                chunks[0],
+                HitTarget::Rows {
+                    offset: list_state.offset(),
+                },
+            );
+
+            if let Some(folder) = self
+                .app
+                .folders
+                .list
+                .selected
+                .and_then(|i| rows.get(i))
+                .and_then(|row| row.folder())
+            {
+                let block = Block::default()
+                    .title_top(
+                        Line::from(format!("| {} |", folder.config.label))
+                            .centered()
+                            .bold(),
+                    )
+                    .borders(Borders::ALL)
+                    .border_style(if self.app.folders.detail.focused {
+                        Style::default().fg(Color::Blue)
+                    } else {
+                        Style::default()
+                    });
+                let folder_info =
+                    FoldersPage::detail(state, folder, self.app.reveal_ids, self.app.config.icons);
+                let mut detail_state = ListState::default().with_offset(
+                    self.app
+                        .folders
+                        .detail
+                        .scroll
+                        .min(folder_info.len().saturating_sub(1)),
+                );
+
+                let inner_area = block.inner(chunks[1]);
+                block.render(chunks[1], buf);
+                let list = List::new(folder_info);
+                StatefulWidget::render(list, inner_area, buf, &mut detail_state);
+            }
+        });
     }
 }
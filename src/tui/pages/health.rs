@@ -0,0 +1,157 @@
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListState, StatefulWidget, Widget},
+};
+
+use crate::tui::{
+    app::App,
+    hits::HitTarget,
+    state::{HealthIssue, PENDING_FLOOD_WINDOW},
+    ui::format_duration,
+};
+
+pub struct HealthPage<'a> {
+    app: &'a App,
+}
+
+impl<'a> HealthPage<'a> {
+    pub fn new(app: &'a App) -> Self {
+        Self { app }
+    }
+}
+
+impl Widget for HealthPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        (&self).render(area, buf);
+    }
+}
+
+impl Widget for &HealthPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let (issues, stale_devices) = self.app.state.read(|state| {
+            let stale_devices: Vec<_> = state
+                .get_stale_devices(&self.app.config)
+                .into_iter()
+                .map(|(device, unseen)| {
+                    let mut spans = vec![
+                        Span::raw(device.config.name.clone()),
+                        Span::raw(format!(" — last seen {} ago", format_duration(unseen))),
+                    ];
+                    if device.config.paused {
+                        spans.push(Span::styled(
+                            " [Paused]",
+                            Style::default().dark_gray().bold(),
+                        ));
+                    }
+                    Line::from(spans)
+                })
+                .collect();
+            (state.get_health_issues(&self.app.config), stale_devices)
+        });
+
+        if issues.is_empty() && stale_devices.is_empty() {
+            Line::from(Span::styled(
+                "Everything looks healthy",
+                Style::default().green().bold(),
+            ))
+            .render(area, buf);
+            return;
+        }
+
+        let list: Vec<_> = issues
+            .iter()
+            .map(|issue| match issue {
+                HealthIssue::StuckFolder {
+                    label,
+                    completion,
+                    duration,
+                } => Line::from(vec![
+                    Span::styled("[Stuck] ", Style::default().yellow().bold()),
+                    Span::raw(format!(
+                        "Folder \"{}\" is at {:.0}% since {}",
+                        label,
+                        completion,
+                        format_duration(*duration)
+                    )),
+                ]),
+                HealthIssue::ClockSkew { ahead, difference } => Line::from(vec![
+                    Span::styled("[Clock] ", Style::default().red().bold()),
+                    Span::raw(format!(
+                        "Syncthing's clock is {} {} this machine's, which breaks versioning cleanup",
+                        format_duration(*difference),
+                        if *ahead { "ahead of" } else { "behind" }
+                    )),
+                ]),
+                HealthIssue::UnannouncedFolder { device, folder } => Line::from(vec![
+                    Span::styled("[Not shared back] ", Style::default().yellow().bold()),
+                    Span::raw(format!(
+                        "Folder \"{}\" is shared with {}, but {} does not share it",
+                        folder, device, device
+                    )),
+                ]),
+                HealthIssue::PathConflict {
+                    folder,
+                    other,
+                    conflict,
+                } => Line::from(vec![
+                    Span::styled("[Path] ", Style::default().red().bold()),
+                    Span::raw(format!(
+                        "Folder \"{}\" {}, so both sync the same files",
+                        folder,
+                        conflict.describe(other)
+                    )),
+                ]),
+                HealthIssue::PendingDeviceFlood { count } => Line::from(vec![
+                    Span::styled("[Exposed] ", Style::default().red().bold()),
+                    Span::raw(format!(
+                        "{} unknown devices tried to connect within {}. Syncthing might be reachable from the internet with global announce on, press (g) to review it",
+                        count,
+                        format_duration(PENDING_FLOOD_WINDOW)
+                    )),
+                ]),
+                HealthIssue::SubscriberLeak { counts } => Line::from(vec![
+                    Span::styled("[Leak] ", Style::default().yellow().bold()),
+                    Span::raw(format!(
+                        "{} listeners for state changes and {} for events are open, more than synctui ever needs. Restart synctui if it gets slow",
+                        counts.changes, counts.events
+                    )),
+                ]),
+            })
+            .collect();
+
+        if stale_devices.is_empty() {
+            Widget::render(List::new(list), area, buf);
+            return;
+        }
+
+        let [issues_area, stale_area] =
+            Layout::vertical([Constraint::Length(list.len() as u16), Constraint::Fill(1)])
+                .areas(area);
+        Widget::render(List::new(list), issues_area, buf);
+
+        let block = Block::default()
+            .title_top(Line::from("| Stale devices |").centered().bold())
+            .title_bottom(Line::from("| (p) pause | (x) remove |").right_aligned())
+            .borders(Borders::TOP | Borders::BOTTOM);
+        let inner_area = block.inner(stale_area);
+        block.render(stale_area, buf);
+
+        let selected = self
+            .app
+            .health
+            .list
+            .selected
+            .map(|i| i.min(stale_devices.len() - 1));
+        let list = List::new(stale_devices).highlight_style(Style::new().bg(Color::DarkGray));
+        let mut list_state = ListState::default().with_selected(selected);
+        StatefulWidget::render(list, inner_area, buf, &mut list_state);
+        self.app.hits.borrow_mut().add(
+            inner_area,
+            HitTarget::Rows {
+                offset: list_state.offset(),
+            },
+        );
+    }
+}
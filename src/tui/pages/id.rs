@@ -1,9 +1,11 @@
+#[cfg(feature = "qr")]
 use qrcode::QrCode;
 use ratatui::{
-    layout::{Constraint, Flex, Layout, Rect},
+    layout::{Constraint, Flex, Layout},
     text::Text,
     widgets::Widget,
 };
+#[cfg(feature = "qr")]
 use tui_qrcode::QrCodeWidget;
 
 pub struct IDPage {
@@ -16,7 +18,12 @@ impl IDPage {
     }
 }
 
-fn center(area: Rect, horizontal: Constraint, vertical: Constraint) -> Rect {
+#[cfg(feature = "qr")]
+fn center(
+    area: ratatui::prelude::Rect,
+    horizontal: Constraint,
+    vertical: Constraint,
+) -> ratatui::prelude::Rect {
     let [area] = Layout::horizontal([horizontal])
         .flex(Flex::Center)
         .areas(area);
@@ -29,23 +36,29 @@ impl Widget for IDPage {
     where
         Self: Sized,
     {
-        // TODO do error handling - e.g., just don't show QR code
-        let qr_code = QrCode::new(&self.id).expect("could not generate QR code");
-        let widget = QrCodeWidget::new(qr_code);
-
-        let mut qr_area = center(
-            area,
-            Constraint::Length(widget.size(area).width),
-            Constraint::Length(widget.size(area).height),
-        );
-        qr_area.y -= 1;
-
-        let text = Text::raw(self.id);
+        let text = Text::raw(self.id.clone());
         let [mut text_area] = Layout::horizontal([Constraint::Length(text.width() as u16)])
             .flex(Flex::Center)
             .areas(area);
-        text_area.y = qr_area.y + qr_area.height;
-        widget.render(qr_area, buf);
+        text_area.y = area.y + area.height / 2;
+
+        // Without a QR code, e.g., if the terminal is too small, the ID is
+        // only shown as text
+        #[cfg(feature = "qr")]
+        if let Ok(qr_code) = QrCode::new(&self.id) {
+            let widget = QrCodeWidget::new(qr_code);
+            let size = widget.size(area);
+            if size.width <= area.width && size.height < area.height {
+                let mut qr_area = center(
+                    area,
+                    Constraint::Length(size.width),
+                    Constraint::Length(size.height),
+                );
+                qr_area.y = qr_area.y.saturating_sub(1).max(area.y);
+                text_area.y = qr_area.y + qr_area.height;
+                widget.render(qr_area, buf);
+            }
+        }
         text.render(text_area, buf);
     }
 }
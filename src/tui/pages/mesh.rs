@@ -0,0 +1,180 @@
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListState, StatefulWidget, Widget},
+};
+
+use crate::tui::{
+    app::{App, CurrentScreen},
+    input::Message,
+    search::SearchTarget,
+    state::{InnerState, Section},
+    ui::{loading_line, split_panes},
+};
+
+/// Folders next to the devices, where selecting one side highlights what it
+/// is linked to on the other
+pub struct MeshPage<'a> {
+    app: &'a App,
+}
+
+#[derive(Debug, Default)]
+pub struct MeshPageState {
+    devices_focused: bool,
+    focused_folder: Option<usize>,
+    focused_device: Option<usize>,
+}
+
+impl MeshPageState {
+    pub fn update(&mut self, msg: &Message, total_folders: usize, total_devices: usize) {
+        let (focused, total) = if self.devices_focused {
+            (&mut self.focused_device, total_devices)
+        } else {
+            (&mut self.focused_folder, total_folders)
+        };
+        match msg {
+            Message::Left | Message::Right | Message::FocusNext | Message::FocusBack => {
+                self.devices_focused = !self.devices_focused;
+            }
+            Message::Down if total > 0 => *focused = Some(focused.map_or(0, |i| (i + 1) % total)),
+            Message::Up if total > 0 => {
+                *focused = Some(focused.map_or(total - 1, |i| (i + total - 1) % total))
+            }
+            _ => {}
+        }
+        // The lists might have shrunk since the last update
+        self.focused_folder = self
+            .focused_folder
+            .filter(|_| total_folders > 0)
+            .map(|i| i.min(total_folders - 1));
+        self.focused_device = self
+            .focused_device
+            .filter(|_| total_devices > 0)
+            .map(|i| i.min(total_devices - 1));
+    }
+
+    /// The folder or device selected on the focused side
+    pub fn selected(&self, state: &InnerState) -> Option<SearchTarget> {
+        if self.devices_focused {
+            self.focused_device
+                .and_then(|i| state.get_other_devices().get(i).copied())
+                .map(|device| SearchTarget::Device(device.config.device_id.clone()))
+        } else {
+            self.focused_folder
+                .and_then(|i| state.get_folders().get(i).copied())
+                .map(|folder| SearchTarget::Folder(folder.config.id.clone()))
+        }
+    }
+}
+
+impl<'a> MeshPage<'a> {
+    pub fn new(app: &'a App) -> Self {
+        Self { app }
+    }
+
+    fn block(&self, title: &str, focused: bool) -> Block<'static> {
+        Block::default()
+            .title_top(Line::from(format!("| {} |", title)).centered().bold())
+            .borders(Borders::ALL)
+            .border_style(if focused {
+                Style::default().fg(Color::Blue)
+            } else {
+                Style::default()
+            })
+    }
+}
+
+/// Marks the items linked to the selection on the other side
+fn linked_line(name: &str, linked: bool) -> Line<'_> {
+    if linked {
+        Line::from(vec![
+            Span::styled("● ", Style::default().yellow()),
+            Span::styled(name, Style::default().yellow().bold()),
+        ])
+    } else {
+        Line::from(vec![Span::raw("  "), Span::raw(name)])
+    }
+}
+
+impl Widget for MeshPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        (&self).render(area, buf);
+    }
+}
+
+impl Widget for &MeshPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        if !self
+            .app
+            .state
+            .read(|state| state.is_loaded(Section::Configuration))
+        {
+            loading_line("folders and devices").render(area, buf);
+            return;
+        }
+
+        let mesh = &self.app.mesh_state;
+        let [folders_area, devices_area] =
+            split_panes(area, self.app.ui_state.get_split(&CurrentScreen::Mesh));
+
+        // Render while reading the state, so that the lines can borrow from it
+        self.app.state.read(|state| {
+            let selected = mesh.selected(state);
+            // The devices sharing the selected folder, looked up once
+            let sharing_selected = match &selected {
+                Some(SearchTarget::Folder(folder_id)) => state
+                    .get_folder(folder_id)
+                    .map(|folder| folder.config.devices.as_slice())
+                    .unwrap_or_default(),
+                _ => &[],
+            };
+            let folders: Vec<_> = state
+                .get_folders()
+                .iter()
+                .map(|folder| {
+                    let linked = matches!(&selected, Some(SearchTarget::Device(device_id))
+                        if folder.config.devices.iter().any(|d| &d.device_id == device_id));
+                    linked_line(&folder.config.label, linked)
+                })
+                .collect();
+            let devices: Vec<_> = state
+                .get_other_devices()
+                .iter()
+                .map(|device| {
+                    let linked = sharing_selected
+                        .iter()
+                        .any(|d| d.device_id == device.config.device_id);
+                    linked_line(&device.config.name, linked)
+                })
+                .collect();
+
+            for (lines, selected, title, focused, area) in [
+                (
+                    folders,
+                    mesh.focused_folder,
+                    "Folders",
+                    !mesh.devices_focused,
+                    folders_area,
+                ),
+                (
+                    devices,
+                    mesh.focused_device,
+                    "Devices",
+                    mesh.devices_focused,
+                    devices_area,
+                ),
+            ] {
+                let block = self.block(title, focused);
+                let inner_area = block.inner(area);
+                block.render(area, buf);
+                let list = List::new(lines).highlight_style(if focused {
+                    Style::new().bg(Color::DarkGray)
+                } else {
+                    Style::new()
+                });
+                let mut list_state = ListState::default().with_selected(selected);
+                StatefulWidget::render(list, inner_area, buf, &mut list_state);
+            }
+        });
+    }
+}
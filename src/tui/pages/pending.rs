@@ -1,5 +1,4 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
     style::{Color, Style, Stylize},
     text::{Line, Span},
     widgets::{Block, List, ListState, StatefulWidget},
@@ -7,7 +6,12 @@ use ratatui::{
 
 use ratatui::widgets::Widget;
 
-use crate::tui::{app::App, input::Message};
+use crate::tui::{
+    app::{App, CurrentScreen},
+    input::Message,
+    state::Section,
+    ui::{display_id, loading_line, split_panes},
+};
 
 pub struct PendingPage<'a> {
     app: &'a App,
@@ -47,6 +51,18 @@ impl PendingPageState {
         }
     }
 
+    /// Focuses the pending device at `index`
+    pub fn select_device(&mut self, index: usize) {
+        self.devices_focused = true;
+        self.focused_device = Some(index);
+    }
+
+    /// Focuses the pending folder at `index`
+    pub fn select_folder(&mut self, index: usize) {
+        self.devices_focused = false;
+        self.focused_folder = Some(index);
+    }
+
     pub fn update(&mut self, msg: &Message, total_devices: usize, total_folders: usize) {
         match msg {
             Message::Left | Message::Right | Message::FocusNext | Message::FocusBack => {
@@ -126,23 +142,37 @@ impl Widget for &PendingPage<'_> {
     where
         Self: Sized,
     {
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(area);
+        let chunks = split_panes(area, self.app.ui_state.get_split(&CurrentScreen::Pending));
 
         // Devices
         let devices_list: Vec<_> = self.app.state.read(|state| {
+            if !state.is_loaded(Section::PendingDevices) {
+                return vec![loading_line("pending devices")];
+            }
             state
                 .get_pending_devices()
                 .iter()
                 .map(|d| {
-                    d.get_name()
-                        .clone()
-                        .unwrap_or("<unknwon name>".to_string())
-                        .clone()
+                    let name = d.get_name().clone().unwrap_or("<unknwon name>".to_string());
+                    let mut line = Line::from(vec![
+                        Span::raw(name),
+                        Span::styled(
+                            format!(" ({})", display_id(d.get_device_id(), self.app.reveal_ids)),
+                            Style::default().dark_gray(),
+                        ),
+                    ]);
+                    if state.is_on_lan(d.get_device_id()) {
+                        line.push_span(Span::styled(" [LAN]", Style::default().green()));
+                    }
+                    if let Some(known) = state.address_book.get(d.get_device_id()) {
+                        line.push_span(Span::styled(
+                            format!(" [Known as '{}']", known.name),
+                            Style::default().cyan(),
+                        ));
+                    }
+                    line
                 })
-                .collect()
+                .collect::<Vec<_>>()
         });
 
         let devices_list = List::new(devices_list)
@@ -156,13 +186,16 @@ impl Widget for &PendingPage<'_> {
 
         // Folders
         let folders_list: Vec<_> = self.app.state.read(|state| {
+            if !state.is_loaded(Section::PendingFolders) {
+                return vec![loading_line("pending folders")];
+            }
             state
                 .get_pending_folders()
                 .iter()
                 .map(|(device_id, folder)| {
                     let device_name = match state.get_device(device_id) {
-                        Ok(d) => &d.config.name,
-                        Err(_) => "<unknown device>",
+                        Ok(d) => d.config.name.clone(),
+                        Err(_) => display_id(device_id, self.app.reveal_ids),
                     };
 
                     let text = match state.get_folder(folder.get_id()) {
@@ -177,7 +210,7 @@ impl Widget for &PendingPage<'_> {
                         "{} \"{}\" ({}) - {}",
                         text,
                         label,
-                        folder.get_id(),
+                        display_id(folder.get_id(), self.app.reveal_ids),
                         device_name
                     ))
                 })
@@ -0,0 +1,214 @@
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListState, StatefulWidget, Widget},
+};
+use serde_json::Value;
+
+use crate::{
+    rest::GuiField,
+    tui::{app::App, hits::HitTarget, state::InnerState, ui::loading_line},
+};
+
+/// How a setting is stored in the options of Syncthing
+#[derive(Clone, Copy, Debug)]
+enum SettingKind {
+    Bool,
+    /// The version of usage reporting which was accepted, or -1 if it was
+    /// declined
+    UsageReporting,
+}
+
+/// A global option of Syncthing which is either on or off
+#[derive(Debug)]
+pub struct Setting {
+    key: &'static str,
+    pub label: &'static str,
+    pub description: &'static str,
+    kind: SettingKind,
+}
+
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "natEnabled",
+        label: "NAT traversal",
+        description: "Open a port on the router with UPnP or NAT-PMP",
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        key: "relaysEnabled",
+        label: "Relays",
+        description: "Connect through relays if there is no direct connection",
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        key: "localAnnounceEnabled",
+        label: "Local discovery",
+        description: "Find devices on the local network",
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        key: "globalAnnounceEnabled",
+        label: "Global discovery",
+        description: "Find devices through the global discovery servers",
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        key: "startBrowser",
+        label: "Start browser",
+        description: "Open the web GUI when Syncthing starts",
+        kind: SettingKind::Bool,
+    },
+    Setting {
+        key: "urAccepted",
+        label: "Usage reporting",
+        description: "Send anonymous usage statistics to the Syncthing project",
+        kind: SettingKind::UsageReporting,
+    },
+];
+
+impl Setting {
+    pub fn is_on(&self, options: &Value) -> bool {
+        let value = options.get(self.key);
+        match self.kind {
+            SettingKind::Bool => value.and_then(Value::as_bool).unwrap_or_default(),
+            SettingKind::UsageReporting => value.and_then(Value::as_i64).unwrap_or_default() > 0,
+        }
+    }
+
+    /// Switches the setting on or off in `options`. Usage reporting is
+    /// accepted in `ur_version`, or the latest version Syncthing asked for if
+    /// it is unknown.
+    pub fn set(&self, options: &mut Value, on: bool, ur_version: u64) {
+        let value = match self.kind {
+            SettingKind::Bool => Value::from(on),
+            SettingKind::UsageReporting if on => {
+                let seen = options.get("urSeen").and_then(Value::as_u64).unwrap_or(0);
+                Value::from(ur_version.max(seen).max(1))
+            }
+            SettingKind::UsageReporting => Value::from(-1),
+        };
+        if let Some(options) = options.as_object_mut() {
+            options.insert(self.key.to_string(), value);
+        }
+    }
+}
+
+/// The values of all settings as saved in Syncthing, once the configuration
+/// is loaded
+pub fn saved_settings(state: &InnerState) -> Option<Vec<bool>> {
+    let options = state.raw_config.as_ref()?.get("options")?;
+    Some(SETTINGS.iter().map(|s| s.is_on(options)).collect())
+}
+
+/// The settings of the GUI listed below the options, which are changed one
+/// at a time. The API key follows them.
+pub const GUI_FIELDS: [GuiField; 3] = [GuiField::Address, GuiField::User, GuiField::Password];
+
+/// Label and shown value of the rows below the options, once the
+/// configuration is loaded. The password and API key are never shown.
+pub fn gui_rows(state: &InnerState) -> Option<Vec<(&'static str, String)>> {
+    let gui = state.raw_config.as_ref()?.get("gui")?;
+    let text = |key: &str| gui.get(key).and_then(Value::as_str).unwrap_or_default();
+    let mut rows: Vec<_> = GUI_FIELDS
+        .iter()
+        .map(|field| {
+            let value = match field {
+                GuiField::Password if text(field.key()).is_empty() => "not set".to_string(),
+                GuiField::Password => "set".to_string(),
+                _ => text(field.key()).to_string(),
+            };
+            (field.label(), value)
+        })
+        .collect();
+    rows.push(("API key", "••••••••".to_string()));
+    Some(rows)
+}
+
+pub struct SettingsPage<'a> {
+    app: &'a App,
+}
+
+impl<'a> SettingsPage<'a> {
+    pub fn new(app: &'a App) -> Self {
+        Self { app }
+    }
+}
+
+impl Widget for SettingsPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        (&self).render(area, buf);
+    }
+}
+
+impl Widget for &SettingsPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let (Some(saved), Some(gui)) = self
+            .app
+            .state
+            .read(|state| (saved_settings(state), gui_rows(state)))
+        else {
+            loading_line("settings").render(area, buf);
+            return;
+        };
+        let controller = &self.app.settings;
+        let label_width = SETTINGS
+            .iter()
+            .map(|s| s.label)
+            .chain(gui.iter().map(|(label, _)| *label))
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut unsaved = 0;
+        let mut rows: Vec<_> = SETTINGS
+            .iter()
+            .zip(&saved)
+            .enumerate()
+            .map(|(i, (setting, saved))| {
+                let value = controller.value(i, *saved);
+                let changed = value != *saved;
+                if changed {
+                    unsaved += 1;
+                }
+                Line::from(vec![
+                    Span::styled(if changed { "* " } else { "  " }, Style::default().yellow()),
+                    Span::raw(format!(
+                        "{} {:<width$}  ",
+                        if value { "[x]" } else { "[ ]" },
+                        setting.label,
+                        width = label_width
+                    )),
+                    Span::styled(setting.description, Style::default().dark_gray()),
+                ])
+            })
+            .collect();
+        rows.extend(gui.into_iter().map(|(label, value)| {
+            Line::from(vec![
+                Span::raw(format!("      {:<width$}  ", label, width = label_width)),
+                Span::styled(value, Style::default().cyan()),
+            ])
+        }));
+
+        let mut block = Block::default()
+            .borders(Borders::BOTTOM)
+            .title_bottom(Line::from("| (enter) toggle or change | (S) save |").right_aligned());
+        if unsaved > 0 {
+            block = block.title_bottom(
+                Line::from(format!("| {} unsaved |", unsaved)).style(Style::default().yellow()),
+            );
+        }
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        let list = List::new(rows).highlight_style(Style::new().bg(Color::DarkGray));
+        let mut list_state = ListState::default().with_selected(controller.list.selected);
+        StatefulWidget::render(list, inner_area, buf, &mut list_state);
+        self.app.hits.borrow_mut().add(
+            inner_area,
+            HitTarget::Rows {
+                offset: list_state.offset(),
+            },
+        );
+    }
+}
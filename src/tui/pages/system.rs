@@ -0,0 +1,127 @@
+use std::{collections::HashMap, time::Duration};
+
+use ratatui::{
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+use crate::{
+    rest::ServiceStatus,
+    tui::{
+        app::App,
+        ui::{format_bytes, format_duration, format_rate, format_timestamp},
+    },
+};
+
+pub struct SystemPage<'a> {
+    app: &'a App,
+}
+
+impl<'a> SystemPage<'a> {
+    pub fn new(app: &'a App) -> Self {
+        Self { app }
+    }
+}
+
+/// A bold heading, preceded by an empty line unless it is the first one
+fn heading(lines: &mut Vec<Line<'static>>, title: &'static str) {
+    if !lines.is_empty() {
+        lines.push(Line::default());
+    }
+    lines.push(Line::from(Span::styled(title, Style::default().bold())));
+}
+
+fn row(label: &str, value: impl Into<String>) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("  {:<14}", label), Style::default().dark_gray()),
+        Span::raw(value.into()),
+    ])
+}
+
+/// One line per service, sorted by name, with its error if it failed
+fn services(lines: &mut Vec<Line<'static>>, services: &HashMap<String, ServiceStatus>) {
+    let mut services: Vec<_> = services.iter().collect();
+    services.sort_by_key(|(name, _)| *name);
+    for (name, status) in services {
+        let status = match &status.error {
+            Some(error) => Span::styled(format!("  {}", error), Style::default().red()),
+            None => Span::styled("  OK", Style::default().green()),
+        };
+        lines.push(Line::from(vec![Span::raw(format!("  {}", name)), status]));
+    }
+}
+
+impl Widget for SystemPage<'_> {
+    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer) {
+        let lines = self.app.state.read(|state| {
+            let Some(status) = &state.system_status else {
+                return vec![Line::from("Loading system status…").dark_gray()];
+            };
+            let mut lines = Vec::new();
+
+            heading(&mut lines, "Syncthing");
+            lines.push(row("Device ID", status.my_id.clone()));
+            lines.push(row(
+                "Uptime",
+                format!(
+                    "{} (since {})",
+                    format_duration(Duration::from_secs(status.uptime)),
+                    format_timestamp(status.start_time)
+                ),
+            ));
+            lines.push(row(
+                "Memory",
+                format!(
+                    "{} allocated, {} from the system",
+                    format_bytes(status.alloc as f64),
+                    format_bytes(status.sys as f64)
+                ),
+            ));
+            if status.cpu_percent > 0.0 {
+                lines.push(row("CPU", format!("{:.1}%", status.cpu_percent)));
+            }
+            lines.push(row("Goroutines", status.goroutines.to_string()));
+            lines.push(row(
+                "Transfer",
+                format!(
+                    "↓ {}  ↑ {}",
+                    format_rate(state.total_traffic.download_rate),
+                    format_rate(state.total_traffic.upload_rate)
+                ),
+            ));
+
+            heading(&mut lines, "Listeners");
+            services(&mut lines, &status.connection_service_status);
+
+            heading(&mut lines, "Discovery");
+            if status.discovery_enabled {
+                services(&mut lines, &status.discovery_status);
+            } else {
+                lines.push(Line::from("  Disabled").dark_gray());
+            }
+
+            heading(&mut lines, "Discovered devices");
+            let mut discovered: Vec<_> = state
+                .discovery_cache
+                .iter()
+                .map(|(device_id, device)| {
+                    let name = state
+                        .get_device(device_id)
+                        .map(|device| device.config.name.clone())
+                        .unwrap_or_else(|_| device_id.clone());
+                    (name, device.addresses.join(", "))
+                })
+                .collect();
+            discovered.sort();
+            if discovered.is_empty() {
+                lines.push(Line::from("  None").dark_gray());
+            }
+            for (name, addresses) in discovered {
+                lines.push(row(&name, addresses));
+            }
+            lines
+        });
+        Paragraph::new(lines).render(area, buf);
+    }
+}
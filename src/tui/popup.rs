@@ -5,69 +5,193 @@ use std::{
 
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout, Margin, Position, Rect},
+    layout::{Constraint, Layout, Margin, Position, Rect},
     style::{Color, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListState, Paragraph, StatefulWidget, Widget},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph, StatefulWidget, Widget, Wrap},
 };
 use strum::IntoEnumIterator;
 use syncthing_rs::types::config::{
-    DeviceConfiguration, FolderConfiguration, FolderDeviceConfiguration, NewFolderConfiguration,
+    Compression, DeviceConfiguration, FolderConfiguration, FolderDeviceConfiguration, FolderType,
+    NewDeviceConfiguration, NewFolderConfiguration, VersioningType,
 };
 
-use super::{app::CurrentMode, input::Message};
+use super::{
+    app::{CurrentMode, CurrentScreen},
+    history::{StateDiff, StateVersion},
+    hits::{HitMap, HitTarget},
+    input::Message,
+    keymap::Action,
+    notes::{Note, NoteTarget},
+    search::{SearchTarget, search},
+    ui::{
+        format_bytes, format_compression, format_folder_type, format_timestamp,
+        format_versioning_type,
+    },
+};
 
-use crate::tui::state::State;
+use crate::{
+    AddressBookEntry, IconSet, parse_device_link,
+    rest::{GuiField, RateLimits},
+    tui::state::{
+        Device, DeviceStatus, FolderCheck, FolderProblem, FolderStatus, InnerState, Reload, State,
+    },
+};
 
 pub trait Popup: std::fmt::Debug {
     /// Updates the state of the popup. If Some(Quit) is returned, the popup gets destroyed
     fn update(&mut self, msg: Message, state: State) -> Option<Message>;
-    fn render(&self, frame: &mut Frame, state: State);
-    fn create_popup_block(&self, title: String) -> Block {
+    /// Draws the popup, and registers its buttons in `hits` to make them
+    /// clickable
+    fn render(&self, frame: &mut Frame, state: State, hits: &mut HitMap);
+    /// The message reopening this popup, if closing it without a decision
+    /// should keep the request around for later
+    fn snooze(&self) -> Option<Message> {
+        None
+    }
+    fn create_popup_block(&self, title: String) -> Block<'_> {
         Block::default()
             .title_top(Line::from(format!("| {} |", title)).centered().bold())
             .borders(Borders::ALL)
     }
 }
 
-/// helper function to create a centered rect using up certain percentage of the available rect `r`
-// Adapted from https://ratatui.rs/tutorials/json-editor/ui/
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    // Cut the given rectangle into three vertical pieces
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage((100 - percent_y) / 2),
-        ])
-        .split(r);
-
-    // Then cut the middle vertical piece into three width-wise pieces
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
-        ])
-        .split(popup_layout[1])[1] // Return the middle chunk
+/// Narrowest and widest a popup gets, unless the terminal is smaller
+const POPUP_MIN_WIDTH: u16 = 40;
+const POPUP_MAX_WIDTH: u16 = 100;
+
+/// Length of a device ID, including the dashes
+const DEVICE_ID_WIDTH: u16 = 63;
+
+/// Failed items listed in the info tab of a folder, the rest is only counted
+const MAX_FAILED_INFO_ITEMS: usize = 5;
+
+/// Unit of the maximum age of staggered versioning, which Syncthing keeps
+/// in seconds
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Joins `buttons` with `gap` into a line drawn in `area`, and makes each
+/// of them clickable as the button with its index
+fn button_line<'a>(
+    buttons: Vec<Span<'a>>,
+    gap: &'a str,
+    centered: bool,
+    area: Rect,
+    hits: &mut HitMap,
+) -> Line<'a> {
+    let gap_width = gap.chars().count() as u16;
+    let width = buttons
+        .iter()
+        .map(|b| b.width() as u16 + gap_width)
+        .sum::<u16>();
+    let mut x = if centered {
+        area.x + area.width.saturating_sub(width.saturating_sub(gap_width)) / 2
+    } else {
+        area.x
+    };
+    let mut spans = Vec::new();
+    for (i, button) in buttons.into_iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(gap));
+            x += gap_width;
+        }
+        let button_area = Rect::new(x, area.y, button.width() as u16, 1);
+        hits.add(button_area.intersection(area), HitTarget::Button(i));
+        x += button.width() as u16;
+        spans.push(button);
+    }
+    let line = Line::from(spans);
+    if centered { line.centered() } else { line }
+}
+
+/// Tabs of a popup, switched to with the digits and listed in its bottom
+/// border. Tabs which are left out, e.g., in basic mode, do not take a
+/// number.
+#[derive(Debug)]
+struct Tabs<T> {
+    tabs: Vec<T>,
+    selected: usize,
+}
+
+impl<T: Copy + PartialEq + std::fmt::Debug> Tabs<T> {
+    /// Shows `tabs` in this order, starting with the first one
+    fn new(tabs: Vec<T>) -> Self {
+        assert!(!tabs.is_empty(), "a popup needs at least one tab");
+        Self { tabs, selected: 0 }
+    }
+
+    fn current(&self) -> T {
+        self.tabs[self.selected]
+    }
+
+    /// Switches to the tab with the 1-based `number`, if there is one
+    fn select_number(&mut self, number: u32) {
+        if let Some(index) = (number as usize).checked_sub(1)
+            && index < self.tabs.len()
+        {
+            self.selected = index;
+        }
+    }
+
+    /// The tabs as listed in the bottom border, with the current one in bold
+    fn title(&self) -> Vec<Span<'static>> {
+        let mut spans: Vec<_> = self
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| {
+                Span::styled(
+                    format!("| ({}) {:?} ", i + 1, tab),
+                    if i == self.selected {
+                        Style::default().bold()
+                    } else {
+                        Style::default()
+                    },
+                )
+            })
+            .collect();
+        spans.push("|".into());
+        spans
+    }
+}
+
+/// Creates a rect centered in `r`, which fits content of `width` and
+/// `height`, including borders and margins. The width is kept between
+/// [`POPUP_MIN_WIDTH`] and [`POPUP_MAX_WIDTH`], and the rect never exceeds `r`.
+pub(super) fn popup_rect(width: u16, height: u16, r: Rect) -> Rect {
+    let width = width.clamp(POPUP_MIN_WIDTH, POPUP_MAX_WIDTH).min(r.width);
+    let height = height.min(r.height);
+    Rect {
+        x: r.x + (r.width - width) / 2,
+        y: r.y + (r.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Width of the widest of `texts`
+pub(super) fn text_width<'a>(texts: impl IntoIterator<Item = &'a str>) -> u16 {
+    texts
+        .into_iter()
+        .map(|text| text.chars().count())
+        .max()
+        .unwrap_or(0) as u16
 }
 
 #[derive(Default, Debug)]
-struct TextBox {
-    text: String,
-    index: usize,
+pub(super) struct TextBox {
+    pub(super) text: String,
+    pub(super) index: usize,
 }
 
 // This impl is heavily inspired (copied) by https://ratatui.rs/examples/apps/user_input/
 impl TextBox {
-    fn move_cursor_left(&mut self) {
+    pub(super) fn move_cursor_left(&mut self) {
         let cursor_moved_left = self.index.saturating_sub(1);
         self.index = self.clamp_cursor(cursor_moved_left);
     }
 
-    fn move_cursor_right(&mut self) {
+    pub(super) fn move_cursor_right(&mut self) {
         let cursor_moved_right = self.index.saturating_add(1);
         self.index = self.clamp_cursor(cursor_moved_right);
     }
@@ -116,7 +240,12 @@ impl TextBox {
         new_cursor_pos.clamp(0, self.text.chars().count())
     }
 
-    fn as_paragraph<'a>(&'a self, title: &'a str, style: Style) -> Paragraph<'a> {
+    /// The text as a rate in KiB/s, 0 meaning unlimited
+    fn as_kbps(&self) -> u64 {
+        self.text.trim().parse().unwrap_or_default()
+    }
+
+    pub(super) fn as_paragraph<'a>(&'a self, title: &'a str, style: Style) -> Paragraph<'a> {
         Paragraph::new(self.text.as_str())
             .style(style)
             .block(Block::bordered().title(title))
@@ -135,10 +264,14 @@ pub struct NewFolderPopup {
     id_input: TextBox,
     label_input: TextBox,
     path_input: TextBox,
+    folder_type: FolderType,
     focus: NewFolderFocus,
     mode: Arc<Mutex<CurrentMode>>,
     state: State,
     selected_devices: HashSet<String>,
+    /// The device offering the folder, if it is accepted from one
+    offered_by: Option<String>,
+    icons: IconSet,
 }
 
 #[derive(Default, Debug, PartialEq, Eq)]
@@ -147,26 +280,30 @@ enum NewFolderFocus {
     Path,
     Label,
     Id,
+    Type,
     Device(usize),
     SubmitButton,
 }
 
 impl NewFolderFocus {
     fn is_input(&self) -> bool {
-        !matches!(self, Self::Device(_) | Self::SubmitButton)
+        !matches!(self, Self::Type | Self::Device(_) | Self::SubmitButton)
     }
 }
 
 impl NewFolderPopup {
-    pub fn new(mode: Arc<Mutex<CurrentMode>>, state: State) -> Self {
+    pub fn new(mode: Arc<Mutex<CurrentMode>>, state: State, icons: IconSet) -> Self {
         Self {
             id_input: TextBox::default(),
             label_input: TextBox::default(),
             path_input: TextBox::default(),
+            folder_type: FolderType::default(),
             focus: NewFolderFocus::default(),
             mode,
             state,
             selected_devices: HashSet::new(),
+            offered_by: None,
+            icons,
         }
     }
 
@@ -177,26 +314,32 @@ impl NewFolderPopup {
         device_id: impl Into<String>,
         mode: Arc<Mutex<CurrentMode>>,
         state: State,
+        icons: IconSet,
     ) -> Self {
+        let device_id = device_id.into();
         let mut selected_devices = HashSet::new();
-        selected_devices.insert(device_id.into());
+        selected_devices.insert(device_id.clone());
         Self {
             id_input: folder_id.into().into(),
             label_input: folder_label.into().into(),
             path_input: TextBox::default(),
+            folder_type: FolderType::default(),
             focus: NewFolderFocus::default(),
             mode,
             state,
             selected_devices,
+            offered_by: Some(device_id),
+            icons,
         }
     }
 
     fn select_next(&mut self) {
-        let devices_len = self.state.read(|state| state.get_other_devices().len());
+        let devices_len = self.state.read(|state| state.count_other_devices());
         match self.focus {
             NewFolderFocus::Path => self.focus = NewFolderFocus::Label,
             NewFolderFocus::Label => self.focus = NewFolderFocus::Id,
-            NewFolderFocus::Id => {
+            NewFolderFocus::Id => self.focus = NewFolderFocus::Type,
+            NewFolderFocus::Type => {
                 if devices_len > 0 {
                     self.focus = NewFolderFocus::Device(0);
                 } else {
@@ -216,21 +359,22 @@ impl NewFolderPopup {
 
     fn select_prev(&mut self) {
         match self.focus {
+            NewFolderFocus::Type => self.focus = NewFolderFocus::Id,
             NewFolderFocus::Id => self.focus = NewFolderFocus::Label,
             NewFolderFocus::Label => self.focus = NewFolderFocus::Path,
             NewFolderFocus::Device(i) => {
                 if i == 0 {
-                    self.focus = NewFolderFocus::Id;
+                    self.focus = NewFolderFocus::Type;
                 } else {
                     self.focus = NewFolderFocus::Device(i - 1);
                 }
             }
             NewFolderFocus::SubmitButton => {
-                let devices_len = self.state.read(|state| state.get_other_devices().len());
+                let devices_len = self.state.read(|state| state.count_other_devices());
                 if devices_len > 0 {
                     self.focus = NewFolderFocus::Device(devices_len - 1);
                 } else {
-                    self.focus = NewFolderFocus::Id;
+                    self.focus = NewFolderFocus::Type;
                 }
             }
             _ => {}
@@ -250,6 +394,7 @@ impl NewFolderPopup {
         Some(Message::NewFolder(Box::new(
             NewFolderConfiguration::new(self.id_input.text.clone(), self.path_input.text.clone())
                 .label(self.label_input.text.clone())
+                .folder_type(self.folder_type)
                 .devices(devices),
         )))
     }
@@ -278,19 +423,24 @@ impl Popup for NewFolderPopup {
             Message::Quit => return Some(Message::Quit),
             Message::FocusNext | Message::Down => self.select_next(),
             Message::FocusBack | Message::Up => self.select_prev(),
-            Message::Left => {
-                if let NewFolderFocus::Device(i) = self.focus {
-                    if i > 0 {
-                        self.select_prev();
-                    }
+            Message::Left => match self.focus {
+                NewFolderFocus::Type => {
+                    self.folder_type = cycle_folder_type(&self.folder_type, false, true)
                 }
-            }
-            Message::Right => {
-                if let NewFolderFocus::Device(_) = self.focus {
-                    self.select_next();
+                NewFolderFocus::Device(i) if i > 0 => self.select_prev(),
+                _ => {}
+            },
+            Message::Right => match self.focus {
+                NewFolderFocus::Type => {
+                    self.folder_type = cycle_folder_type(&self.folder_type, true, true)
                 }
-            }
+                NewFolderFocus::Device(_) => self.select_next(),
+                _ => {}
+            },
             Message::Select => match self.focus {
+                NewFolderFocus::Type => {
+                    self.folder_type = cycle_folder_type(&self.folder_type, true, true)
+                }
                 NewFolderFocus::SubmitButton => return self.submit(),
                 NewFolderFocus::Device(i) => {
                     if let Some(device_id) = self.state.read(|state| {
@@ -314,10 +464,17 @@ impl Popup for NewFolderPopup {
         None
     }
 
-    fn render(&self, frame: &mut Frame, _state: State) {
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
         let block = self.create_popup_block("New Folder".to_string());
+        let offer = match &self.offered_by {
+            Some(device_id) => self
+                .state
+                .read(|state| offer_lines(state, &self.id_input.text, device_id)),
+            None => Vec::new(),
+        };
         let vertical = Layout::vertical([
-            Constraint::Length(1),
+            Constraint::Length(offer.len() as u16 + 1),
+            Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Length(3),
@@ -325,20 +482,37 @@ impl Popup for NewFolderPopup {
             Constraint::Length(1),
         ]);
 
-        let area = centered_rect(50, 50, frame.area());
+        let width = text_width([
+            self.path_input.text.as_str(),
+            self.label_input.text.as_str(),
+            self.id_input.text.as_str(),
+        ])
+        .max(offer.iter().map(Line::width).max().unwrap_or(0) as u16);
+        let area = popup_rect(width + 5, 18 + offer.len() as u16, frame.area());
         Clear.render(area, frame.buffer_mut());
-        let [_, path_area, label_area, id_area, devices_area, submit_area] =
-            vertical.areas(area.inner(Margin {
-                horizontal: 1,
-                vertical: 1,
-            }));
+        let [
+            offer_area,
+            path_area,
+            label_area,
+            id_area,
+            type_area,
+            devices_area,
+            submit_area,
+        ] = vertical.areas(area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        }));
 
+        let mut path_block = Block::bordered().title("Path");
+        if let Some(warning) = path_warning(&self.state, &self.path_input.text) {
+            path_block = path_block.title_bottom(Line::styled(warning, Style::default().yellow()));
+        }
         let path_input = Paragraph::new(self.path_input.text.as_str())
             .style(match self.focus {
                 NewFolderFocus::Path => Style::default().fg(Color::Blue),
                 _ => Style::default(),
             })
-            .block(Block::bordered().title("Path"));
+            .block(path_block);
 
         let label_input = Paragraph::new(self.label_input.text.as_str())
             .style(match self.focus {
@@ -355,6 +529,13 @@ impl Popup for NewFolderPopup {
             })
             .block(Block::bordered().title("ID"));
 
+        let type_input = Paragraph::new(format!("< {} >", format_folder_type(&self.folder_type)))
+            .style(match self.focus {
+                NewFolderFocus::Type => Style::default().fg(Color::Blue),
+                _ => Style::default(),
+            })
+            .block(Block::bordered().title("Type"));
+
         let devices_line: Line = self.state.read(|state| {
             state
                 .get_other_devices()
@@ -366,12 +547,9 @@ impl Popup for NewFolderPopup {
                     } else {
                         Style::new()
                     };
-                    let selected_char = if self.selected_devices.contains(&device.config.device_id)
-                    {
-                        "✓"
-                    } else {
-                        "☐"
-                    };
+                    let selected_char = self
+                        .icons
+                        .checkbox(self.selected_devices.contains(&device.config.device_id));
                     Span::styled(
                         format!("| {} {} ", selected_char, device.config.name.clone()),
                         style,
@@ -407,12 +585,488 @@ impl Popup for NewFolderPopup {
         }
 
         frame.render_widget(block, area);
+        frame.render_widget(Paragraph::new(offer), offer_area);
         frame.render_widget(path_input, path_area);
         frame.render_widget(label_input, label_area);
         frame.render_widget(id_input, id_area);
+        frame.render_widget(type_input, type_area);
         frame.render_widget(devices_select, devices_area);
         frame.render_widget(submit, submit_area);
     }
+
+    fn snooze(&self) -> Option<Message> {
+        self.offered_by
+            .as_ref()
+            .map(|device_id| Message::NewPendingFolder {
+                folder_label: self.label_input.text.clone(),
+                folder_id: self.id_input.text.clone(),
+                device_id: device_id.clone(),
+            })
+    }
+}
+
+/// Warns if a new folder at `path` would overlap with an existing one
+fn path_warning(state: &State, path: &str) -> Option<String> {
+    state
+        .read(|state| state.find_path_conflict(path, None))
+        .map(|(other, conflict)| format!("Path {}", conflict.describe(&other)))
+}
+
+/// What is known about the offer of `device_id` to share `folder_id`.
+/// Syncthing only exchanges the contents of a folder once it is shared, so
+/// its size and file count are unknown until then.
+fn offer_lines(state: &InnerState, folder_id: &str, device_id: &str) -> Vec<Line<'static>> {
+    let name = |device_id: &str| {
+        state
+            .get_device(device_id)
+            .map(|device| device.config.name.clone())
+            .unwrap_or_else(|_| device_id.to_string())
+    };
+    let mut lines = Vec::new();
+    if let Some(offer) = state.get_pending_offer(device_id, folder_id) {
+        lines.push(Line::from(format!(
+            "Offered by {} at {}",
+            name(device_id),
+            format_timestamp(offer.time)
+        )));
+        if offer.receive_encrypted {
+            lines.push(Line::styled(
+                "Shared encrypted, this device only receives encrypted data",
+                Style::default().yellow(),
+            ));
+        }
+        if offer.remote_encrypted {
+            lines.push(Line::styled(
+                format!("{} only stores it encrypted", name(device_id)),
+                Style::default().yellow(),
+            ));
+        }
+    }
+    let others: Vec<_> = state
+        .get_pending_offers(folder_id)
+        .into_iter()
+        .filter(|other| *other != device_id)
+        .map(|other| name(other))
+        .collect();
+    if !others.is_empty() {
+        lines.push(Line::from(format!("Also offered by {}", others.join(", "))));
+    }
+    lines.push(Line::styled(
+        "Size unknown until the folder is shared",
+        Style::default().dark_gray(),
+    ));
+    lines
+}
+
+/// Steps of the [`FolderWizardPopup`], in order
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, strum::EnumIter)]
+enum WizardStep {
+    #[default]
+    Path,
+    Identity,
+    Devices,
+    Advanced,
+    Review,
+}
+
+impl WizardStep {
+    fn title(&self) -> &'static str {
+        match self {
+            WizardStep::Path => "Path",
+            WizardStep::Identity => "Label and ID",
+            WizardStep::Devices => "Devices",
+            WizardStep::Advanced => "Advanced",
+            WizardStep::Review => "Review",
+        }
+    }
+
+    fn next(self) -> Self {
+        WizardStep::iter()
+            .skip_while(|step| *step != self)
+            .nth(1)
+            .unwrap_or(self)
+    }
+
+    fn prev(self) -> Self {
+        WizardStep::iter()
+            .take_while(|step| *step != self)
+            .last()
+            .unwrap_or(self)
+    }
+}
+
+/// Creates a folder one step at a time, as an alternative to the
+/// [`NewFolderPopup`], enabled with `folder-wizard` in the config
+#[derive(Debug)]
+pub struct FolderWizardPopup {
+    step: WizardStep,
+    /// Index of the focused field of the step, followed by the back and next
+    /// buttons
+    focus: usize,
+    path_input: TextBox,
+    label_input: TextBox,
+    id_input: TextBox,
+    /// Shared with all selected devices, which receive the folder in
+    /// plaintext if it is empty
+    password_input: TextBox,
+    selected_devices: HashSet<String>,
+    /// Why the current step cannot be left yet
+    error: Option<String>,
+    mode: Arc<Mutex<CurrentMode>>,
+    state: State,
+    /// The device offering the folder, if it is accepted from one
+    offered_by: Option<String>,
+    icons: IconSet,
+}
+
+impl FolderWizardPopup {
+    pub fn new(mode: Arc<Mutex<CurrentMode>>, state: State, icons: IconSet) -> Self {
+        Self {
+            step: WizardStep::default(),
+            focus: 0,
+            path_input: TextBox::default(),
+            label_input: TextBox::default(),
+            id_input: TextBox::default(),
+            password_input: TextBox::default(),
+            selected_devices: HashSet::new(),
+            error: None,
+            mode,
+            state,
+            offered_by: None,
+            icons,
+        }
+    }
+
+    /// This can be used if accepting a folder from another device
+    pub fn new_from_device(
+        folder_label: impl Into<String>,
+        folder_id: impl Into<String>,
+        device_id: impl Into<String>,
+        mode: Arc<Mutex<CurrentMode>>,
+        state: State,
+        icons: IconSet,
+    ) -> Self {
+        let device_id = device_id.into();
+        let mut wizard = Self::new(mode, state, icons);
+        wizard.label_input = folder_label.into().into();
+        wizard.id_input = folder_id.into().into();
+        wizard.selected_devices.insert(device_id.clone());
+        wizard.offered_by = Some(device_id);
+        wizard
+    }
+
+    /// Number of fields of the current step, without the buttons
+    fn fields(&self) -> usize {
+        match self.step {
+            WizardStep::Path | WizardStep::Advanced => 1,
+            WizardStep::Identity => 2,
+            WizardStep::Devices => self.state.read(|state| state.count_other_devices()),
+            WizardStep::Review => 0,
+        }
+    }
+
+    fn input(&mut self) -> Option<&mut TextBox> {
+        match (self.step, self.focus) {
+            (WizardStep::Path, 0) => Some(&mut self.path_input),
+            (WizardStep::Identity, 0) => Some(&mut self.label_input),
+            (WizardStep::Identity, 1) => Some(&mut self.id_input),
+            (WizardStep::Advanced, 0) => Some(&mut self.password_input),
+            _ => None,
+        }
+    }
+
+    /// Why the current step is not complete yet
+    fn validate(&self) -> Option<String> {
+        match self.step {
+            WizardStep::Path if self.path_input.text.trim().is_empty() => {
+                Some("The folder needs a path".to_string())
+            }
+            WizardStep::Identity if self.id_input.text.trim().is_empty() => {
+                Some("The folder needs an ID".to_string())
+            }
+            WizardStep::Identity
+                if self
+                    .state
+                    .read(|state| state.get_folder(self.id_input.text.trim()).is_ok()) =>
+            {
+                Some(format!(
+                    "A folder with ID '{}' exists already",
+                    self.id_input.text.trim()
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Moves to the next step, or creates the folder after the review
+    fn forward(&mut self) -> Option<Message> {
+        self.error = self.validate();
+        if self.error.is_some() {
+            return None;
+        }
+        if self.step == WizardStep::Review {
+            return self.submit();
+        }
+        // Suggest the name of the directory as label
+        if self.step == WizardStep::Path && self.label_input.text.is_empty() {
+            let path = self.path_input.text.trim().trim_end_matches(['/', '\\']);
+            if let Some(name) = path.rsplit(['/', '\\']).next() {
+                self.label_input = name.to_string().into();
+            }
+        }
+        self.step = self.step.next();
+        self.focus = 0;
+        None
+    }
+
+    fn back(&mut self) {
+        self.error = None;
+        self.step = self.step.prev();
+        self.focus = 0;
+    }
+
+    fn submit(&mut self) -> Option<Message> {
+        *self.mode.lock().unwrap() = CurrentMode::Normal;
+        let devices: Vec<FolderDeviceConfiguration> = self
+            .selected_devices
+            .iter()
+            .map(|d| FolderDeviceConfiguration {
+                device_id: d.to_string(),
+                introduced_by: "".to_string(),
+                encryption_password: self.password_input.text.clone(),
+            })
+            .collect();
+        Some(Message::NewFolder(Box::new(
+            NewFolderConfiguration::new(
+                self.id_input.text.trim().to_string(),
+                self.path_input.text.trim().to_string(),
+            )
+            .label(self.label_input.text.clone())
+            .devices(devices),
+        )))
+    }
+
+    fn toggle_device(&mut self, i: usize) {
+        if let Some(device_id) = self.state.read(|state| {
+            state
+                .get_other_devices()
+                .get(i)
+                .map(|d| d.config.device_id.clone())
+        }) && !self.selected_devices.remove(&device_id)
+        {
+            self.selected_devices.insert(device_id);
+        }
+    }
+
+    fn style(&self, focus: usize) -> Style {
+        if self.focus == focus {
+            Style::default().fg(Color::Blue)
+        } else {
+            Style::default()
+        }
+    }
+
+    /// Lines summarizing the folder, shown in the review step
+    fn review_lines(&self) -> Vec<Line<'_>> {
+        let devices = self.state.read(|state| {
+            state
+                .get_other_devices()
+                .iter()
+                .filter(|d| self.selected_devices.contains(&d.config.device_id))
+                .map(|d| d.config.name.clone())
+                .collect::<Vec<_>>()
+        });
+        let field = |name: &'static str, value: String| {
+            Line::from(vec![
+                Span::styled(name, Style::default().bold()),
+                Span::raw(" : "),
+                Span::raw(value),
+            ])
+        };
+        vec![
+            field("Path", self.path_input.text.trim().to_string()),
+            field("Label", self.label_input.text.clone()),
+            field("ID", self.id_input.text.trim().to_string()),
+            field(
+                "Shared with",
+                if devices.is_empty() {
+                    "no one".to_string()
+                } else {
+                    devices.join(", ")
+                },
+            ),
+            field(
+                "Encryption",
+                if self.password_input.text.is_empty() {
+                    "none".to_string()
+                } else {
+                    "password set".to_string()
+                },
+            ),
+        ]
+    }
+}
+
+impl Popup for FolderWizardPopup {
+    fn update(&mut self, msg: Message, _: State) -> Option<Message> {
+        let fields = self.fields();
+        if let Some(input) = self.input() {
+            match msg {
+                Message::Character(c) => input.enter_char(c),
+                Message::Backspace => input.delete_char(),
+                Message::Left => input.move_cursor_left(),
+                Message::Right => input.move_cursor_right(),
+                _ => {}
+            }
+        }
+
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::FocusNext | Message::Down => self.focus = (self.focus + 1).min(fields + 1),
+            Message::FocusBack | Message::Up => self.focus = self.focus.saturating_sub(1),
+            Message::Left if self.focus == fields + 1 => self.focus = fields,
+            Message::Right if self.focus == fields => self.focus = fields + 1,
+            Message::Select => match self.focus {
+                focus if focus == fields => self.back(),
+                focus if focus == fields + 1 => return self.forward(),
+                focus if self.step == WizardStep::Devices => self.toggle_device(focus),
+                // Continue with the next button after the last field
+                focus if focus + 1 == fields => self.focus = fields + 1,
+                focus => self.focus = focus + 1,
+            },
+            Message::Submit => return self.forward(),
+            _ => {}
+        };
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let steps = WizardStep::iter().count();
+        let index = WizardStep::iter()
+            .position(|step| step == self.step)
+            .unwrap_or(0);
+        let block = self.create_popup_block(format!(
+            "New Folder {}/{}: {}",
+            index + 1,
+            steps,
+            self.step.title()
+        ));
+
+        let fields = self.fields();
+        let mut body: Vec<Line> = Vec::new();
+        let mut inputs: Vec<(&TextBox, &str)> = Vec::new();
+        match self.step {
+            WizardStep::Path => {
+                inputs.push((&self.path_input, "Path"));
+                if let Some(device_id) = &self.offered_by {
+                    body = self
+                        .state
+                        .read(|state| offer_lines(state, &self.id_input.text, device_id));
+                }
+                if let Some(warning) = path_warning(&self.state, &self.path_input.text) {
+                    body.push(Line::styled(warning, Style::default().yellow()));
+                }
+            }
+            WizardStep::Identity => {
+                inputs.push((&self.label_input, "Label"));
+                inputs.push((&self.id_input, "ID"));
+            }
+            WizardStep::Devices => self.state.read(|state| {
+                let devices = state.get_other_devices();
+                if devices.is_empty() {
+                    body.push(Line::styled(
+                        "There are no other devices to share with yet.",
+                        Style::default().dark_gray(),
+                    ));
+                }
+                for (i, device) in devices.iter().enumerate() {
+                    let selected_char = self
+                        .icons
+                        .checkbox(self.selected_devices.contains(&device.config.device_id));
+                    body.push(Line::styled(
+                        format!("{} {}", selected_char, device.config.name),
+                        self.style(i),
+                    ));
+                }
+            }),
+            WizardStep::Advanced => {
+                inputs.push((&self.password_input, "Encryption password"));
+                body.push(Line::styled(
+                    "Devices only receive encrypted data if set.",
+                    Style::default().dark_gray(),
+                ));
+            }
+            WizardStep::Review => body = self.review_lines(),
+        }
+        if let Some(error) = &self.error {
+            body.push(Line::styled(error.as_str(), Style::default().red()));
+        }
+
+        let width = text_width(inputs.iter().map(|(input, _)| input.text.as_str()))
+            .max(body.iter().map(Line::width).max().unwrap_or(0) as u16);
+        let height = 3 * inputs.len() as u16 + body.len() as u16 + 5;
+        let area = popup_rect(width + 5, height, frame.area());
+        Clear.render(area, frame.buffer_mut());
+        let inner = area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+        let [inputs_area, body_area, _, buttons_area] = Layout::vertical([
+            Constraint::Length(3 * inputs.len() as u16),
+            Constraint::Length(body.len() as u16),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+        let input_areas =
+            Layout::vertical(vec![Constraint::Length(3); inputs.len()]).split(inputs_area);
+
+        frame.render_widget(block, area);
+        for (i, ((input, title), input_area)) in inputs.iter().zip(input_areas.iter()).enumerate() {
+            frame.render_widget(input.as_paragraph(title, self.style(i)), *input_area);
+            if self.focus == i && *self.mode.lock().unwrap() == CurrentMode::Insert {
+                frame.set_cursor_position(Position::new(
+                    input_area.x + input.index as u16 + 1,
+                    input_area.y + 1,
+                ));
+            }
+        }
+        frame.render_widget(Paragraph::new(body), body_area);
+
+        let button = |text: &'static str, focus: usize| {
+            Span::styled(
+                text,
+                if self.focus == focus {
+                    Style::default().bg(Color::DarkGray)
+                } else {
+                    Style::default()
+                },
+            )
+        };
+        let next = if self.step == WizardStep::Review {
+            "Create"
+        } else {
+            "Next"
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                button("Back", fields),
+                Span::raw(" | "),
+                button(next, fields + 1),
+            ])),
+            buttons_area,
+        );
+    }
+
+    fn snooze(&self) -> Option<Message> {
+        self.offered_by
+            .as_ref()
+            .map(|device_id| Message::NewPendingFolder {
+                folder_label: self.label_input.text.clone(),
+                folder_id: self.id_input.text.clone(),
+                device_id: device_id.clone(),
+            })
+    }
 }
 
 #[derive(Debug)]
@@ -421,7 +1075,7 @@ pub struct PendingDevicePopup {
     focus: PendingFocus,
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 enum PendingFocus {
     #[default]
     Accept,
@@ -430,6 +1084,13 @@ enum PendingFocus {
 }
 
 impl PendingFocus {
+    /// In the order the buttons are drawn in
+    const BUTTONS: [PendingFocus; 3] = [
+        PendingFocus::Accept,
+        PendingFocus::Ignore,
+        PendingFocus::Dismiss,
+    ];
+
     fn next(&mut self) {
         match self {
             PendingFocus::Accept => *self = PendingFocus::Ignore,
@@ -471,27 +1132,50 @@ impl Popup for PendingDevicePopup {
             Message::FocusNext | Message::Right => self.focus.next(),
             Message::FocusBack | Message::Left => self.focus.prev(),
             Message::Select | Message::Submit => return self.submit(),
+            Message::Press(i) => {
+                if let Some(focus) = PendingFocus::BUTTONS.get(i) {
+                    self.focus = *focus;
+                    return self.submit();
+                }
+            }
             _ => {}
         };
         None
     }
 
-    fn render(&self, frame: &mut Frame, _state: State) {
+    fn render(&self, frame: &mut Frame, state: State, hits: &mut HitMap) {
         let block = self.create_popup_block("Pending Device".to_string());
         let vertical = Layout::vertical([Constraint::Length(2), Constraint::Length(1)]);
 
-        let area = centered_rect(50, 50, frame.area());
-        Clear.render(area, frame.buffer_mut());
+        // TODO use state to load device name
+        let mut lines = vec![Line::from(format!(
+            "Device {} wants to connect.",
+            self.device_id
+        ))];
+        if let Some(name) = state.read(|state| {
+            state
+                .address_book
+                .get(&self.device_id)
+                .map(|entry| entry.name.clone())
+        }) {
+            lines.push(Line::styled(
+                format!("This ID matches '{}' in your address book.", name),
+                Style::default().cyan(),
+            ));
+        }
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+        let line = Paragraph::new(lines);
+
+        let area = popup_rect(width + 2, 5, frame.area());
+        Clear.render(area, frame.buffer_mut());
         let [message_area, buttons_area] = vertical.areas(area.inner(Margin {
             horizontal: 1,
             vertical: 1,
         }));
-        // TODO use state to load device name
-        let line = Line::from(format!("Device {} wants to connect.", self.device_id));
 
         let selected_style = Style::new().bg(Color::DarkGray);
 
-        let buttons_line: Line = vec![
+        let buttons = vec![
             Span::styled(
                 "Accept",
                 if matches!(self.focus, PendingFocus::Accept) {
@@ -500,7 +1184,6 @@ impl Popup for PendingDevicePopup {
                     Style::new()
                 },
             ),
-            Span::raw(" "),
             Span::styled(
                 "Ignore",
                 if matches!(self.focus, PendingFocus::Ignore) {
@@ -509,7 +1192,6 @@ impl Popup for PendingDevicePopup {
                     Style::new()
                 },
             ),
-            Span::raw(" "),
             Span::styled(
                 "Dismiss",
                 if matches!(self.focus, PendingFocus::Dismiss) {
@@ -518,13 +1200,17 @@ impl Popup for PendingDevicePopup {
                     Style::new()
                 },
             ),
-        ]
-        .into();
+        ];
+        let buttons_line = button_line(buttons, " ", false, buttons_area, hits);
 
         frame.render_widget(block, area);
         frame.render_widget(line, message_area);
         frame.render_widget(buttons_line, buttons_area);
     }
+
+    fn snooze(&self) -> Option<Message> {
+        Some(Message::NewPendingDevice(self.device_id.clone()))
+    }
 }
 
 /// Popup to share an already existing folder with a new device
@@ -550,7 +1236,10 @@ impl PendingShareFolderPopup {
                 folder_id: self.folder_id.clone(),
                 device_id: self.device_id.clone(),
             }),
-            PendingFocus::Ignore => todo!(),
+            PendingFocus::Ignore => Some(Message::IgnoreFolder {
+                folder_id: self.folder_id.clone(),
+                device_id: self.device_id.clone(),
+            }),
             PendingFocus::Dismiss => Some(Message::DismissFolder {
                 folder_id: self.folder_id.clone(),
                 device_id: self.device_id.clone(),
@@ -566,21 +1255,21 @@ impl Popup for PendingShareFolderPopup {
             Message::FocusNext | Message::Right => self.focus.next(),
             Message::FocusBack | Message::Left => self.focus.prev(),
             Message::Select | Message::Submit => return self.submit(),
+            Message::Press(i) => {
+                if let Some(focus) = PendingFocus::BUTTONS.get(i) {
+                    self.focus = *focus;
+                    return self.submit();
+                }
+            }
             _ => {}
         };
         None
     }
 
-    fn render(&self, frame: &mut Frame, state: State) {
+    fn render(&self, frame: &mut Frame, state: State, hits: &mut HitMap) {
         let block = self.create_popup_block("Share Folder".to_string());
         let vertical = Layout::vertical([Constraint::Length(2), Constraint::Length(1)]);
 
-        let area = centered_rect(50, 50, frame.area());
-        Clear.render(area, frame.buffer_mut());
-        let [message_area, buttons_area] = vertical.areas(area.inner(Margin {
-            horizontal: 1,
-            vertical: 1,
-        }));
         let line = state.read(|state| {
             // TODO maybe show device label too
             let folder = state
@@ -591,9 +1280,16 @@ impl Popup for PendingShareFolderPopup {
                 folder.config.label, folder.config.id, self.device_id
             ))
         });
+
+        let area = popup_rect(line.width() as u16 + 2, 5, frame.area());
+        Clear.render(area, frame.buffer_mut());
+        let [message_area, buttons_area] = vertical.areas(area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        }));
         let selected_style = Style::new().bg(Color::DarkGray);
 
-        let buttons_line: Line = vec![
+        let buttons = vec![
             Span::styled(
                 "Share",
                 if matches!(self.focus, PendingFocus::Accept) {
@@ -602,7 +1298,6 @@ impl Popup for PendingShareFolderPopup {
                     Style::new()
                 },
             ),
-            Span::raw(" "),
             Span::styled(
                 "Ignore",
                 if matches!(self.focus, PendingFocus::Ignore) {
@@ -611,7 +1306,6 @@ impl Popup for PendingShareFolderPopup {
                     Style::new()
                 },
             ),
-            Span::raw(" "),
             Span::styled(
                 "Dismiss",
                 if matches!(self.focus, PendingFocus::Dismiss) {
@@ -620,13 +1314,21 @@ impl Popup for PendingShareFolderPopup {
                     Style::new()
                 },
             ),
-        ]
-        .into();
+        ];
+        let buttons_line = button_line(buttons, " ", false, buttons_area, hits);
 
         frame.render_widget(block, area);
         frame.render_widget(line, message_area);
         frame.render_widget(buttons_line, buttons_area);
     }
+
+    fn snooze(&self) -> Option<Message> {
+        Some(Message::NewPendingFolder {
+            folder_label: String::new(),
+            folder_id: self.folder_id.clone(),
+            device_id: self.device_id.clone(),
+        })
+    }
 }
 
 /// Popup representing a folder
@@ -638,92 +1340,148 @@ pub struct FolderPopup {
     path: TextBox,
     devices: Vec<FolderDeviceConfiguration>,
     selected_device: Option<usize>,
-    focus: FolderFocus,
+    /// Whether the sharing tab only lists the devices the folder is not
+    /// shared with yet
+    unshared_only: bool,
+    /// Versions kept by simple versioning
+    keep: TextBox,
+    /// Days staggered versioning keeps versions for
+    max_age: TextBox,
+    versions_path: TextBox,
+    /// Command external versioning runs instead of deleting a file
+    command: TextBox,
+    tabs: Tabs<FolderTab>,
     general_focus: FolderGeneralFocus,
+    versioning_focus: VersioningFocus,
     mode: Arc<Mutex<CurrentMode>>,
+    icons: IconSet,
+    /// Whether the type, the versioning tab and the remove button are shown
+    advanced: bool,
 }
 
-#[derive(Debug, Default, strum::EnumIter, PartialEq, Eq)]
-enum FolderFocus {
-    #[default]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FolderTab {
     General,
     Sharing,
+    /// What Syncthing reports about the folder, which cannot be edited
+    Info,
+    Versioning,
 }
 
-impl TryFrom<u32> for FolderFocus {
-    type Error = ();
-
-    fn try_from(v: u32) -> Result<Self, Self::Error> {
-        if let Some((_, screen)) = FolderFocus::iter()
-            .enumerate()
-            .find(|(i, _)| i + 1 == (v as usize))
-        {
-            Ok(screen)
-        } else {
-            Err(())
-        }
-    }
+/// Field of the versioning tab. Which ones are shown depends on the type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum VersioningFocus {
+    #[default]
+    Type,
+    Keep,
+    MaxAge,
+    Path,
+    Command,
+    Submit,
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 enum FolderGeneralFocus {
     #[default]
     Label,
-    ID,
     Path,
+    Type,
     Submit,
     Remove,
 }
 
 impl FolderGeneralFocus {
-    fn next(&mut self) {
+    /// Fields which are only shown in advanced mode
+    fn is_advanced(&self) -> bool {
+        matches!(self, FolderGeneralFocus::Type | FolderGeneralFocus::Remove)
+    }
+
+    fn next(&mut self, advanced: bool) {
+        skip_hidden(self, advanced, Self::step_next, Self::is_advanced);
+    }
+
+    fn prev(&mut self, advanced: bool) {
+        skip_hidden(self, advanced, Self::step_prev, Self::is_advanced);
+    }
+
+    fn step_next(&mut self) {
         match self {
-            FolderGeneralFocus::Label => *self = FolderGeneralFocus::ID,
-            FolderGeneralFocus::ID => *self = FolderGeneralFocus::Path,
-            FolderGeneralFocus::Path => *self = FolderGeneralFocus::Submit,
+            FolderGeneralFocus::Label => *self = FolderGeneralFocus::Path,
+            FolderGeneralFocus::Path => *self = FolderGeneralFocus::Type,
+            FolderGeneralFocus::Type => *self = FolderGeneralFocus::Submit,
             FolderGeneralFocus::Submit => *self = FolderGeneralFocus::Remove,
             FolderGeneralFocus::Remove => {}
         }
     }
 
-    fn prev(&mut self) {
+    fn step_prev(&mut self) {
         match self {
             FolderGeneralFocus::Label => {}
-            FolderGeneralFocus::ID => *self = FolderGeneralFocus::Label,
-            FolderGeneralFocus::Path => *self = FolderGeneralFocus::ID,
-            FolderGeneralFocus::Submit => *self = FolderGeneralFocus::Path,
+            FolderGeneralFocus::Path => *self = FolderGeneralFocus::Label,
+            FolderGeneralFocus::Type => *self = FolderGeneralFocus::Path,
+            FolderGeneralFocus::Submit => *self = FolderGeneralFocus::Type,
             FolderGeneralFocus::Remove => *self = FolderGeneralFocus::Submit,
         }
     }
 }
 
 impl FolderPopup {
-    pub fn new(folder: FolderConfiguration, mode: Arc<Mutex<CurrentMode>>) -> Self {
+    pub fn new(
+        folder: FolderConfiguration,
+        mode: Arc<Mutex<CurrentMode>>,
+        icons: IconSet,
+        advanced: bool,
+    ) -> Self {
         let devices = folder.devices.to_vec();
+        let params = &folder.versioning.params;
+        let param = |key: &str| params.get(key).cloned().unwrap_or_default();
+        let max_age = params
+            .get("maxAge")
+            .and_then(|seconds| seconds.parse::<u64>().ok())
+            .map(|seconds| (seconds / SECONDS_PER_DAY).to_string())
+            .unwrap_or_default();
+        // Older configurations keep the path in the parameters
+        let versions_path = if folder.versioning.fs_path.is_empty() {
+            param("versionsPath")
+        } else {
+            folder.versioning.fs_path.clone()
+        };
         Self {
+            keep: param("keep").into(),
+            max_age: max_age.into(),
+            versions_path: versions_path.into(),
+            command: param("command").into(),
             folder: folder.clone(),
             id: folder.id.into(),
             label: folder.label.into(),
             path: folder.path.into(),
             devices,
             selected_device: None,
-            focus: FolderFocus::default(),
+            unshared_only: false,
+            tabs: Tabs::new(if advanced {
+                vec![
+                    FolderTab::General,
+                    FolderTab::Sharing,
+                    FolderTab::Info,
+                    FolderTab::Versioning,
+                ]
+            } else {
+                vec![FolderTab::General, FolderTab::Sharing, FolderTab::Info]
+            }),
             general_focus: FolderGeneralFocus::default(),
+            versioning_focus: VersioningFocus::default(),
             mode,
+            icons,
+            advanced,
         }
     }
 
     fn submit(&mut self) -> Option<Message> {
-        if self.folder.id != self.id.text {
-            // TODO this is currently unsafe as a potentially different folder
-            // is edited, so don't do anything
-            return None;
-        }
-
         self.folder.path = self.path.text.clone();
         self.folder.label = self.label.text.clone();
 
         self.folder.devices = self.devices.clone();
+        self.apply_versioning();
 
         Some(Message::EditFolder(Box::new(self.folder.clone())))
     }
@@ -731,32 +1489,242 @@ impl FolderPopup {
     fn remove(&self) -> Option<Message> {
         Some(Message::RemoveFolder(self.folder.id.clone()))
     }
+
+    /// Syncthing only sets up encrypted folders when they are created, and
+    /// cannot turn them into regular ones
+    fn type_editable(&self) -> bool {
+        self.folder.folder_type != FolderType::ReceiveEncrypted
+    }
+
+    fn cycle_type(&mut self, forward: bool) {
+        if self.type_editable() {
+            self.folder.folder_type = cycle_folder_type(&self.folder.folder_type, forward, false);
+        }
+    }
+
+    fn cycle_versioning_type(&mut self, forward: bool) {
+        let versioning = &mut self.folder.versioning;
+        versioning.versioning_type = cycle_versioning_type(&versioning.versioning_type, forward);
+    }
+
+    /// Fields of the versioning tab which apply to the selected type, in
+    /// the order they are shown
+    fn versioning_fields(&self) -> Vec<VersioningFocus> {
+        let mut fields = vec![VersioningFocus::Type];
+        fields.extend_from_slice(match self.folder.versioning.versioning_type {
+            VersioningType::None => &[],
+            VersioningType::Trashcan => &[VersioningFocus::Path],
+            VersioningType::Simple => &[VersioningFocus::Keep, VersioningFocus::Path],
+            VersioningType::Staggered => &[VersioningFocus::MaxAge, VersioningFocus::Path],
+            VersioningType::External => &[VersioningFocus::Command],
+        });
+        fields.push(VersioningFocus::Submit);
+        fields
+    }
+
+    fn move_versioning_focus(&mut self, forward: bool) {
+        let fields = self.versioning_fields();
+        let i = fields
+            .iter()
+            .position(|field| *field == self.versioning_focus)
+            .unwrap_or(0);
+        self.versioning_focus = if forward {
+            fields[(i + 1).min(fields.len() - 1)]
+        } else {
+            fields[i.saturating_sub(1)]
+        };
+    }
+
+    fn versioning_input(&mut self) -> Option<&mut TextBox> {
+        match self.versioning_focus {
+            VersioningFocus::Keep => Some(&mut self.keep),
+            VersioningFocus::MaxAge => Some(&mut self.max_age),
+            VersioningFocus::Path => Some(&mut self.versions_path),
+            VersioningFocus::Command => Some(&mut self.command),
+            VersioningFocus::Type | VersioningFocus::Submit => None,
+        }
+    }
+
+    /// Writes the inputs of the versioning tab to the folder. Parameters
+    /// which are not edited here, e.g., the cleanup interval, are kept.
+    fn apply_versioning(&mut self) {
+        let versioning = &mut self.folder.versioning;
+        let max_age = self
+            .max_age
+            .text
+            .trim()
+            .parse::<u64>()
+            .map(|days| (days * SECONDS_PER_DAY).to_string())
+            .unwrap_or_default();
+        for (key, value) in [
+            ("keep", self.keep.text.trim().to_string()),
+            ("maxAge", max_age),
+            ("command", self.command.text.trim().to_string()),
+        ] {
+            if value.is_empty() {
+                versioning.params.remove(key);
+            } else {
+                versioning.params.insert(key.to_string(), value);
+            }
+        }
+        versioning.params.remove("versionsPath");
+        versioning.fs_path = self.versions_path.text.trim().to_string();
+    }
+
+    /// What Syncthing reports about the folder, as shown in the info tab
+    fn info_lines(&self, state: &InnerState) -> Vec<Line<'static>> {
+        let Ok(folder) = state.get_folder(&self.folder.id) else {
+            return vec![Line::styled(
+                "The folder no longer exists",
+                Style::default().red(),
+            )];
+        };
+        let row = |label: &str, value: Span<'static>| {
+            Line::from(vec![
+                Span::styled(format!("{:<12}: ", label), Style::default().bold()),
+                value,
+            ])
+        };
+        let mut lines = Vec::new();
+
+        let status = match &folder.status {
+            _ if folder.config.paused => Span::raw("paused"),
+            FolderStatus::Unknown => Span::styled("unknown", Style::default().dark_gray()),
+            FolderStatus::Idle => Span::raw("idle"),
+            FolderStatus::Scanning => Span::styled("scanning", Style::default().cyan()),
+            FolderStatus::Syncing => Span::styled("syncing", Style::default().blue()),
+            FolderStatus::Waiting => Span::raw("waiting"),
+            FolderStatus::Cleaning => Span::raw("cleaning"),
+            FolderStatus::Error(error) => {
+                Span::styled(format!("error: {}", error), Style::default().red())
+            }
+        };
+        lines.push(row("State", status));
+        if folder.completion_loaded {
+            lines.push(row(
+                "Size",
+                Span::raw(format!(
+                    "{}, {} items",
+                    format_bytes(folder.global_bytes as f64),
+                    folder.global_items
+                )),
+            ));
+            let mut completion = format!("{:.0}%", folder.completion);
+            if folder.progress.need_bytes > 0 {
+                completion.push_str(&format!(
+                    ", {} in {} items left",
+                    format_bytes(folder.progress.need_bytes as f64),
+                    folder.need_items
+                ));
+            }
+            lines.push(row("Completion", Span::raw(completion)));
+        } else {
+            lines.push(row(
+                "Size",
+                Span::styled("not loaded yet", Style::default().dark_gray()),
+            ));
+        }
+        if let Some(error) = &folder.watch_error {
+            lines.push(row(
+                "Watcher",
+                Span::styled(error.clone(), Style::default().red()),
+            ));
+        }
+        if !folder.failed_items.is_empty() {
+            lines.push(row(
+                "Failed items",
+                Span::styled(
+                    folder.failed_items.len().to_string(),
+                    Style::default().red(),
+                ),
+            ));
+            for (item, error) in folder.failed_items.iter().take(MAX_FAILED_INFO_ITEMS) {
+                lines.push(Line::from(vec![
+                    Span::raw(format!("  {}: ", item)),
+                    Span::styled(error.clone(), Style::default().red()),
+                ]));
+            }
+        }
+
+        let sharers = folder.get_sharer_excluded(&state.id);
+        lines.push(Line::from(""));
+        lines.push(Line::styled("Devices", Style::default().bold()));
+        if sharers.is_empty() {
+            lines.push(Line::styled(
+                "  Not shared with any device",
+                Style::default().dark_gray(),
+            ));
+        }
+        for device_id in sharers {
+            let name = state
+                .get_device(device_id)
+                .map(|device| device.config.name.clone())
+                .unwrap_or_else(|_| device_id.clone());
+            let completion = match state.get_remote_completion(device_id, &folder.config.id) {
+                Some(remote) if remote.completion < 100.0 => Span::styled(
+                    format!(
+                        "{:.0}%, {} behind",
+                        remote.completion,
+                        format_bytes(remote.need_bytes as f64)
+                    ),
+                    Style::default().yellow(),
+                ),
+                Some(_) => Span::styled("up to date", Style::default().green()),
+                None => Span::styled("unknown", Style::default().dark_gray()),
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {}: ", name)),
+                completion,
+            ]));
+        }
+        lines
+    }
+
+    /// Devices listed in the sharing tab. Those the folder is not shared
+    /// with yet are sorted by when they were last connected, as they are
+    /// the most likely to be added.
+    fn sharing_devices<'a>(&self, state: &'a InnerState) -> Vec<&'a Device> {
+        let mut devices = state.get_other_devices();
+        if self.unshared_only {
+            devices.retain(|device| {
+                !self
+                    .folder
+                    .devices
+                    .iter()
+                    .any(|d| d.device_id == device.config.device_id)
+            });
+            devices.sort_by_key(|device| {
+                (
+                    device.connected == DeviceStatus::Disconnected,
+                    std::cmp::Reverse(device.last_seen),
+                )
+            });
+        }
+        devices
+    }
 }
 
 impl Popup for FolderPopup {
     fn update(&mut self, msg: Message, state: State) -> Option<Message> {
         match msg {
             Message::Quit => return Some(Message::Quit),
-            Message::Number(i) => {
-                if let Ok(focus) = FolderFocus::try_from(i) {
-                    self.focus = focus;
-                }
-            }
+            Message::Number(i) => self.tabs.select_number(i),
             _ => {}
         }
 
-        match self.focus {
-            FolderFocus::General => {
+        let advanced = self.advanced;
+        match self.tabs.current() {
+            FolderTab::Info => {}
+            FolderTab::General => {
                 let input = match self.general_focus {
                     FolderGeneralFocus::Label => Some(&mut self.label),
-                    FolderGeneralFocus::ID => Some(&mut self.id),
                     FolderGeneralFocus::Path => Some(&mut self.path),
                     _ => None,
                 };
 
                 match msg {
-                    Message::FocusNext | Message::Down => self.general_focus.next(),
-                    Message::FocusBack | Message::Up => self.general_focus.prev(),
+                    Message::FocusNext | Message::Down => self.general_focus.next(advanced),
+                    Message::FocusBack | Message::Up => self.general_focus.prev(advanced),
                     Message::Character(c) => {
                         if let Some(input) = input {
                             input.enter_char(c);
@@ -770,18 +1738,23 @@ impl Popup for FolderPopup {
                     Message::Left => {
                         if let Some(input) = input {
                             input.move_cursor_left();
+                        } else if matches!(self.general_focus, FolderGeneralFocus::Type) {
+                            self.cycle_type(false);
                         } else if matches!(self.general_focus, FolderGeneralFocus::Remove) {
-                            self.general_focus.prev();
+                            self.general_focus.prev(advanced);
                         }
                     }
                     Message::Right => {
                         if let Some(input) = input {
                             input.move_cursor_right();
+                        } else if matches!(self.general_focus, FolderGeneralFocus::Type) {
+                            self.cycle_type(true);
                         } else if matches!(self.general_focus, FolderGeneralFocus::Submit) {
-                            self.general_focus.next();
+                            self.general_focus.next(advanced);
                         }
                     }
                     Message::Select => match self.general_focus {
+                        FolderGeneralFocus::Type => self.cycle_type(true),
                         FolderGeneralFocus::Submit => return self.submit(),
                         FolderGeneralFocus::Remove => return self.remove(),
                         _ => {}
@@ -789,9 +1762,54 @@ impl Popup for FolderPopup {
                     _ => {}
                 }
             }
-            FolderFocus::Sharing => {
-                let len = state.read(|state| state.get_other_devices().len());
+            FolderTab::Versioning => {
+                let focus = self.versioning_focus;
+                match msg {
+                    Message::FocusNext | Message::Down => self.move_versioning_focus(true),
+                    Message::FocusBack | Message::Up => self.move_versioning_focus(false),
+                    Message::Character(c) => {
+                        let numeric =
+                            matches!(focus, VersioningFocus::Keep | VersioningFocus::MaxAge);
+                        if let Some(input) = self.versioning_input()
+                            && (!numeric || c.is_ascii_digit())
+                        {
+                            input.enter_char(c);
+                        }
+                    }
+                    Message::Backspace => {
+                        if let Some(input) = self.versioning_input() {
+                            input.delete_char();
+                        }
+                    }
+                    Message::Left => {
+                        if let Some(input) = self.versioning_input() {
+                            input.move_cursor_left();
+                        } else if focus == VersioningFocus::Type {
+                            self.cycle_versioning_type(false);
+                        }
+                    }
+                    Message::Right => {
+                        if let Some(input) = self.versioning_input() {
+                            input.move_cursor_right();
+                        } else if focus == VersioningFocus::Type {
+                            self.cycle_versioning_type(true);
+                        }
+                    }
+                    Message::Select => match focus {
+                        VersioningFocus::Type => self.cycle_versioning_type(true),
+                        VersioningFocus::Submit => return self.submit(),
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+            FolderTab::Sharing => {
+                let len = state.read(|state| self.sharing_devices(state).len());
                 match msg {
+                    Message::Filter => {
+                        self.unshared_only = !self.unshared_only;
+                        self.selected_device = None;
+                    }
                     Message::FocusNext | Message::Down => {
                         if len == 0 {
                             return None;
@@ -813,28 +1831,27 @@ impl Popup for FolderPopup {
                         }
                     }
                     Message::Select => {
-                        if let Some(selected_device) = self.selected_device {
-                            if let Some(selected_device_id) = state.read(|state| {
-                                state
-                                    .get_other_devices()
+                        if let Some(selected_device) = self.selected_device
+                            && let Some(selected_device_id) = state.read(|state| {
+                                self.sharing_devices(state)
                                     .get(selected_device)
                                     .map(|device| device.config.device_id.clone())
-                            }) {
-                                match self
-                                    .devices
-                                    .iter()
-                                    .position(|d| d.device_id == selected_device_id)
-                                {
-                                    Some(index) => {
-                                        self.devices.remove(index);
-                                    }
-                                    // TODO support passwords
-                                    None => self.devices.push(FolderDeviceConfiguration {
-                                        device_id: selected_device_id,
-                                        introduced_by: "".to_string(),
-                                        encryption_password: "".to_string(),
-                                    }),
+                            })
+                        {
+                            match self
+                                .devices
+                                .iter()
+                                .position(|d| d.device_id == selected_device_id)
+                            {
+                                Some(index) => {
+                                    self.devices.remove(index);
                                 }
+                                // TODO support passwords
+                                None => self.devices.push(FolderDeviceConfiguration {
+                                    device_id: selected_device_id,
+                                    introduced_by: "".to_string(),
+                                    encryption_password: "".to_string(),
+                                }),
                             }
                         }
                     }
@@ -846,37 +1863,43 @@ impl Popup for FolderPopup {
         None
     }
 
-    fn render(&self, frame: &mut Frame, state: State) {
+    fn render(&self, frame: &mut Frame, state: State, _hits: &mut HitMap) {
         let block = self.create_popup_block(format!("Edit Folder ({})", self.folder.label));
 
-        let mut bottom_string = FolderFocus::iter()
-            .enumerate()
-            .map(|(i, focus)| {
-                Span::styled(
-                    format!("| ({}) {:?} ", i + 1, focus),
-                    if focus == self.focus {
-                        Style::default().bold()
-                    } else {
-                        Style::default()
-                    },
-                )
-            })
-            .collect::<Vec<Span>>();
-        bottom_string.push("|".into());
-        let block = block.title_bottom(bottom_string);
+        let block = block.title_bottom(self.tabs.title());
+        let block = if self.tabs.current() == FolderTab::Sharing {
+            block.title_bottom(
+                Line::from(if self.unshared_only {
+                    "| (/) all devices |"
+                } else {
+                    "| (/) not shared yet |"
+                })
+                .right_aligned(),
+            )
+        } else {
+            block
+        };
 
-        let area = centered_rect(75, 75, frame.area());
+        let width = text_width([
+            self.label.text.as_str(),
+            self.id.text.as_str(),
+            self.path.text.as_str(),
+        ]);
+        // Sharing lists every other device, one per line
+        let devices = state.read(|state| state.count_other_devices()) as u16;
+        let area = popup_rect(width + 7, (devices + 4).max(17), frame.area());
         Clear.render(area, frame.buffer_mut());
 
-        match self.focus {
-            FolderFocus::General => {
+        match self.tabs.current() {
+            FolderTab::General => {
                 let vertical = Layout::vertical([
                     Constraint::Length(3),
                     Constraint::Length(3),
                     Constraint::Length(3),
+                    Constraint::Length(if self.advanced { 3 } else { 0 }),
                     Constraint::Length(1),
                 ]);
-                let [label_area, id_area, path_area, buttons_area] =
+                let [label_area, id_area, path_area, type_area, buttons_area] =
                     vertical.areas(area.inner(Margin {
                         horizontal: 2,
                         vertical: 2,
@@ -893,14 +1916,8 @@ impl Popup for FolderPopup {
                     },
                 );
 
-                let id_paragraph = self.id.as_paragraph(
-                    "ID",
-                    if self.general_focus == FolderGeneralFocus::ID {
-                        focused_style
-                    } else {
-                        Style::default()
-                    },
-                );
+                // Syncthing identifies folders by their ID, so it cannot change
+                let id_paragraph = self.id.as_paragraph("ID", Style::default().dark_gray());
 
                 let path_paragraph = self.path.as_paragraph(
                     "Path",
@@ -911,6 +1928,20 @@ impl Popup for FolderPopup {
                     },
                 );
 
+                let type_paragraph = Paragraph::new(if self.type_editable() {
+                    format!("< {} >", format_folder_type(&self.folder.folder_type))
+                } else {
+                    format_folder_type(&self.folder.folder_type).to_string()
+                })
+                .style(if !self.type_editable() {
+                    Style::default().dark_gray()
+                } else if self.general_focus == FolderGeneralFocus::Type {
+                    focused_style
+                } else {
+                    Style::default()
+                })
+                .block(Block::bordered().title("Type"));
+
                 let submit = Span::styled(
                     "Submit",
                     match self.general_focus {
@@ -926,19 +1957,23 @@ impl Popup for FolderPopup {
                     },
                 );
 
-                let buttons: Line = vec![submit, Span::raw(" "), remove].into();
+                let buttons: Line = if self.advanced {
+                    vec![submit, Span::raw(" "), remove].into()
+                } else {
+                    submit.into()
+                };
 
                 // Show cursor
                 if *self.mode.lock().unwrap() == CurrentMode::Insert {
                     let (cursor_area, index) = match self.general_focus {
                         FolderGeneralFocus::Label => (label_area, self.label.index),
-                        FolderGeneralFocus::ID => (id_area, self.id.index),
                         FolderGeneralFocus::Path => (path_area, self.path.index),
                         _ => (area, 0),
                     };
-                    if self.general_focus != FolderGeneralFocus::Submit
-                        && self.general_focus != FolderGeneralFocus::Remove
-                    {
+                    if matches!(
+                        self.general_focus,
+                        FolderGeneralFocus::Label | FolderGeneralFocus::Path
+                    ) {
                         frame.set_cursor_position(Position::new(
                             cursor_area.x + index as u16 + 1,
                             cursor_area.y + 1,
@@ -949,23 +1984,36 @@ impl Popup for FolderPopup {
                 frame.render_widget(label_paragraph, label_area);
                 frame.render_widget(id_paragraph, id_area);
                 frame.render_widget(path_paragraph, path_area);
+                if self.advanced {
+                    frame.render_widget(type_paragraph, type_area);
+                }
                 frame.render_widget(buttons, buttons_area);
             }
-            FolderFocus::Sharing => state.read(|state| {
-                let lines: Vec<_> = state
-                    .get_other_devices()
+            FolderTab::Sharing => state.read(|state| {
+                let lines: Vec<_> = self
+                    .sharing_devices(state)
                     .iter()
                     .map(|device| {
-                        let selected_char = if self
-                            .devices
-                            .iter()
-                            .any(|d| d.device_id == device.config.device_id)
-                        {
-                            "✓"
-                        } else {
-                            "☐"
-                        };
-                        Span::raw(format!("{} {}", selected_char, device.config.name))
+                        let selected_char = self.icons.checkbox(
+                            self.devices
+                                .iter()
+                                .any(|d| d.device_id == device.config.device_id),
+                        );
+                        let mut line =
+                            Line::from(format!("{} {}", selected_char, device.config.name));
+                        if self.unshared_only {
+                            line.push_span(match device.last_seen {
+                                _ if device.connected != DeviceStatus::Disconnected => {
+                                    Span::styled(" [Connected]", Style::default().green())
+                                }
+                                Some(last_seen) => Span::styled(
+                                    format!(" — last seen {}", format_timestamp(last_seen)),
+                                    Style::default().dark_gray(),
+                                ),
+                                None => Span::raw(""),
+                            });
+                        }
+                        line
                     })
                     .collect();
 
@@ -979,6 +2027,80 @@ impl Popup for FolderPopup {
 
                 StatefulWidget::render(list, area, frame.buffer_mut(), &mut list_state);
             }),
+            FolderTab::Info => {
+                let lines = state.read(|state| self.info_lines(state));
+                frame.render_widget(
+                    Paragraph::new(lines).wrap(Wrap { trim: false }),
+                    area.inner(Margin {
+                        horizontal: 2,
+                        vertical: 2,
+                    }),
+                );
+            }
+            FolderTab::Versioning => {
+                let fields = self.versioning_fields();
+                let areas = Layout::vertical(fields.iter().map(|field| match field {
+                    VersioningFocus::Submit => Constraint::Length(1),
+                    _ => Constraint::Length(3),
+                }))
+                .split(area.inner(Margin {
+                    horizontal: 2,
+                    vertical: 2,
+                }));
+                let focused_style = Style::default().fg(Color::Blue);
+
+                for (field, field_area) in fields.iter().zip(areas.iter()) {
+                    let style = if *field == self.versioning_focus {
+                        focused_style
+                    } else {
+                        Style::default()
+                    };
+                    let (input, title) = match field {
+                        VersioningFocus::Type => {
+                            let versioning_type = format!(
+                                "< {} >",
+                                format_versioning_type(&self.folder.versioning.versioning_type)
+                            );
+                            frame.render_widget(
+                                Paragraph::new(versioning_type)
+                                    .style(style)
+                                    .block(Block::bordered().title("Type")),
+                                *field_area,
+                            );
+                            continue;
+                        }
+                        VersioningFocus::Submit => {
+                            let submit = Span::styled(
+                                "Submit",
+                                if self.versioning_focus == VersioningFocus::Submit {
+                                    Style::default().bg(Color::DarkGray)
+                                } else {
+                                    Style::default()
+                                },
+                            );
+                            frame.render_widget(submit, *field_area);
+                            continue;
+                        }
+                        VersioningFocus::Keep => (&self.keep, "Versions to keep"),
+                        VersioningFocus::MaxAge => {
+                            (&self.max_age, "Maximum age in days, 0 keeps them forever")
+                        }
+                        VersioningFocus::Path => {
+                            (&self.versions_path, "Versions path, .stversions if empty")
+                        }
+                        VersioningFocus::Command => (&self.command, "Command"),
+                    };
+                    frame.render_widget(input.as_paragraph(title, style), *field_area);
+                    if *field == self.versioning_focus
+                        && *self.mode.lock().unwrap() == CurrentMode::Insert
+                    {
+                        frame.set_cursor_position(Position::new(
+                            field_area.x + input.index as u16 + 1,
+                            field_area.y + 1,
+                        ));
+                    }
+                }
+            }
         }
 
         frame.render_widget(block, area);
@@ -991,53 +2113,216 @@ pub struct DevicePopup {
     device: DeviceConfiguration,
     id: TextBox,
     name: TextBox,
+    /// Comma-separated, e.g., `dynamic, tcp://192.168.1.2:22000`
+    addresses: TextBox,
+    /// In KiB/s, 0 for unlimited
+    send_limit: TextBox,
+    receive_limit: TextBox,
     focus: DeviceFocus,
     mode: Arc<Mutex<CurrentMode>>,
+    /// Whether the addresses, compression, introducer and the remove button
+    /// are shown
+    advanced: bool,
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 enum DeviceFocus {
     #[default]
     Name,
+    Addresses,
+    SendLimit,
+    ReceiveLimit,
+    Compression,
+    Introducer,
+    Paused,
     Submit,
     Remove,
 }
 
 impl DeviceFocus {
-    fn next(&mut self) {
+    /// Fields which are only shown in advanced mode
+    fn is_advanced(&self) -> bool {
+        matches!(
+            self,
+            DeviceFocus::Addresses
+                | DeviceFocus::Compression
+                | DeviceFocus::Introducer
+                | DeviceFocus::Remove
+        )
+    }
+
+    fn next(&mut self, advanced: bool) {
+        skip_hidden(self, advanced, Self::step_next, Self::is_advanced);
+    }
+
+    fn prev(&mut self, advanced: bool) {
+        skip_hidden(self, advanced, Self::step_prev, Self::is_advanced);
+    }
+
+    fn step_next(&mut self) {
         match self {
-            DeviceFocus::Name => *self = DeviceFocus::Submit,
+            DeviceFocus::Name => *self = DeviceFocus::Addresses,
+            DeviceFocus::Addresses => *self = DeviceFocus::SendLimit,
+            DeviceFocus::SendLimit => *self = DeviceFocus::ReceiveLimit,
+            DeviceFocus::ReceiveLimit => *self = DeviceFocus::Compression,
+            DeviceFocus::Compression => *self = DeviceFocus::Introducer,
+            DeviceFocus::Introducer => *self = DeviceFocus::Paused,
+            DeviceFocus::Paused => *self = DeviceFocus::Submit,
             DeviceFocus::Submit => *self = DeviceFocus::Remove,
             DeviceFocus::Remove => {}
         }
     }
 
-    fn prev(&mut self) {
+    fn step_prev(&mut self) {
         match self {
             DeviceFocus::Name => {}
-            DeviceFocus::Submit => *self = DeviceFocus::Name,
+            DeviceFocus::Addresses => *self = DeviceFocus::Name,
+            DeviceFocus::SendLimit => *self = DeviceFocus::Addresses,
+            DeviceFocus::ReceiveLimit => *self = DeviceFocus::SendLimit,
+            DeviceFocus::Compression => *self = DeviceFocus::ReceiveLimit,
+            DeviceFocus::Introducer => *self = DeviceFocus::Compression,
+            DeviceFocus::Paused => *self = DeviceFocus::Introducer,
+            DeviceFocus::Submit => *self = DeviceFocus::Paused,
             DeviceFocus::Remove => *self = DeviceFocus::Submit,
         }
     }
 }
 
+/// Moves `focus` with `step` until it reaches a field which is shown, or
+/// stays where it is if there is none. Fields for which `is_advanced` holds
+/// are only shown in advanced mode.
+fn skip_hidden<F: Copy + PartialEq>(
+    focus: &mut F,
+    advanced: bool,
+    step: fn(&mut F),
+    is_advanced: fn(&F) -> bool,
+) {
+    let mut next = *focus;
+    loop {
+        let before = next;
+        step(&mut next);
+        if next == before {
+            return;
+        }
+        if advanced || !is_advanced(&next) {
+            *focus = next;
+            return;
+        }
+    }
+}
+
+/// Cycles through the folder types, in the order Syncthing lists them.
+/// Encrypted folders can only be chosen when creating a folder.
+fn cycle_folder_type(folder_type: &FolderType, forward: bool, encrypted: bool) -> FolderType {
+    let mut types = vec![
+        FolderType::SendReceive,
+        FolderType::SendOnly,
+        FolderType::ReceiveOnly,
+    ];
+    if encrypted {
+        types.push(FolderType::ReceiveEncrypted);
+    }
+    let i = types.iter().position(|t| t == folder_type).unwrap_or(0);
+    let len = types.len();
+    types[if forward {
+        (i + 1) % len
+    } else {
+        (i + len - 1) % len
+    }]
+}
+
+/// Cycles through the versioning types, in the order Syncthing lists them
+fn cycle_versioning_type(versioning_type: &VersioningType, forward: bool) -> VersioningType {
+    let types = [
+        VersioningType::None,
+        VersioningType::Trashcan,
+        VersioningType::Simple,
+        VersioningType::Staggered,
+        VersioningType::External,
+    ];
+    let i = types.iter().position(|t| t == versioning_type).unwrap_or(0);
+    let len = types.len();
+    types[if forward {
+        (i + 1) % len
+    } else {
+        (i + len - 1) % len
+    }]
+}
+
+/// Cycles through the compression settings, in the order Syncthing lists them
+fn cycle_compression(compression: &Compression, forward: bool) -> Compression {
+    match (compression, forward) {
+        (Compression::Metadata, true) | (Compression::Never, false) => Compression::Always,
+        (Compression::Always, true) | (Compression::Metadata, false) => Compression::Never,
+        (Compression::Never, true) | (Compression::Always, false) => Compression::Metadata,
+    }
+}
+
 impl DevicePopup {
-    pub fn new(device: DeviceConfiguration, mode: Arc<Mutex<CurrentMode>>) -> Self {
+    pub fn new(
+        device: DeviceConfiguration,
+        rate_limits: RateLimits,
+        mode: Arc<Mutex<CurrentMode>>,
+        advanced: bool,
+    ) -> Self {
         let id = device.device_id.clone().into();
         let name = device.name.clone().into();
+        let addresses = device.addresses.join(", ").into();
         Self {
             device,
             id,
             name,
+            addresses,
+            send_limit: rate_limits.max_send_kbps.to_string().into(),
+            receive_limit: rate_limits.max_recv_kbps.to_string().into(),
             focus: DeviceFocus::default(),
             mode,
+            advanced,
+        }
+    }
+
+    fn input(&mut self) -> Option<&mut TextBox> {
+        match self.focus {
+            DeviceFocus::Name => Some(&mut self.name),
+            DeviceFocus::Addresses => Some(&mut self.addresses),
+            DeviceFocus::SendLimit => Some(&mut self.send_limit),
+            DeviceFocus::ReceiveLimit => Some(&mut self.receive_limit),
+            _ => None,
+        }
+    }
+
+    /// Flips the checkbox in focus, if any
+    fn toggle(&mut self) {
+        match self.focus {
+            DeviceFocus::Introducer => self.device.introducer = !self.device.introducer,
+            DeviceFocus::Paused => self.device.paused = !self.device.paused,
+            _ => {}
         }
     }
 
     fn submit(&mut self) -> Option<Message> {
         self.device.name = self.name.text.clone();
+        self.device.addresses = self
+            .addresses
+            .text
+            .split(',')
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(str::to_string)
+            .collect();
+        // Without any address, Syncthing could never connect to the device
+        if self.device.addresses.is_empty() {
+            self.device.addresses = vec!["dynamic".to_string()];
+        }
 
-        Some(Message::EditDevice(Box::new(self.device.clone())))
+        let rate_limits = RateLimits {
+            max_send_kbps: self.send_limit.as_kbps(),
+            max_recv_kbps: self.receive_limit.as_kbps(),
+        };
+        Some(Message::EditDevice(
+            Box::new(self.device.clone()),
+            rate_limits,
+        ))
     }
 
     fn remove(&self) -> Option<Message> {
@@ -1049,30 +2334,64 @@ impl Popup for DevicePopup {
     fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
         match msg {
             Message::Quit => return Some(Message::Quit),
-            Message::FocusNext | Message::Down => self.focus.next(),
-            Message::FocusBack | Message::Up => self.focus.prev(),
+            Message::FocusNext | Message::Down => self.focus.next(self.advanced),
+            Message::FocusBack | Message::Up => self.focus.prev(self.advanced),
             Message::Left => match self.focus {
-                DeviceFocus::Name => self.name.move_cursor_left(),
-                DeviceFocus::Submit => {}
-                DeviceFocus::Remove => self.focus.prev(),
+                DeviceFocus::Name
+                | DeviceFocus::Addresses
+                | DeviceFocus::SendLimit
+                | DeviceFocus::ReceiveLimit => {
+                    if let Some(input) = self.input() {
+                        input.move_cursor_left()
+                    }
+                }
+                DeviceFocus::Compression => {
+                    self.device.compression = cycle_compression(&self.device.compression, false)
+                }
+                DeviceFocus::Introducer | DeviceFocus::Paused | DeviceFocus::Submit => {}
+                DeviceFocus::Remove => self.focus.prev(self.advanced),
             },
             Message::Right => match self.focus {
-                DeviceFocus::Name => self.name.move_cursor_right(),
-                DeviceFocus::Submit => self.focus.next(),
+                DeviceFocus::Name
+                | DeviceFocus::Addresses
+                | DeviceFocus::SendLimit
+                | DeviceFocus::ReceiveLimit => {
+                    if let Some(input) = self.input() {
+                        input.move_cursor_right()
+                    }
+                }
+                DeviceFocus::Compression => {
+                    self.device.compression = cycle_compression(&self.device.compression, true)
+                }
+                DeviceFocus::Introducer | DeviceFocus::Paused => {}
+                DeviceFocus::Submit => self.focus.next(self.advanced),
                 DeviceFocus::Remove => {}
             },
             Message::Character(c) => {
-                if matches!(self.focus, DeviceFocus::Name) {
-                    self.name.enter_char(c);
+                let numeric = matches!(
+                    self.focus,
+                    DeviceFocus::SendLimit | DeviceFocus::ReceiveLimit
+                );
+                if let Some(input) = self.input()
+                    && (!numeric || c.is_ascii_digit())
+                {
+                    input.enter_char(c);
                 }
             }
             Message::Backspace => {
-                if matches!(self.focus, DeviceFocus::Name) {
-                    self.name.delete_char();
+                if let Some(input) = self.input() {
+                    input.delete_char();
                 }
             }
             Message::Select => match self.focus {
-                DeviceFocus::Name => {}
+                DeviceFocus::Name
+                | DeviceFocus::Addresses
+                | DeviceFocus::SendLimit
+                | DeviceFocus::ReceiveLimit => self.focus.next(self.advanced),
+                DeviceFocus::Compression => {
+                    self.device.compression = cycle_compression(&self.device.compression, true)
+                }
+                DeviceFocus::Introducer | DeviceFocus::Paused => self.toggle(),
                 DeviceFocus::Submit => return self.submit(),
                 DeviceFocus::Remove => return self.remove(),
             },
@@ -1082,42 +2401,117 @@ impl Popup for DevicePopup {
         None
     }
 
-    fn render(&self, frame: &mut Frame, _state: State) {
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
         let block = self.create_popup_block(format!("Edit Device ({})", self.device.name));
 
-        let area = centered_rect(50, 50, frame.area());
+        let width = text_width([
+            self.id.text.as_str(),
+            self.name.text.as_str(),
+            self.addresses.text.as_str(),
+        ]);
+        let area = popup_rect(width.max(DEVICE_ID_WIDTH) + 7, 23, frame.area());
         Clear.render(area, frame.buffer_mut());
 
+        // Fields only shown in advanced mode take no space otherwise
+        let advanced = |height| if self.advanced { height } else { 0 };
         let vertical = Layout::vertical([
             Constraint::Length(3),
             Constraint::Length(3),
+            Constraint::Length(advanced(3)),
+            Constraint::Length(3),
+            Constraint::Length(advanced(3)),
+            Constraint::Length(advanced(1)),
+            Constraint::Length(1),
+            Constraint::Length(1),
             Constraint::Length(1),
         ]);
-        let [id_area, name_area, buttons_area] = vertical.areas(area.inner(Margin {
+        let [
+            id_area,
+            name_area,
+            addresses_area,
+            limits_area,
+            compression_area,
+            introducer_area,
+            paused_area,
+            _,
+            buttons_area,
+        ] = vertical.areas(area.inner(Margin {
             horizontal: 2,
             vertical: 2,
         }));
 
         let focused_style = Style::default().fg(Color::Blue);
 
-        let id_paragraph = self.id.as_paragraph("ID", Style::default());
-
-        let name_paragraph = self.name.as_paragraph(
-            "Name",
-            if self.focus == DeviceFocus::Name {
+        let addresses_paragraph = self.addresses.as_paragraph(
+            "Addresses",
+            if self.focus == DeviceFocus::Addresses {
                 focused_style
             } else {
                 Style::default()
             },
         );
 
-        let submit = Span::styled(
-            "Submit",
-            match self.focus {
-                DeviceFocus::Submit => Style::default().bg(Color::DarkGray),
-                _ => Style::default(),
-            },
-        );
+        let [send_limit_area, receive_limit_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(limits_area);
+        let limit_style = |focus| {
+            if self.focus == focus {
+                focused_style
+            } else {
+                Style::default()
+            }
+        };
+        let send_limit = self
+            .send_limit
+            .as_paragraph("Send KiB/s", limit_style(DeviceFocus::SendLimit));
+        let receive_limit = self
+            .receive_limit
+            .as_paragraph("Receive KiB/s", limit_style(DeviceFocus::ReceiveLimit));
+
+        let checkbox = |label: &'static str, checked: bool, focus: DeviceFocus| {
+            Paragraph::new(format!("{} {}", if checked { "[x]" } else { "[ ]" }, label)).style(
+                if self.focus == focus {
+                    focused_style
+                } else {
+                    Style::default()
+                },
+            )
+        };
+        let introducer = checkbox(
+            "Introducer",
+            self.device.introducer,
+            DeviceFocus::Introducer,
+        );
+        let paused = checkbox("Paused", self.device.paused, DeviceFocus::Paused);
+
+        let id_paragraph = self.id.as_paragraph("ID", Style::default());
+
+        let compression_paragraph = Paragraph::new(format!(
+            "< {} >",
+            format_compression(&self.device.compression)
+        ))
+        .style(if self.focus == DeviceFocus::Compression {
+            focused_style
+        } else {
+            Style::default()
+        })
+        .block(Block::bordered().title("Compression"));
+
+        let name_paragraph = self.name.as_paragraph(
+            "Name",
+            if self.focus == DeviceFocus::Name {
+                focused_style
+            } else {
+                Style::default()
+            },
+        );
+
+        let submit = Span::styled(
+            "Submit",
+            match self.focus {
+                DeviceFocus::Submit => Style::default().bg(Color::DarkGray),
+                _ => Style::default(),
+            },
+        );
         let remove = Span::styled(
             "Remove",
             match self.focus {
@@ -1126,21 +2520,1860 @@ impl Popup for DevicePopup {
             },
         );
 
-        let buttons: Line = vec![submit, Span::raw(" "), remove].into();
+        let buttons: Line = if self.advanced {
+            vec![submit, Span::raw(" "), remove].into()
+        } else {
+            submit.into()
+        };
 
         // Show cursor
-        if *self.mode.lock().unwrap() == CurrentMode::Insert
-            && matches!(self.focus, DeviceFocus::Name)
-        {
-            frame.set_cursor_position(Position::new(
-                name_area.x + (self.name.index as u16) + 1,
-                name_area.y + 1,
-            ));
+        if *self.mode.lock().unwrap() == CurrentMode::Insert {
+            let cursor = match self.focus {
+                DeviceFocus::Name => Some((name_area, self.name.index)),
+                DeviceFocus::Addresses => Some((addresses_area, self.addresses.index)),
+                DeviceFocus::SendLimit => Some((send_limit_area, self.send_limit.index)),
+                DeviceFocus::ReceiveLimit => Some((receive_limit_area, self.receive_limit.index)),
+                _ => None,
+            };
+            if let Some((input_area, index)) = cursor {
+                frame.set_cursor_position(Position::new(
+                    input_area.x + (index as u16) + 1,
+                    input_area.y + 1,
+                ));
+            }
         }
 
         frame.render_widget(id_paragraph, id_area);
         frame.render_widget(name_paragraph, name_area);
+        if self.advanced {
+            frame.render_widget(addresses_paragraph, addresses_area);
+            frame.render_widget(compression_paragraph, compression_area);
+            frame.render_widget(introducer, introducer_area);
+        }
+        frame.render_widget(send_limit, send_limit_area);
+        frame.render_widget(receive_limit, receive_limit_area);
+        frame.render_widget(paused, paused_area);
         frame.render_widget(buttons, buttons_area);
         frame.render_widget(block, area);
     }
 }
+
+#[derive(Debug)]
+pub struct NewDevicePopup {
+    id: TextBox,
+    name: TextBox,
+    focus: NewDeviceFocus,
+    mode: Arc<Mutex<CurrentMode>>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+enum NewDeviceFocus {
+    #[default]
+    Id,
+    Name,
+    Submit,
+}
+
+impl NewDeviceFocus {
+    fn next(&mut self) {
+        match self {
+            NewDeviceFocus::Id => *self = NewDeviceFocus::Name,
+            NewDeviceFocus::Name => *self = NewDeviceFocus::Submit,
+            NewDeviceFocus::Submit => {}
+        }
+    }
+
+    fn prev(&mut self) {
+        match self {
+            NewDeviceFocus::Id => {}
+            NewDeviceFocus::Name => *self = NewDeviceFocus::Id,
+            NewDeviceFocus::Submit => *self = NewDeviceFocus::Name,
+        }
+    }
+}
+
+impl NewDevicePopup {
+    /// Creates a popup to add a device, pre-filled with `device_id` and `name`
+    pub fn new(device_id: String, name: String, mode: Arc<Mutex<CurrentMode>>) -> Self {
+        // Jump straight to submitting if we already know the ID
+        let focus = if device_id.is_empty() {
+            NewDeviceFocus::Id
+        } else {
+            NewDeviceFocus::Submit
+        };
+        Self {
+            id: device_id.into(),
+            name: name.into(),
+            focus,
+            mode,
+        }
+    }
+
+    fn selected_text_box(&mut self) -> Option<&mut TextBox> {
+        match self.focus {
+            NewDeviceFocus::Id => Some(&mut self.id),
+            NewDeviceFocus::Name => Some(&mut self.name),
+            NewDeviceFocus::Submit => None,
+        }
+    }
+
+    /// Accepts a pasted `synctui://add-device` link as well, whose name is
+    /// used unless one was entered
+    fn submit(&mut self, state: State) -> Option<Message> {
+        let (device_id, link_name) = match parse_device_link(&self.id.text) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                state.hint(e.to_string());
+                self.focus = NewDeviceFocus::Id;
+                return None;
+            }
+        };
+        let mut device = NewDeviceConfiguration::new(device_id);
+        if !self.name.text.is_empty() {
+            device = device.name(self.name.text.clone());
+        } else if let Some(name) = link_name {
+            device = device.name(name);
+        }
+        Some(Message::AddDevice(Box::new(device)))
+    }
+}
+
+impl Popup for NewDevicePopup {
+    fn update(&mut self, msg: Message, state: State) -> Option<Message> {
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::FocusNext | Message::Down => self.focus.next(),
+            Message::FocusBack | Message::Up => self.focus.prev(),
+            Message::Left => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.move_cursor_left();
+                }
+            }
+            Message::Right => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.move_cursor_right();
+                }
+            }
+            Message::Character(c) => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.enter_char(c);
+                }
+            }
+            Message::Backspace => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.delete_char();
+                }
+            }
+            Message::Select if self.focus == NewDeviceFocus::Submit => return self.submit(state),
+            Message::Submit => return self.submit(state),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block("Add Device".to_string());
+
+        let width = text_width([self.id.text.as_str(), self.name.text.as_str()]);
+        let area = popup_rect(width.max(DEVICE_ID_WIDTH) + 7, 11, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let vertical = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ]);
+        let [id_area, name_area, submit_area] = vertical.areas(area.inner(Margin {
+            horizontal: 2,
+            vertical: 2,
+        }));
+
+        let focused_style = Style::default().fg(Color::Blue);
+        let style = |focus: NewDeviceFocus| {
+            if self.focus == focus {
+                focused_style
+            } else {
+                Style::default()
+            }
+        };
+
+        let id_paragraph = self.id.as_paragraph("ID", style(NewDeviceFocus::Id));
+        let name_paragraph = self.name.as_paragraph("Name", style(NewDeviceFocus::Name));
+        let submit = Span::styled(
+            "Submit",
+            match self.focus {
+                NewDeviceFocus::Submit => Style::default().bg(Color::DarkGray),
+                _ => Style::default(),
+            },
+        );
+
+        // Show cursor
+        if *self.mode.lock().unwrap() == CurrentMode::Insert {
+            let cursor = match self.focus {
+                NewDeviceFocus::Id => Some((id_area, self.id.index)),
+                NewDeviceFocus::Name => Some((name_area, self.name.index)),
+                NewDeviceFocus::Submit => None,
+            };
+            if let Some((text_area, index)) = cursor {
+                frame.set_cursor_position(Position::new(
+                    text_area.x + (index as u16) + 1,
+                    text_area.y + 1,
+                ));
+            }
+        }
+
+        frame.render_widget(id_paragraph, id_area);
+        frame.render_widget(name_paragraph, name_area);
+        frame.render_widget(submit, submit_area);
+        frame.render_widget(block, area);
+    }
+}
+
+/// Limits the bandwidth of all connections
+#[derive(Debug)]
+pub struct RateLimitsPopup {
+    /// In KiB/s, 0 for unlimited
+    send: TextBox,
+    receive: TextBox,
+    focus: RateLimitsFocus,
+    mode: Arc<Mutex<CurrentMode>>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum RateLimitsFocus {
+    #[default]
+    Send,
+    Receive,
+    Submit,
+}
+
+impl RateLimitsFocus {
+    fn next(&mut self) {
+        match self {
+            RateLimitsFocus::Send => *self = RateLimitsFocus::Receive,
+            RateLimitsFocus::Receive => *self = RateLimitsFocus::Submit,
+            RateLimitsFocus::Submit => {}
+        }
+    }
+
+    fn prev(&mut self) {
+        match self {
+            RateLimitsFocus::Send => {}
+            RateLimitsFocus::Receive => *self = RateLimitsFocus::Send,
+            RateLimitsFocus::Submit => *self = RateLimitsFocus::Receive,
+        }
+    }
+}
+
+impl RateLimitsPopup {
+    pub fn new(rate_limits: RateLimits, mode: Arc<Mutex<CurrentMode>>) -> Self {
+        Self {
+            send: rate_limits.max_send_kbps.to_string().into(),
+            receive: rate_limits.max_recv_kbps.to_string().into(),
+            focus: RateLimitsFocus::default(),
+            mode,
+        }
+    }
+
+    fn selected_text_box(&mut self) -> Option<&mut TextBox> {
+        match self.focus {
+            RateLimitsFocus::Send => Some(&mut self.send),
+            RateLimitsFocus::Receive => Some(&mut self.receive),
+            RateLimitsFocus::Submit => None,
+        }
+    }
+
+    fn submit(&self) -> Option<Message> {
+        Some(Message::SetRateLimits(RateLimits {
+            max_send_kbps: self.send.as_kbps(),
+            max_recv_kbps: self.receive.as_kbps(),
+        }))
+    }
+}
+
+impl Popup for RateLimitsPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::FocusNext | Message::Down => self.focus.next(),
+            Message::FocusBack | Message::Up => self.focus.prev(),
+            Message::Left => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.move_cursor_left();
+                }
+            }
+            Message::Right => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.move_cursor_right();
+                }
+            }
+            Message::Character(c) if c.is_ascii_digit() => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.enter_char(c);
+                }
+            }
+            Message::Backspace => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.delete_char();
+                }
+            }
+            Message::Select if self.focus == RateLimitsFocus::Submit => return self.submit(),
+            Message::Select => self.focus.next(),
+            Message::Submit => return self.submit(),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block("Rate Limits".to_string());
+
+        let hint = "0 means unlimited. Devices can be limited further.";
+        let area = popup_rect(text_width([hint]) + 6, 13, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let vertical = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ]);
+        let [send_area, receive_area, hint_area, submit_area] =
+            vertical.areas(area.inner(Margin {
+                horizontal: 2,
+                vertical: 2,
+            }));
+
+        let focused_style = Style::default().fg(Color::Blue);
+        let style = |focus: RateLimitsFocus| {
+            if self.focus == focus {
+                focused_style
+            } else {
+                Style::default()
+            }
+        };
+
+        let send = self
+            .send
+            .as_paragraph("Send KiB/s", style(RateLimitsFocus::Send));
+        let receive = self
+            .receive
+            .as_paragraph("Receive KiB/s", style(RateLimitsFocus::Receive));
+        let submit = Span::styled(
+            "Submit",
+            match self.focus {
+                RateLimitsFocus::Submit => Style::default().bg(Color::DarkGray),
+                _ => Style::default(),
+            },
+        );
+
+        // Show cursor
+        if *self.mode.lock().unwrap() == CurrentMode::Insert {
+            let cursor = match self.focus {
+                RateLimitsFocus::Send => Some((send_area, self.send.index)),
+                RateLimitsFocus::Receive => Some((receive_area, self.receive.index)),
+                RateLimitsFocus::Submit => None,
+            };
+            if let Some((text_area, index)) = cursor {
+                frame.set_cursor_position(Position::new(
+                    text_area.x + (index as u16) + 1,
+                    text_area.y + 1,
+                ));
+            }
+        }
+
+        frame.render_widget(send, send_area);
+        frame.render_widget(receive, receive_area);
+        frame.render_widget(Line::styled(hint, Style::default().dark_gray()), hint_area);
+        frame.render_widget(submit, submit_area);
+        frame.render_widget(block, area);
+    }
+}
+
+/// Edits the note and tags of a folder or device
+#[derive(Debug)]
+pub struct NotePopup {
+    target: NoteTarget,
+    /// Label or name of the target
+    name: String,
+    text: TextBox,
+    /// Comma separated
+    tags: TextBox,
+    focus: NoteFocus,
+    mode: Arc<Mutex<CurrentMode>>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+enum NoteFocus {
+    #[default]
+    Text,
+    Tags,
+    Submit,
+}
+
+impl NoteFocus {
+    fn next(&mut self) {
+        *self = match self {
+            NoteFocus::Text => NoteFocus::Tags,
+            NoteFocus::Tags | NoteFocus::Submit => NoteFocus::Submit,
+        }
+    }
+
+    fn prev(&mut self) {
+        *self = match self {
+            NoteFocus::Text | NoteFocus::Tags => NoteFocus::Text,
+            NoteFocus::Submit => NoteFocus::Tags,
+        }
+    }
+}
+
+impl NotePopup {
+    pub fn new(
+        target: NoteTarget,
+        name: String,
+        note: Note,
+        mode: Arc<Mutex<CurrentMode>>,
+    ) -> Self {
+        Self {
+            target,
+            name,
+            text: note.text.into(),
+            tags: note.tags.join(", ").into(),
+            focus: NoteFocus::default(),
+            mode,
+        }
+    }
+
+    fn selected_text_box(&mut self) -> Option<&mut TextBox> {
+        match self.focus {
+            NoteFocus::Text => Some(&mut self.text),
+            NoteFocus::Tags => Some(&mut self.tags),
+            NoteFocus::Submit => None,
+        }
+    }
+
+    fn submit(&self) -> Option<Message> {
+        let tags = self
+            .tags
+            .text
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+        Some(Message::SetNote {
+            target: self.target.clone(),
+            note: Note {
+                text: self.text.text.trim().to_string(),
+                tags,
+            },
+        })
+    }
+}
+
+impl Popup for NotePopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::FocusNext | Message::Down => self.focus.next(),
+            Message::FocusBack | Message::Up => self.focus.prev(),
+            Message::Left => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.move_cursor_left();
+                }
+            }
+            Message::Right => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.move_cursor_right();
+                }
+            }
+            Message::Character(c) => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.enter_char(c);
+                }
+            }
+            Message::Backspace => {
+                if let Some(text_box) = self.selected_text_box() {
+                    text_box.delete_char();
+                }
+            }
+            Message::Select if self.focus == NoteFocus::Submit => return self.submit(),
+            Message::Submit => return self.submit(),
+            _ => {}
+        }
+
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block(format!("Note ({})", self.name));
+
+        let width = text_width([self.text.text.as_str(), self.tags.text.as_str()]);
+        let area = popup_rect(width + 7, 11, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let vertical = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ]);
+        let [text_area, tags_area, submit_area] = vertical.areas(area.inner(Margin {
+            horizontal: 2,
+            vertical: 2,
+        }));
+
+        let focused_style = Style::default().fg(Color::Blue);
+        let style = |focus: NoteFocus| {
+            if self.focus == focus {
+                focused_style
+            } else {
+                Style::default()
+            }
+        };
+
+        let submit = Span::styled(
+            "Submit",
+            match self.focus {
+                NoteFocus::Submit => Style::default().bg(Color::DarkGray),
+                _ => Style::default(),
+            },
+        );
+
+        // Show cursor
+        if *self.mode.lock().unwrap() == CurrentMode::Insert {
+            let cursor = match self.focus {
+                NoteFocus::Text => Some((text_area, self.text.index)),
+                NoteFocus::Tags => Some((tags_area, self.tags.index)),
+                NoteFocus::Submit => None,
+            };
+            if let Some((input_area, index)) = cursor {
+                frame.set_cursor_position(Position::new(
+                    input_area.x + (index as u16) + 1,
+                    input_area.y + 1,
+                ));
+            }
+        }
+
+        frame.render_widget(
+            self.text.as_paragraph("Note", style(NoteFocus::Text)),
+            text_area,
+        );
+        frame.render_widget(
+            self.tags
+                .as_paragraph("Tags, separated by commas", style(NoteFocus::Tags)),
+            tags_area,
+        );
+        frame.render_widget(submit, submit_area);
+        frame.render_widget(block, area);
+    }
+}
+
+/// Number of results visible at once in the [`SearchPopup`]
+const SEARCH_RESULT_ROWS: u16 = 15;
+
+/// Searches all folders, devices, pending items and recent events
+#[derive(Debug)]
+pub struct SearchPopup {
+    query: TextBox,
+    selected: usize,
+    mode: Arc<Mutex<CurrentMode>>,
+}
+
+impl SearchPopup {
+    pub fn new(mode: Arc<Mutex<CurrentMode>>) -> Self {
+        Self {
+            query: TextBox::default(),
+            selected: 0,
+            mode,
+        }
+    }
+}
+
+impl Popup for SearchPopup {
+    fn update(&mut self, msg: Message, state: State) -> Option<Message> {
+        let results = state.read(|state| search(state, &self.query.text));
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::Character(c) => {
+                self.query.enter_char(c);
+                self.selected = 0;
+            }
+            Message::Backspace => {
+                self.query.delete_char();
+                self.selected = 0;
+            }
+            Message::Left => self.query.move_cursor_left(),
+            Message::Right => self.query.move_cursor_right(),
+            Message::Down | Message::FocusNext if !results.is_empty() => {
+                self.selected = (self.selected + 1) % results.len();
+            }
+            Message::Up | Message::FocusBack if !results.is_empty() => {
+                self.selected = (self.selected + results.len() - 1) % results.len();
+            }
+            Message::Select | Message::Submit => {
+                return results
+                    .get(self.selected)
+                    .and_then(|r| r.target.clone())
+                    .map(Message::Jump);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block("Search".to_string());
+
+        // The query, and a fixed number of results, so the popup does not
+        // jump around while typing
+        let area = popup_rect(80, SEARCH_RESULT_ROWS + 5, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let [query_area, results_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(area.inner(
+                Margin {
+                    horizontal: 2,
+                    vertical: 1,
+                },
+            ));
+
+        let results = state.read(|state| search(state, &self.query.text));
+        let items: Vec<Line> = results
+            .iter()
+            .map(|result| {
+                Line::from(vec![
+                    Span::styled(format!("{:<15}", result.kind), Style::default().bold()),
+                    Span::raw(result.text.clone()),
+                ])
+            })
+            .collect();
+        let list = List::new(items).highlight_style(Style::new().bg(Color::DarkGray));
+        let mut list_state = ListState::default().with_selected(if results.is_empty() {
+            None
+        } else {
+            Some(self.selected)
+        });
+
+        if *self.mode.lock().unwrap() == CurrentMode::Insert {
+            frame.set_cursor_position(Position::new(
+                query_area.x + (self.query.index as u16) + 1,
+                query_area.y + 1,
+            ));
+        }
+
+        frame.render_widget(
+            self.query
+                .as_paragraph("Folders, devices, pending, events", Style::default()),
+            query_area,
+        );
+        StatefulWidget::render(list, results_area, frame.buffer_mut(), &mut list_state);
+        frame.render_widget(block, area);
+    }
+}
+
+/// Asks for the text to filter the raw configuration by. An empty filter
+/// shows everything again.
+#[derive(Debug)]
+pub struct FilterPopup {
+    filter: TextBox,
+}
+
+impl FilterPopup {
+    pub fn new(filter: String) -> Self {
+        Self {
+            filter: filter.into(),
+        }
+    }
+}
+
+impl Popup for FilterPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::Character(c) => self.filter.enter_char(c),
+            Message::Backspace => self.filter.delete_char(),
+            Message::Left => self.filter.move_cursor_left(),
+            Message::Right => self.filter.move_cursor_right(),
+            Message::Select | Message::Submit => {
+                return Some(Message::SetFilter(self.filter.text.clone()));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block("Filter".to_string());
+
+        let area = popup_rect(text_width([self.filter.text.as_str()]) + 7, 5, frame.area());
+        Clear.render(area, frame.buffer_mut());
+        let input_area = area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+
+        frame.set_cursor_position(Position::new(
+            input_area.x + (self.filter.index as u16) + 1,
+            input_area.y + 1,
+        ));
+        frame.render_widget(
+            self.filter
+                .as_paragraph("Key or value", Style::default().fg(Color::Blue)),
+            input_area,
+        );
+        frame.render_widget(block, area);
+    }
+}
+
+/// Changes a single setting of Syncthing's GUI. Passwords start out empty and
+/// are masked, Syncthing only keeps their hash.
+#[derive(Debug)]
+pub struct GuiFieldPopup {
+    field: GuiField,
+    value: TextBox,
+}
+
+impl GuiFieldPopup {
+    pub fn new(field: GuiField, value: String) -> Self {
+        let value = match field {
+            GuiField::Password => String::new(),
+            _ => value,
+        };
+        Self {
+            field,
+            value: value.into(),
+        }
+    }
+}
+
+impl Popup for GuiFieldPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::Character(c) => self.value.enter_char(c),
+            Message::Backspace => self.value.delete_char(),
+            Message::Left => self.value.move_cursor_left(),
+            Message::Right => self.value.move_cursor_right(),
+            Message::Select | Message::Submit => {
+                return Some(Message::SetGui {
+                    field: self.field,
+                    value: self.value.text.clone(),
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block(self.field.label().to_string());
+
+        let area = popup_rect(
+            text_width([self.value.text.as_str(), self.field.label()]) + 7,
+            5,
+            frame.area(),
+        );
+        Clear.render(area, frame.buffer_mut());
+        let input_area = area.inner(Margin {
+            horizontal: 1,
+            vertical: 1,
+        });
+
+        frame.set_cursor_position(Position::new(
+            input_area.x + (self.value.index as u16) + 1,
+            input_area.y + 1,
+        ));
+        let style = Style::default().fg(Color::Blue);
+        match self.field {
+            GuiField::Password => frame.render_widget(
+                Paragraph::new("•".repeat(self.value.text.chars().count()))
+                    .style(style)
+                    .block(Block::bordered().title("New password")),
+                input_area,
+            ),
+            _ => frame.render_widget(self.value.as_paragraph("Value", style), input_area),
+        }
+        frame.render_widget(block, area);
+    }
+}
+
+/// Lists recently viewed folders and devices to jump back to them
+#[derive(Debug)]
+pub struct RecentPopup {
+    /// Target, kind and name of each item, most recent first
+    items: Vec<(SearchTarget, &'static str, String)>,
+    selected: usize,
+}
+
+impl RecentPopup {
+    pub fn new(items: Vec<(SearchTarget, &'static str, String)>) -> Self {
+        Self { items, selected: 0 }
+    }
+}
+
+impl Popup for RecentPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        let len = self.items.len();
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::Down | Message::FocusNext if len > 0 => {
+                self.selected = (self.selected + 1) % len
+            }
+            Message::Up | Message::FocusBack if len > 0 => {
+                self.selected = (self.selected + len - 1) % len
+            }
+            Message::Select | Message::Submit => {
+                return self
+                    .items
+                    .get(self.selected)
+                    .map(|(target, _, _)| Message::Jump(target.clone()));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block("Recent".to_string());
+
+        let lines: Vec<_> = self
+            .items
+            .iter()
+            .map(|(_, kind, name)| {
+                Line::from(vec![
+                    Span::styled(format!("{:<7} ", kind), Style::default().dark_gray()),
+                    Span::raw(name.clone()),
+                ])
+            })
+            .collect();
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+        let area = popup_rect(width + 4, lines.len() as u16 + 2, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let list = List::new(lines).highlight_style(Style::new().bg(Color::DarkGray));
+        let mut list_state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(
+            list,
+            area.inner(Margin {
+                horizontal: 1,
+                vertical: 1,
+            }),
+            frame.buffer_mut(),
+            &mut list_state,
+        );
+        frame.render_widget(block, area);
+    }
+}
+
+/// Lists the custom commands which can be run on a folder
+#[derive(Debug)]
+pub struct CommandsPopup {
+    folder_id: String,
+    label: String,
+    /// Index in the config and name of each command
+    commands: Vec<(usize, String)>,
+    selected: usize,
+}
+
+impl CommandsPopup {
+    pub fn new(folder_id: String, label: String, commands: Vec<(usize, String)>) -> Self {
+        Self {
+            folder_id,
+            label,
+            commands,
+            selected: 0,
+        }
+    }
+}
+
+impl Popup for CommandsPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        let len = self.commands.len();
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::Down | Message::FocusNext if len > 0 => {
+                self.selected = (self.selected + 1) % len
+            }
+            Message::Up | Message::FocusBack if len > 0 => {
+                self.selected = (self.selected + len - 1) % len
+            }
+            Message::Select | Message::Submit => {
+                return self
+                    .commands
+                    .get(self.selected)
+                    .map(|(command, _)| Message::RunCommand {
+                        command: *command,
+                        folder_id: self.folder_id.clone(),
+                    });
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block(format!("Commands ({})", self.label));
+
+        let width = text_width(self.commands.iter().map(|(_, name)| name.as_str()));
+        let area = popup_rect(width + 4, self.commands.len() as u16 + 2, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let list = List::new(
+            self.commands
+                .iter()
+                .map(|(_, name)| Line::from(name.clone())),
+        )
+        .highlight_style(Style::new().bg(Color::DarkGray));
+        let mut list_state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(
+            list,
+            area.inner(Margin {
+                horizontal: 1,
+                vertical: 1,
+            }),
+            frame.buffer_mut(),
+            &mut list_state,
+        );
+        frame.render_widget(block, area);
+    }
+}
+
+/// Lists the errors which have not been acknowledged yet, newest first
+#[derive(Debug, Default)]
+pub struct ErrorsPopup {
+    scroll: u16,
+}
+
+impl Popup for ErrorsPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::Select | Message::Submit => return Some(Message::ClearErrors),
+            Message::Down => self.scroll = self.scroll.saturating_add(1),
+            Message::Up => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, state: State, _hits: &mut HitMap) {
+        let (count, lines): (usize, Vec<Line>) = state.read(|state| {
+            let lines = state
+                .errors
+                .iter()
+                .rev()
+                .flat_map(|entry| {
+                    [
+                        Line::styled(format_timestamp(entry.time), Style::default().dark_gray()),
+                        Line::styled(entry.describe(), Style::default().red()),
+                        Line::from(""),
+                    ]
+                })
+                .collect();
+            (state.errors.len(), lines)
+        });
+        let block = self
+            .create_popup_block(format!("Errors ({})", count))
+            .title_bottom(Line::from("| (enter) acknowledge all | (q) close |").right_aligned());
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+        let area = popup_rect(width + 4, lines.len() as u16 + 2, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let paragraph = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(
+            paragraph,
+            area.inner(Margin {
+                horizontal: 1,
+                vertical: 1,
+            }),
+        );
+        frame.render_widget(block, area);
+    }
+}
+
+/// Picks the level the log file is written with
+#[derive(Debug)]
+pub struct LogLevelPopup {
+    selected: usize,
+}
+
+impl LogLevelPopup {
+    pub fn new() -> Self {
+        Self {
+            selected: log::LevelFilter::iter()
+                .position(|level| level == log::max_level())
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl Popup for LogLevelPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        let len = log::LevelFilter::iter().count();
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::Down | Message::FocusNext => self.selected = (self.selected + 1) % len,
+            Message::Up | Message::FocusBack => self.selected = (self.selected + len - 1) % len,
+            Message::Select | Message::Submit => {
+                return log::LevelFilter::iter()
+                    .nth(self.selected)
+                    .map(Message::SetLogLevel);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let title = "Log level";
+        let block = self.create_popup_block(title.to_string());
+
+        let levels: Vec<_> = log::LevelFilter::iter()
+            .map(|level| {
+                if level == log::max_level() {
+                    Line::from(vec![
+                        Span::raw(level.to_string()),
+                        Span::styled(" (current)", Style::default().dark_gray()),
+                    ])
+                } else {
+                    Line::from(level.to_string())
+                }
+            })
+            .collect();
+        // Leave room for the title, which is framed by "| " and " |"
+        let width = levels
+            .iter()
+            .map(Line::width)
+            .chain([title.len() + 4])
+            .max()
+            .unwrap_or(0) as u16;
+        let area = popup_rect(width + 4, levels.len() as u16 + 2, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let list = List::new(levels).highlight_style(Style::new().bg(Color::DarkGray));
+        let mut list_state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(
+            list,
+            area.inner(Margin {
+                horizontal: 1,
+                vertical: 1,
+            }),
+            frame.buffer_mut(),
+            &mut list_state,
+        );
+        frame.render_widget(block, area);
+    }
+}
+
+/// Lists the profiles of the config, and no profile first, to switch to one
+#[derive(Debug)]
+pub struct ProfilesPopup {
+    names: Vec<String>,
+    /// The applied profile
+    current: Option<String>,
+    /// Index into `names`, offset by one for no profile
+    selected: usize,
+}
+
+impl ProfilesPopup {
+    pub fn new(names: Vec<String>, current: Option<String>) -> Self {
+        let selected = current
+            .as_ref()
+            .and_then(|current| names.iter().position(|name| name == current))
+            .map_or(0, |i| i + 1);
+        Self {
+            names,
+            current,
+            selected,
+        }
+    }
+}
+
+impl Popup for ProfilesPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        let len = self.names.len() + 1;
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::Down | Message::FocusNext => self.selected = (self.selected + 1) % len,
+            Message::Up | Message::FocusBack => self.selected = (self.selected + len - 1) % len,
+            Message::Select | Message::Submit => {
+                let name = self.selected.checked_sub(1).map(|i| self.names[i].clone());
+                return Some(Message::SwitchProfile(name));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let title = "Profiles";
+        let block = self.create_popup_block(title.to_string());
+
+        let entries = std::iter::once(None).chain(self.names.iter().map(Some));
+        let lines: Vec<_> = entries
+            .map(|name| {
+                let label = name.map_or("No profile", String::as_str);
+                if name == self.current.as_ref() {
+                    Line::from(vec![
+                        Span::raw(label.to_string()),
+                        Span::styled(" (current)", Style::default().dark_gray()),
+                    ])
+                } else {
+                    Line::from(label.to_string())
+                }
+            })
+            .collect();
+        // Leave room for the title, which is framed by "| " and " |"
+        let width = lines
+            .iter()
+            .map(Line::width)
+            .chain([title.len() + 4])
+            .max()
+            .unwrap_or(0) as u16;
+        let area = popup_rect(width + 4, lines.len() as u16 + 2, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let list = List::new(lines).highlight_style(Style::new().bg(Color::DarkGray));
+        let mut list_state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(
+            list,
+            area.inner(Margin {
+                horizontal: 1,
+                vertical: 1,
+            }),
+            frame.buffer_mut(),
+            &mut list_state,
+        );
+        frame.render_widget(block, area);
+    }
+}
+
+/// Steps through the recent versions of the state, showing what caused each
+/// one and what it changed, or the whole state at that version
+#[derive(Debug, Default)]
+pub struct StateHistoryPopup {
+    /// Index of the shown version, the latest one if unset
+    selected: Option<usize>,
+    /// Show the whole state instead of what changed
+    full: bool,
+    scroll: usize,
+}
+
+impl StateHistoryPopup {
+    fn lines(&self, version: &StateVersion) -> Vec<Line<'static>> {
+        let mut lines = vec![Line::from(vec![
+            Span::styled(format!("#{} ", version.seq), Style::default().bold()),
+            Span::raw(format_timestamp(version.time)),
+        ])];
+        lines.push(Line::from(version.cause.clone()));
+        if !version.triggered.is_empty() {
+            lines.push(
+                Line::from(format!("→ reloads {}", version.triggered.join(", "))).dark_gray(),
+            );
+        }
+        lines.push(Line::default());
+
+        if self.full {
+            for (key, value) in &version.summary {
+                let changed = version.diff.iter().any(|diff| match diff {
+                    StateDiff::Added { key: k, .. } | StateDiff::Changed { key: k, .. } => k == key,
+                    StateDiff::Removed { .. } => false,
+                });
+                let line = Line::from(format!("{} = {}", key, value));
+                lines.push(if changed { line.yellow() } else { line });
+            }
+        } else {
+            for diff in &version.diff {
+                lines.push(match diff {
+                    StateDiff::Added { key, value } => {
+                        Line::from(format!("+ {} = {}", key, value)).green()
+                    }
+                    StateDiff::Removed { key, value } => {
+                        Line::from(format!("- {} = {}", key, value)).red()
+                    }
+                    StateDiff::Changed { key, from, to } => {
+                        Line::from(format!("~ {}: {} → {}", key, from, to)).yellow()
+                    }
+                });
+            }
+        }
+        lines
+    }
+}
+
+impl Popup for StateHistoryPopup {
+    fn update(&mut self, msg: Message, state: State) -> Option<Message> {
+        let len = state.read(|state| state.history.as_ref().map_or(0, |h| h.versions().len()));
+        let current = self.selected.unwrap_or(len.saturating_sub(1));
+        match msg {
+            Message::Quit | Message::StateHistory => return Some(Message::Quit),
+            Message::Left if current > 0 => {
+                self.selected = Some(current - 1);
+                self.scroll = 0;
+            }
+            Message::Right if self.selected.is_some() => {
+                // Follow new versions again once the latest one is reached
+                self.selected = Some(current + 1).filter(|i| *i + 1 < len);
+                self.scroll = 0;
+            }
+            Message::FocusNext | Message::FocusBack => {
+                self.full = !self.full;
+                self.scroll = 0;
+            }
+            Message::Down => self.scroll += 1,
+            Message::Up => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, state: State, _hits: &mut HitMap) {
+        let (title, lines) = state.read(|state| {
+            let versions = state.history.as_ref().map(|h| h.versions());
+            match versions.filter(|versions| !versions.is_empty()) {
+                Some(versions) => {
+                    let index = self
+                        .selected
+                        .unwrap_or(versions.len() - 1)
+                        .min(versions.len() - 1);
+                    (
+                        format!("State {}/{}", index + 1, versions.len()),
+                        self.lines(&versions[index]),
+                    )
+                }
+                None => (
+                    "State".to_string(),
+                    vec![Line::from("Nothing changed yet").dark_gray()],
+                ),
+            }
+        });
+        let block = self.create_popup_block(title).title_bottom(
+            Line::from(format!(
+                "| (h/l) step | (Tab) {} |{}",
+                if self.full { "changes" } else { "whole state" },
+                if self.selected.is_none() {
+                    " following |"
+                } else {
+                    ""
+                }
+            ))
+            .right_aligned(),
+        );
+
+        let area = popup_rect(
+            POPUP_MAX_WIDTH,
+            frame.area().height.saturating_sub(4),
+            frame.area(),
+        );
+        Clear.render(area, frame.buffer_mut());
+        let inner_area = block.inner(area);
+        frame.render_widget(block, area);
+        let mut list_state =
+            ListState::default().with_offset(self.scroll.min(lines.len().saturating_sub(1)));
+        StatefulWidget::render(
+            List::new(lines),
+            inner_area.inner(Margin {
+                horizontal: 1,
+                vertical: 0,
+            }),
+            frame.buffer_mut(),
+            &mut list_state,
+        );
+    }
+}
+
+/// Goes through the reasons why a folder might not sync, and suggests how to
+/// fix the first one which applies
+#[derive(Debug)]
+pub struct DiagnosePopup {
+    folder_id: String,
+    icons: IconSet,
+}
+
+impl DiagnosePopup {
+    pub fn new(folder_id: String, icons: IconSet) -> Self {
+        Self { folder_id, icons }
+    }
+
+    fn describe(check: FolderCheck) -> &'static str {
+        match check {
+            FolderCheck::Unpaused => "Not paused",
+            FolderCheck::Connected => "Shared with a connected device",
+            FolderCheck::Accepted => "Accepted by the other devices",
+            FolderCheck::NoErrors => "No folder errors",
+            FolderCheck::Watching => "Watching for changes",
+            FolderCheck::UpToDate => "Up to date",
+        }
+    }
+
+    /// What went wrong, and how to fix it
+    fn explain(problem: &FolderProblem) -> (String, &'static str) {
+        match problem {
+            FolderProblem::Paused => (
+                "The folder is paused.".to_string(),
+                "Resume it with (p) on the folders screen.",
+            ),
+            FolderProblem::NotShared => (
+                "The folder is not shared with any other device.".to_string(),
+                "Share it in the sharing tab of the folder popup.",
+            ),
+            FolderProblem::Disconnected { devices } => (
+                format!(
+                    "None of the devices sharing it is connected: {}.",
+                    devices.join(", ")
+                ),
+                "Make sure they run Syncthing and can reach this device.",
+            ),
+            FolderProblem::NotAccepted { devices } => (
+                format!("{} did not accept the folder yet.", devices.join(", ")),
+                "Accept the folder on these devices, where it shows up as pending.",
+            ),
+            FolderProblem::Error(error) => (
+                format!("Syncthing reports: {}", error),
+                "Fix the cause, often a missing path or permissions, then wait for the next scan.",
+            ),
+            FolderProblem::WatcherDisabled => (
+                "Changes are only noticed by the periodic rescan.".to_string(),
+                "Enable fsWatcherEnabled for the folder on the config screen.",
+            ),
+            FolderProblem::WatchFailed(error) => (
+                format!("Watching for changes failed: {}", error),
+                "On Linux, raising the inotify limits usually helps. Until then, changes are noticed by the periodic rescan.",
+            ),
+            FolderProblem::OutOfSync { items, bytes } => (
+                format!(
+                    "{} items ({}) still have to be synced.",
+                    items,
+                    format_bytes(*bytes as f64)
+                ),
+                "Wait for them, or make sure the devices which have them stay connected.",
+            ),
+        }
+    }
+}
+
+impl Popup for DiagnosePopup {
+    fn update(&mut self, msg: Message, state: State) -> Option<Message> {
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::Select | Message::Submit => {
+                state.reload(Reload::Configuration);
+                state.reload(Reload::Completion {
+                    folder_id: Some(self.folder_id.clone()),
+                    device_id: None,
+                });
+                state.hint("Checking again…");
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, state: State, _hits: &mut HitMap) {
+        let (label, lines) = state.read(|state| {
+            let Ok(folder) = state.get_folder(&self.folder_id) else {
+                return (self.folder_id.clone(), vec![Line::from("Folder not found")]);
+            };
+            let mut lines = Vec::new();
+            let mut failed = false;
+            for check in FolderCheck::iter() {
+                if failed {
+                    lines.push(Line::styled(
+                        format!("  {}", Self::describe(check)),
+                        Style::default().dark_gray(),
+                    ));
+                    continue;
+                }
+                match state.check_folder(folder, check) {
+                    None => lines.push(Line::from(vec![
+                        Span::styled(self.icons.checkbox(true), Style::default().green()),
+                        Span::raw(format!(" {}", Self::describe(check))),
+                    ])),
+                    Some(problem) => {
+                        failed = true;
+                        let (problem, fix) = Self::explain(&problem);
+                        lines.push(Line::from(vec![
+                            Span::styled(self.icons.checkbox(false), Style::default().red()),
+                            Span::styled(
+                                format!(" {}", Self::describe(check)),
+                                Style::default().red().bold(),
+                            ),
+                        ]));
+                        lines.push(Line::from(format!("  {}", problem)));
+                        lines.push(Line::styled(
+                            format!("  Fix: {}", fix),
+                            Style::default().yellow(),
+                        ));
+                    }
+                }
+            }
+            if !failed {
+                lines.push(Line::default());
+                lines.push(Line::styled(
+                    "Everything looks fine. If files still differ, compare the ignore patterns on both devices.",
+                    Style::default().green(),
+                ));
+            }
+            (folder.config.label.clone(), lines)
+        });
+
+        let block = self
+            .create_popup_block(format!("Why is {} not syncing?", label))
+            .title_bottom(Line::from("| (Enter) check again |").right_aligned());
+        // Wrap long explanations instead of growing wider than that
+        let width = 70;
+        let height: usize = lines
+            .iter()
+            .map(|line| line.width().div_ceil(width - 4).max(1))
+            .sum();
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
+        let area = popup_rect(width as u16, height as u16 + 2, frame.area());
+        Clear.render(area, frame.buffer_mut());
+        frame.render_widget(
+            paragraph,
+            area.inner(Margin {
+                horizontal: 2,
+                vertical: 1,
+            }),
+        );
+        frame.render_widget(block, area);
+    }
+}
+
+/// Shows how synctui connects to Syncthing, and offers to rotate the API key
+#[derive(Debug)]
+pub struct SessionPopup {
+    /// Labels and values, in the order they are shown
+    rows: Vec<(&'static str, String)>,
+}
+
+impl SessionPopup {
+    pub fn new(rows: Vec<(&'static str, String)>) -> Self {
+        Self { rows }
+    }
+}
+
+impl Popup for SessionPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        match msg {
+            Message::Quit => Some(Message::Quit),
+            Message::Select | Message::Submit => Some(Message::RotateApiKey),
+            _ => None,
+        }
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let label_width = self
+            .rows
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+        let lines: Vec<_> = self
+            .rows
+            .iter()
+            .map(|(label, value)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:width$} : ", label, width = label_width),
+                        Style::default().bold(),
+                    ),
+                    Span::raw(value.clone()),
+                ])
+            })
+            .collect();
+        let hint = "| (Enter) rotate API key |";
+        let width = lines
+            .iter()
+            .map(Line::width)
+            .chain([hint.len()])
+            .max()
+            .unwrap_or(0) as u16;
+        let area = popup_rect(width + 4, lines.len() as u16 + 2, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let block = self
+            .create_popup_block("Session".to_string())
+            .title_bottom(Line::from(hint).right_aligned());
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+/// Lists the devices of the address book which are not configured, to add
+/// one of them again
+#[derive(Debug)]
+pub struct AddressBookPopup {
+    entries: Vec<AddressBookEntry>,
+    selected: usize,
+}
+
+impl AddressBookPopup {
+    pub fn new(entries: Vec<AddressBookEntry>) -> Self {
+        Self {
+            entries,
+            selected: 0,
+        }
+    }
+
+    fn line(entry: &AddressBookEntry) -> Line<'static> {
+        let mut line = Line::from(vec![
+            Span::raw(entry.name.clone()),
+            Span::styled(
+                format!(" ({})", entry.device_id),
+                Style::default().dark_gray(),
+            ),
+        ]);
+        if !entry.notes.is_empty() {
+            line.push_span(Span::raw(format!(" — {}", entry.notes)));
+        }
+        line
+    }
+}
+
+impl Popup for AddressBookPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        let len = self.entries.len();
+        match msg {
+            Message::Quit => return Some(Message::Quit),
+            Message::Down | Message::FocusNext if len > 0 => {
+                self.selected = (self.selected + 1) % len
+            }
+            Message::Up | Message::FocusBack if len > 0 => {
+                self.selected = (self.selected + len - 1) % len
+            }
+            Message::Select | Message::Submit => {
+                return self
+                    .entries
+                    .get(self.selected)
+                    .map(|entry| Message::AddKnownDevice {
+                        device_id: entry.device_id.clone(),
+                        name: entry.name.clone(),
+                    });
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block("Address Book".to_string());
+
+        let lines: Vec<_> = self.entries.iter().map(Self::line).collect();
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+        let area = popup_rect(width + 4, lines.len() as u16 + 2, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let list = List::new(lines).highlight_style(Style::new().bg(Color::DarkGray));
+        let mut list_state = ListState::default().with_selected(Some(self.selected));
+        StatefulWidget::render(
+            list,
+            area.inner(Margin {
+                horizontal: 1,
+                vertical: 1,
+            }),
+            frame.buffer_mut(),
+            &mut list_state,
+        );
+        frame.render_widget(block, area);
+    }
+}
+
+/// Shows the output of a command
+#[derive(Debug)]
+pub struct OutputPopup {
+    title: String,
+    lines: Vec<String>,
+    scroll: usize,
+}
+
+impl OutputPopup {
+    pub fn new(title: String, output: String) -> Self {
+        Self {
+            title,
+            lines: output.lines().map(str::to_string).collect(),
+            scroll: 0,
+        }
+    }
+}
+
+impl Popup for OutputPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        match msg {
+            Message::Quit | Message::Select => return Some(Message::Quit),
+            Message::Down => {
+                self.scroll = (self.scroll + 1).min(self.lines.len().saturating_sub(1))
+            }
+            Message::Up => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block(self.title.clone());
+
+        let width = text_width(self.lines.iter().map(String::as_str));
+        let area = popup_rect(width + 4, self.lines.len() as u16 + 2, frame.area());
+        Clear.render(area, frame.buffer_mut());
+
+        let mut list_state = ListState::default().with_offset(self.scroll);
+        StatefulWidget::render(
+            List::new(self.lines.iter().map(|line| Line::from(line.as_str()))),
+            area.inner(Margin {
+                horizontal: 1,
+                vertical: 1,
+            }),
+            frame.buffer_mut(),
+            &mut list_state,
+        );
+        frame.render_widget(block, area);
+    }
+}
+
+/// Lists all keys, with the ones not doing anything on the current screen
+/// dimmed
+#[derive(Debug)]
+pub struct HelpPopup {
+    bindings: Vec<(String, &'static Action)>,
+    screen: CurrentScreen,
+    scroll: usize,
+}
+
+impl HelpPopup {
+    pub fn new(bindings: Vec<(String, &'static Action)>, screen: CurrentScreen) -> Self {
+        Self {
+            bindings,
+            screen,
+            scroll: 0,
+        }
+    }
+}
+
+impl Popup for HelpPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        match msg {
+            Message::Quit | Message::Help | Message::Select => return Some(Message::Quit),
+            Message::Down => {
+                self.scroll = (self.scroll + 1).min(self.bindings.len().saturating_sub(1))
+            }
+            Message::Up => self.scroll = self.scroll.saturating_sub(1),
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, _hits: &mut HitMap) {
+        let block = self.create_popup_block("Keys".to_string());
+
+        let width = self
+            .bindings
+            .iter()
+            .map(|(keys, _)| keys.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(3);
+        let description_width =
+            text_width(self.bindings.iter().map(|(_, action)| action.description));
+
+        // Keys, descriptions and the line for switching screens
+        let area = popup_rect(
+            width as u16 + description_width + 6,
+            self.bindings.len() as u16 + 3,
+            frame.area(),
+        );
+        Clear.render(area, frame.buffer_mut());
+
+        let mut lines = vec![Line::from(vec![
+            Span::styled(format!("{:<width$}", "1-9"), Style::default().bold()),
+            Span::raw("  Switch screen"),
+        ])];
+        lines.extend(self.bindings.iter().map(|(keys, action)| {
+            let style = if action.screens.is_empty() || action.screens.contains(&self.screen) {
+                Style::default()
+            } else {
+                Style::default().dark_gray()
+            };
+            Line::from(vec![
+                Span::styled(format!("{:<width$}", keys), style.bold()),
+                Span::styled(format!("  {}", action.description), style),
+            ])
+        }));
+
+        let mut list_state = ListState::default().with_offset(self.scroll);
+        StatefulWidget::render(
+            List::new(lines),
+            block.inner(area).inner(Margin {
+                horizontal: 1,
+                vertical: 0,
+            }),
+            frame.buffer_mut(),
+            &mut list_state,
+        );
+        frame.render_widget(block, area);
+    }
+}
+
+/// A button of a [`ConfirmPopup`]
+#[derive(Clone, Debug)]
+pub struct ConfirmAction {
+    label: String,
+    /// Shortcut which triggers the action directly
+    key: char,
+    message: Message,
+    /// Whether the action destroys something, shown in red
+    danger: bool,
+}
+
+impl ConfirmAction {
+    pub fn new(label: impl Into<String>, key: char, message: Message) -> Self {
+        Self {
+            label: label.into(),
+            key,
+            message,
+            danger: false,
+        }
+    }
+
+    pub fn danger(mut self) -> Self {
+        self.danger = true;
+        self
+    }
+}
+
+/// Asks the user to choose one of several actions, e.g., whether to really
+/// remove a folder. Each action can be triggered with its own key. Starts in
+/// insert mode, so that the keys are not taken by the keymap.
+#[derive(Debug)]
+pub struct ConfirmPopup {
+    title: String,
+    message: String,
+    actions: Vec<ConfirmAction>,
+    focus: usize,
+    mode: Arc<Mutex<CurrentMode>>,
+}
+
+impl ConfirmPopup {
+    /// Focuses the first action which is not dangerous, so that a stray
+    /// Enter never destroys anything
+    pub fn new(
+        title: impl Into<String>,
+        message: impl Into<String>,
+        actions: Vec<ConfirmAction>,
+        mode: Arc<Mutex<CurrentMode>>,
+    ) -> Self {
+        *mode.lock().unwrap() = CurrentMode::Insert;
+        let focus = actions.iter().position(|a| !a.danger).unwrap_or(0);
+        Self {
+            title: title.into(),
+            message: message.into(),
+            actions,
+            focus,
+            mode,
+        }
+    }
+
+    /// A yes/no question, which emits `confirmed` wrapped in
+    /// [`Message::Confirmed`] on yes
+    pub fn destructive(
+        title: impl Into<String>,
+        message: impl Into<String>,
+        label: impl Into<String>,
+        confirmed: Message,
+        mode: Arc<Mutex<CurrentMode>>,
+    ) -> Self {
+        Self::new(
+            title,
+            message,
+            vec![
+                ConfirmAction::new(label, 'y', Message::Confirmed(Box::new(confirmed))).danger(),
+                ConfirmAction::new("Cancel", 'n', Message::Quit),
+            ],
+            mode,
+        )
+    }
+
+    fn finish(&self, msg: Message) -> Option<Message> {
+        *self.mode.lock().unwrap() = CurrentMode::Normal;
+        Some(msg)
+    }
+}
+
+impl Popup for ConfirmPopup {
+    fn update(&mut self, msg: Message, _state: State) -> Option<Message> {
+        match msg {
+            Message::Quit | Message::Normal | Message::Character('q') => {
+                return self.finish(Message::Quit);
+            }
+            Message::Character(c) => {
+                if let Some(action) = self.actions.iter().find(|a| a.key.eq_ignore_ascii_case(&c)) {
+                    return self.finish(action.message.clone());
+                }
+            }
+            Message::FocusNext | Message::Right | Message::Down => {
+                self.focus = (self.focus + 1).min(self.actions.len().saturating_sub(1))
+            }
+            Message::FocusBack | Message::Left | Message::Up => {
+                self.focus = self.focus.saturating_sub(1)
+            }
+            Message::Select | Message::Submit => {
+                if let Some(action) = self.actions.get(self.focus) {
+                    return self.finish(action.message.clone());
+                }
+            }
+            Message::Press(i) => {
+                if let Some(action) = self.actions.get(i) {
+                    return self.finish(action.message.clone());
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn render(&self, frame: &mut Frame, _state: State, hits: &mut HitMap) {
+        let block = self.create_popup_block(self.title.clone());
+
+        // Wrap long messages instead of growing wider than that
+        let width = (text_width([self.message.as_str()]) + 6).min(70);
+        let lines = text_width([self.message.as_str()]).div_ceil((width - 4).max(1));
+        let area = popup_rect(width, lines + 4, frame.area());
+        Clear.render(area, frame.buffer_mut());
+        let [message_area, buttons_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area.inner(
+                Margin {
+                    horizontal: 2,
+                    vertical: 1,
+                },
+            ));
+
+        let message = Paragraph::new(self.message.as_str()).wrap(Wrap { trim: true });
+
+        let mut buttons = Vec::new();
+        for (i, action) in self.actions.iter().enumerate() {
+            let mut style = if action.danger {
+                Style::default().red().bold()
+            } else {
+                Style::default()
+            };
+            if i == self.focus {
+                style = style.bg(Color::DarkGray);
+            }
+            buttons.push(Span::styled(
+                format!("({}) {}", action.key, action.label),
+                style,
+            ));
+        }
+
+        frame.render_widget(block, area);
+        frame.render_widget(message, message_area);
+        frame.render_widget(
+            button_line(buttons, "  ", true, buttons_area, hits),
+            buttons_area,
+        );
+    }
+}
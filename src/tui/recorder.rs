@@ -0,0 +1,189 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use serde_json::Value;
+use syncthing_rs::types::events::Event;
+
+use crate::{
+    AppError,
+    config::{RecordFormat, RecorderConfig},
+    tui::state::State,
+};
+
+/// Starts recording the events of `state` to the file configured in `config`
+pub fn start(state: &State, config: &RecorderConfig) {
+    match FileRecorder::new(config) {
+        Some(recorder) => {
+            state.hint(format!("Recording events to {}", recorder.describe()));
+            state.set_event_sink(Some(Box::new(recorder)));
+        }
+        None => state.hint("No data directory to record events to, set recorder.path"),
+    }
+}
+
+/// Receives every event, e.g., to keep them for diagnosing issues later on
+pub trait EventSink: Send + std::fmt::Debug {
+    fn record(&mut self, event: &Event) -> Result<(), AppError>;
+    /// Where the events end up, for the status line
+    fn describe(&self) -> String;
+}
+
+/// Appends events to a file, one per line, and moves the file aside once it
+/// grows too large
+#[derive(Debug)]
+pub struct FileRecorder {
+    path: PathBuf,
+    format: RecordFormat,
+    /// Bytes after which the file is rotated
+    max_size: u64,
+    /// Rotated files kept besides the current one
+    keep: usize,
+    /// The open file and its size, once the first event arrived
+    file: Option<(File, u64)>,
+}
+
+impl FileRecorder {
+    /// Recorder as configured in `config`, unless there is no path to
+    /// record to
+    pub fn new(config: &RecorderConfig) -> Option<Self> {
+        let path = config.path.clone().or_else(|| {
+            dirs::data_dir().map(|path| {
+                path.join("synctui").join(match config.format {
+                    RecordFormat::Jsonl => "events.jsonl",
+                    RecordFormat::Csv => "events.csv",
+                })
+            })
+        })?;
+        Some(Self {
+            path,
+            format: config.format,
+            max_size: config.max_size * 1024 * 1024,
+            keep: config.keep,
+            file: None,
+        })
+    }
+
+    /// Path of the `n`th rotated file, the current one for 0
+    fn rotated(&self, n: usize) -> PathBuf {
+        if n == 0 {
+            return self.path.clone();
+        }
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        path.into()
+    }
+
+    /// Shifts the rotated files by one, dropping the oldest
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        for n in (0..=self.keep).rev() {
+            let from = self.rotated(n);
+            if !from.exists() {
+                continue;
+            }
+            if n == self.keep {
+                fs::remove_file(from)?;
+            } else {
+                fs::rename(from, self.rotated(n + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn open(&mut self) -> io::Result<&mut (File, u64)> {
+        if self.file.is_none() {
+            if let Some(parent) = self.path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            let mut size = file.metadata()?.len();
+            if size == 0 && self.format == RecordFormat::Csv {
+                let header = "id,time,type,folder,device,data\n";
+                file.write_all(header.as_bytes())?;
+                size = header.len() as u64;
+            }
+            self.file = Some((file, size));
+        }
+        Ok(self.file.as_mut().expect("file was just opened"))
+    }
+
+    fn write(&mut self, event: &Event) -> io::Result<()> {
+        let line = match self.format {
+            RecordFormat::Jsonl => serde_json::to_string(event).map_err(io::Error::other)? + "\n",
+            RecordFormat::Csv => csv_row(event)?,
+        };
+        let max_size = self.max_size;
+        let (_, size) = self.open()?;
+        if *size > 0 && *size + line.len() as u64 > max_size {
+            self.rotate()?;
+        }
+        let (file, size) = self.open()?;
+        file.write_all(line.as_bytes())?;
+        *size += line.len() as u64;
+        Ok(())
+    }
+}
+
+impl EventSink for FileRecorder {
+    fn record(&mut self, event: &Event) -> Result<(), AppError> {
+        self.write(event).map_err(|source| {
+            // Reopen the file next time, in case it was moved meanwhile
+            self.file = None;
+            AppError::RecordError {
+                path: self.path.clone(),
+                source,
+            }
+        })
+    }
+
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// `event` as a line of CSV, with the folder and device it concerns in
+/// their own columns and all data as JSON
+fn csv_row(event: &Event) -> io::Result<String> {
+    let value = serde_json::to_value(event).map_err(io::Error::other)?;
+    let data = value.get("data").cloned().unwrap_or(Value::Null);
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| data.get(key).and_then(Value::as_str))
+            .unwrap_or_default()
+            .to_string()
+    };
+    let columns = [
+        event.id.to_string(),
+        event.time.to_rfc3339(),
+        value
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        field(&["folder", "folderID"]),
+        field(&["device", "deviceID", "id"]),
+        if data.is_null() {
+            String::new()
+        } else {
+            data.to_string()
+        },
+    ];
+    let mut row = columns.map(|column| csv_escape(&column)).join(",");
+    row.push('\n');
+    Ok(row)
+}
+
+/// Quotes `field` if it contains a separator, quote or line break
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
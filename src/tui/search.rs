@@ -0,0 +1,209 @@
+use serde_json::Value;
+use syncthing_rs::types::events::Event;
+
+use super::{
+    notes::{Note, NoteTarget},
+    state::InnerState,
+    ui::format_timestamp,
+};
+
+/// Where a search result leads to when selected
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchTarget {
+    Folder(String),
+    Device(String),
+    PendingDevice(String),
+    PendingFolder {
+        folder_id: String,
+        device_id: String,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    /// What kind of entity this is, e.g., "Folder"
+    pub kind: &'static str,
+    pub text: String,
+    /// `None` for events, which do not refer to a known folder or device
+    pub target: Option<SearchTarget>,
+    score: usize,
+}
+
+/// How well `text` matches `query`, lower is better. Substring matches
+/// always rank before fuzzy ones, where all characters of `query` appear in
+/// order.
+fn score(text: &str, query: &str) -> Option<usize> {
+    let text = text.to_lowercase();
+    let query = query.to_lowercase();
+    if let Some(position) = text.find(&query) {
+        return Some(text[..position].chars().count());
+    }
+
+    let mut chars = text.chars().enumerate();
+    let mut last = 0;
+    for q in query.chars() {
+        last = chars.find(|(_, c)| *c == q)?.0;
+    }
+    Some(text.chars().count() + last)
+}
+
+/// What `event` is about: its type as Syncthing calls it, the folder and
+/// device it concerns by label and name, and the item or error it mentions.
+/// Leads to the folder, or else the device, if it is known.
+fn event_summary(state: &InnerState, event: &Event) -> (String, Option<SearchTarget>) {
+    let value = serde_json::to_value(event).unwrap_or_default();
+    let data = value.get("data").cloned().unwrap_or_default();
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| data.get(key).and_then(Value::as_str))
+            .filter(|field| !field.is_empty())
+    };
+    let mut parts = vec![
+        value
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or("Unknown")
+            .to_string(),
+    ];
+    let mut target = None;
+
+    if let Some(folder_id) = field(&["folder", "folderID"]) {
+        match state.get_folder(folder_id) {
+            Ok(folder) => {
+                parts.push(folder.config.label.clone());
+                target = Some(SearchTarget::Folder(folder_id.to_string()));
+            }
+            Err(_) => parts.push(folder_id.to_string()),
+        }
+    }
+    if let Some(item) = field(&["item", "path"]) {
+        parts.push(item.to_string());
+    }
+    // Some events only carry the short form of device IDs
+    if let Some(device_id) = field(&["device", "deviceID", "id", "modifiedBy"]) {
+        match state
+            .get_other_devices()
+            .into_iter()
+            .find(|device| device.config.device_id.starts_with(device_id))
+        {
+            Some(device) => {
+                parts.push(device.config.name.clone());
+                target.get_or_insert_with(|| SearchTarget::Device(device.config.device_id.clone()));
+            }
+            None => parts.push(device_id.to_string()),
+        }
+    }
+    if let Some(error) = field(&["error"]) {
+        parts.push(error.to_string());
+    }
+    (parts.join(" "), target)
+}
+
+/// Searches folders, devices, pending items and recent events for `query`,
+/// returning the best matches first
+pub fn search(state: &InnerState, query: &str) -> Vec<SearchResult> {
+    let mut results = Vec::new();
+    if query.is_empty() {
+        return results;
+    }
+
+    let mut add = |kind, text: String, haystack: &[&str], target: Option<SearchTarget>| {
+        if let Some(score) = haystack.iter().filter_map(|h| score(h, query)).min() {
+            results.push(SearchResult {
+                kind,
+                text,
+                target,
+                score,
+            });
+        }
+    };
+
+    let note_haystack = |note: Option<&Note>| -> Vec<String> {
+        note.map(|note| {
+            let mut haystack = note.tags.clone();
+            haystack.push(note.text.clone());
+            haystack
+        })
+        .unwrap_or_default()
+    };
+
+    for folder in state.get_folders() {
+        let note = state
+            .notes
+            .get(&NoteTarget::Folder(folder.config.id.clone()));
+        let notes = note_haystack(note);
+        let mut haystack = vec![
+            folder.config.label.as_str(),
+            &folder.config.id,
+            &folder.config.path,
+        ];
+        haystack.extend(notes.iter().map(String::as_str));
+        add(
+            "Folder",
+            folder.config.label.clone(),
+            &haystack,
+            Some(SearchTarget::Folder(folder.config.id.clone())),
+        );
+    }
+    for device in state.get_other_devices() {
+        let note = state
+            .notes
+            .get(&NoteTarget::Device(device.config.device_id.clone()));
+        let notes = note_haystack(note);
+        let mut haystack = vec![device.config.name.as_str(), &device.config.device_id];
+        haystack.extend(notes.iter().map(String::as_str));
+        add(
+            "Device",
+            device.config.name.clone(),
+            &haystack,
+            Some(SearchTarget::Device(device.config.device_id.clone())),
+        );
+    }
+    for device in state.get_pending_devices() {
+        let name = device.get_name().clone().unwrap_or_default();
+        add(
+            "Pending device",
+            format!("{} ({})", name, device.get_device_id()),
+            &[&name, device.get_device_id()],
+            Some(SearchTarget::PendingDevice(device.get_device_id().clone())),
+        );
+    }
+    for (device_id, folder) in state.get_pending_folders() {
+        let label = folder.get_label().clone().unwrap_or_default();
+        add(
+            "Pending folder",
+            format!("{} ({})", label, folder.get_id()),
+            &[&label, folder.get_id()],
+            Some(SearchTarget::PendingFolder {
+                folder_id: folder.get_id().clone(),
+                device_id: device_id.clone(),
+            }),
+        );
+    }
+    for event in state.events.iter().rev() {
+        let (text, target) = event_summary(state, event);
+        add(
+            "Event",
+            format!("{} {}", format_timestamp(event.time), text),
+            &[&text],
+            target,
+        );
+    }
+
+    // Stable, so events stay ordered newest first among equal scores
+    results.sort_by_key(|r| r.score);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_counts_characters() {
+        assert_eq!(score("Über uns", "uns"), Some(5));
+        // Fuzzy matches rank after all substring matches of the same text
+        assert_eq!(score("Fotos ä", "fä"), Some(7 + 6));
+        assert_eq!(score("Fotos", "x"), None);
+    }
+}
@@ -0,0 +1,249 @@
+//! First run of synctui, asking for the address and API key of Syncthing
+//! before the config file exists
+
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre;
+use crossterm::event::{Event as CrosstermEvent, EventStream, KeyEventKind};
+use futures::StreamExt;
+use ratatui::{
+    Frame, Terminal,
+    layout::{Constraint, Layout, Margin, Position},
+    prelude::Backend,
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use super::{
+    app::CurrentMode,
+    init_tui,
+    input::{self, Message},
+    keymap::Keymap,
+    popup::{TextBox, popup_rect, text_width},
+    restore_tui,
+};
+use crate::{AppConfig, LocalSyncthing};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Focus {
+    Address,
+    ApiKey,
+}
+
+/// What the form shows below the inputs
+#[derive(Debug)]
+enum Status {
+    Hint(String),
+    Connecting,
+    Failed(String),
+}
+
+/// What has to happen after a message was handled by the form
+#[derive(Debug, PartialEq)]
+enum Step {
+    Continue,
+    Connect,
+    Quit,
+}
+
+#[derive(Debug)]
+struct SetupForm {
+    address: TextBox,
+    api_key: TextBox,
+    focus: Focus,
+    status: Status,
+}
+
+impl SetupForm {
+    /// Prefills the form with `address`, or what was found in the config.xml
+    /// of the `local` Syncthing
+    fn new(address: Option<String>, local: Option<LocalSyncthing>) -> Self {
+        let status = match &local {
+            Some(local) => Status::Hint(format!("Read from {}", local.path.display())),
+            None => Status::Hint(
+                "Find the API key in Syncthing's GUI under Actions > Settings".to_string(),
+            ),
+        };
+        let address = address
+            .or_else(|| local.as_ref().and_then(|local| local.address.clone()))
+            .unwrap_or_else(|| AppConfig::default().address().to_string());
+        Self {
+            address: address.into(),
+            api_key: local.map(|local| local.api_key).unwrap_or_default().into(),
+            focus: Focus::ApiKey,
+            status,
+        }
+    }
+
+    fn focused(&mut self) -> &mut TextBox {
+        match self.focus {
+            Focus::Address => &mut self.address,
+            Focus::ApiKey => &mut self.api_key,
+        }
+    }
+
+    fn update(&mut self, msg: Message) -> Step {
+        match msg {
+            Message::Quit | Message::Normal => return Step::Quit,
+            Message::Character(c) => self.focused().enter_char(c),
+            Message::Backspace => self.focused().delete_char(),
+            Message::Left => self.focused().move_cursor_left(),
+            Message::Right => self.focused().move_cursor_right(),
+            Message::FocusNext | Message::FocusBack | Message::Down | Message::Up => {
+                self.focus = match self.focus {
+                    Focus::Address => Focus::ApiKey,
+                    Focus::ApiKey => Focus::Address,
+                }
+            }
+            Message::Select if self.focus == Focus::Address => self.focus = Focus::ApiKey,
+            Message::Select | Message::Submit => return Step::Connect,
+            _ => {}
+        }
+        Step::Continue
+    }
+
+    fn render(&self, frame: &mut Frame, path: &Path) {
+        let intro = format!(
+            "Welcome to synctui! Connect it to Syncthing to create {}.",
+            path.display()
+        );
+        let area = popup_rect(
+            text_width([
+                intro.as_str(),
+                self.address.text.as_str(),
+                self.api_key.text.as_str(),
+            ]) + 4,
+            13,
+            frame.area(),
+        );
+        Clear.render(area, frame.buffer_mut());
+        let block = Block::default()
+            .title_top(Line::from("| Setup |").centered().bold())
+            .title_bottom(
+                Line::from("| (enter) connect | (tab) next | (esc) quit |").right_aligned(),
+            )
+            .borders(Borders::ALL);
+        let [intro_area, address_area, api_key_area, status_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(1),
+        ])
+        .areas(block.inner(area).inner(Margin {
+            horizontal: 1,
+            vertical: 0,
+        }));
+        frame.render_widget(block, area);
+
+        Paragraph::new(intro)
+            .wrap(Wrap { trim: true })
+            .render(intro_area, frame.buffer_mut());
+        let style = |focus| {
+            if self.focus == focus {
+                Style::default().fg(Color::Blue)
+            } else {
+                Style::default()
+            }
+        };
+        frame.render_widget(
+            self.address.as_paragraph("Address", style(Focus::Address)),
+            address_area,
+        );
+        frame.render_widget(
+            self.api_key.as_paragraph("API key", style(Focus::ApiKey)),
+            api_key_area,
+        );
+        let (input_area, input) = match self.focus {
+            Focus::Address => (address_area, &self.address),
+            Focus::ApiKey => (api_key_area, &self.api_key),
+        };
+        frame.set_cursor_position(Position::new(
+            input_area.x + (input.index as u16) + 1,
+            input_area.y + 1,
+        ));
+
+        let status = match &self.status {
+            Status::Hint(hint) => Line::styled(hint.as_str(), Style::default().dark_gray()),
+            Status::Connecting => Line::from("Connecting…"),
+            Status::Failed(error) => Line::styled(error.as_str(), Style::default().red()),
+        };
+        Paragraph::new(status)
+            .wrap(Wrap { trim: true })
+            .render(status_area, frame.buffer_mut());
+    }
+}
+
+/// Asks for the address and API key of Syncthing, prefilled from its
+/// config.xml if it runs on this machine. Once Syncthing answers with them,
+/// they are written to the config file at `path`, which is then loaded.
+/// Returns `None` if the user quits instead.
+pub async fn setup(
+    path: PathBuf,
+    address: Option<String>,
+    insecure: bool,
+) -> eyre::Result<Option<AppConfig>> {
+    let keymap = Keymap::new(&AppConfig::default().keys)?;
+    let mut form = SetupForm::new(address, LocalSyncthing::find());
+
+    let mut terminal = init_tui()?;
+    // The terminal is restored even if drawing or reading keys failed
+    let config = run(&mut terminal, &mut form, &path, &keymap, insecure).await;
+    restore_tui()?;
+    terminal.show_cursor()?;
+    config
+}
+
+async fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    form: &mut SetupForm,
+    path: &Path,
+    keymap: &Keymap,
+    insecure: bool,
+) -> eyre::Result<Option<AppConfig>> {
+    terminal.clear()?;
+    let mut events = EventStream::new();
+    loop {
+        terminal.draw(|f| form.render(f, path))?;
+        let key = match events.next().await {
+            Some(Ok(CrosstermEvent::Key(key))) if key.kind == KeyEventKind::Press => key,
+            Some(Err(e)) => return Err(e.into()),
+            Some(Ok(_)) => continue,
+            None => return Ok(None),
+        };
+        match form.update(input::handler(key, CurrentMode::Insert, keymap)) {
+            Step::Continue => {}
+            Step::Quit => return Ok(None),
+            Step::Connect => {
+                form.status = Status::Connecting;
+                terminal.draw(|f| form.render(f, path))?;
+                match connect(path, &form.address.text, &form.api_key.text, insecure).await {
+                    Ok(config) => return Ok(Some(config)),
+                    Err(e) => {
+                        log::warn!("failed to set up the connection: {:?}", e);
+                        form.status = Status::Failed(format!("{:#}", e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Pings Syncthing at `address` with `api_key`, and saves both to the config
+/// file at `path` if that works
+async fn connect(
+    path: &Path,
+    address: &str,
+    api_key: &str,
+    insecure: bool,
+) -> eyre::Result<AppConfig> {
+    let config = AppConfig {
+        api_key: api_key.trim().to_string(),
+        address: Some(address.trim().to_string()),
+        insecure,
+        ..Default::default()
+    };
+    config.client()?.ping().await?;
+    AppConfig::create(path, config.address(), &config.api_key)?;
+    AppConfig::load(Some(path))
+}
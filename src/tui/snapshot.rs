@@ -0,0 +1,120 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::state::InnerState;
+
+/// Completion drops smaller than this many percent are not reported
+const COMPLETION_DROP_THRESHOLD: f64 = 1.0;
+
+/// The folders and devices at the end of a session, to summarize what
+/// changed until the next one
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Snapshot {
+    /// Names of the devices, keyed by their ID
+    #[serde(default)]
+    devices: BTreeMap<String, String>,
+    #[serde(default)]
+    folders: BTreeMap<String, FolderSnapshot>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct FolderSnapshot {
+    label: String,
+    completion: f64,
+}
+
+impl Snapshot {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|path| path.join("synctui").join("snapshot.toml"))
+    }
+
+    /// The snapshot of the last session, if there was one
+    pub fn load() -> Option<Self> {
+        let path = Self::path()?;
+        let content = fs::read_to_string(&path)
+            .inspect_err(|e| log::debug!("no snapshot loaded from {}: {:?}", path.display(), e))
+            .ok()?;
+        toml::from_str(&content)
+            .inspect_err(|e| log::warn!("failed to parse snapshot at {}: {:?}", path.display(), e))
+            .ok()
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            log::warn!("could not determine where to save the snapshot");
+            return;
+        };
+        let result = toml::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                fs::write(&path, content).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            log::warn!("failed to save snapshot to {}: {}", path.display(), e);
+        }
+    }
+
+    /// Takes a snapshot of the folders and devices of `state`
+    pub fn of(state: &InnerState) -> Self {
+        Self {
+            devices: state
+                .get_other_devices()
+                .iter()
+                .map(|d| (d.config.device_id.clone(), d.config.name.clone()))
+                .collect(),
+            folders: state
+                .get_folders()
+                .iter()
+                .map(|f| {
+                    (
+                        f.config.id.clone(),
+                        FolderSnapshot {
+                            label: f.config.label.clone(),
+                            completion: f.completion,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Describes what changed from `previous` to this snapshot, one line
+    /// per change
+    pub fn changes_since(&self, previous: &Snapshot) -> Vec<String> {
+        let mut changes = Vec::new();
+        for (id, name) in &self.devices {
+            if !previous.devices.contains_key(id) {
+                changes.push(format!("+ Device {} was added", name));
+            }
+        }
+        for (id, name) in &previous.devices {
+            if !self.devices.contains_key(id) {
+                changes.push(format!("- Device {} was removed", name));
+            }
+        }
+        for (id, folder) in &self.folders {
+            match previous.folders.get(id) {
+                None => changes.push(format!("+ Folder {} was added", folder.label)),
+                Some(before)
+                    if before.completion - folder.completion >= COMPLETION_DROP_THRESHOLD =>
+                {
+                    changes.push(format!(
+                        "~ Folder {} dropped from {:.0}% to {:.0}%",
+                        folder.label, before.completion, folder.completion
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        for (id, folder) in &previous.folders {
+            if !self.folders.contains_key(id) {
+                changes.push(format!("- Folder {} was removed", folder.label));
+            }
+        }
+        changes
+    }
+}
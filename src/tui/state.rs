@@ -1,7 +1,18 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
 
+use chrono::{DateTime, Utc};
 use color_eyre::eyre;
+use futures::StreamExt;
+use serde::Deserialize;
 use syncthing_rs::Client;
 use syncthing_rs::types as api;
 use syncthing_rs::types::config::DeviceConfiguration;
@@ -13,15 +24,147 @@ use syncthing_rs::types::events::EventType;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 
+use crate::AddressBook;
+use crate::AppConfig;
 use crate::AppError;
+use crate::SharedClock;
+use crate::config::Preload;
+use crate::rest::{
+    DiscoveredDevice, EventStreamItem, GuiField, RateLimits, RestClient, SystemStatus,
+};
 
-#[derive(Clone, Debug)]
+use super::disk_watch::local_path;
+use super::history::{StateHistory, StateSummary};
+use super::notes::{Note, NoteTarget, Notes};
+use super::recorder::EventSink;
+
+/// How often connections are polled to compute transfer rates, unless
+/// configured otherwise
+const CONNECTIONS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a device discovered on the local network is considered reachable.
+/// Syncthing announces every 30 seconds, so this allows for a few missed ones.
+const LAN_DISCOVERY_TTL: Duration = Duration::from_secs(2 * 60);
+
+/// Length of a rotated API key, as generated by Syncthing itself
+const API_KEY_LENGTH: usize = 32;
+
+/// How many of the most recent events are kept for [`State::subscribe_with_replay`]
+const EVENT_REPLAY_SIZE: usize = 200;
+
+/// How long events are collected before they are handled together
+const EVENT_BATCH_WINDOW: Duration = Duration::from_millis(100);
+
+/// Maximum number of events handled together
+const EVENT_BATCH_SIZE: usize = 1000;
+
+/// How many errors are kept until they are acknowledged
+const MAX_ERRORS: usize = 50;
+
+/// How long a hint set with [`State::hint`] is shown
+const HINT_DURATION: Duration = Duration::from_secs(10);
+
+/// How often paused folders are checked for [`State::auto_resume`]
+const AUTO_RESUME_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Checks to skip before resuming a folder on trial again, after a remote
+/// Syncthing still reported an error for it
+const AUTO_RESUME_BACKOFF: u32 = 10;
+
+/// Failed trials in a row after which a folder is not resumed anymore
+const AUTO_RESUME_TRIALS: u32 = 5;
+
+/// Age after which polled data is flagged as outdated. Connections are
+/// polled every few seconds, so this means Syncthing stopped answering.
+pub const STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// Window in which connection attempts of unknown devices are counted
+pub const PENDING_FLOOD_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Number of unknown devices trying to connect within
+/// [`PENDING_FLOOD_WINDOW`] from which on synctui warns. Legitimate devices
+/// rarely show up in bulk, so this hints at an instance reachable from the
+/// internet.
+const PENDING_FLOOD_THRESHOLD: usize = 5;
+
+/// Number of receivers of either state channel from which on they are
+/// considered leaked. Only a handful of long-lived tasks subscribe, so more
+/// means that receivers outlive whoever subscribed.
+const MAX_SUBSCRIBERS: usize = 16;
+
+/// Difference between the clocks of Syncthing and this machine above which
+/// the health page warns, as skewed clocks break versioning cleanup
+pub const CLOCK_SKEW_THRESHOLD: Duration = Duration::from_secs(60);
+
+/// A part of the state which is fetched separately from Syncthing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Section {
+    Configuration,
+    Connections,
+    PendingDevices,
+    PendingFolders,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Reload {
     ID,
     Configuration,
     PendingDevices,
     PendingFolders,
     Connections,
+    DeviceStats,
+    RestartRequired,
+    RawConfig,
+    ClockSkew,
+    SystemStatus,
+    /// The addresses cached by discovery
+    Discovery,
+    /// Which of the folders shared with the device it announces
+    Announcements {
+        device_id: String,
+    },
+    Completion {
+        folder_id: Option<String>,
+        device_id: Option<String>,
+    },
+}
+
+impl Reload {
+    /// The parts of the state which change once this reload completes
+    fn changes(&self) -> Vec<StateChange> {
+        match self {
+            Reload::ID | Reload::DeviceStats | Reload::Announcements { .. } => {
+                vec![StateChange::Devices]
+            }
+            Reload::Configuration => vec![StateChange::Folders, StateChange::Devices],
+            Reload::PendingDevices | Reload::PendingFolders => vec![StateChange::Pending],
+            Reload::Connections => vec![StateChange::Connections],
+            Reload::RestartRequired | Reload::ClockSkew => vec![StateChange::Status],
+            Reload::RawConfig => vec![StateChange::RawConfig],
+            Reload::SystemStatus | Reload::Discovery => vec![StateChange::System],
+            Reload::Completion {
+                folder_id,
+                device_id,
+            } => vec![StateChange::Completion {
+                folder_id: folder_id.clone(),
+                device_id: device_id.clone(),
+            }],
+        }
+    }
+}
+
+/// Describes which part of the [`State`] has changed
+#[derive(Clone, Debug, PartialEq)]
+pub enum StateChange {
+    Folders,
+    Devices,
+    Connections,
+    Pending,
+    /// Hints and whether a restart is required
+    Status,
+    RawConfig,
+    /// Status of Syncthing itself, as shown on the system screen
+    System,
     Completion {
         folder_id: Option<String>,
         device_id: Option<String>,
@@ -30,36 +173,108 @@ pub enum Reload {
 
 #[derive(Clone, Debug)]
 pub struct State {
-    client: Client,
+    /// Replaced when the API key is rotated
+    client: Arc<RwLock<Client>>,
+    rest: RestClient,
     inner: Arc<RwLock<InnerState>>,
     event_tx: broadcast::Sender<api::events::Event>,
-    config_tx: broadcast::Sender<()>,
+    change_tx: broadcast::Sender<StateChange>,
     reload_tx: mpsc::Sender<Reload>,
+    /// Where received events are recorded to, if anywhere
+    sink: Arc<Mutex<Option<Box<dyn EventSink>>>>,
 }
 
 impl State {
-    pub fn new(client: Client) -> Self {
+    /// Loads the `preload`ed data right away, anything else once
+    /// [`State::load`] is called for it
+    pub fn new(client: Client, rest: RestClient, preload: &[Preload]) -> Self {
+        Self::with_clock(client, rest, preload, SharedClock::default())
+    }
+
+    /// Like [`State::new`], but takes the time from `clock`
+    pub fn with_clock(
+        client: Client,
+        rest: RestClient,
+        preload: &[Preload],
+        clock: SharedClock,
+    ) -> Self {
+        let (api_event_tx, mut api_event_rx) = broadcast::channel(100);
         let (event_tx, event_rx) = broadcast::channel(100);
-        let (config_tx, _) = broadcast::channel(100);
+        let (change_tx, _) = broadcast::channel(100);
         let (reload_tx, reload_rx) = mpsc::channel(10);
-        let event_tx_clone = event_tx.clone();
-        let client_clone = client.clone();
 
         let state = Self {
-            client,
-            inner: Arc::new(RwLock::new(InnerState::default())),
+            client: Arc::new(RwLock::new(client)),
+            rest,
+            inner: Arc::new(RwLock::new(InnerState {
+                address_book: AddressBook::load(),
+                notes: Notes::load(),
+                preload: preload.iter().copied().collect(),
+                poll_interval: CONNECTIONS_POLL_INTERVAL,
+                clock,
+                ..Default::default()
+            })),
             event_tx,
-            config_tx,
+            change_tx,
             reload_tx,
+            sink: Arc::default(),
         };
 
         // Start listening to events
+        let events = state.rest.events(true);
+        let state_handle = state.clone();
+        tokio::spawn(async move {
+            let mut events = std::pin::pin!(events);
+            while let Some(item) = events.next().await {
+                match item {
+                    EventStreamItem::Event(event) => {
+                        if api_event_tx.send(event).is_err() {
+                            break;
+                        }
+                    }
+                    EventStreamItem::Disconnected { attempt, retry_in } => {
+                        state_handle.write(|state| {
+                            let since = state
+                                .disconnected
+                                .as_ref()
+                                .map_or_else(|| state.clock.now(), |d| d.since);
+                            state.disconnected = Some(Disconnected {
+                                since,
+                                attempt,
+                                retry_in,
+                            });
+                        });
+                        state_handle.notify(StateChange::Status);
+                    }
+                    EventStreamItem::Reconnected => {
+                        state_handle.write(|state| state.disconnected = None);
+                        state_handle.hint("Reconnected to Syncthing");
+                        // Whatever happened meanwhile is only known from a reload
+                        state_handle.reload_all();
+                    }
+                }
+            }
+        });
+
+        // Record events before passing them on, so that late subscribers can
+        // replay them without gaps or duplicates
         let state_handle = state.clone();
         tokio::spawn(async move {
-            if let Err(e) = client_clone.get_events(event_tx_clone, true).await {
-                log::error!("failed to get events: {:?}", e);
-                state_handle.set_error(e.into());
-            };
+            loop {
+                match api_event_rx.recv().await {
+                    Ok(event) => {
+                        state_handle.record_to_sink(&event);
+                        state_handle.write(|state| {
+                            state.record_event(event.clone());
+                            let _ = state_handle.event_tx.send(event);
+                        })
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
         });
 
         // Start reacting to events
@@ -72,16 +287,54 @@ impl State {
         let state_handle = state.clone();
         tokio::spawn(async move { Self::listen_to_reload(reload_rx, state_handle).await });
 
-        // Start reloading everything ones.
-        // These blocks all start a thread, so are non-blocking.
-        state.reload(Reload::ID);
-        state.reload(Reload::Configuration);
-        state.reload(Reload::PendingDevices);
-        state.reload(Reload::PendingFolders);
+        // Poll connections, as there are no events for transfer rates
+        let state_handle = state.clone();
+        tokio::spawn(async move {
+            loop {
+                state_handle.count_subscribers();
+                state_handle.reload(Reload::Connections);
+                if state_handle.read(|state| state.poll_system) {
+                    state_handle.reload(Reload::SystemStatus);
+                    state_handle.reload(Reload::Discovery);
+                }
+                // Follow the progress of syncing folders, which has no events
+                let syncing: Vec<String> = state_handle.read(|state| {
+                    state
+                        .folders
+                        .iter()
+                        .filter(|f| f.completion < 100.0 && !f.config.paused)
+                        .map(|f| f.config.id.clone())
+                        .collect()
+                });
+                for folder_id in syncing {
+                    state_handle.reload(Reload::Completion {
+                        folder_id: Some(folder_id),
+                        device_id: None,
+                    });
+                }
+                tokio::time::sleep(state_handle.read(|state| state.poll_interval)).await;
+            }
+        });
+
+        state.reload_all();
 
         state
     }
 
+    /// Reloads everything, as when starting. Reloading the configuration
+    /// reloads everything which depends on it.
+    fn reload_all(&self) {
+        // These blocks all start a thread, so are non-blocking.
+        self.reload(Reload::ID);
+        self.reload(Reload::Configuration);
+        self.reload(Reload::PendingDevices);
+        self.reload(Reload::PendingFolders);
+    }
+
+    fn client(&self) -> Client {
+        self.client.read().unwrap().clone()
+    }
+
     pub fn read<F, R>(&self, f: F) -> R
     where
         F: FnOnce(&InnerState) -> R,
@@ -114,18 +367,268 @@ impl State {
         });
     }
 
-    pub fn set_error(&self, _error: AppError) {}
+    /// Keeps the most recent versions of the state from now on, to step
+    /// through them
+    pub fn keep_history(&self) {
+        self.write(|state| state.history = Some(StateHistory::default()));
+    }
+
+    /// Loads `data`, unless it has been preloaded or loaded before. From then
+    /// on, it is reloaded along with the configuration.
+    pub fn load(&self, data: Preload) {
+        let first = self.write(|state| !state.preload.contains(&data) && state.loaded.insert(data));
+        if !first {
+            return;
+        }
+        match data {
+            Preload::DeviceStats => self.reload(Reload::DeviceStats),
+            Preload::ClockSkew => self.reload(Reload::ClockSkew),
+            Preload::Completion => {
+                let folder_ids: Vec<String> =
+                    self.read(|state| state.folders.iter().map(|f| f.config.id.clone()).collect());
+                for folder_id in folder_ids {
+                    self.reload(Reload::Completion {
+                        folder_id: Some(folder_id),
+                        device_id: None,
+                    });
+                }
+            }
+            Preload::RemoteCompletion => {
+                let folders: Vec<FolderConfiguration> =
+                    self.read(|state| state.folders.iter().map(|f| f.config.clone()).collect());
+                for folder in &folders {
+                    self.reload_remote_completions(folder);
+                }
+            }
+        }
+    }
+
+    /// Loads how far the other devices are with the folder, unless that has
+    /// been preloaded or loaded before
+    pub fn load_remote_completions(&self, folder_id: &str) {
+        let first = self.write(|state| {
+            !state.loads(Preload::RemoteCompletion)
+                && state
+                    .remote_completions_loaded
+                    .insert(folder_id.to_string())
+        });
+        if !first {
+            return;
+        }
+        if let Some(folder) =
+            self.read(|state| state.get_folder(folder_id).ok().map(|f| f.config.clone()))
+        {
+            self.reload_remote_completions(&folder);
+        }
+    }
+
+    /// Reloads how far each other device sharing `folder` is with it
+    fn reload_remote_completions(&self, folder: &FolderConfiguration) {
+        let id = self.read(|state| state.id.clone());
+        for device in folder.devices.iter().filter(|d| d.device_id != id) {
+            self.reload(Reload::Completion {
+                folder_id: Some(folder.id.clone()),
+                device_id: Some(device.device_id.clone()),
+            });
+        }
+    }
+
+    /// Waits `interval` between polls of the connections from now on
+    pub fn set_poll_interval(&self, interval: Duration) {
+        self.write(|state| state.poll_interval = interval);
+    }
+
+    /// Polls the status of Syncthing along with the connections while
+    /// `poll` is set, e.g., while it is shown. Starting loads it right away.
+    pub fn poll_system(&self, poll: bool) {
+        if !self.write(|state| std::mem::replace(&mut state.poll_system, poll)) && poll {
+            self.reload(Reload::SystemStatus);
+            self.reload(Reload::Discovery);
+        }
+    }
+
+    /// Queues `error` to be shown until the user acknowledges it
+    pub fn set_error(&self, error: AppError) {
+        self.write(|state| {
+            if state.errors.len() == MAX_ERRORS {
+                state.errors.pop_front();
+            }
+            let time = state.clock.utc_now();
+            state.errors.push_back(ErrorEntry { error, time });
+        });
+        self.notify(StateChange::Status);
+    }
+
+    /// Records all events received from now on to `sink`, or stops recording
+    /// them if there is none
+    pub fn set_event_sink(&self, sink: Option<Box<dyn EventSink>>) {
+        match &sink {
+            Some(sink) => log::info!("recording events to {}", sink.describe()),
+            None => log::info!("stopped recording events"),
+        }
+        *self.sink.lock().unwrap() = sink;
+        self.notify(StateChange::Status);
+    }
+
+    /// Where events are recorded to, if they are
+    pub fn recording_to(&self) -> Option<String> {
+        self.sink
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|sink| sink.describe())
+    }
+
+    /// Passes `event` on to the sink. A sink which fails is dropped, instead
+    /// of failing for every following event as well.
+    fn record_to_sink(&self, event: &api::events::Event) {
+        let result = match self.sink.lock().unwrap().as_mut() {
+            Some(sink) => sink.record(event),
+            None => return,
+        };
+        if let Err(e) = result {
+            log::error!("failed to record event: {:?}", e);
+            *self.sink.lock().unwrap() = None;
+            self.hint("Stopped recording events");
+            self.set_error(e);
+        }
+    }
+
+    /// Shows `hint` in the status line for a few seconds
+    pub fn hint(&self, hint: impl Into<String>) {
+        let hint = hint.into();
+        log::info!("hint: {}", hint);
+        self.write(|state| state.hint = Some((hint, state.clock.now())));
+        self.notify(StateChange::Status);
+    }
+
+    /// Replaces the note of `target` and saves all notes
+    pub fn set_note(&self, target: NoteTarget, note: Note) {
+        let change = match target {
+            NoteTarget::Folder(_) => StateChange::Folders,
+            NoteTarget::Device(_) => StateChange::Devices,
+        };
+        self.write(|state| {
+            state.notes.set(target, note);
+            state.notes.save();
+        });
+        self.notify(change);
+    }
+
+    /// Explains why an update of `folder_id` failed with `error`, in the
+    /// status line
+    fn folder_failed(&self, folder_id: &str, error: AppError) {
+        let paused = self.read(|state| {
+            state
+                .get_folder(folder_id)
+                .ok()
+                .filter(|f| f.config.paused)
+                .map(|f| f.config.label.clone())
+        });
+        match paused {
+            Some(label) => self.hint(format!("{} is paused, resume it and try again", label)),
+            None => self.hint(format!("Failed to update folder {}", folder_id)),
+        }
+        self.set_error(error);
+    }
+
+    /// Restarts Syncthing, e.g., to apply configuration changes
+    pub fn restart(&self) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            match state.rest.restart().await {
+                Ok(()) => {
+                    state.write(|state| state.requires_restart = false);
+                    state.hint("Restarting Syncthing…");
+                }
+                Err(e) => {
+                    log::error!("failed to restart: {:?}", e);
+                    state.hint("Failed to restart Syncthing");
+                    state.set_error(e);
+                }
+            }
+        });
+    }
+
+    /// Replaces Syncthing's API key with a random one, and continues to
+    /// use it from now on. `config` is the configuration the current clients
+    /// were built from. Returns the new key.
+    pub async fn rotate_api_key(&self, config: &AppConfig) -> Result<String, AppError> {
+        let api_key = self.rest.random_string(API_KEY_LENGTH).await?;
+        let client = AppConfig {
+            api_key: api_key.clone(),
+            ..config.clone()
+        }
+        .client()?;
+        self.rest.set_gui(GuiField::ApiKey, &api_key).await?;
+        log::info!("rotated the API key");
+        self.rest.set_api_key(api_key.clone());
+        *self.client.write().unwrap() = client;
+        Ok(api_key)
+    }
 
-    pub fn clear_error(&self) {}
+    /// Acknowledges all errors
+    pub fn clear_errors(&self) {
+        self.write(|state| state.errors.clear());
+        self.notify(StateChange::Status);
+    }
 
     /// Emits an [`Event`](api::events::Event) if a new one arrives
     pub fn subscribe_to_events(&self) -> broadcast::Receiver<api::events::Event> {
         self.event_tx.subscribe()
     }
 
-    /// Emits `()` if the config (everything except events) changes
-    pub fn subscribe_to_config(&self) -> broadcast::Receiver<()> {
-        self.config_tx.subscribe()
+    /// Like [`subscribe_to_events`](Self::subscribe_to_events), but also
+    /// returns up to `n` of the most recent events, oldest first. No event is
+    /// both returned and emitted, and none is missed in between.
+    pub fn subscribe_with_replay(
+        &self,
+        n: usize,
+    ) -> (
+        Vec<api::events::Event>,
+        broadcast::Receiver<api::events::Event>,
+    ) {
+        // Events are recorded and sent under the write lock, so holding the
+        // read lock keeps both in sync
+        self.read(|state| {
+            let skip = state.events.len().saturating_sub(n);
+            (
+                state.events.iter().skip(skip).cloned().collect(),
+                self.event_tx.subscribe(),
+            )
+        })
+    }
+
+    /// Emits a [`StateChange`] if the state (everything except events) changes
+    pub fn subscribe_to_changes(&self) -> broadcast::Receiver<StateChange> {
+        self.change_tx.subscribe()
+    }
+
+    /// Informs all subscribers about `change`. Having none is fine, e.g.,
+    /// before the UI subscribed during startup.
+    fn notify(&self, change: StateChange) {
+        if let Err(e) = self.change_tx.send(change) {
+            log::debug!("no subscribers for state change {:?}", e.0);
+        }
+    }
+
+    /// Records how many receivers both channels have, and warns once the
+    /// number exceeds [`MAX_SUBSCRIBERS`]
+    fn count_subscribers(&self) {
+        let counts = SubscriberCounts {
+            changes: self.change_tx.receiver_count(),
+            events: self.event_tx.receiver_count(),
+        };
+        let previous = self.write(|state| std::mem::replace(&mut state.subscribers, counts));
+        log::trace!("subscribers: {:?}", counts);
+        if counts.is_leaking() && !previous.is_leaking() {
+            log::warn!("state subscribers are piling up: {:?}", counts);
+        } else if previous.is_leaking() && !counts.is_leaking() {
+            log::info!("state subscribers are back to normal: {:?}", counts);
+        }
+        if counts != previous {
+            self.notify(StateChange::Status);
+        }
     }
 
     /// Starts listening to reload commands, and will start reloading parts
@@ -133,18 +636,43 @@ impl State {
     // TODO maybe reload in separate threads, so reloads can be handled faster
     async fn listen_to_reload(mut reload_rx: mpsc::Receiver<Reload>, state: State) {
         while let Some(reload) = reload_rx.recv().await {
+            let changes = reload.changes();
+            let before = state.read(|state| state.tracked_summary());
+            let cause = format!("Reload {:?}", reload);
             match reload {
                 Reload::Configuration => {
-                    let config = state.client.get_configuration().await;
+                    let config = state.client().get_configuration().await;
                     match config {
                         Ok(conf) => {
-                            state.write(|state| state.update_from_configuration(conf.clone()));
+                            state.write(|state| {
+                                state.update_from_configuration(conf.clone());
+                                state.set_loaded(Section::Configuration);
+                            });
                             state.reload(Reload::Connections);
+                            state.reload(Reload::RestartRequired);
+                            state.reload(Reload::RawConfig);
+                            if state.read(|state| state.loads(Preload::DeviceStats)) {
+                                state.reload(Reload::DeviceStats);
+                            }
+                            if state.read(|state| state.loads(Preload::ClockSkew)) {
+                                state.reload(Reload::ClockSkew);
+                            }
                             for f in conf.folders {
-                                state.reload(Reload::Completion {
-                                    folder_id: Some(f.id),
-                                    device_id: None,
+                                let (remote, local) = state.read(|state| {
+                                    (
+                                        state.loads_remote_completions(&f.id),
+                                        state.loads(Preload::Completion),
+                                    )
                                 });
+                                if remote {
+                                    state.reload_remote_completions(&f);
+                                }
+                                if local {
+                                    state.reload(Reload::Completion {
+                                        folder_id: Some(f.id),
+                                        device_id: None,
+                                    });
+                                }
                             }
                         }
                         Err(e) => {
@@ -154,7 +682,7 @@ impl State {
                     }
                 }
                 Reload::ID => {
-                    let id = state.client.get_id().await;
+                    let id = state.client().get_id().await;
                     match id {
                         Ok(id) => {
                             state.write(|state| state.id = id);
@@ -166,31 +694,52 @@ impl State {
                     }
                 }
                 Reload::PendingDevices => {
-                    let devices = state.client.get_pending_devices().await;
+                    let devices = state.client().get_pending_devices().await;
                     match devices {
-                        Ok(devices) => state.write(|state| state.set_pending_devices(devices)),
+                        Ok(devices) => state.write(|state| {
+                            state.set_pending_devices(devices);
+                            state.set_loaded(Section::PendingDevices);
+                        }),
                         Err(e) => log::warn!("failed to reload pending devices: {:?}", e),
                     }
                 }
                 Reload::PendingFolders => {
-                    let folders = state.client.get_pending_folders().await;
+                    let folders = state.client().get_pending_folders().await;
                     match folders {
-                        Ok(folders) => state.write(|state| state.set_pending_folders(folders)),
+                        Ok(folders) => state.write(|state| {
+                            state.set_pending_folders(folders);
+                            state.set_loaded(Section::PendingFolders);
+                        }),
                         Err(e) => log::warn!("failed to reload pending folders: {:?}", e),
                     }
                 }
                 Reload::Connections => {
-                    let connections = state.client.get_connections().await;
+                    let connections = state.client().get_connections().await;
                     match connections {
                         Ok(connections) => state.write(|inner_state| {
+                            inner_state.set_loaded(Section::Connections);
+                            let now = inner_state.clock.now();
+                            inner_state.total_traffic.update(
+                                connections.total.in_bytes_total,
+                                connections.total.out_bytes_total,
+                                now,
+                            );
                             for (device_id, connection) in connections.connections {
                                 if let Ok(device) = inner_state.get_device_mut(&device_id) {
+                                    device.traffic.update(
+                                        connection.in_bytes_total,
+                                        connection.out_bytes_total,
+                                        now,
+                                    );
                                     if connection.connected {
-                                        device.connected = DeviceStatus::UpToDate;
-                                        state.reload(Reload::Completion {
-                                            folder_id: None,
-                                            device_id: Some(device_id),
-                                        });
+                                        // Only newly connected devices need to fetch their completion
+                                        if device.connected == DeviceStatus::Disconnected {
+                                            device.connected = DeviceStatus::UpToDate;
+                                            state.reload(Reload::Completion {
+                                                folder_id: None,
+                                                device_id: Some(device_id),
+                                            });
+                                        }
                                     } else {
                                         device.connected = DeviceStatus::Disconnected;
                                     }
@@ -200,19 +749,107 @@ impl State {
                         Err(e) => log::warn!("failed to reload connections: {:?}", e),
                     }
                 }
+                Reload::DeviceStats => {
+                    let stats = state.client().get_device_stats().await;
+                    match stats {
+                        Ok(stats) => state.write(|state| {
+                            for (device_id, stats) in stats {
+                                if let Ok(device) = state.get_device_mut(&device_id) {
+                                    device.last_seen = Some(stats.last_seen);
+                                }
+                            }
+                        }),
+                        Err(e) => log::warn!("failed to reload device statistics: {:?}", e),
+                    }
+                }
+                Reload::RestartRequired => match state.rest.requires_restart().await {
+                    Ok(requires_restart) => {
+                        state.write(|state| state.requires_restart = requires_restart)
+                    }
+                    Err(e) => log::warn!("failed to check if a restart is required: {:?}", e),
+                },
+                Reload::RawConfig => match state.rest.get_config().await {
+                    Ok(config) => state.write(|state| {
+                        state.update_from_raw_configuration(&config);
+                        state.raw_config = Some(config);
+                    }),
+                    Err(e) => log::warn!("failed to load the raw configuration: {:?}", e),
+                },
+                Reload::ClockSkew => {
+                    let clock = state.read(|state| state.clock.clone());
+                    let sent = clock.utc_now();
+                    match state.rest.server_time().await {
+                        Ok(server_time) => {
+                            // Assume the server answered halfway through the request
+                            let local_time = sent + (clock.utc_now() - sent) / 2;
+                            state.write(|state| state.clock_skew = Some(server_time - local_time))
+                        }
+                        Err(e) => log::warn!("failed to get the time of syncthing: {:?}", e),
+                    }
+                }
+                Reload::SystemStatus => match state.rest.system_status().await {
+                    Ok(status) => state.write(|state| state.system_status = Some(status)),
+                    Err(e) => log::warn!("failed to load the system status: {:?}", e),
+                },
+                Reload::Discovery => match state.rest.discovery().await {
+                    Ok(discovery) => state.write(|state| state.discovery_cache = discovery),
+                    Err(e) => log::warn!("failed to load the discovery cache: {:?}", e),
+                },
+                Reload::Announcements { device_id } => {
+                    let shared: Vec<String> = state.read(|state| {
+                        state
+                            .get_folders()
+                            .iter()
+                            .filter(|f| f.config.devices.iter().any(|d| d.device_id == device_id))
+                            .map(|f| f.config.id.clone())
+                            .collect()
+                    });
+                    let mut unannounced = Vec::new();
+                    for folder_id in shared {
+                        match state
+                            .client()
+                            .get_completion(Some(&folder_id), Some(&device_id))
+                            .await
+                        {
+                            Ok(completion) if completion.remote_state == "notSharing" => {
+                                unannounced.push(folder_id)
+                            }
+                            Ok(_) => {}
+                            Err(e) => log::warn!(
+                                "failed to get the remote state of {} on {}: {:?}",
+                                folder_id,
+                                device_id,
+                                e
+                            ),
+                        }
+                    }
+                    state.write(|state| {
+                        if let Ok(device) = state.get_device_mut(&device_id) {
+                            device.unannounced_folders = unannounced;
+                        }
+                    });
+                }
                 Reload::Completion {
                     folder_id,
                     device_id,
                 } => {
                     let completion = state
-                        .client
+                        .client()
                         .get_completion(folder_id.as_deref(), device_id.as_deref())
                         .await;
                     match completion {
                         Ok(completion) => {
                             if let Some(device_id) = device_id {
-                                if let Some(_folder_id) = folder_id {
-                                    todo!("update folder completion for device");
+                                if let Some(folder_id) = folder_id {
+                                    state.write(|state| {
+                                        state.remote_completions.insert(
+                                            (device_id, folder_id),
+                                            RemoteCompletion {
+                                                completion: completion.completion,
+                                                need_bytes: completion.need_bytes,
+                                            },
+                                        )
+                                    });
                                 } else {
                                     state.write(|state| {
                                         if let Ok(device) = state.get_device_mut(&device_id) {
@@ -229,90 +866,116 @@ impl State {
                             // Set local completion of folder
                             else if let Some(folder_id) = folder_id {
                                 state.write(|state| {
-                                    if let Ok(folder) = state.get_folder_mut(&folder_id) {
-                                        folder.completion = completion.completion;
-                                    }
+                                    state.set_folder_completion(&folder_id, &completion)
                                 });
                             }
                         }
+                        // Syncthing does not report the completion of paused folders
+                        Err(_)
+                            if folder_id.as_ref().is_some_and(|id| {
+                                state.read(|state| {
+                                    state.get_folder(id).is_ok_and(|f| f.config.paused)
+                                })
+                            }) => {}
                         Err(e) => log::warn!("failed to reload completion: {:?}", e),
                     }
                 }
             }
-            // For every case, if we reach this point, the config has changed
-            if let Err(e) = state.config_tx.send(()) {
-                log::warn!(
-                    "could not initiate a config update after a reload has been completed: {:?}",
-                    e
-                );
+            if let Some(before) = before {
+                state.write(|state| state.record_version(cause, Vec::new(), &before));
+            }
+            // For every case, if we reach this point, the state has changed
+            for change in changes {
+                state.notify(change);
             }
         }
     }
 
     /// Some events motivate a reload of the configuration. That is done here
-    /// in the background.
+    /// in the background. Events are handled in batches, so that event storms,
+    /// e.g., during the initial scan of a large folder, result in a single
+    /// write and one notification per change.
     async fn handle_event(mut event_rx: broadcast::Receiver<api::events::Event>, state: State) {
-        while let Ok(event) = event_rx.recv().await {
-            log::debug!("state is handling event {:?}", event);
-            match event.ty {
-                EventType::ConfigSaved { .. } => {
-                    if let Err(e) = state.reload_tx.send(Reload::Configuration).await {
-                        log::error!(
-                            "failed to initiate configuration reload due to new saved config: {:?}",
-                            e
-                        );
-                        state.set_error(e.into());
-                    }
-                }
-                EventType::DeviceConnected { id, .. } => {
-                    state.write(|state| {
-                        log::debug!("Device {id} connected");
-                        if let Ok(device) = state.get_device_mut(&id) {
-                            device.connected = DeviceStatus::UpToDate;
-                        }
-                    });
-                    // Not that important of an event
-                    let _ = state.config_tx.send(());
-                }
-                EventType::DeviceDisconnected { id, .. } => {
-                    state.write(|state| {
-                        if let Ok(device) = state.get_device_mut(&id) {
-                            device.connected = DeviceStatus::Disconnected;
-                        }
-                    });
-                    // Not that important of an event
-                    let _ = state.config_tx.send(());
-                }
-                EventType::PendingDevicesChanged { .. } => {
-                    if let Err(e) = state.reload_tx.send(Reload::PendingDevices).await {
-                        log::error!("failed to initiate pending devices reload: {:?}", e);
-                        state.set_error(e.into());
+        while let Some(batch) = Self::next_event_batch(&mut event_rx).await {
+            log::debug!("state is handling {} events", batch.len());
+            let mut reloads = Vec::new();
+            let mut changes = Vec::new();
+
+            state.write(|state| {
+                let before = state.tracked_summary();
+                let mut causes = Vec::new();
+                for event in batch {
+                    log::debug!("state is handling event {:?}", event);
+                    if before.is_some() {
+                        causes.push(format!("{} {}", event.id, event_name(&event)));
                     }
-                }
-                EventType::PendingFoldersChanged { .. } => {
-                    if let Err(e) = state.reload_tx.send(Reload::PendingFolders).await {
-                        log::error!("failed to initiate pending devices reload: {:?}", e);
-                        state.set_error(e.into());
+                    let (reload, change) = state.apply_event(event);
+                    if let Some(reload) = reload
+                        && !reloads.contains(&reload)
+                    {
+                        reloads.push(reload);
                     }
-                }
-                EventType::RemoteDownloadProgress { ref device, .. } => {
-                    if let Err(e) = state
-                        .reload_tx
-                        .send(Reload::Completion {
-                            device_id: Some(device.to_string()),
-                            folder_id: None,
-                        })
-                        .await
+                    if let Some(change) = change
+                        && !changes.contains(&change)
                     {
-                        log::error!(
-                            "failed to initiate completion status based on remote download progress: {:?}",
-                            e
-                        );
+                        changes.push(change);
                     }
                 }
-                _ => {}
+                if let Some(before) = before {
+                    let triggered = reloads.iter().map(|r| format!("{:?}", r)).collect();
+                    state.record_version(
+                        format!("Events {}", causes.join(", ")),
+                        triggered,
+                        &before,
+                    );
+                }
+            });
+
+            for change in changes {
+                state.notify(change);
+            }
+            for reload in reloads {
+                if let Err(e) = state.reload_tx.send(reload.clone()).await {
+                    log::error!(
+                        "failed to initiate {:?} reload due to event: {:?}",
+                        reload,
+                        e
+                    );
+                    state.set_error(e.into());
+                }
+            }
+        }
+    }
+
+    /// Waits for the next event, and collects all events arriving within
+    /// [`EVENT_BATCH_WINDOW`] after it. Returns `None` once no more events
+    /// can arrive.
+    async fn next_event_batch(
+        event_rx: &mut broadcast::Receiver<api::events::Event>,
+    ) -> Option<Vec<api::events::Event>> {
+        let first = loop {
+            match event_rx.recv().await {
+                Ok(event) => break event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("state skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        };
+
+        let mut batch = vec![first];
+        let deadline = tokio::time::Instant::now() + EVENT_BATCH_WINDOW;
+        while batch.len() < EVENT_BATCH_SIZE {
+            match tokio::time::timeout_at(deadline, event_rx.recv()).await {
+                Ok(Ok(event)) => batch.push(event),
+                Ok(Err(broadcast::error::RecvError::Lagged(skipped))) => {
+                    log::warn!("state skipped {} events", skipped);
+                }
+                // Handle what we have, the next call notices a closed channel
+                Ok(Err(broadcast::error::RecvError::Closed)) | Err(_) => break,
             }
         }
+        Some(batch)
     }
 
     /// Accept device `device_id` in the background. This function is
@@ -320,17 +983,7 @@ impl State {
     /// been applied.
     pub fn accept_device(&self, device_id: &str) {
         match self.read(|state| state.get_pending_device(device_id).cloned()) {
-            Ok(device) => {
-                let state = self.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = state.client.add_device(device).await {
-                        log::error!("failed to add device to api: {:?}", e);
-                        state.set_error(e.into());
-                    } else {
-                        state.reload(Reload::Configuration);
-                    }
-                });
-            }
+            Ok(device) => self.add_device(device),
             Err(e) => {
                 log::error!("failed to accept device: {:?}", e);
                 self.set_error(e);
@@ -338,11 +991,24 @@ impl State {
         }
     }
 
+    /// Add a new device, which does not have to be pending
+    pub fn add_device(&self, device: NewDeviceConfiguration) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = state.client().add_device(device).await {
+                log::error!("failed to add device to api: {:?}", e);
+                state.set_error(e.into());
+            } else {
+                state.reload(Reload::Configuration);
+            }
+        });
+    }
+
     /// Add a new folder
     pub fn add_foler(&self, folder: NewFolderConfiguration) {
         let state = self.clone();
         tokio::spawn(async move {
-            if let Err(e) = state.client.add_folder(folder).await {
+            if let Err(e) = state.client().add_folder(folder).await {
                 log::error!("failed to add folder to api: {:?}", e);
                 state.set_error(e.into());
             } else {
@@ -353,13 +1019,28 @@ impl State {
     }
 
     pub fn share_folder(&self, folder_id: &str, device_id: &str) {
+        self.share_folder_with_all(folder_id, &[device_id.to_string()]);
+    }
+
+    /// Shares `folder_id` with every device of `device_ids`, in a single
+    /// update of the folder
+    pub fn share_folder_with_all(&self, folder_id: &str, device_ids: &[String]) {
         if let Some(folder) = self.write(|state| match state.get_folder_mut(folder_id) {
             Ok(folder) => {
-                folder.config.devices.push(FolderDeviceConfiguration {
-                    device_id: device_id.to_string(),
-                    introduced_by: String::new(),
-                    encryption_password: String::new(),
-                });
+                for device_id in device_ids {
+                    if !folder
+                        .config
+                        .devices
+                        .iter()
+                        .any(|d| &d.device_id == device_id)
+                    {
+                        folder.config.devices.push(FolderDeviceConfiguration {
+                            device_id: device_id.to_string(),
+                            introduced_by: String::new(),
+                            encryption_password: String::new(),
+                        });
+                    }
+                }
                 Some(folder.config.clone())
             }
             Err(e) => {
@@ -370,9 +1051,10 @@ impl State {
         }) {
             let state = self.clone();
             tokio::spawn(async move {
-                if let Err(e) = state.client.post_folder(folder).await {
+                let folder_id = folder.id.clone();
+                if let Err(e) = state.client().post_folder(folder).await {
                     log::error!("failed to share folder on api: {:?}", e);
-                    state.set_error(e.into());
+                    state.folder_failed(&folder_id, e.into());
                 }
             });
         }
@@ -381,73 +1063,540 @@ impl State {
     pub fn edit_folder(&self, folder: FolderConfiguration) {
         let state = self.clone();
         tokio::spawn(async move {
-            if let Err(e) = state.client.post_folder(folder).await {
+            let folder_id = folder.id.clone();
+            if let Err(e) = state.client().post_folder(folder).await {
                 log::error!("failed to update folder on api: {:?}", e);
-                state.set_error(e.into());
+                state.folder_failed(&folder_id, e.into());
             }
         });
     }
 
-    pub fn dismiss_folder(&self, folder_id: impl Into<String>, device_id: impl Into<String>) {
+    /// Ignores all future offers of `device_id` to share `folder_id`, and
+    /// drops its current offer
+    pub fn ignore_folder(&self, folder_id: impl Into<String>, device_id: impl Into<String>) {
         let state = self.clone();
         let folder_id = folder_id.into();
         let device_id = device_id.into();
+        let label = self.read(|state| {
+            state
+                .pending_folders
+                .iter()
+                .find(|(d, f)| d == &device_id && f.get_id() == &folder_id)
+                .and_then(|(_, f)| f.get_label().clone())
+                .unwrap_or_default()
+        });
         tokio::spawn(async move {
             if let Err(e) = state
-                .client
+                .rest
+                .ignore_folders(&device_id, &[(folder_id.clone(), label)])
+                .await
+            {
+                log::error!("failed to ignore folder on api: {:?}", e);
+                state.set_error(e);
+                return;
+            }
+            // Syncthing drops the offer once the config is saved, this only
+            // makes sure of it
+            if let Err(e) = state
+                .client()
                 .dismiss_pending_folder(&folder_id, Some(&device_id))
                 .await
             {
-                log::error!("failed to dismiss folder to api: {:?}", e);
-                state.set_error(e.into());
+                log::warn!("failed to dismiss ignored folder on api: {:?}", e);
             }
-            // We don't need to update the config, the event should handle that
+            state.write(|state| {
+                state
+                    .pending_folders
+                    .retain(|(d, f)| d != &device_id || f.get_id() != &folder_id);
+                state.ignored_folders.push((device_id, folder_id));
+            });
+            state.notify(StateChange::Pending);
         });
     }
 
-    pub fn remove_folder(&self, folder_id: impl Into<String>) {
+    pub fn dismiss_folder(&self, folder_id: impl Into<String>, device_id: impl Into<String>) {
         let state = self.clone();
         let folder_id = folder_id.into();
-
+        let device_id = device_id.into();
         tokio::spawn(async move {
-            if let Err(e) = state.client.delete_folder(&folder_id).await {
-                log::error!("failed to delete folder from api: {:?}", e);
+            if let Err(e) = state
+                .client()
+                .dismiss_pending_folder(&folder_id, Some(&device_id))
+                .await
+            {
+                log::error!("failed to dismiss folder to api: {:?}", e);
                 state.set_error(e.into());
             }
+            // We don't need to update the config, the event should handle that
         });
     }
 
-    pub fn dismiss_device(&self, device_id: impl Into<String>) {
+    /// The IDs and labels of all folders `device_id` offers
+    fn offers_of(&self, device_id: &str) -> Vec<(String, String)> {
+        self.read(|state| {
+            state
+                .pending_folders
+                .iter()
+                .filter(|(d, _)| d == device_id)
+                .map(|(_, f)| {
+                    (
+                        f.get_id().clone(),
+                        f.get_label().clone().unwrap_or_default(),
+                    )
+                })
+                .collect()
+        })
+    }
+
+    /// Ignores all folders `device_id` offers, with a single change of its
+    /// configuration
+    pub fn ignore_folders_of(&self, device_id: impl Into<String>) {
         let state = self.clone();
         let device_id = device_id.into();
+        let offers = self.offers_of(&device_id);
+        if offers.is_empty() {
+            return;
+        }
         tokio::spawn(async move {
-            if let Err(e) = state.client.dismiss_pending_device(&device_id).await {
-                log::error!("failed to dismiss device to api: {:?}", e);
-                state.set_error(e.into());
+            if let Err(e) = state.rest.ignore_folders(&device_id, &offers).await {
+                log::error!("failed to ignore folders on api: {:?}", e);
+                state.set_error(e);
+                return;
             }
-            // We don't need to update the config, the event should handle that
+            for (folder_id, _) in &offers {
+                if let Err(e) = state
+                    .client()
+                    .dismiss_pending_folder(folder_id, Some(&device_id))
+                    .await
+                {
+                    log::warn!("failed to dismiss ignored folder on api: {:?}", e);
+                }
+            }
+            state.write(|state| {
+                state.pending_folders.retain(|(d, _)| d != &device_id);
+                state.ignored_folders.extend(
+                    offers
+                        .iter()
+                        .map(|(folder_id, _)| (device_id.clone(), folder_id.clone())),
+                );
+            });
+            state.notify(StateChange::Pending);
+            state.hint(format!("Ignored {} folders of {}", offers.len(), device_id));
         });
     }
 
-    pub fn edit_device(&self, device: DeviceConfiguration) {
+    /// Dismisses all folders `device_id` offers, which it offers again once
+    /// it reconnects
+    pub fn dismiss_folders_of(&self, device_id: impl Into<String>) {
         let state = self.clone();
+        let device_id = device_id.into();
+        let offers = self.offers_of(&device_id);
         tokio::spawn(async move {
-            if let Err(e) = state.client.post_device(device).await {
-                log::error!("failed to update device on api: {:?}", e);
-                state.set_error(e.into());
+            for (folder_id, _) in &offers {
+                if let Err(e) = state
+                    .client()
+                    .dismiss_pending_folder(folder_id, Some(&device_id))
+                    .await
+                {
+                    log::error!("failed to dismiss folder to api: {:?}", e);
+                    state.set_error(e.into());
+                    return;
+                }
             }
+            // We don't need to update the config, the event should handle that
+            state.hint(format!(
+                "Dismissed {} folders of {}",
+                offers.len(),
+                device_id
+            ));
         });
     }
 
+    /// Scans the folder right away, e.g., to retry items which failed
+    pub fn rescan_folder(&self, folder_id: impl Into<String>) {
+        let state = self.clone();
+        let folder_id = folder_id.into();
+        let label = self.read(|state| {
+            state
+                .get_folder(&folder_id)
+                .map_or(folder_id.clone(), |f| f.config.label.clone())
+        });
+        tokio::spawn(async move {
+            match state.rest.rescan(&folder_id).await {
+                Ok(()) => state.hint(format!("Rescanning {}", label)),
+                Err(e) => {
+                    log::error!("failed to rescan folder: {:?}", e);
+                    state.set_error(e);
+                }
+            }
+        });
+    }
+
+    pub fn remove_folder(&self, folder_id: impl Into<String>) {
+        let state = self.clone();
+        let folder_id = folder_id.into();
+
+        tokio::spawn(async move {
+            if let Err(e) = state.client().delete_folder(&folder_id).await {
+                log::error!("failed to delete folder from api: {:?}", e);
+                state.set_error(e.into());
+            }
+        });
+    }
+
+    /// Ignores all future connection attempts of the pending device
+    /// `device_id`, and drops its request
+    pub fn ignore_device(&self, device_id: impl Into<String>) {
+        let state = self.clone();
+        let device_id = device_id.into();
+        let name = self.read(|state| {
+            state
+                .get_pending_device(&device_id)
+                .ok()
+                .and_then(|device| device.get_name().clone())
+                .unwrap_or_default()
+        });
+        tokio::spawn(async move {
+            if let Err(e) = state.rest.ignore_device(&device_id, &name).await {
+                log::error!("failed to ignore device on api: {:?}", e);
+                state.set_error(e);
+                return;
+            }
+            // Syncthing drops the request once the config is saved, this
+            // only makes sure of it
+            if let Err(e) = state.client().dismiss_pending_device(&device_id).await {
+                log::warn!("failed to dismiss ignored device on api: {:?}", e);
+            }
+            state.write(|state| {
+                state
+                    .pending_devices
+                    .retain(|d| d.get_device_id() != &device_id);
+                state.ignored_devices.push(device_id);
+            });
+            state.notify(StateChange::Pending);
+        });
+    }
+
+    pub fn dismiss_device(&self, device_id: impl Into<String>) {
+        let state = self.clone();
+        let device_id = device_id.into();
+        tokio::spawn(async move {
+            if let Err(e) = state.client().dismiss_pending_device(&device_id).await {
+                log::error!("failed to dismiss device to api: {:?}", e);
+                state.set_error(e.into());
+            }
+            // We don't need to update the config, the event should handle that
+        });
+    }
+
+    /// Saves the device, and its bandwidth limits if they changed
+    pub fn edit_device(&self, device: DeviceConfiguration, rate_limits: RateLimits) {
+        let state = self.clone();
+        let limits_changed = self.read(|state| {
+            state
+                .get_device(&device.device_id)
+                .is_ok_and(|d| d.rate_limits != rate_limits)
+        });
+        tokio::spawn(async move {
+            let device_id = device.device_id.clone();
+            if let Err(e) = state.client().post_device(device).await {
+                log::error!("failed to update device on api: {:?}", e);
+                state.set_error(e.into());
+                return;
+            }
+            if limits_changed
+                && let Err(e) = state
+                    .rest
+                    .set_rate_limits(Some(&device_id), rate_limits)
+                    .await
+            {
+                log::error!("failed to update rate limits of device on api: {:?}", e);
+                state.set_error(e);
+            }
+        });
+    }
+
+    /// Changes a setting of Syncthing's GUI
+    pub fn set_gui(&self, field: GuiField, value: String) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            match state.rest.set_gui(field, &value).await {
+                Ok(()) if field == GuiField::Password => {
+                    state.hint("Saved the GUI password, Syncthing keeps only its hash")
+                }
+                Ok(()) => state.hint(format!("Saved the {}", field.label())),
+                Err(e) => {
+                    log::error!("failed to update the gui on api: {:?}", e);
+                    state.set_error(e);
+                }
+            }
+        });
+    }
+
+    /// Replaces the global options of Syncthing
+    pub fn put_options(&self, options: serde_json::Value) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            match state.rest.put_options(&options).await {
+                Ok(()) => state.hint("Saved the settings"),
+                Err(e) => {
+                    log::error!("failed to update options on api: {:?}", e);
+                    state.set_error(e);
+                }
+            }
+        });
+    }
+
+    /// Limits the bandwidth of all connections
+    pub fn set_rate_limits(&self, rate_limits: RateLimits) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            match state.rest.set_rate_limits(None, rate_limits).await {
+                Ok(()) => state.hint("Saved the rate limits"),
+                Err(e) => {
+                    log::error!("failed to update rate limits on api: {:?}", e);
+                    state.set_error(e);
+                }
+            }
+        });
+    }
+
+    /// Accepts the devices in `device_ids` as soon as they show up as
+    /// pending, e.g., when provisioning devices whose IDs are known in
+    /// advance
+    pub fn auto_accept(&self, device_ids: Vec<String>) {
+        if device_ids.is_empty() {
+            return;
+        }
+        let state = self.clone();
+        let mut change_rx = self.subscribe_to_changes();
+        tokio::spawn(async move {
+            // Devices which have been added, so that they are not added
+            // twice while they are still pending. Failed ones are retried
+            // once pending devices change again.
+            let mut accepted: HashSet<String> = HashSet::new();
+            loop {
+                let acceptable: Vec<NewDeviceConfiguration> = state.read(|state| {
+                    state
+                        .get_pending_devices()
+                        .into_iter()
+                        .filter(|device| {
+                            device_ids
+                                .iter()
+                                .any(|id| id.eq_ignore_ascii_case(device.get_device_id()))
+                        })
+                        .cloned()
+                        .collect()
+                });
+                accepted.retain(|id| acceptable.iter().any(|device| device.get_device_id() == id));
+                for device in acceptable {
+                    let device_id = device.get_device_id().clone();
+                    if accepted.contains(&device_id) {
+                        continue;
+                    }
+                    let name = device
+                        .get_name()
+                        .clone()
+                        .filter(|name| !name.is_empty())
+                        .unwrap_or_else(|| device_id.clone());
+                    log::info!("accepting {} automatically", device_id);
+                    match state.client().add_device(device).await {
+                        Ok(()) => {
+                            accepted.insert(device_id);
+                            state.hint(format!("Accepted {} automatically", name));
+                            state.reload(Reload::Configuration);
+                        }
+                        Err(e) => {
+                            log::error!("failed to accept {} automatically: {:?}", device_id, e);
+                            state.set_error(e.into());
+                        }
+                    }
+                }
+
+                loop {
+                    match change_rx.recv().await {
+                        Ok(StateChange::Pending) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                            break;
+                        }
+                        Ok(_) => continue,
+                        Err(broadcast::error::RecvError::Closed) => return,
+                    }
+                }
+            }
+        });
+    }
+
+    /// Remembers that files of `folder_id` changed on disk, unless
+    /// Syncthing is writing them itself while syncing
+    pub fn local_change(&self, folder_id: &str) {
+        let changed = self.write(|state| {
+            let syncing = state
+                .get_folder(folder_id)
+                .is_ok_and(|folder| folder.status == FolderStatus::Syncing);
+            if syncing || state.local_changes.contains_key(folder_id) {
+                return false;
+            }
+            let now = state.clock.now();
+            state.local_changes.insert(folder_id.to_string(), now);
+            true
+        });
+        if changed {
+            log::debug!("local changes in {}, waiting for scan", folder_id);
+            self.notify(StateChange::Folders);
+        }
+    }
+
+    /// Periodically checks the paused folders in `folder_ids`, and resumes
+    /// them as soon as their path exists again, e.g., when a removable
+    /// drive got plugged back in. Only folders which were paused already when
+    /// they were first seen, or which were paused after failing, are resumed,
+    /// so that pausing one deliberately sticks. Unless Syncthing runs on this
+    /// machine, the path cannot be checked here, so it is left to Syncthing:
+    /// folders are resumed on trial, and paused again if it still reports an
+    /// error for them, up to [`AUTO_RESUME_TRIALS`] times in a row.
+    pub fn auto_resume(&self, folder_ids: Vec<String>, local: bool) {
+        if folder_ids.is_empty() {
+            return;
+        }
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(AUTO_RESUME_INTERVAL);
+            // Folders which have been checked at least once
+            let mut seen: HashSet<String> = HashSet::new();
+            // Paused folders which are resumed once their path exists
+            let mut resumable: HashSet<String> = HashSet::new();
+            // Folders which reported an error while running at the last check
+            let mut failing: HashSet<String> = HashSet::new();
+            // Folders resumed on trial at the previous check
+            let mut on_trial: HashSet<String> = HashSet::new();
+            // Failed trials in a row, by folder ID
+            let mut trials: HashMap<String, u32> = HashMap::new();
+            // Checks to skip before the next trial, by folder ID
+            let mut backoff: HashMap<String, u32> = HashMap::new();
+            loop {
+                interval.tick().await;
+                let folders: Vec<(FolderConfiguration, FolderStatus)> = state.read(|state| {
+                    folder_ids
+                        .iter()
+                        .filter_map(|folder_id| state.get_folder(folder_id).ok())
+                        .map(|folder| (folder.config.clone(), folder.status.clone()))
+                        .collect()
+                });
+                for (mut folder, status) in folders {
+                    if seen.insert(folder.id.clone()) && folder.paused {
+                        resumable.insert(folder.id.clone());
+                    }
+                    if on_trial.remove(&folder.id) {
+                        if let FolderStatus::Error(error) = status
+                            && !folder.paused
+                        {
+                            let failed = trials.entry(folder.id.clone()).or_default();
+                            *failed += 1;
+                            if *failed >= AUTO_RESUME_TRIALS {
+                                log::info!("{} still fails ({}), giving up", folder.id, error);
+                                resumable.remove(&folder.id);
+                                state.hint(format!(
+                                    "Stopped resuming {}, it failed {} times in a row",
+                                    folder.id, failed
+                                ));
+                            } else {
+                                log::info!("{} still fails ({}), pausing again", folder.id, error);
+                                backoff.insert(folder.id.clone(), AUTO_RESUME_BACKOFF);
+                            }
+                            folder.paused = true;
+                            state.edit_folder(folder);
+                        } else {
+                            trials.remove(&folder.id);
+                            backoff.remove(&folder.id);
+                        }
+                        continue;
+                    }
+                    if !folder.paused {
+                        resumable.remove(&folder.id);
+                        if matches!(status, FolderStatus::Error(_)) {
+                            failing.insert(folder.id.clone());
+                        } else {
+                            failing.remove(&folder.id);
+                        }
+                        continue;
+                    }
+                    if failing.remove(&folder.id) {
+                        resumable.insert(folder.id.clone());
+                    }
+                    if !resumable.contains(&folder.id) {
+                        continue;
+                    }
+                    if local && !local_path(&folder.path).exists() {
+                        continue;
+                    }
+                    if let Some(skip) = backoff.get_mut(&folder.id)
+                        && *skip > 0
+                    {
+                        *skip -= 1;
+                        continue;
+                    }
+                    log::info!("resuming {} on trial", folder.id);
+                    on_trial.insert(folder.id.clone());
+                    folder.paused = false;
+                    state.edit_folder(folder);
+                }
+            }
+        });
+    }
+
+    pub fn pause_device(&self, device_id: &str) {
+        self.set_device_paused(device_id, true);
+    }
+
+    pub fn resume_device(&self, device_id: &str) {
+        self.set_device_paused(device_id, false);
+    }
+
+    fn set_device_paused(&self, device_id: &str, paused: bool) {
+        if let Ok((mut device, rate_limits)) = self.read(|state| {
+            state
+                .get_device(device_id)
+                .map(|d| (d.config.clone(), d.rate_limits))
+        }) {
+            device.paused = paused;
+            self.edit_device(device, rate_limits);
+        }
+    }
+
+    /// Stops sharing every folder with `device_id`, and removes the device
+    /// afterwards
     pub fn remove_device(&self, device_id: impl Into<String>) {
         let state = self.clone();
         let device_id = device_id.into();
 
         tokio::spawn(async move {
-            if let Err(e) = state.client.delete_device(&device_id).await {
+            let folders: Vec<FolderConfiguration> = state.read(|state| {
+                state
+                    .get_device_folders(&device_id)
+                    .into_iter()
+                    .map(|folder| {
+                        let mut folder = folder.config.clone();
+                        folder.devices.retain(|d| d.device_id != device_id);
+                        folder
+                    })
+                    .collect()
+            });
+            for folder in folders {
+                if let Err(e) = state.client().post_folder(folder).await {
+                    log::error!("failed to unshare folder on api: {:?}", e);
+                    state.set_error(e.into());
+                    return;
+                }
+            }
+            if let Err(e) = state.client().delete_device(&device_id).await {
                 log::error!("failed to delete device from api: {:?}", e);
                 state.set_error(e.into());
+                return;
             }
+            // Don't wait for the reload after the config got saved
+            state.write(|state| state.forget_device(&device_id));
+            state.notify(StateChange::Devices);
+            state.notify(StateChange::Folders);
         });
     }
 }
@@ -457,28 +1606,441 @@ pub struct InnerState {
     folders: Vec<Folder>,
     devices: Vec<Device>,
     pending_folders: Vec<(String, NewFolderConfiguration)>,
+    /// What the offering device told about each pending folder, keyed by
+    /// device and folder ID
+    pending_offers: HashMap<(String, String), api::cluster::FolderOfferer>,
     pending_devices: Vec<NewDeviceConfiguration>,
-    pub events: Vec<api::events::Event>,
-    pub error: Option<AppError>,
+    /// The most recent events, oldest first
+    pub events: VecDeque<api::events::Event>,
+    /// Errors which have not been acknowledged yet, oldest first
+    pub errors: VecDeque<ErrorEntry>,
+    /// Whether saved configuration changes need a restart to take effect
+    pub requires_restart: bool,
+    /// Hint for the status line, and when it was set
+    hint: Option<(String, Instant)>,
+    /// The configuration as sent by Syncthing, including everything which is
+    /// not modeled
+    pub raw_config: Option<serde_json::Value>,
+    /// IDs of the devices whose connection attempts are ignored
+    ignored_devices: Vec<String>,
+    /// Folders whose offers are ignored, by device and folder ID
+    ignored_folders: Vec<(String, String)>,
+    /// Every device which has been configured, across sessions and instances
+    pub address_book: AddressBook,
+    /// Notes and tags on folders and devices
+    pub notes: Notes,
+    /// Bandwidth limits of all connections, from the raw configuration
+    pub rate_limits: RateLimits,
+    /// How far the clock of Syncthing is ahead of the local one
+    pub clock_skew: Option<chrono::TimeDelta>,
+    /// Since when and how often polling events failed, until it works again
+    disconnected: Option<Disconnected>,
+    /// Uptime, listeners and discovery of Syncthing, once polled
+    pub system_status: Option<SystemStatus>,
+    /// Addresses of all devices found through discovery, keyed by device ID
+    pub discovery_cache: HashMap<String, DiscoveredDevice>,
+    /// Whether the system status is polled along with the connections
+    poll_system: bool,
+    /// How long to wait between polls of the connections
+    poll_interval: Duration,
+    /// Data loaded on startup
+    preload: HashSet<Preload>,
+    /// Data which was not preloaded, but has been loaded since
+    loaded: HashSet<Preload>,
+    /// Folders whose remote completions have been loaded, although they were
+    /// not preloaded, by ID
+    remote_completions_loaded: HashSet<String>,
+    /// Transfer rates over all connections
+    pub total_traffic: Traffic,
+    /// When each section has last been loaded successfully
+    loaded_at: HashMap<Section, Instant>,
     /// The device ID of this device
     pub id: String,
+    /// Since when each folder is out of sync, keyed by folder ID
+    out_of_sync_since: HashMap<String, Instant>,
+    /// When each device last sent an index update for a folder, keyed by
+    /// device and folder ID
+    remote_index_updates: HashMap<(String, String), DateTime<Utc>>,
+    /// How far each device got with each folder, keyed by device and folder ID
+    remote_completions: HashMap<(String, String), RemoteCompletion>,
+    /// Addresses of devices discovered on the local network, and when
+    discovered_devices: HashMap<String, (Vec<String>, Instant)>,
+    /// When unknown devices tried to connect, oldest first
+    pending_device_requests: VecDeque<Instant>,
+    /// Since when files of each folder changed on disk without Syncthing
+    /// having scanned them yet, keyed by folder ID
+    local_changes: HashMap<String, Instant>,
+    /// How many receivers the state channels had when last counted
+    pub subscribers: SubscriberCounts,
+    /// Recent versions of all of the above, if they are kept for debugging
+    pub history: Option<StateHistory>,
+    /// Where all of the above gets the current time from
+    clock: SharedClock,
 }
 
 impl InnerState {
+    /// Whether `data` is loaded, and therefore reloaded along with the
+    /// configuration
+    fn loads(&self, data: Preload) -> bool {
+        self.preload.contains(&data) || self.loaded.contains(&data)
+    }
+
+    fn loads_remote_completions(&self, folder_id: &str) -> bool {
+        self.loads(Preload::RemoteCompletion) || self.remote_completions_loaded.contains(folder_id)
+    }
+
+    /// What can be observed of the state right now, if its history is kept
+    fn tracked_summary(&self) -> Option<StateSummary> {
+        self.history.as_ref().map(|_| self.summarize())
+    }
+
+    /// Records how the state changed since `before`, due to `cause`
+    fn record_version(&mut self, cause: String, triggered: Vec<String>, before: &StateSummary) {
+        let after = self.summarize();
+        let time = self.clock.utc_now();
+        if let Some(history) = self.history.as_mut() {
+            history.record(time, cause, triggered, before, after);
+        }
+    }
+
+    /// Everything races between events and reloads tend to get wrong, such
+    /// as completions and pending requests, but not what changes constantly,
+    /// such as transfer rates
+    fn summarize(&self) -> StateSummary {
+        let mut summary = StateSummary::new();
+        summary.insert("id".to_string(), self.id.clone());
+        summary.insert(
+            "requires restart".to_string(),
+            self.requires_restart.to_string(),
+        );
+        summary.insert("errors".to_string(), self.errors.len().to_string());
+        for folder in &self.folders {
+            let key = |field: &str| format!("folder {} {}", folder.config.id, field);
+            summary.insert(key("label"), folder.config.label.clone());
+            summary.insert(key("paused"), folder.config.paused.to_string());
+            summary.insert(key("status"), format!("{:?}", folder.status));
+            summary.insert(
+                key("completion"),
+                format!(
+                    "{:.2}{}",
+                    folder.completion,
+                    if folder.completion_loaded {
+                        ""
+                    } else {
+                        " (assumed)"
+                    }
+                ),
+            );
+            summary.insert(key("need items"), folder.need_items.to_string());
+            summary.insert(key("need bytes"), folder.progress.need_bytes.to_string());
+            let mut sharers: Vec<_> = folder
+                .config
+                .devices
+                .iter()
+                .map(|d| d.device_id.as_str())
+                .collect();
+            sharers.sort();
+            summary.insert(key("devices"), sharers.join(", "));
+        }
+        for device in &self.devices {
+            let key = |field: &str| format!("device {} {}", device.config.device_id, field);
+            summary.insert(key("name"), device.config.name.clone());
+            summary.insert(key("paused"), device.config.paused.to_string());
+            summary.insert(key("connection"), format!("{:?}", device.connected));
+        }
+        for ((device_id, folder_id), remote) in &self.remote_completions {
+            summary.insert(
+                format!("remote {} {} completion", device_id, folder_id),
+                format!("{:.2}", remote.completion),
+            );
+        }
+        for device in &self.pending_devices {
+            summary.insert(
+                format!("pending device {}", device.get_device_id()),
+                device.get_name().clone().unwrap_or_default(),
+            );
+        }
+        for (device_id, folder) in &self.pending_folders {
+            summary.insert(
+                format!("pending folder {} from {}", folder.get_id(), device_id),
+                folder.get_label().clone().unwrap_or_default(),
+            );
+        }
+        summary
+    }
+
+    /// Applies the direct effects of an event, and returns what still has
+    /// to be reloaded and what changed
+    fn apply_event(&mut self, event: api::events::Event) -> (Option<Reload>, Option<StateChange>) {
+        match event.ty {
+            EventType::ConfigSaved { .. } => (Some(Reload::Configuration), None),
+            EventType::DeviceConnected { id, .. } => {
+                log::debug!("Device {id} connected");
+                if let Ok(device) = self.get_device_mut(&id) {
+                    device.connected = DeviceStatus::UpToDate;
+                }
+                (None, Some(StateChange::Connections))
+            }
+            EventType::DeviceDisconnected { id, .. } => {
+                let now = self.clock.utc_now();
+                if let Ok(device) = self.get_device_mut(&id) {
+                    device.connected = DeviceStatus::Disconnected;
+                    device.last_seen = Some(now);
+                }
+                (None, Some(StateChange::Connections))
+            }
+            EventType::DevicePaused { device } => {
+                if let Ok(device) = self.get_device_mut(&device) {
+                    device.config.paused = true;
+                }
+                (None, Some(StateChange::Devices))
+            }
+            EventType::DeviceResumed { device } => {
+                if let Ok(device) = self.get_device_mut(&device) {
+                    device.config.paused = false;
+                }
+                (None, Some(StateChange::Devices))
+            }
+            EventType::DeviceDiscovered { device, addrs } => {
+                let now = self.clock.now();
+                // Expired discoveries are never shown, drop them instead of
+                // keeping every device ever seen on the network
+                self.discovered_devices.retain(|_, (_, discovered_at)| {
+                    now.saturating_duration_since(*discovered_at) < LAN_DISCOVERY_TTL
+                });
+                self.discovered_devices.insert(device, (addrs, now));
+                (None, Some(StateChange::Connections))
+            }
+            EventType::PendingDevicesChanged { added, .. } => {
+                let now = self.clock.now();
+                for _ in added.unwrap_or_default() {
+                    self.pending_device_requests.push_back(now);
+                }
+                while self.pending_device_requests.front().is_some_and(|since| {
+                    now.saturating_duration_since(*since) > PENDING_FLOOD_WINDOW
+                }) {
+                    self.pending_device_requests.pop_front();
+                }
+                (Some(Reload::PendingDevices), None)
+            }
+            EventType::PendingFoldersChanged { .. } => (Some(Reload::PendingFolders), None),
+            // The device told us which folders it shares with us
+            EventType::ClusterConfigReceived { device } => {
+                (Some(Reload::Announcements { device_id: device }), None)
+            }
+            EventType::RemoteIndexUpdated { device, folder, .. } => {
+                self.remote_index_updates
+                    .insert((device, folder), event.time);
+                (None, Some(StateChange::Folders))
+            }
+            EventType::StateChanged {
+                folder, to, error, ..
+            } => {
+                if let Ok(f) = self.get_folder_mut(&folder) {
+                    f.status = FolderStatus::new(&to, error);
+                }
+                if to == "scanning" {
+                    self.local_changes.remove(&folder);
+                }
+                (None, Some(StateChange::Folders))
+            }
+            EventType::ItemFinished {
+                item,
+                folder,
+                error,
+                ..
+            } => {
+                let Ok(f) = self.get_folder_mut(&folder) else {
+                    return (None, None);
+                };
+                let changed = match error {
+                    Some(error) => f.failed_items.insert(item, error.clone()) != Some(error),
+                    None => f.failed_items.remove(&item).is_some(),
+                };
+                (None, changed.then_some(StateChange::Folders))
+            }
+            EventType::FolderWatchStateChanged { folder, to, .. } => {
+                if let Ok(f) = self.get_folder_mut(&folder) {
+                    f.watch_error = to.filter(|error| !error.is_empty());
+                }
+                (None, Some(StateChange::Folders))
+            }
+            EventType::FolderCompletion {
+                completion,
+                device,
+                folder,
+                need_bytes,
+                ..
+            } => {
+                self.remote_completions.insert(
+                    (device.clone(), folder.clone()),
+                    RemoteCompletion {
+                        completion,
+                        need_bytes,
+                    },
+                );
+                (
+                    None,
+                    Some(StateChange::Completion {
+                        folder_id: Some(folder),
+                        device_id: Some(device),
+                    }),
+                )
+            }
+            EventType::RemoteDownloadProgress { device, .. } => (
+                Some(Reload::Completion {
+                    device_id: Some(device.to_string()),
+                    folder_id: None,
+                }),
+                None,
+            ),
+            _ => (None, None),
+        }
+    }
+
+    /// Remembers `event`, dropping the oldest one once the replay buffer is full
+    fn record_event(&mut self, event: api::events::Event) {
+        if self.events.len() == EVENT_REPLAY_SIZE {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Replaces the configuration of all devices and folders, while keeping
+    /// the runtime information (connection, completion, ...) of known ones.
     fn update_from_configuration(&mut self, configuration: api::config::Configuration) {
-        self.folders.clear();
-        self.devices.clear();
-        for device in configuration.devices {
-            self.devices.push(device.into());
+        let mut remembered = false;
+        for config in &configuration.devices {
+            remembered |= self.address_book.remember(&config.device_id, &config.name);
+        }
+        if remembered {
+            self.address_book.save();
+        }
+
+        let mut devices = std::mem::take(&mut self.devices);
+        for config in configuration.devices {
+            match devices
+                .iter()
+                .position(|d| d.config.device_id == config.device_id)
+            {
+                Some(index) => {
+                    let mut device = devices.swap_remove(index);
+                    device.config = config;
+                    self.devices.push(device);
+                }
+                None => self.devices.push(config.into()),
+            }
+        }
+
+        let mut folders = std::mem::take(&mut self.folders);
+        for config in configuration.folders {
+            match folders.iter().position(|f| f.config.id == config.id) {
+                Some(index) => {
+                    let mut folder = folders.swap_remove(index);
+                    folder.config = config;
+                    self.folders.push(folder);
+                }
+                None => self.folders.push(config.into()),
+            }
+        }
+    }
+
+    /// Takes the device and folder settings which are not modeled by the API
+    /// types from the raw configuration
+    fn update_from_raw_configuration(&mut self, config: &serde_json::Value) {
+        self.rate_limits = config
+            .get("options")
+            .and_then(|options| RateLimits::deserialize(options).ok())
+            .unwrap_or_default();
+
+        for raw in config
+            .get("folders")
+            .and_then(|f| f.as_array())
+            .into_iter()
+            .flatten()
+        {
+            if let Some(folder_id) = raw.get("id").and_then(|id| id.as_str())
+                && let Ok(folder) = self.get_folder_mut(folder_id)
+            {
+                folder.watcher_enabled = raw
+                    .get("fsWatcherEnabled")
+                    .and_then(|enabled| enabled.as_bool())
+                    .unwrap_or(true);
+            }
+        }
+
+        self.ignored_devices = config
+            .get("remoteIgnoredDevices")
+            .and_then(|ignored| ignored.as_array())
+            .map(|ignored| {
+                ignored
+                    .iter()
+                    .filter_map(|device| device.get("deviceID").and_then(|id| id.as_str()))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.pending_devices
+            .retain(|device| !self.ignored_devices.contains(device.get_device_id()));
+
+        let Some(devices) = config.get("devices").and_then(|d| d.as_array()) else {
+            return;
+        };
+        self.ignored_folders = devices
+            .iter()
+            .filter_map(|raw| {
+                let device_id = raw.get("deviceID")?.as_str()?;
+                let ignored = raw.get("ignoredFolders")?.as_array()?;
+                Some(ignored.iter().filter_map(move |folder| {
+                    let folder_id = folder.get("id")?.as_str()?;
+                    Some((device_id.to_string(), folder_id.to_string()))
+                }))
+            })
+            .flatten()
+            .collect();
+        self.pending_folders.retain(|(device_id, folder)| {
+            !self
+                .ignored_folders
+                .contains(&(device_id.clone(), folder.get_id().clone()))
+        });
+        for raw in devices {
+            if let Some(device_id) = raw.get("deviceID").and_then(|id| id.as_str())
+                && let Ok(device) = self.get_device_mut(device_id)
+            {
+                device.untrusted = raw
+                    .get("untrusted")
+                    .and_then(|untrusted| untrusted.as_bool())
+                    .unwrap_or_default();
+                device.rate_limits = RateLimits::deserialize(raw).unwrap_or_default();
+            }
+        }
+    }
+
+    fn set_folder_completion(&mut self, folder_id: &str, completion: &api::db::Completion) {
+        let now = self.clock.now();
+        if let Ok(folder) = self.get_folder_mut(folder_id) {
+            folder.completion = completion.completion;
+            folder.completion_loaded = true;
+            folder.need_items = completion.need_items;
+            folder.global_bytes = completion.global_bytes;
+            folder.global_items = completion.global_items;
+            folder.progress.update(completion.need_bytes, now);
         }
-        for folder in configuration.folders {
-            self.folders.push(folder.into());
+        if completion.completion < 100.0 {
+            self.out_of_sync_since
+                .entry(folder_id.to_string())
+                .or_insert_with(|| self.clock.now());
+        } else {
+            self.out_of_sync_since.remove(folder_id);
         }
     }
 
     fn set_pending_devices(&mut self, pending_devices: api::cluster::PendingDevices) {
         self.pending_devices.clear();
         for (device_id, device) in pending_devices.devices.iter() {
+            if self.ignored_devices.contains(device_id) {
+                continue;
+            }
             self.pending_devices
                 .push(NewDeviceConfiguration::new(device_id.to_string()).name(device.name.clone()));
         }
@@ -486,14 +2048,23 @@ impl InnerState {
 
     fn set_pending_folders(&mut self, pending_folders: api::cluster::PendingFolders) {
         self.pending_folders.clear();
+        self.pending_offers.clear();
         for (folder_id, folder) in pending_folders.folders.iter() {
             for (introducer_id, offerer) in folder.offered_by.clone() {
+                if self
+                    .ignored_folders
+                    .contains(&(introducer_id.clone(), folder_id.to_string()))
+                {
+                    continue;
+                }
                 self.pending_folders.push((
-                    introducer_id,
+                    introducer_id.clone(),
                     // TODO find a cleaner way to handle the unknown path at this point
                     NewFolderConfiguration::new(folder_id.to_string(), "?".to_string())
-                        .label(offerer.label),
+                        .label(offerer.label.clone()),
                 ));
+                self.pending_offers
+                    .insert((introducer_id, folder_id.to_string()), offerer);
             }
         }
 
@@ -505,12 +2076,7 @@ impl InnerState {
     pub fn get_devices(&self) -> Vec<&Device> {
         let mut res: Vec<&Device> = self.devices.iter().collect();
 
-        res.sort_by(|a, b| {
-            a.config
-                .name
-                .to_lowercase()
-                .cmp(&b.config.name.to_lowercase())
-        });
+        res.sort_by(|a, b| cmp_ignore_case(&a.config.name, &b.config.name));
         res
     }
 
@@ -538,6 +2104,14 @@ impl InnerState {
             .collect()
     }
 
+    /// The devices as listed on the devices page, with this device pinned
+    /// to the top
+    pub fn get_listed_devices(&self) -> Vec<&Device> {
+        let mut devices = self.get_devices();
+        devices.sort_by_key(|device| device.config.device_id != self.id);
+        devices
+    }
+
     /// All devices with which `folder_id` is shared.
     pub fn get_devices_sharing_folder(
         &self,
@@ -582,15 +2156,19 @@ impl InnerState {
         let mut res: Vec<&Folder> = self.folders.iter().collect();
 
         // TODO id
-        res.sort_by(|a, b| {
-            a.config
-                .label
-                .to_lowercase()
-                .cmp(&b.config.label.to_lowercase())
-        });
+        res.sort_by(|a, b| cmp_ignore_case(&a.config.label, &b.config.label));
         res
     }
 
+    /// Number of devices besides this one, without sorting them as
+    /// [`get_other_devices`](Self::get_other_devices) does
+    pub fn count_other_devices(&self) -> usize {
+        self.devices
+            .iter()
+            .filter(|device| device.config.device_id != self.id)
+            .count()
+    }
+
     pub fn get_pending_folders(&self) -> Vec<&(String, NewFolderConfiguration)> {
         let mut res: Vec<_> = self.pending_folders.iter().collect();
 
@@ -600,6 +2178,25 @@ impl InnerState {
         res
     }
 
+    /// IDs of the devices, which offered us to share `folder_id`
+    pub fn get_pending_offers(&self, folder_id: &str) -> Vec<&String> {
+        self.pending_folders
+            .iter()
+            .filter(|(_, folder)| folder.get_id() == folder_id)
+            .map(|(device_id, _)| device_id)
+            .collect()
+    }
+
+    /// When and how `device_id` offered to share `folder_id`, if it still does
+    pub fn get_pending_offer(
+        &self,
+        device_id: &str,
+        folder_id: &str,
+    ) -> Option<&api::cluster::FolderOfferer> {
+        self.pending_offers
+            .get(&(device_id.to_string(), folder_id.to_string()))
+    }
+
     pub fn get_folder(&self, folder_id: &str) -> eyre::Result<&Folder, AppError> {
         self.folders
             .iter()
@@ -614,20 +2211,414 @@ impl InnerState {
             .ok_or(AppError::UnknownFolder)
     }
 
+    /// For how long `folder_id` has been out of sync, if it currently is
+    pub fn get_out_of_sync_duration(&self, folder_id: &str) -> Option<Duration> {
+        self.out_of_sync_since
+            .get(folder_id)
+            .map(|since| self.clock.elapsed(*since))
+    }
+
+    /// For how long files of `folder_id` have changed on disk without
+    /// Syncthing having scanned them yet
+    pub fn get_local_change(&self, folder_id: &str) -> Option<Duration> {
+        self.local_changes
+            .get(folder_id)
+            .map(|since| self.clock.elapsed(*since))
+    }
+
+    /// Whether `folder_id` has been out of sync for longer than allowed by `config`
+    pub fn is_folder_stuck(&self, folder_id: &str, config: &AppConfig) -> bool {
+        self.get_out_of_sync_duration(folder_id)
+            .is_some_and(|duration| duration > config.stuck_after())
+    }
+
+    /// The addresses under which `device_id` was recently discovered on
+    /// the local network. Discoveries expire after [`LAN_DISCOVERY_TTL`].
+    pub fn get_lan_addresses(&self, device_id: &str) -> Option<&Vec<String>> {
+        self.discovered_devices
+            .get(device_id)
+            .filter(|(_, discovered_at)| self.clock.elapsed(*discovered_at) < LAN_DISCOVERY_TTL)
+            .map(|(addresses, _)| addresses)
+    }
+
+    /// When `device_id` last sent an index update for `folder_id`, during
+    /// this session
+    pub fn get_last_remote_index(&self, device_id: &str, folder_id: &str) -> Option<DateTime<Utc>> {
+        self.remote_index_updates
+            .get(&(device_id.to_string(), folder_id.to_string()))
+            .copied()
+    }
+
+    /// How far `device_id` got with `folder_id`, once loaded
+    pub fn get_remote_completion(
+        &self,
+        device_id: &str,
+        folder_id: &str,
+    ) -> Option<&RemoteCompletion> {
+        self.remote_completions
+            .get(&(device_id.to_string(), folder_id.to_string()))
+    }
+
+    /// Whether `device_id` is currently reachable on the local network
+    pub fn is_on_lan(&self, device_id: &str) -> bool {
+        self.get_lan_addresses(device_id).is_some()
+    }
+
+    fn set_loaded(&mut self, section: Section) {
+        self.loaded_at.insert(section, self.clock.now());
+    }
+
+    /// Whether `section` has been loaded at least once
+    pub fn is_loaded(&self, section: Section) -> bool {
+        self.loaded_at.contains_key(&section)
+    }
+
+    /// How long the event stream has been down for, if it is
+    pub fn get_disconnected(&self) -> Option<(Duration, &Disconnected)> {
+        self.disconnected
+            .as_ref()
+            .map(|disconnected| (self.clock.elapsed(disconnected.since), disconnected))
+    }
+
+    /// How long ago `section` has last been loaded, if it is older than
+    /// [`STALE_AFTER`], or than two polls if they are further apart
+    pub fn staleness(&self, section: Section) -> Option<Duration> {
+        let stale_after = STALE_AFTER.max(self.poll_interval * 2);
+        self.loaded_at
+            .get(&section)
+            .map(|loaded_at| self.clock.elapsed(*loaded_at))
+            .filter(|age| *age > stale_after)
+    }
+
+    /// Whether any section has not been loaded yet
+    pub fn is_loading(&self) -> bool {
+        [
+            Section::Configuration,
+            Section::Connections,
+            Section::PendingDevices,
+            Section::PendingFolders,
+        ]
+        .into_iter()
+        .any(|section| !self.is_loaded(section))
+    }
+
+    /// The hint to show in the status line, if any
+    pub fn get_hint(&self) -> Option<String> {
+        if self.requires_restart {
+            return Some("Change saved, restart required — press R".to_string());
+        }
+        self.hint
+            .as_ref()
+            .filter(|(_, since)| self.clock.elapsed(*since) < HINT_DURATION)
+            .map(|(hint, _)| hint.clone())
+    }
+
+    /// Number of unknown devices which tried to connect within
+    /// [`PENDING_FLOOD_WINDOW`], if suspiciously many did
+    pub fn get_pending_device_flood(&self) -> Option<usize> {
+        let count = self
+            .pending_device_requests
+            .iter()
+            .filter(|since| self.clock.elapsed(**since) <= PENDING_FLOOD_WINDOW)
+            .count();
+        (count >= PENDING_FLOOD_THRESHOLD).then_some(count)
+    }
+
+    /// Drops `device_id` and every folder's association with it
+    fn forget_device(&mut self, device_id: &str) {
+        self.devices.retain(|d| d.config.device_id != device_id);
+        for folder in &mut self.folders {
+            folder.config.devices.retain(|d| d.device_id != device_id);
+        }
+        self.remote_index_updates
+            .retain(|(device, _), _| device != device_id);
+        self.remote_completions
+            .retain(|(device, _), _| device != device_id);
+    }
+
+    /// The first folder, other than `folder_id`, whose path overlaps with
+    /// `path`, by label
+    pub fn find_path_conflict(
+        &self,
+        path: &str,
+        folder_id: Option<&str>,
+    ) -> Option<(String, PathConflict)> {
+        self.get_folders()
+            .into_iter()
+            .filter(|folder| Some(folder.config.id.as_str()) != folder_id)
+            .find_map(|folder| {
+                PathConflict::between(path, &folder.config.path)
+                    .map(|conflict| (folder.config.label.clone(), conflict))
+            })
+    }
+
+    /// All problems which should be brought to the attention of the user
+    pub fn get_health_issues(&self, config: &AppConfig) -> Vec<HealthIssue> {
+        let mut issues = Vec::new();
+        for folder in self.get_folders() {
+            if let Some(duration) = self.get_out_of_sync_duration(&folder.config.id)
+                && duration > config.stuck_after()
+            {
+                issues.push(HealthIssue::StuckFolder {
+                    label: folder.config.label.clone(),
+                    completion: folder.completion,
+                    duration,
+                });
+            }
+        }
+        if let Some(skew) = self.clock_skew
+            && let Ok(difference) = skew.abs().to_std()
+            && difference > CLOCK_SKEW_THRESHOLD
+        {
+            issues.push(HealthIssue::ClockSkew {
+                ahead: skew > chrono::TimeDelta::zero(),
+                difference,
+            });
+        }
+        let folders = self.get_folders();
+        for (i, folder) in folders.iter().enumerate() {
+            for other in &folders[i + 1..] {
+                if let Some(conflict) =
+                    PathConflict::between(&folder.config.path, &other.config.path)
+                {
+                    issues.push(HealthIssue::PathConflict {
+                        folder: folder.config.label.clone(),
+                        other: other.config.label.clone(),
+                        conflict,
+                    });
+                }
+            }
+        }
+        if let Some(count) = self.get_pending_device_flood() {
+            issues.push(HealthIssue::PendingDeviceFlood { count });
+        }
+        if self.subscribers.is_leaking() {
+            issues.push(HealthIssue::SubscriberLeak {
+                counts: self.subscribers,
+            });
+        }
+        for device in self.get_other_devices() {
+            for folder_id in &device.unannounced_folders {
+                if let Ok(folder) = self.get_folder(folder_id) {
+                    issues.push(HealthIssue::UnannouncedFolder {
+                        device: device.config.name.clone(),
+                        folder: folder.config.label.clone(),
+                    });
+                }
+            }
+        }
+        issues
+    }
+
+    /// Runs `check` on `folder`, returning the problem it found, if any
+    pub fn check_folder(&self, folder: &Folder, check: FolderCheck) -> Option<FolderProblem> {
+        let sharers: Vec<&Device> = self
+            .get_other_devices()
+            .into_iter()
+            .filter(|device| folder.get_sharer().contains(&&device.config.device_id))
+            .collect();
+        let connected = || {
+            sharers
+                .iter()
+                .filter(|device| device.connected != DeviceStatus::Disconnected)
+        };
+        match check {
+            FolderCheck::Unpaused => folder.config.paused.then_some(FolderProblem::Paused),
+            FolderCheck::Connected if sharers.is_empty() => Some(FolderProblem::NotShared),
+            FolderCheck::Connected => {
+                (connected().count() == 0).then(|| FolderProblem::Disconnected {
+                    devices: sharers.iter().map(|d| d.config.name.clone()).collect(),
+                })
+            }
+            FolderCheck::Accepted => {
+                let devices: Vec<String> = connected()
+                    .filter(|device| device.unannounced_folders.contains(&folder.config.id))
+                    .map(|device| device.config.name.clone())
+                    .collect();
+                (!devices.is_empty()).then_some(FolderProblem::NotAccepted { devices })
+            }
+            FolderCheck::NoErrors => match &folder.status {
+                FolderStatus::Error(error) => Some(FolderProblem::Error(error.clone())),
+                _ => None,
+            },
+            FolderCheck::Watching => match &folder.watch_error {
+                _ if !folder.watcher_enabled => Some(FolderProblem::WatcherDisabled),
+                Some(error) => Some(FolderProblem::WatchFailed(error.clone())),
+                None => None,
+            },
+            FolderCheck::UpToDate => {
+                (folder.completion < 100.0).then_some(FolderProblem::OutOfSync {
+                    items: folder.need_items,
+                    bytes: folder.progress.need_bytes,
+                })
+            }
+        }
+    }
+
+    /// Whether the configuration and the completion of every folder, which
+    /// is not paused, have been loaded
+    pub fn is_fully_loaded(&self) -> bool {
+        self.is_loaded(Section::Configuration)
+            && self
+                .get_folders()
+                .iter()
+                .all(|f| f.completion_loaded || f.config.paused)
+    }
+
+    /// Devices which have not been connected for longer than `stale-after`,
+    /// together with how long ago they were last seen, longest first.
+    /// Devices which were never seen are not considered stale.
+    pub fn get_stale_devices(&self, config: &AppConfig) -> Vec<(&Device, Duration)> {
+        let mut stale: Vec<_> = self
+            .get_other_devices()
+            .into_iter()
+            .filter(|device| device.connected == DeviceStatus::Disconnected)
+            .filter_map(|device| {
+                let last_seen = device.last_seen?;
+                // Syncthing reports the epoch for devices it never saw
+                if last_seen.timestamp() <= 0 {
+                    return None;
+                }
+                let unseen = (self.clock.utc_now() - last_seen).to_std().ok()?;
+                (unseen > config.stale_after()).then_some((device, unseen))
+            })
+            .collect();
+        stale.sort_by(|(_, a), (_, b)| b.cmp(a));
+        stale
+    }
+
     // Get all folders which are shared with `device_id`. Does not check
     // if `device_id` actually exists.
     pub fn get_device_folders(&self, device_id: &str) -> Vec<&Folder> {
         self.get_folders()
             .into_iter()
-            .filter(|f| f.get_sharer().iter().any(|d| d == &device_id))
+            .filter(|f| f.config.devices.iter().any(|d| d.device_id == device_id))
             .collect()
     }
 }
 
+/// The type of `event` as Syncthing calls it, e.g., `FolderSummary`
+fn event_name(event: &api::events::Event) -> String {
+    serde_json::to_value(event)
+        .ok()
+        .and_then(|value| value.get("type")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Compares `a` and `b` ignoring case, without allocating lowercase copies
+/// as the lists are sorted on every frame
+fn cmp_ignore_case(a: &str, b: &str) -> std::cmp::Ordering {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .cmp(b.chars().flat_map(char::to_lowercase))
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Folder {
     pub config: FolderConfiguration,
     pub completion: f64,
+    /// Whether `completion` has been reported by Syncthing, instead of
+    /// being assumed
+    pub completion_loaded: bool,
+    pub status: FolderStatus,
+    pub progress: SyncProgress,
+    /// Files and directories which still have to be fetched
+    pub need_items: u64,
+    /// Size and number of items of the newest version of the folder, known
+    /// once the completion has been loaded
+    pub global_bytes: u64,
+    pub global_items: u64,
+    /// Whether Syncthing watches the path for changes. Not part of
+    /// [`FolderConfiguration`], so taken from the raw configuration.
+    pub watcher_enabled: bool,
+    /// Why watching the path for changes failed, if it did
+    pub watch_error: Option<String>,
+    /// Why items failed to sync, by path. Dropped once they sync.
+    pub failed_items: BTreeMap<String, String>,
+}
+
+/// How far another device got with a folder, as reported by Syncthing
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemoteCompletion {
+    pub completion: f64,
+    /// Bytes which the device still has to fetch
+    pub need_bytes: u64,
+}
+
+/// Weight of the newest observation in the smoothed sync rate
+const SYNC_RATE_SMOOTHING: f64 = 0.3;
+
+/// How fast a folder syncs, estimated from how the bytes it still needs
+/// shrink between observations
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SyncProgress {
+    /// Bytes which still have to be fetched from other devices
+    pub need_bytes: u64,
+    measured_at: Option<Instant>,
+    /// Smoothed bytes per second, once there are two observations
+    pub rate: Option<f64>,
+}
+
+impl SyncProgress {
+    fn update(&mut self, need_bytes: u64, now: Instant) {
+        if need_bytes == 0 {
+            self.rate = None;
+        } else if let Some(measured_at) = self.measured_at {
+            let elapsed = now.saturating_duration_since(measured_at).as_secs_f64();
+            if elapsed > 0.0 {
+                let rate = self.need_bytes.saturating_sub(need_bytes) as f64 / elapsed;
+                // Syncthing fetches whole blocks, so single observations jump
+                self.rate = Some(match self.rate {
+                    Some(previous) => {
+                        previous * (1.0 - SYNC_RATE_SMOOTHING) + rate * SYNC_RATE_SMOOTHING
+                    }
+                    None => rate,
+                });
+            }
+        }
+        self.need_bytes = need_bytes;
+        self.measured_at = Some(now);
+    }
+
+    /// How long syncing takes at the current rate, if it makes progress
+    pub fn eta(&self) -> Option<Duration> {
+        self.rate
+            .filter(|rate| *rate > 0.0)
+            .and_then(|rate| Duration::try_from_secs_f64(self.need_bytes as f64 / rate).ok())
+    }
+}
+
+/// What Syncthing is doing with a folder, as reported by `StateChanged`
+/// events
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum FolderStatus {
+    /// No state change has been seen yet
+    #[default]
+    Unknown,
+    Idle,
+    Scanning,
+    Syncing,
+    /// Waiting for another folder to finish scanning or syncing
+    Waiting,
+    Cleaning,
+    Error(String),
+}
+
+impl FolderStatus {
+    fn new(state: &str, error: Option<String>) -> Self {
+        match state {
+            "idle" => Self::Idle,
+            "scanning" => Self::Scanning,
+            "syncing" | "sync-preparing" => Self::Syncing,
+            "scan-waiting" | "sync-waiting" | "clean-waiting" => Self::Waiting,
+            "cleaning" => Self::Cleaning,
+            "error" => Self::Error(error.unwrap_or_default()),
+            other => {
+                log::debug!("unknown folder state {}", other);
+                Self::Unknown
+            }
+        }
+    }
 }
 
 impl Folder {
@@ -651,6 +2642,158 @@ impl Folder {
             .filter(|d| d != &device_id)
             .collect()
     }
+
+    /// Whether `device_id` only receives data encrypted with a password
+    pub fn is_encrypted_for(&self, device_id: &str) -> bool {
+        self.config
+            .devices
+            .iter()
+            .any(|d| d.device_id == device_id && !d.encryption_password.is_empty())
+    }
+}
+
+/// A failure, e.g., of an API call, and when it happened
+#[derive(Debug)]
+pub struct ErrorEntry {
+    pub error: AppError,
+    pub time: DateTime<Utc>,
+}
+
+impl ErrorEntry {
+    /// The error followed by its causes, e.g., `syncthing API error: 403`
+    pub fn describe(&self) -> String {
+        let mut description = self.error.to_string();
+        let mut source = std::error::Error::source(&self.error);
+        while let Some(cause) = source {
+            description.push_str(&format!(": {}", cause));
+            source = cause.source();
+        }
+        description
+    }
+}
+
+/// A problem which likely needs an action of the user
+#[derive(Clone, Debug, PartialEq)]
+pub enum HealthIssue {
+    /// Folder has been out of sync for a suspiciously long time
+    StuckFolder {
+        label: String,
+        completion: f64,
+        duration: Duration,
+    },
+    /// The clocks of Syncthing and this machine differ
+    ClockSkew {
+        /// Whether the clock of Syncthing is ahead
+        ahead: bool,
+        difference: Duration,
+    },
+    /// We share the folder with the device, but the device does not share it
+    /// back, so nothing gets synced between the two
+    UnannouncedFolder { device: String, folder: String },
+    /// Many unknown devices tried to connect recently, so the instance is
+    /// likely reachable from the internet with global discovery on
+    PendingDeviceFlood { count: usize },
+    /// The paths of two folders are the same or nested, so both sync the
+    /// same files
+    PathConflict {
+        folder: String,
+        other: String,
+        conflict: PathConflict,
+    },
+    /// Receivers of state changes or events are not dropped anymore, so
+    /// every change is kept around for them
+    SubscriberLeak { counts: SubscriberCounts },
+}
+
+/// Number of receivers subscribed to the state
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SubscriberCounts {
+    /// Receivers of [`State::subscribe_to_changes`]
+    pub changes: usize,
+    /// Receivers of [`State::subscribe_to_events`]
+    pub events: usize,
+}
+
+impl SubscriberCounts {
+    pub fn is_leaking(&self) -> bool {
+        self.changes > MAX_SUBSCRIBERS || self.events > MAX_SUBSCRIBERS
+    }
+}
+
+/// A step of finding out why a folder does not sync, in the order they are
+/// checked. Each one only makes sense once the previous ones passed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum::EnumIter)]
+pub enum FolderCheck {
+    Unpaused,
+    /// Shared with at least one connected device
+    Connected,
+    /// The connected devices share the folder back
+    Accepted,
+    NoErrors,
+    /// Syncthing notices changes right away instead of on the next rescan
+    Watching,
+    UpToDate,
+}
+
+/// Why a folder does not sync, as found by a [`FolderCheck`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum FolderProblem {
+    Paused,
+    /// Not shared with any other device
+    NotShared,
+    /// None of these devices sharing the folder is connected, by name
+    Disconnected {
+        devices: Vec<String>,
+    },
+    /// These connected devices have not accepted the folder, by name
+    NotAccepted {
+        devices: Vec<String>,
+    },
+    Error(String),
+    WatcherDisabled,
+    WatchFailed(String),
+    OutOfSync {
+        items: u64,
+        bytes: u64,
+    },
+}
+
+/// How the path of a folder overlaps with the one of another folder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathConflict {
+    Same,
+    /// The path lies inside of the other folder
+    Inside,
+    /// The other folder lies inside of the path
+    Contains,
+}
+
+impl PathConflict {
+    /// How `path` overlaps with `other`, if at all. Paths are compared by
+    /// their components, as they might not exist on this machine.
+    pub fn between(path: &str, other: &str) -> Option<Self> {
+        let (path, other) = (Path::new(path.trim()), Path::new(other.trim()));
+        if path.as_os_str().is_empty() || other.as_os_str().is_empty() {
+            None
+        } else if path == other {
+            Some(Self::Same)
+        } else if path.starts_with(other) {
+            Some(Self::Inside)
+        } else if other.starts_with(path) {
+            Some(Self::Contains)
+        } else {
+            None
+        }
+    }
+
+    /// Describes the conflict with the folder labeled `other`
+    pub fn describe(&self, other: &str) -> String {
+        match self {
+            Self::Same => format!("has the same path as \"{}\"", other),
+            Self::Inside => format!("lies inside of \"{}\"", other),
+            Self::Contains => format!("contains \"{}\"", other),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -664,6 +2807,75 @@ pub enum DeviceStatus {
 pub struct Device {
     pub config: DeviceConfiguration,
     pub connected: DeviceStatus,
+    /// When we were last connected to the device
+    pub last_seen: Option<DateTime<Utc>>,
+    pub traffic: Traffic,
+    /// Whether the device may only receive encrypted data. Not part of
+    /// [`DeviceConfiguration`], so taken from the raw configuration.
+    pub untrusted: bool,
+    /// IDs of folders we share with the device, which the device did not
+    /// announce the last time it sent us its cluster configuration
+    pub unannounced_folders: Vec<String>,
+    /// Bandwidth limits of the connection to the device, from the raw
+    /// configuration
+    pub rate_limits: RateLimits,
+}
+
+/// The event stream is down, and being reconnected
+#[derive(Clone, Debug, PartialEq)]
+pub struct Disconnected {
+    pub since: Instant,
+    /// Number of failed attempts in a row
+    pub attempt: u32,
+    /// Delay until the next attempt
+    pub retry_in: Duration,
+}
+
+/// Number of measured transfer rates kept for the graphs, one per poll of
+/// the connections
+const TRAFFIC_HISTORY: usize = 120;
+
+/// Transfer rates with a device, derived from the total transferred bytes
+/// of two consecutive measurements
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Traffic {
+    in_bytes_total: u64,
+    out_bytes_total: u64,
+    measured_at: Option<Instant>,
+    /// Bytes per second received from the device
+    pub download_rate: f64,
+    /// Bytes per second sent to the device
+    pub upload_rate: f64,
+    /// Recent download rates in bytes per second, oldest first
+    pub download_history: VecDeque<u64>,
+    /// Recent upload rates in bytes per second, oldest first
+    pub upload_history: VecDeque<u64>,
+}
+
+impl Traffic {
+    fn update(&mut self, in_bytes_total: u64, out_bytes_total: u64, now: Instant) {
+        if let Some(measured_at) = self.measured_at {
+            let elapsed = now.duration_since(measured_at).as_secs_f64();
+            if elapsed > 0.0 {
+                self.download_rate =
+                    in_bytes_total.saturating_sub(self.in_bytes_total) as f64 / elapsed;
+                self.upload_rate =
+                    out_bytes_total.saturating_sub(self.out_bytes_total) as f64 / elapsed;
+                for (history, rate) in [
+                    (&mut self.download_history, self.download_rate),
+                    (&mut self.upload_history, self.upload_rate),
+                ] {
+                    if history.len() == TRAFFIC_HISTORY {
+                        history.pop_front();
+                    }
+                    history.push_back(rate as u64);
+                }
+            }
+        }
+        self.in_bytes_total = in_bytes_total;
+        self.out_bytes_total = out_bytes_total;
+        self.measured_at = Some(now);
+    }
 }
 
 impl From<api::config::DeviceConfiguration> for Device {
@@ -671,6 +2883,11 @@ impl From<api::config::DeviceConfiguration> for Device {
         Self {
             config: value,
             connected: DeviceStatus::Disconnected,
+            last_seen: None,
+            traffic: Traffic::default(),
+            untrusted: false,
+            unannounced_folders: Vec::new(),
+            rate_limits: RateLimits::default(),
         }
     }
 }
@@ -680,6 +2897,122 @@ impl From<api::config::FolderConfiguration> for Folder {
         Self {
             config: folder,
             completion: 100.0,
+            completion_loaded: false,
+            status: FolderStatus::default(),
+            progress: SyncProgress::default(),
+            need_items: 0,
+            global_bytes: 0,
+            global_items: 0,
+            watcher_enabled: true,
+            watch_error: None,
+            failed_items: BTreeMap::new(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockClock;
+
+    /// An empty state whose time only moves when `clock` is advanced
+    fn inner_state(clock: &MockClock) -> InnerState {
+        InnerState {
+            clock: SharedClock::new(clock.clone()),
+            ..Default::default()
+        }
+    }
+
+    fn fixture<T: serde::de::DeserializeOwned>(name: &str) -> T {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn folder_is_stuck_once_out_of_sync_for_too_long() {
+        let clock = MockClock::new();
+        let mut state = inner_state(&clock);
+        let config = AppConfig::default();
+        let mut completion: api::db::Completion = fixture("completion.json");
+
+        state.set_folder_completion("abcd-1234", &completion);
+        clock.advance(config.stuck_after());
+        assert!(!state.is_folder_stuck("abcd-1234", &config));
+
+        // Further progress does not reset the time it has been out of sync
+        state.set_folder_completion("abcd-1234", &completion);
+        clock.advance(Duration::from_secs(1));
+        assert!(state.is_folder_stuck("abcd-1234", &config));
+
+        completion.completion = 100.0;
+        state.set_folder_completion("abcd-1234", &completion);
+        assert!(!state.is_folder_stuck("abcd-1234", &config));
+    }
+
+    #[test]
+    fn sections_become_stale_without_polls() {
+        let clock = MockClock::new();
+        let mut state = inner_state(&clock);
+        assert_eq!(state.staleness(Section::Connections), None);
+
+        state.set_loaded(Section::Connections);
+        clock.advance(STALE_AFTER);
+        assert_eq!(state.staleness(Section::Connections), None);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(
+            state.staleness(Section::Connections),
+            Some(STALE_AFTER + Duration::from_secs(1))
+        );
+
+        // Polls further apart than that are only late after two of them
+        state.poll_interval = STALE_AFTER;
+        assert_eq!(state.staleness(Section::Connections), None);
+    }
+
+    #[test]
+    fn expired_discoveries_are_dropped() {
+        let clock = MockClock::new();
+        let mut state = inner_state(&clock);
+        let discovered: api::events::Event = fixture("events/device_discovered.json");
+
+        let EventType::DeviceDiscovered { device, .. } = &discovered.ty else {
+            panic!("not a discovery: {:?}", discovered.ty);
+        };
+        state.apply_event(discovered.clone());
+        assert!(state.is_on_lan(device));
+        clock.advance(LAN_DISCOVERY_TTL);
+        assert!(!state.is_on_lan(device));
+
+        // Discovering another device drops the expired one
+
+        let mut other = serde_json::to_value(&discovered).unwrap();
+        other["data"]["device"] =
+            "P56IOI7-MZJNU2Y-IQGDREY-DM2MGTI-MGL3BXN-PQ6W5BM-TBBZ4TJ-XZWICQ2".into();
+        state.apply_event(serde_json::from_value(other).unwrap());
+        assert_eq!(state.discovered_devices.len(), 1);
+        assert!(!state.discovered_devices.contains_key(device));
+    }
+
+    #[test]
+    fn pending_flood_is_counted_within_its_window() {
+        let clock = MockClock::new();
+        let mut state = inner_state(&clock);
+
+        for _ in 1..PENDING_FLOOD_THRESHOLD {
+            state.apply_event(fixture("events/pending_devices_changed.json"));
+            clock.advance(Duration::from_secs(60));
+        }
+        assert_eq!(state.get_pending_device_flood(), None);
+        state.apply_event(fixture("events/pending_devices_changed.json"));
+        assert_eq!(
+            state.get_pending_device_flood(),
+            Some(PENDING_FLOOD_THRESHOLD)
+        );
+
+        // The first request drops out of the window
+        clock.advance(PENDING_FLOOD_WINDOW - Duration::from_secs(60 * 3));
+        assert_eq!(state.get_pending_device_flood(), None);
+    }
+}
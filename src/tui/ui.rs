@@ -1,40 +1,35 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local, Utc};
+
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
-    text::{Line, Span, Text},
-    widgets::{Block, Borders, Paragraph, Widget, Wrap},
+    text::{Line, Span},
+    widgets::{Block, Borders, Widget},
 };
 use strum::IntoEnumIterator;
+use syncthing_rs::types::config::{Compression, FolderType, VersioningType};
 
 use super::{
     app::{App, CurrentScreen},
-    pages::{DevicesPage, FoldersPage, IDPage, PendingPage},
+    hits::{HitMap, HitTarget},
+    pages::{
+        ActivityPage, ConfigPage, DevicesPage, FoldersPage, HealthPage, IDPage, MeshPage,
+        PendingPage, SettingsPage, SystemPage,
+    },
+    state::{DeviceStatus, Section},
 };
 
-pub fn ui(frame: &mut Frame, app: &App) {
-    // If we have an error, show only that
-    if app.state.read(|state| {
-        if let Some(error) = &state.error {
-            let popup_block =
-                create_popup_block(app, "Error".to_string()).style(Style::default().fg(Color::Red));
-
-            let error_text = Text::styled(error.to_string(), Style::default().fg(Color::default()));
-            let error_paragraph = Paragraph::new(error_text)
-                .block(popup_block)
-                .alignment(ratatui::layout::Alignment::Center)
-                .wrap(Wrap { trim: false }); // Do not cut off whn over edge
-
-            let area = centered_rect(50, 50, frame.area());
-            frame.render_widget(error_paragraph, area);
-            true
-        } else {
-            false
-        }
-    }) {
-        return;
-    }
+/// Frames of the spinner shown while something is loading
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How long each frame of the spinner is shown
+pub const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
 
+pub fn ui(frame: &mut Frame, app: &App) {
+    app.hits.borrow_mut().clear();
     let background = create_background(app);
     let inner_area = background.inner(frame.area());
     match app.current_screen {
@@ -43,26 +38,56 @@ pub fn ui(frame: &mut Frame, app: &App) {
         CurrentScreen::ID => IDPage::new(app.state.read(|state| state.id.clone()))
             .render(inner_area, frame.buffer_mut()),
         CurrentScreen::Pending => PendingPage::new(app).render(inner_area, frame.buffer_mut()),
+        CurrentScreen::Health => HealthPage::new(app).render(inner_area, frame.buffer_mut()),
+        CurrentScreen::Config => ConfigPage::new(app).render(inner_area, frame.buffer_mut()),
+        CurrentScreen::Mesh => MeshPage::new(app).render(inner_area, frame.buffer_mut()),
+        CurrentScreen::Activity => ActivityPage::new(app).render(inner_area, frame.buffer_mut()),
+        CurrentScreen::System => SystemPage::new(app).render(inner_area, frame.buffer_mut()),
+        CurrentScreen::Settings => SettingsPage::new(app).render(inner_area, frame.buffer_mut()),
     };
 
     frame.render_widget(background, frame.area());
+    add_tab_hits(frame.area(), app.advanced, &mut app.hits.borrow_mut());
 
     if let Some(popup) = &app.popup {
         let state = app.state.clone();
-        popup.render(frame, state);
+        popup.render(frame, state, &mut app.hits.borrow_mut());
+    }
+}
+
+/// Label of the tab of `screen` in the bottom border, the `i`th one
+fn tab_label(i: usize, screen: &CurrentScreen) -> String {
+    format!("| ({}) {:?} ", (i + 1) % 10, screen)
+}
+
+/// Screens with a tab, together with their index. Screens only shown in
+/// advanced mode keep their number when they are hidden.
+fn tabs(advanced: bool) -> impl Iterator<Item = (usize, CurrentScreen)> {
+    CurrentScreen::iter()
+        .enumerate()
+        .filter(move |(_, screen)| advanced || !screen.is_advanced())
+}
+
+/// Makes the tabs in the bottom border of `area` clickable
+fn add_tab_hits(area: Rect, advanced: bool, hits: &mut HitMap) {
+    let mut x = area.x + 1;
+    for (i, screen) in tabs(advanced) {
+        let width = tab_label(i, &screen).len() as u16;
+        let tab = Rect::new(x, area.bottom().saturating_sub(1), width, 1);
+        hits.add(tab.intersection(area), HitTarget::Tab(screen));
+        x += width;
     }
 }
 
-fn create_background(app: &App) -> Block {
+fn create_background(app: &App) -> Block<'_> {
     let block = Block::default()
         .title_top(Line::from("| SyncTUI |").centered().bold())
         .borders(Borders::ALL);
 
-    let mut bottom_string = CurrentScreen::iter()
-        .enumerate()
+    let mut bottom_string = tabs(app.advanced)
         .map(|(i, screen)| {
             Span::styled(
-                format!("| ({}) {:?} ", i + 1, screen),
+                tab_label(i, &screen),
                 if screen == app.current_screen {
                     Style::default().add_modifier(Modifier::BOLD)
                 } else {
@@ -73,37 +98,299 @@ fn create_background(app: &App) -> Block {
         .collect::<Vec<Span>>();
     bottom_string.push("|".into());
 
-    block.title_bottom(bottom_string).title_bottom(
+    let block = block.title_bottom(bottom_string).title_bottom(
         Line::from(format!("| (q) quit | {} |", app.mode.lock().unwrap())).right_aligned(),
+    );
+
+    let restrictions = app.view_restrictions();
+    let block = if restrictions.is_empty() {
+        block
+    } else {
+        block.title_top(
+            Line::from(format!("| {} — (C) clear |", restrictions.join(" | ")))
+                .centered()
+                .style(Style::default().fg(Color::Cyan)),
+        )
+    };
+
+    let snoozed = app.snoozed_count();
+    let block = if snoozed > 0 {
+        block.title_bottom(
+            Line::from(format!("| (z) {} snoozed |", snoozed))
+                .centered()
+                .style(Style::default().fg(Color::Yellow)),
+        )
+    } else {
+        block
+    };
+
+    let block = match app.state.read(|state| {
+        state
+            .get_disconnected()
+            .map(|(down_for, disconnected)| (down_for, disconnected.clone()))
+    }) {
+        Some((down_for, disconnected)) => block.title_bottom(
+            Line::from(format!(
+                "| ! Disconnected from Syncthing for {} — reconnecting in {} (attempt {}) |",
+                format_duration(down_for),
+                format_duration(disconnected.retry_in),
+                disconnected.attempt
+            ))
+            .centered()
+            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        ),
+        None => block,
+    };
+
+    let block = match app.state.read(|state| state.get_pending_device_flood()) {
+        Some(count) => block.title_bottom(
+            Line::from(if app.advanced {
+                format!(
+                    "| ! {} unknown devices connecting — (g) global announce |",
+                    count
+                )
+            } else {
+                format!("| ! {} unknown devices connecting |", count)
+            })
+            .centered()
+            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        ),
+        None => block,
+    };
+
+    let block = match app.state.read(|state| {
+        state
+            .errors
+            .back()
+            .map(|latest| (state.errors.len(), latest.describe()))
+    }) {
+        Some((count, latest)) => block.title_top(
+            Line::from(format!(
+                "| {}: {} — (e) details |",
+                if count == 1 {
+                    "Error".to_string()
+                } else {
+                    format!("{} errors", count)
+                },
+                truncate(&latest, ERROR_TOAST_LENGTH)
+            ))
+            .left_aligned()
+            .style(Style::default().fg(Color::Red)),
+        ),
+        None => block,
+    };
+
+    let block = match app
+        .state
+        .read(|state| state.staleness(Section::Connections))
+    {
+        Some(age) => block.title_top(
+            Line::from(format!("| Last update {} ago |", format_duration(age)))
+                .left_aligned()
+                .style(Style::default().fg(Color::Red)),
+        ),
+        None => block,
+    };
+
+    let block = if app.state.recording_to().is_some() {
+        block.title_top(
+            Line::from("| REC |")
+                .right_aligned()
+                .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        )
+    } else {
+        block
+    };
+
+    match app.state.read(|state| state.get_hint()) {
+        Some(hint) => block.title_top(
+            Line::from(format!("| {} |", hint))
+                .right_aligned()
+                .style(Style::default().fg(Color::Yellow)),
+        ),
+        None => block,
+    }
+}
+
+/// Summary for the title of the terminal window, e.g.,
+/// `synctui — 12 folders, 4/5 devices, 1 pending`
+pub fn terminal_title(app: &App) -> String {
+    app.state.read(|state| {
+        let devices = state.get_other_devices();
+        let connected = devices
+            .iter()
+            .filter(|device| device.connected != DeviceStatus::Disconnected)
+            .count();
+        let pending = state.get_pending_devices().len() + state.get_pending_folders().len();
+        let mut title = format!(
+            "synctui — {} folders, {}/{} devices",
+            state.get_folders().len(),
+            connected,
+            devices.len()
+        );
+        if pending > 0 {
+            title.push_str(&format!(", {} pending", pending));
+        }
+        if state.staleness(Section::Connections).is_some() {
+            title.push_str(" — not responding");
+        }
+        title
+    })
+}
+
+/// Number of characters of the latest error shown in the status line
+const ERROR_TOAST_LENGTH: usize = 50;
+
+/// Cuts `text` off after `length` characters, marking it with an ellipsis
+fn truncate(text: &str, length: usize) -> String {
+    if text.chars().count() <= length {
+        text.to_string()
+    } else {
+        format!("{}…", text.chars().take(length).collect::<String>())
+    }
+}
+
+/// The current frame of the spinner
+pub fn spinner() -> &'static str {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    SPINNER[(millis / SPINNER_INTERVAL.as_millis()) as usize % SPINNER.len()]
+}
+
+/// Placeholder shown instead of `what` until it has been loaded
+pub fn loading_line(what: &str) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{} ", spinner()), Style::default().blue()),
+        Span::styled(format!("Loading {}…", what), Style::default().dark_gray()),
+    ])
+}
+
+/// Number of characters kept on each side of a truncated ID
+const ID_AFFIX_LENGTH: usize = 7;
+
+/// Shortens long IDs, e.g., device IDs, by replacing their middle with an
+/// ellipsis, unless `reveal` is set. For device IDs, this keeps the first
+/// and last group.
+pub fn display_id(id: &str, reveal: bool) -> String {
+    let length = id.chars().count();
+    if reveal || length <= 2 * ID_AFFIX_LENGTH + 1 {
+        return id.to_string();
+    }
+    let prefix: String = id.chars().take(ID_AFFIX_LENGTH).collect();
+    let suffix: String = id.chars().skip(length - ID_AFFIX_LENGTH).collect();
+    format!("{}…{}", prefix, suffix)
+}
+
+/// Minimum width of each pane of a split page
+const MIN_PANE_WIDTH: u16 = 20;
+
+/// Splits `area` horizontally into a list and a detail pane, where the list
+/// takes `percent` of the width, but each pane gets at least [`MIN_PANE_WIDTH`].
+pub fn split_panes(area: Rect, percent: u16) -> [Rect; 2] {
+    let list_width = (area.width as u32 * percent as u32 / 100) as u16;
+    let list_width = if area.width < 2 * MIN_PANE_WIDTH {
+        list_width
+    } else {
+        list_width.clamp(MIN_PANE_WIDTH, area.width - MIN_PANE_WIDTH)
+    };
+    Layout::horizontal([Constraint::Length(list_width), Constraint::Fill(1)]).areas(area)
+}
+
+/// Formats a duration coarsely, e.g. `2h 5m`, as used for ages and timeouts
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+        _ => format!("{}d {}h", secs / 86400, (secs % 86400) / 3600),
+    }
+}
+
+/// Formats a timestamp of Syncthing in local time, with the date only if it
+/// is not today, followed by how long ago it was, e.g. `14:03:12 (5m ago)`
+pub fn format_timestamp(time: DateTime<Utc>) -> String {
+    let local = time.with_timezone(&Local);
+    let now = Local::now();
+    let absolute = if local.date_naive() == now.date_naive() {
+        local.format("%H:%M:%S").to_string()
+    } else {
+        local.format("%Y-%m-%d %H:%M").to_string()
+    };
+    match (now.with_timezone(&Utc) - time).to_std() {
+        Ok(age) => format!("{} ({} ago)", absolute, format_duration(age)),
+        // Timestamps from the future happen with skewed clocks
+        Err(_) => absolute,
+    }
+}
+
+/// Index updates younger than this count as up to date
+const INDEX_UP_TO_DATE: Duration = Duration::from_secs(5 * 60);
+
+/// Describes when a device last sent an index update for a folder. Nothing
+/// is shown for disconnected devices, which cannot send any.
+pub fn index_span(last_index: Option<DateTime<Utc>>, connected: bool) -> Option<Span<'static>> {
+    if !connected {
+        return None;
+    }
+    Some(
+        match last_index.and_then(|time| (Utc::now() - time).to_std().ok()) {
+            Some(age) if age < INDEX_UP_TO_DATE => {
+                Span::styled(" · index up to date", Style::default().green())
+            }
+            Some(age) => Span::styled(
+                format!(" · last index {} ago", format_duration(age)),
+                Style::default().yellow(),
+            ),
+            None => Span::styled(" · no index this session", Style::default().dark_gray()),
+        },
     )
 }
 
-fn create_popup_block(_: &App, title: String) -> Block {
-    Block::default()
-        .title_top(Line::from(format!("| {} |", title)).centered())
-        .borders(Borders::ALL)
-}
-
-/// helper function to create a centered rect using up certain percentage of the available rect `r`
-// Adapted from https://ratatui.rs/tutorials/json-editor/ui/
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    // Cut the given rectangle into three vertical pieces
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage((100 - percent_y) / 2),
-        ])
-        .split(r);
-
-    // Then cut the middle vertical piece into three width-wise pieces
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
-        ])
-        .split(popup_layout[1])[1] // Return the middle chunk
+/// Describes what a device compresses when sending data
+pub fn format_compression(compression: &Compression) -> &'static str {
+    match compression {
+        Compression::Metadata => "Metadata only",
+        Compression::Always => "All data",
+        Compression::Never => "Off",
+    }
+}
+
+/// Describes in which directions a folder syncs
+pub fn format_folder_type(folder_type: &FolderType) -> &'static str {
+    match folder_type {
+        FolderType::SendReceive => "Send & Receive",
+        FolderType::SendOnly => "Send Only",
+        FolderType::ReceiveOnly => "Receive Only",
+        FolderType::ReceiveEncrypted => "Receive Encrypted",
+    }
+}
+
+pub fn format_versioning_type(versioning_type: &VersioningType) -> &'static str {
+    match versioning_type {
+        VersioningType::None => "No Versioning",
+        VersioningType::Trashcan => "Trash Can",
+        VersioningType::Simple => "Simple",
+        VersioningType::Staggered => "Staggered",
+        VersioningType::External => "External",
+    }
+}
+
+/// Formats a size in bytes with a binary unit prefix
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Formats a transfer rate in bytes per second with a binary unit prefix
+pub fn format_rate(bytes_per_second: f64) -> String {
+    format!("{}/s", format_bytes(bytes_per_second))
 }
@@ -0,0 +1,126 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::app::CurrentScreen;
+
+/// Default width of the list pane in percent
+const DEFAULT_SPLIT: u16 = 50;
+const MIN_SPLIT: u16 = 20;
+const MAX_SPLIT: u16 = 80;
+const SPLIT_STEP: u16 = 5;
+
+/// UI settings which are changed from within the TUI and persisted
+/// across sessions
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct UiState {
+    /// Width of the list pane in percent, keyed by screen
+    #[serde(default)]
+    splits: HashMap<String, u16>,
+    /// Names of the folder groups which are collapsed
+    #[serde(default, rename = "collapsed-groups")]
+    collapsed_groups: HashSet<String>,
+    /// IDs of the folders pinned to the top of the folders list
+    #[serde(default, rename = "favorite-folders")]
+    favorite_folders: HashSet<String>,
+}
+
+impl UiState {
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|path| path.join("synctui").join("ui-state.toml"))
+    }
+
+    /// Loads the UI state of the last session. Falls back to the default
+    /// state, as losing it is not worth failing over.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("failed to parse UI state at {}: {:?}", path.display(), e);
+                Self::default()
+            }),
+            Err(e) => {
+                log::debug!("no UI state loaded from {}: {:?}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            log::warn!("could not determine where to save the UI state");
+            return;
+        };
+        let result = toml::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                fs::write(&path, content).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            log::warn!("failed to save UI state to {}: {}", path.display(), e);
+        }
+    }
+
+    /// Width of the list pane on `screen` in percent
+    pub fn get_split(&self, screen: &CurrentScreen) -> u16 {
+        self.splits
+            .get(&screen.to_string())
+            .copied()
+            .unwrap_or(DEFAULT_SPLIT)
+    }
+
+    /// Grows (or shrinks, if `grow` is false) the list pane on `screen`
+    pub fn resize_split(&mut self, screen: &CurrentScreen, grow: bool) {
+        let split = self.get_split(screen);
+        let split = if grow {
+            split.saturating_add(SPLIT_STEP)
+        } else {
+            split.saturating_sub(SPLIT_STEP)
+        };
+        self.splits
+            .insert(screen.to_string(), split.clamp(MIN_SPLIT, MAX_SPLIT));
+    }
+
+    pub fn is_group_collapsed(&self, group: &str) -> bool {
+        self.collapsed_groups.contains(group)
+    }
+
+    /// Collapses `group` if it is expanded, and the other way round
+    pub fn toggle_group(&mut self, group: &str) {
+        if !self.collapsed_groups.remove(group) {
+            self.collapsed_groups.insert(group.to_string());
+        }
+    }
+
+    pub fn expand_group(&mut self, group: &str) {
+        self.collapsed_groups.remove(group);
+    }
+
+    pub fn expand_all_groups(&mut self) {
+        self.collapsed_groups.clear();
+    }
+
+    pub fn is_favorite(&self, folder_id: &str) -> bool {
+        self.favorite_folders.contains(folder_id)
+    }
+
+    pub fn has_favorites(&self) -> bool {
+        !self.favorite_folders.is_empty()
+    }
+
+    /// Pins the folder to the top if it is not yet, and unpins it otherwise
+    pub fn toggle_favorite(&mut self, folder_id: &str) {
+        if !self.favorite_folders.remove(folder_id) {
+            self.favorite_folders.insert(folder_id.to_string());
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::{collections::HashSet, time::Duration};
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre;
+#[cfg(feature = "email")]
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    transport::smtp::authentication::Credentials,
+};
+use serde::Serialize;
+use syncthing_rs::{Client, types::events::EventType};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    AppConfig,
+    config::{NotificationKind, Preload},
+    rest::RestClient,
+    tui::{recorder, state::State, ui::format_duration},
+};
+
+/// How often folders are checked for being out of sync for too long
+const OUT_OF_SYNC_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A notification as sent to the configured sinks
+#[derive(Clone, Debug, Serialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub message: String,
+    pub time: DateTime<Utc>,
+}
+
+impl Notification {
+    fn new(kind: NotificationKind, title: String, message: String) -> Self {
+        Self {
+            kind,
+            title,
+            message,
+            time: Utc::now(),
+        }
+    }
+}
+
+/// Runs without the TUI and sends a notification to every configured sink
+/// whenever something requires the attention of the user
+pub async fn watch(client: Client, config: AppConfig) -> eyre::Result<()> {
+    if config.notifications.webhook.is_none() && config.notifications.smtp.is_none() {
+        log::warn!("no notification sinks configured, notifications are only printed");
+    }
+    if cfg!(not(feature = "email")) && config.notifications.smtp.is_some() {
+        log::warn!("synctui was built without the email feature, no emails are sent");
+    }
+
+    // Only whether folders are out of sync is needed, nothing is shown
+    let state = State::new(client, RestClient::new(&config)?, &[Preload::Completion]);
+    if config.recorder.enabled {
+        recorder::start(&state, &config.recorder);
+    }
+    // Shares the TLS settings with the connection to Syncthing
+    let http = config.http_client()?;
+    let mut event_rx = state.subscribe_to_events();
+    let mut interval = tokio::time::interval(OUT_OF_SYNC_CHECK_INTERVAL);
+    // Folders we already notified about, until they are in sync again
+    let mut out_of_sync = HashSet::new();
+    let mut shutdown = std::pin::pin!(crate::signal::shutdown());
+
+    loop {
+        tokio::select! {
+            signal = &mut shutdown => {
+                log::info!("received {}, shutting down", signal);
+                state.set_event_sink(None);
+                return Ok(());
+            }
+            _ = interval.tick() => {
+                for notification in check_out_of_sync(&state, &config, &mut out_of_sync) {
+                    send(&config, &http, notification).await;
+                }
+            }
+            event = event_rx.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("watch skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return Ok(()),
+                };
+                for notification in notifications_for_event(&event.ty) {
+                    if config.notifies(notification.kind) {
+                        send(&config, &http, notification).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn check_out_of_sync(
+    state: &State,
+    config: &AppConfig,
+    notified: &mut HashSet<String>,
+) -> Vec<Notification> {
+    if !config.notifies(NotificationKind::OutOfSync) {
+        return Vec::new();
+    }
+
+    state.read(|state| {
+        let mut notifications = Vec::new();
+        for folder in state.get_folders() {
+            let id = &folder.config.id;
+            if !state.is_folder_stuck(id, config) {
+                notified.remove(id);
+            } else if notified.insert(id.clone()) {
+                notifications.push(Notification::new(
+                    NotificationKind::OutOfSync,
+                    format!("{} is out of sync", folder.config.label),
+                    format!(
+                        "Folder {} ({}) has been at {:.0}% for longer than {}",
+                        folder.config.label,
+                        id,
+                        folder.completion,
+                        format_duration(config.stuck_after())
+                    ),
+                ));
+            }
+        }
+        notifications
+    })
+}
+
+fn notifications_for_event(event: &EventType) -> Vec<Notification> {
+    match event {
+        EventType::PendingDevicesChanged {
+            added: Some(added), ..
+        } => added
+            .iter()
+            .map(|device| {
+                Notification::new(
+                    NotificationKind::PendingDevice,
+                    format!("{} wants to connect", device.name),
+                    format!(
+                        "Device {} ({}) at {} wants to connect",
+                        device.name, device.device_id, device.address
+                    ),
+                )
+            })
+            .collect(),
+        EventType::PendingFoldersChanged {
+            added: Some(added), ..
+        } => added
+            .iter()
+            .map(|folder| {
+                Notification::new(
+                    NotificationKind::PendingFolder,
+                    format!("{} is offered", folder.folder_label),
+                    format!(
+                        "Device {} offers folder {} ({})",
+                        folder.device_id, folder.folder_label, folder.folder_id
+                    ),
+                )
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Sends `notification` to all sinks. Failures are only logged, so one
+/// broken sink does not stop the others.
+async fn send(config: &AppConfig, http: &reqwest::Client, notification: Notification) {
+    println!("{}: {}", notification.time, notification.message);
+
+    if let Some(url) = &config.notifications.webhook
+        && let Err(e) = send_webhook(http, url, &notification).await
+    {
+        log::error!("failed to send webhook notification: {:?}", e);
+    }
+    #[cfg(feature = "email")]
+    if let Some(smtp) = &config.notifications.smtp
+        && let Err(e) = send_mail(smtp, &notification).await
+    {
+        log::error!("failed to send email notification: {:?}", e);
+    }
+}
+
+async fn send_webhook(
+    http: &reqwest::Client,
+    url: &str,
+    notification: &Notification,
+) -> eyre::Result<()> {
+    http.post(url)
+        .json(notification)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[cfg(feature = "email")]
+async fn send_mail(
+    smtp: &crate::config::SmtpConfig,
+    notification: &Notification,
+) -> eyre::Result<()> {
+    let mail = Message::builder()
+        .from(smtp.from.parse()?)
+        .to(smtp.to.parse()?)
+        .subject(format!("synctui: {}", notification.title))
+        .body(notification.message.clone())?;
+
+    let mut transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.server)?;
+    if let Some(port) = smtp.port {
+        transport = transport.port(port);
+    }
+    if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+    transport.build().send(mail).await?;
+    Ok(())
+}
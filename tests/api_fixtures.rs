@@ -0,0 +1,416 @@
+//! Deserializes recorded Syncthing responses and events, so that changes of
+//! the models or of the format of Syncthing are caught before the TUI breaks.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::{Serialize, de::DeserializeOwned};
+use syncthing_rs::types::{
+    cluster::{PendingDevices, PendingFolders},
+    config::{Compression, Configuration, FolderType, VersioningType},
+    db::Completion,
+    events::{Event, EventType},
+    stats::DeviceStatistics,
+    system::Connections,
+};
+use synctui::{DiscoveredDevice, SystemStatus};
+
+const LAPTOP: &str = "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD";
+const PHONE: &str = "P56IOI7-MZJNU2Y-IQGDREY-DM2MGTI-MGL3BXN-PQ6W5BM-TBBZ4TJ-XZWICQ2";
+const DESKTOP: &str = "XHMOAUZ-5ZTBD2F-YBJ5S7R-XJJLS6N-UUZ7XD7-2OMRNLK-YBOR5TP-ZTQEGQA";
+
+/// Parses the fixture `name`, and checks that serializing and parsing it
+/// again does not lose anything
+fn round_trip<T: DeserializeOwned + Serialize>(name: &str) -> T {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    let content = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e));
+    let parsed: T = serde_json::from_str(&content)
+        .unwrap_or_else(|e| panic!("failed to parse {}: {}", name, e));
+
+    let serialized = serde_json::to_value(&parsed).unwrap();
+    let reparsed: T = serde_json::from_value(serialized.clone())
+        .unwrap_or_else(|e| panic!("failed to parse serialized {}: {}", name, e));
+    assert_eq!(
+        serialized,
+        serde_json::to_value(&reparsed).unwrap(),
+        "{} changed in a round trip",
+        name
+    );
+    parsed
+}
+
+#[test]
+fn configuration() {
+    let config: Configuration = round_trip("config.json");
+    assert_eq!(config.version, 37);
+
+    assert_eq!(config.folders.len(), 2);
+    let documents = &config.folders[0];
+    assert_eq!(documents.id, "abcd-1234");
+    assert_eq!(documents.label, "Documents");
+    assert_eq!(documents.path, "/home/user/Documents");
+    assert!(!documents.paused);
+    let sharers: Vec<_> = documents
+        .devices
+        .iter()
+        .map(|d| d.device_id.as_str())
+        .collect();
+    assert_eq!(sharers, [LAPTOP, PHONE]);
+    assert_eq!(documents.folder_type, FolderType::SendReceive);
+    assert!(config.folders[1].paused);
+    assert_eq!(config.folders[1].folder_type, FolderType::ReceiveOnly);
+    assert_eq!(documents.versioning.versioning_type, VersioningType::None);
+    let photos = &config.folders[1].versioning;
+    assert_eq!(photos.versioning_type, VersioningType::Staggered);
+    assert_eq!(photos.params["maxAge"], "31536000");
+    assert_eq!(photos.fs_path, ".stversions");
+
+    assert_eq!(config.devices.len(), 2);
+    let laptop = &config.devices[0];
+    assert_eq!(laptop.device_id, LAPTOP);
+    assert_eq!(laptop.name, "laptop");
+    assert_eq!(laptop.addresses, ["dynamic"]);
+    assert_eq!(laptop.compression, Compression::Metadata);
+    assert!(!laptop.introducer);
+    let phone = &config.devices[1];
+    assert_eq!(phone.compression, Compression::Always);
+    assert!(phone.introducer);
+    assert!(phone.paused);
+}
+
+#[test]
+fn connections() {
+    let connections: Connections = round_trip("connections.json");
+    assert_eq!(connections.connections.len(), 2);
+
+    let laptop = &connections.connections[LAPTOP];
+    assert!(laptop.connected);
+    assert!(laptop.is_local);
+    assert_eq!(laptop.in_bytes_total, 556325);
+    assert_eq!(laptop.out_bytes_total, 1843);
+    assert_eq!(laptop.address, "192.168.1.10:22000");
+
+    let phone = &connections.connections[PHONE];
+    assert!(!phone.connected);
+    assert!(phone.paused);
+    assert_eq!(connections.total.in_bytes_total, 556325);
+}
+
+#[test]
+fn system_status() {
+    let status: SystemStatus = round_trip("system_status.json");
+    assert_eq!(status.my_id, LAPTOP);
+    assert_eq!(status.uptime, 2635);
+    assert_eq!(status.sys, 42092792);
+    assert!(status.discovery_enabled);
+
+    let listeners = &status.connection_service_status;
+    assert_eq!(listeners.len(), 3);
+    assert!(listeners["tcp://0.0.0.0:22000"].error.is_none());
+    assert!(listeners["quic://0.0.0.0:22000"].error.is_some());
+    assert_eq!(
+        listeners["tcp://0.0.0.0:22000"].lan_addresses,
+        ["tcp://0.0.0.0:22000"]
+    );
+    assert_eq!(
+        status
+            .discovery_status
+            .values()
+            .filter(|discovery| discovery.error.is_some())
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn discovery() {
+    let discovery: HashMap<String, DiscoveredDevice> = round_trip("discovery.json");
+    assert_eq!(discovery.len(), 2);
+    assert_eq!(discovery[PHONE].addresses.len(), 2);
+    assert_eq!(discovery[DESKTOP].addresses, ["tcp://203.0.113.7:22000"]);
+}
+
+#[test]
+fn completion() {
+    let completion: Completion = round_trip("completion.json");
+    assert!((completion.completion - 99.9937565835).abs() < f64::EPSILON);
+    assert_eq!(completion.need_bytes, 9789241);
+    assert_eq!(completion.need_items, 1);
+    assert_eq!(completion.remote_state, "valid");
+}
+
+#[test]
+fn pending_devices() {
+    let pending: PendingDevices = round_trip("pending_devices.json");
+    assert_eq!(pending.devices.len(), 1);
+    let desktop = &pending.devices[DESKTOP];
+    assert_eq!(desktop.name, "desktop");
+    assert_eq!(desktop.address, "192.168.1.30:22000");
+}
+
+#[test]
+fn pending_folders() {
+    let pending: PendingFolders = round_trip("pending_folders.json");
+    let music = &pending.folders["cpkn4-57ysy"];
+    assert_eq!(music.offered_by.len(), 2);
+    assert_eq!(music.offered_by[LAPTOP].label, "Music");
+    assert!(!music.offered_by[PHONE].receive_encrypted);
+}
+
+#[test]
+fn device_stats() {
+    let stats: HashMap<String, DeviceStatistics> = round_trip("device_stats.json");
+    assert!(stats[LAPTOP].last_seen.timestamp() > 0);
+    // Syncthing reports the epoch for devices it never saw
+    assert_eq!(stats[PHONE].last_seen.timestamp(), 0);
+}
+
+#[test]
+fn config_saved_event() {
+    let event: Event = round_trip("events/config_saved.json");
+    assert_eq!(event.id, 1);
+    assert!(matches!(event.ty, EventType::ConfigSaved { version: 37 }));
+}
+
+#[test]
+fn device_connected_event() {
+    let event: Event = round_trip("events/device_connected.json");
+    let EventType::DeviceConnected {
+        addr,
+        id,
+        device_name,
+        ..
+    } = event.ty
+    else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(addr, "192.168.1.10:22000");
+    assert_eq!(id, LAPTOP);
+    assert_eq!(device_name, "laptop");
+}
+
+#[test]
+fn device_disconnected_event() {
+    let event: Event = round_trip("events/device_disconnected.json");
+    let EventType::DeviceDisconnected { error, id } = event.ty else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(error, "reading length: EOF");
+    assert_eq!(id, LAPTOP);
+}
+
+#[test]
+fn device_discovered_event() {
+    let event: Event = round_trip("events/device_discovered.json");
+    let EventType::DeviceDiscovered { addrs, device } = event.ty else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(addrs.len(), 2);
+    assert_eq!(device, LAPTOP);
+}
+
+#[test]
+fn pending_devices_changed_event() {
+    let event: Event = round_trip("events/pending_devices_changed.json");
+    let EventType::PendingDevicesChanged { added, removed } = event.ty else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    let added = added.unwrap();
+    assert_eq!(added[0].device_id, DESKTOP);
+    assert_eq!(added[0].name, "desktop");
+    assert_eq!(removed.unwrap()[0].device_id, PHONE);
+}
+
+#[test]
+fn pending_folders_changed_event() {
+    let event: Event = round_trip("events/pending_folders_changed.json");
+    let EventType::PendingFoldersChanged { added, removed } = event.ty else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    let added = added.unwrap();
+    assert_eq!(added[0].device_id, LAPTOP);
+    assert_eq!(added[0].folder_id, "cpkn4-57ysy");
+    assert_eq!(added[0].folder_label, "Music");
+    let removed = removed.unwrap();
+    assert_eq!(removed[0].folder_id, "abcd-1234");
+    assert_eq!(removed[0].device_id, None);
+}
+
+#[test]
+fn remote_download_progress_event() {
+    let event: Event = round_trip("events/remote_download_progress.json");
+    let EventType::RemoteDownloadProgress { device, folder } = event.ty else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(device, LAPTOP);
+    assert_eq!(folder, "abcd-1234");
+}
+
+#[test]
+fn unknown_event() {
+    let event: Event = round_trip("events/unknown.json");
+    assert!(matches!(event.ty, EventType::Unknown));
+}
+
+#[test]
+fn device_paused_event() {
+    let event: Event = round_trip("events/device_paused.json");
+    let EventType::DevicePaused { device } = event.ty else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(device, PHONE);
+}
+
+#[test]
+fn device_resumed_event() {
+    let event: Event = round_trip("events/device_resumed.json");
+    let EventType::DeviceResumed { device } = event.ty else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(device, PHONE);
+}
+
+#[test]
+fn cluster_config_received_event() {
+    let event: Event = round_trip("events/cluster_config_received.json");
+    let EventType::ClusterConfigReceived { device } = event.ty else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(device, LAPTOP);
+}
+
+#[test]
+fn remote_index_updated_event() {
+    let event: Event = round_trip("events/remote_index_updated.json");
+    let EventType::RemoteIndexUpdated {
+        device,
+        folder,
+        items,
+        ..
+    } = event.ty
+    else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(device, LAPTOP);
+    assert_eq!(folder, "abcd-1234");
+    assert_eq!(items, 1000);
+}
+
+#[test]
+fn state_changed_event() {
+    let event: Event = round_trip("events/state_changed.json");
+    let EventType::StateChanged {
+        folder,
+        from,
+        to,
+        error,
+        ..
+    } = event.ty
+    else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(folder, "abcd-1234");
+    assert_eq!(from, "idle");
+    assert_eq!(to, "scanning");
+    assert_eq!(error, None);
+}
+
+#[test]
+fn folder_watch_state_changed_event() {
+    let event: Event = round_trip("events/folder_watch_state_changed.json");
+    let EventType::FolderWatchStateChanged { folder, from, to } = event.ty else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(folder, "abcd-1234");
+    assert_eq!(from, None);
+    assert!(to.unwrap().starts_with("failed to setup inotify handler"));
+}
+
+#[test]
+fn item_finished_event() {
+    let event: Event = round_trip("events/item_finished.json");
+    let EventType::ItemFinished {
+        item,
+        folder,
+        error,
+        action,
+        ..
+    } = event.ty
+    else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(item, "notes/todo.md");
+    assert_eq!(folder, "abcd-1234");
+    assert_eq!(error, None);
+    assert_eq!(action, "update");
+}
+
+#[test]
+fn failed_item_finished_event() {
+    let event: Event = round_trip("events/item_finished_error.json");
+    let EventType::ItemFinished { item, error, .. } = event.ty else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(item, "photos/raw/IMG_0042.CR2");
+    assert_eq!(
+        error.as_deref(),
+        Some("open /data/photos/raw/IMG_0042.CR2: permission denied")
+    );
+}
+
+#[test]
+fn local_change_detected_event() {
+    let event: Event = round_trip("events/local_change_detected.json");
+    let EventType::LocalChangeDetected {
+        action,
+        folder_id,
+        path,
+        ..
+    } = event.ty
+    else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(action, "modified");
+    assert_eq!(folder_id, "abcd-1234");
+    assert_eq!(path, "notes/todo.md");
+}
+
+#[test]
+fn remote_change_detected_event() {
+    let event: Event = round_trip("events/remote_change_detected.json");
+    let EventType::RemoteChangeDetected {
+        action,
+        folder_id,
+        path,
+        modified_by,
+        ..
+    } = event.ty
+    else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(action, "deleted");
+    assert_eq!(folder_id, "abcd-1234");
+    assert_eq!(path, "old/draft.md");
+    assert!(PHONE.starts_with(&modified_by));
+}
+
+#[test]
+fn folder_completion_event() {
+    let event: Event = round_trip("events/folder_completion.json");
+    let EventType::FolderCompletion {
+        completion,
+        device,
+        folder,
+        need_bytes,
+        ..
+    } = event.ty
+    else {
+        panic!("unexpected event {:?}", event.ty);
+    };
+    assert_eq!(completion, 87.5);
+    assert_eq!(device, PHONE);
+    assert_eq!(folder, "abcd-1234");
+    assert_eq!(need_bytes, 1024);
+}
@@ -0,0 +1,46 @@
+//! Checks the mock clock, which time-dependent behavior is tested with.
+
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use synctui::{Clock, MockClock, SharedClock};
+
+#[test]
+fn mock_clock_stands_still() {
+    let clock = MockClock::new();
+    let (now, utc_now) = (clock.now(), clock.utc_now());
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(clock.now(), now);
+    assert_eq!(clock.utc_now(), utc_now);
+}
+
+#[test]
+fn mock_clock_advances_both_times() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 21, 14, 0, 0).unwrap();
+    let clock = MockClock::starting_at(start);
+    let earlier = clock.now();
+
+    clock.advance(Duration::from_secs(90));
+    assert_eq!(clock.elapsed(earlier), Duration::from_secs(90));
+    assert_eq!(
+        clock.utc_now(),
+        Utc.with_ymd_and_hms(2025, 3, 21, 14, 1, 30).unwrap()
+    );
+}
+
+#[test]
+fn clones_share_the_time() {
+    let clock = MockClock::new();
+    let shared = SharedClock::new(clock.clone());
+    let earlier = shared.now();
+
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(shared.elapsed(earlier), Duration::from_secs(5));
+}
+
+#[test]
+fn elapsed_is_zero_for_later_instants() {
+    let clock = MockClock::new();
+    let later = clock.now() + Duration::from_secs(1);
+    assert_eq!(clock.elapsed(later), Duration::ZERO);
+}
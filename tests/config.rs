@@ -0,0 +1,231 @@
+use std::path::Path;
+
+use syncthing_rs::types::config::{Configuration, FolderConfiguration};
+use synctui::{AppConfig, FolderCommand, IconSet, LocalSyncthing};
+
+/// The first folder of the recorded configuration
+fn folder() -> FolderConfiguration {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/config.json");
+    let config: Configuration =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    config.folders[0].clone()
+}
+
+#[test]
+fn replace_api_key_keeps_the_rest() {
+    let content = "# Syncthing on the NAS\naddress = \"https://nas:8384\"\napi-key = \"old\" # from the GUI\ninsecure = true\n";
+    assert_eq!(
+        AppConfig::replace_api_key(content, "new"),
+        "# Syncthing on the NAS\naddress = \"https://nas:8384\"\napi-key = \"new\"\ninsecure = true\n"
+    );
+}
+
+#[test]
+fn replace_api_key_adds_a_missing_key() {
+    let replaced = AppConfig::replace_api_key("insecure = true", "new");
+    assert_eq!(replaced, "api-key = \"new\"\ninsecure = true");
+    let (config, issues) = AppConfig::validate(&replaced);
+    assert!(issues.is_empty());
+    assert_eq!(config.unwrap().api_key, "new");
+}
+
+#[test]
+fn switching_profiles_restores_the_replaced_settings() {
+    let content = r#"
+api-key = "key"
+icons = "ascii"
+poll-interval = 5
+keys = { quit = ["q", "Ctrl+c"] }
+
+[profiles.monitoring]
+screen = "activity"
+poll-interval = 30
+
+[profiles.admin]
+icons = "nerd-font"
+keys = { reload = ["F5"] }
+"#;
+    let (config, issues) = AppConfig::validate(content);
+    assert!(issues.is_empty());
+    let config = config.unwrap();
+
+    let monitoring = config.with_profile("monitoring").unwrap();
+    assert_eq!(monitoring.poll_interval, 30);
+    assert_eq!(monitoring.icons, IconSet::Ascii);
+
+    let admin = monitoring.with_profile("admin").unwrap();
+    assert_eq!(admin.profile().map(|(name, _)| name), Some("admin"));
+    assert_eq!(admin.poll_interval, 5);
+    assert_eq!(admin.icons, IconSet::NerdFont);
+    assert_eq!(admin.keys.len(), 2);
+
+    let none = admin.without_profile();
+    assert!(none.profile().is_none());
+    assert_eq!(none.icons, IconSet::Ascii);
+    assert_eq!(none.keys.len(), 1);
+    assert!(config.with_profile("unknown").is_err());
+}
+
+#[test]
+fn first_run_content_is_a_valid_config() {
+    let (config, issues) = AppConfig::validate(&AppConfig::first_run_content(
+        "https://nas:8384",
+        "key\"with quote",
+    ));
+    assert!(issues.is_empty());
+    let config = config.unwrap();
+    assert_eq!(config.address(), "https://nas:8384");
+    assert_eq!(config.api_key, "key\"with quote");
+}
+
+#[cfg(unix)]
+#[test]
+fn config_file_is_only_readable_by_the_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("synctui-test-{}", std::process::id()));
+    let path = dir.join("config.toml");
+    let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+    AppConfig::create(&path, "http://localhost:8384", "key").unwrap();
+    assert_eq!(mode(&path), 0o600);
+
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    AppConfig::save_api_key(&path, "new").unwrap();
+    assert_eq!(mode(&path), 0o600);
+    assert_eq!(AppConfig::load(Some(&path)).unwrap().api_key, "new");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn local_syncthing_is_read_from_config_xml() {
+    let content = r#"<configuration version="37">
+    <folder id="default" path="/home/user/Sync"></folder>
+    <gui enabled="true" tls="true" debugging="false">
+        <address>0.0.0.0:8384</address>
+        <apikey>abc123</apikey>
+        <theme>default</theme>
+    </gui>
+</configuration>"#;
+    let local = LocalSyncthing::parse(Path::new("config.xml"), content).unwrap();
+    assert_eq!(local.address.as_deref(), Some("https://localhost:8384"));
+    assert_eq!(local.api_key, "abc123");
+
+    let unix = content.replace("0.0.0.0:8384", "/run/syncthing.sock");
+    let local = LocalSyncthing::parse(Path::new("config.xml"), &unix).unwrap();
+    assert_eq!(local.address, None);
+
+    let without_key = content.replace("<apikey>abc123</apikey>", "");
+    assert_eq!(
+        LocalSyncthing::parse(Path::new("config.xml"), &without_key),
+        None
+    );
+}
+
+#[test]
+fn is_local_judges_the_host_of_the_address() {
+    let config = |address: &str| AppConfig {
+        address: Some(address.to_string()),
+        ..Default::default()
+    };
+    assert!(AppConfig::default().is_local());
+    assert!(config("https://127.0.0.1:8384/").is_local());
+    assert!(config("http://[::1]:8384").is_local());
+    assert!(!config("https://nas:8384").is_local());
+    assert!(!config("http://localhost.example.com:8384").is_local());
+}
+
+#[test]
+fn address_without_scheme_is_reported() {
+    let (_, issues) = AppConfig::validate("api-key = \"key\"\naddress = \"nas:8384\"\n");
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].location, Some((2, 1)));
+    assert_eq!(
+        issues[0].message,
+        "address 'nas:8384' is not an http(s) URL"
+    );
+}
+
+#[test]
+fn unknown_keys_of_nested_tables_are_reported() {
+    let content = r#"api-key = "key"
+
+[notifications]
+webhok = "http://localhost/hook"
+
+[notifications.smtp]
+server = "mail.example.com"
+from = "synctui@example.com"
+to = "me@example.com"
+prot = 25
+
+[profiles.work]
+colour = "red"
+
+[[folder-groups]]
+name = "Work"
+label_prefix = "W"
+
+[[folder-commands]]
+name = "Open"
+command = "xdg-open {path}"
+folder = ["abcd-1234"]
+
+[recorder]
+rotate = 2
+"#;
+    let (config, issues) = AppConfig::validate(content);
+    assert!(config.is_some());
+    let issues: Vec<_> = issues
+        .iter()
+        .map(|issue| (issue.location, issue.message.as_str()))
+        .collect();
+    assert!(issues.contains(&(Some((4, 1)), "unknown key 'webhok' in 'notifications'")));
+    assert!(issues.contains(&(Some((10, 1)), "unknown key 'prot' in 'notifications.smtp'")));
+    assert!(issues.contains(&(Some((13, 1)), "unknown key 'colour' in 'profiles.work'")));
+    assert!(issues.contains(&(
+        Some((17, 1)),
+        "unknown key 'label_prefix' in 'folder-groups'"
+    )));
+    assert!(issues.contains(&(Some((22, 1)), "unknown key 'folder' in 'folder-commands'")));
+    assert!(issues.contains(&(Some((25, 1)), "unknown key 'rotate' in 'recorder'")));
+}
+
+#[test]
+fn issues_point_at_the_key_in_its_table() {
+    let content = "api-key = \"key\"\n[recorder]\nkeep = 1\n\n[notifications.smtp]\nserver = \"mail\"\nfrom = \"a@b\"\nto = \"nobody\"\n\n[profiles.quiet]\nto = 1\n";
+    let (_, issues) = AppConfig::validate(content);
+    let issue = issues
+        .iter()
+        .find(|issue| issue.message == "'nobody' is not an email address")
+        .unwrap();
+    assert_eq!(issue.location, Some((8, 1)));
+}
+
+#[cfg(unix)]
+#[test]
+fn folder_command_placeholders_are_expanded_once() {
+    let mut folder = folder();
+    folder.path = "/x/{id}".to_string();
+    folder.id = "a'; touch pwned; '{label}".to_string();
+    folder.label = "My {path}".to_string();
+    let command = FolderCommand {
+        name: "Print".to_string(),
+        command: "printf '%s\\n' {path} {id} {label} {unknown}".to_string(),
+        folders: Vec::new(),
+    };
+    let script = command.expand(&folder).unwrap();
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{}\n{}\n{}\n{{unknown}}\n",
+            folder.path, folder.id, folder.label
+        )
+    );
+}